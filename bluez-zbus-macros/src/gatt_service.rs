@@ -0,0 +1,209 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::meta::ParseNestedMeta;
+use syn::spanned::Spanned;
+use syn::{Fields, Ident, ItemStruct, Lit};
+
+/// Parse `uuid = 0x180f` or `uuid = "0000180f-0000-1000-8000-00805f9b34fb"`
+fn parse_uuid(meta: &ParseNestedMeta<'_>) -> syn::Result<TokenStream> {
+    let lit: Lit = meta.value()?.parse()?;
+    match &lit {
+        Lit::Int(int) => {
+            let short: u32 = int.base10_parse()?;
+            Ok(quote!(::bluez_zbus::uuids::uuid_from_u32(#short)))
+        }
+        Lit::Str(s) => {
+            let hex = s.value().replace('-', "");
+            let value = (hex.len() == 32)
+                .then(|| u128::from_str_radix(&hex, 16).ok())
+                .flatten()
+                .ok_or_else(|| syn::Error::new(s.span(), "invalid UUID"))?;
+            let value = Literal::u128_unsuffixed(value);
+            Ok(quote!(::bluez_zbus::uuid::Uuid::from_u128(#value)))
+        }
+        _ => Err(syn::Error::new(
+            lit.span(),
+            "expected a 16/32-bit assigned number or a UUID string",
+        )),
+    }
+}
+
+/// `write_without_response` to `WriteWithoutResponse`
+fn flag_variant(flag: &Ident) -> Ident {
+    let name: String = flag
+        .to_string()
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    Ident::new(&name, flag.span())
+}
+
+struct Characteristic {
+    field: Ident,
+    ty: syn::Type,
+    uuid: TokenStream,
+    flags: Vec<Ident>,
+}
+
+fn characteristic(field: &mut syn::Field) -> syn::Result<Characteristic> {
+    let span = field.span();
+    let Some(index) = field
+        .attrs
+        .iter()
+        .position(|attr| attr.path().is_ident("characteristic"))
+    else {
+        return Err(syn::Error::new(
+            span,
+            "every field needs a #[characteristic(uuid = .., flags(..))]",
+        ));
+    };
+    let attr = field.attrs.remove(index);
+    let mut uuid = None;
+    let mut flags = Vec::new();
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("uuid") {
+            uuid = Some(parse_uuid(&meta)?);
+            Ok(())
+        } else if meta.path.is_ident("flags") {
+            meta.parse_nested_meta(|flag| {
+                let ident = flag.path.require_ident()?;
+                flags.push(flag_variant(ident));
+                Ok(())
+            })
+        } else {
+            Err(meta.error("expected `uuid` or `flags`"))
+        }
+    })?;
+    Ok(Characteristic {
+        field: field.ident.clone().expect("named field"),
+        ty: field.ty.clone(),
+        uuid: uuid.ok_or_else(|| syn::Error::new(span, "missing `uuid = ..`"))?,
+        flags,
+    })
+}
+
+pub fn expand(args: TokenStream, mut item: ItemStruct) -> syn::Result<TokenStream> {
+    let mut uuid = None;
+    let mut primary = true;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("uuid") {
+            uuid = Some(parse_uuid(&meta)?);
+            Ok(())
+        } else if meta.path.is_ident("secondary") {
+            primary = false;
+            Ok(())
+        } else {
+            Err(meta.error("expected `uuid` or `secondary`"))
+        }
+    });
+    syn::parse::Parser::parse2(parser, args)?;
+    let uuid = uuid.ok_or_else(|| syn::Error::new(item.span(), "missing `uuid = ..`"))?;
+
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new(
+            item.generics.span(),
+            "gatt_service structs can't be generic",
+        ));
+    }
+    let Fields::Named(fields) = &mut item.fields else {
+        return Err(syn::Error::new(
+            item.span(),
+            "gatt_service needs a struct with named fields",
+        ));
+    };
+    let characteristics = fields
+        .named
+        .iter_mut()
+        .map(characteristic)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &item.ident;
+    let vis = &item.vis;
+    let handle = format_ident!("{name}Handle");
+    let gatt = quote!(::bluez_zbus::interface::gatt);
+
+    let handle_fields = characteristics.iter().map(|c| {
+        let field = &c.field;
+        let ty = &c.ty;
+        quote!(pub #field: #gatt::TypedCharacteristicHandle<'a, #ty>)
+    });
+    let build = characteristics.iter().map(|c| {
+        let field = &c.field;
+        let ty = &c.ty;
+        let uuid = &c.uuid;
+        let flags = &c.flags;
+        quote! {
+            (
+                #gatt::TypedCharacteristic::<#ty>::new(
+                    #uuid,
+                    ::core::option::Option::Some(self.#field),
+                    ::std::vec![#(#gatt::CharacteristicFlags::#flags),*],
+                )
+                .into_inner(),
+                ::std::vec::Vec::new(),
+            )
+        }
+    });
+    let lookup = characteristics.iter().map(|c| {
+        let field = &c.field;
+        let uuid = &c.uuid;
+        quote! {
+            #field: #gatt::TypedCharacteristicHandle::new(
+                service.characteristics().get(&#uuid)?,
+            )
+        }
+    });
+
+    Ok(quote! {
+        #item
+
+        /// Typed handles to the characteristics of a registered
+        #[doc = ::core::concat!("[`", ::core::stringify!(#name), "`]")]
+        #vis struct #handle<'a> {
+            #(#handle_fields,)*
+        }
+
+        impl #name {
+            pub const UUID: ::bluez_zbus::uuid::Uuid = #uuid;
+
+            /// The service and its characteristics, holding the field values,
+            /// for `GattApplication1::register_new()`
+            #[allow(clippy::type_complexity)]
+            pub fn into_service(
+                self,
+            ) -> (
+                #gatt::GattService1,
+                ::std::vec::Vec<(#gatt::GattCharacteristic1, ::std::vec::Vec<#gatt::GattDescriptor1>)>,
+            ) {
+                (
+                    #gatt::GattService1::new(Self::UUID, #primary),
+                    ::std::vec![#(#build),*],
+                )
+            }
+
+            /// Handles to the characteristics of `service`
+            pub fn handle(service: &#gatt::GattServiceHandle) -> ::core::option::Option<#handle<'_>> {
+                ::core::option::Option::Some(#handle {
+                    #(#lookup,)*
+                })
+            }
+
+            /// Find this service among the services of `application`
+            pub fn find(
+                application: &#gatt::GattApplicationHandle,
+            ) -> ::core::option::Option<#handle<'_>> {
+                application
+                    .services()
+                    .iter()
+                    .filter(|service| service.uuid() == Self::UUID)
+                    .find_map(Self::handle)
+            }
+        }
+    })
+}
@@ -0,0 +1,159 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, LitInt};
+
+struct Field {
+    /// Accessor, `self.name` or `self.0`
+    member: syn::Member,
+    binding: syn::Ident,
+    ty: syn::Type,
+    len: Option<LitInt>,
+}
+
+fn field_len(field: &syn::Field) -> syn::Result<Option<LitInt>> {
+    let mut len = None;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("gatt"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("len") {
+                len = Some(meta.value()?.parse::<LitInt>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `len = N`"))
+            }
+        })?;
+    }
+    Ok(len)
+}
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "GattValue can only be derived for structs",
+        ));
+    };
+
+    let fields = data
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let (member, binding) = match &field.ident {
+                Some(ident) => (syn::Member::Named(ident.clone()), ident.clone()),
+                None => (syn::Member::Unnamed(i.into()), format_ident!("field{i}")),
+            };
+            Ok(Field {
+                member,
+                binding,
+                ty: field.ty.clone(),
+                len: field_len(field)?,
+            })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let krate = quote!(::bluez_zbus::codec);
+
+    let sizes = fields.iter().map(|field| match &field.len {
+        Some(len) => quote!(::core::option::Option::Some(#len)),
+        None => {
+            let ty = &field.ty;
+            quote!(<#ty as #krate::GattValue>::SIZE)
+        }
+    });
+
+    let encode = fields.iter().map(|field| {
+        let member = &field.member;
+        let ty = &field.ty;
+        let bytes = quote!(<#ty as #krate::GattValue>::to_bytes(&self.#member));
+        match &field.len {
+            Some(len) => quote!(buf.extend(#krate::__derive::fit(#bytes, #len));),
+            None => quote!(buf.extend(#bytes);),
+        }
+    });
+
+    // Every field but the last needs a known length to find where the next
+    // one starts, the last one takes what is left. The input is `__data` so
+    // it can't clash with the field bindings.
+    let mut asserts = Vec::new();
+    let decode = fields.iter().enumerate().map(|(i, field)| {
+        let binding = &field.binding;
+        let ty = &field.ty;
+        let label = format!("{name}::{}", quote!(#binding));
+        let len = match &field.len {
+            Some(len) => quote!(#len),
+            None if i + 1 == fields.len() => {
+                quote!(<#ty as #krate::GattValue>::SIZE.unwrap_or(__data.len()))
+            }
+            None => {
+                let msg = format!(
+                    "field `{}` has no fixed size, add #[gatt(len = N)]",
+                    quote!(#binding)
+                );
+                // Generic parameters can't be used in a const item
+                if input.generics.params.is_empty() {
+                    asserts.push(quote_spanned_assert(ty, &krate, &msg));
+                }
+                quote! {
+                    match <#ty as #krate::GattValue>::SIZE {
+                        ::core::option::Option::Some(len) => len,
+                        ::core::option::Option::None => {
+                            return ::core::result::Result::Err(
+                                #krate::__derive::Error::Failure(::std::format!("{}: {}", #label, #msg)),
+                            );
+                        }
+                    }
+                }
+            }
+        };
+        quote! {
+            let __len = #len;
+            let #binding = <#ty as #krate::GattValue>::from_bytes(
+                #krate::__derive::take(&mut __data, __len, #label)?,
+            )?;
+        }
+    });
+    let decode: Vec<_> = decode.collect();
+
+    let bindings = fields.iter().map(|field| &field.binding);
+    let construct = match &data.fields {
+        Fields::Named(_) => quote!(Self { #(#bindings),* }),
+        Fields::Unnamed(_) => quote!(Self(#(#bindings),*)),
+        Fields::Unit => quote!(Self),
+    };
+
+    Ok(quote! {
+        #(#asserts)*
+
+        impl #impl_generics #krate::GattValue for #name #ty_generics #where_clause {
+            const SIZE: ::core::option::Option<usize> = #krate::__derive::sum(&[#(#sizes),*]);
+
+            fn to_bytes(&self) -> ::std::vec::Vec<u8> {
+                let mut buf = ::std::vec::Vec::new();
+                #(#encode)*
+                buf
+            }
+
+            #[allow(unused_mut)]
+            fn from_bytes(mut __data: &[u8]) -> ::core::result::Result<Self, #krate::__derive::Error> {
+                #(#decode)*
+                ::core::result::Result::Ok(#construct)
+            }
+        }
+    })
+}
+
+fn quote_spanned_assert(ty: &syn::Type, krate: &TokenStream, msg: &str) -> TokenStream {
+    quote::quote_spanned! {ty.span()=>
+        const _: () = ::core::assert!(
+            <#ty as #krate::GattValue>::SIZE.is_some(),
+            #msg
+        );
+    }
+}
@@ -0,0 +1,62 @@
+//! Procedural macros for `bluez-zbus`, re-exported by it with the `macros`
+//! feature
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput, ItemStruct};
+
+mod gatt_service;
+mod gatt_value;
+
+/// Derive `bluez_zbus::codec::GattValue` for a struct by packing its fields
+/// in order, each with its own `GattValue` encoding.
+///
+/// Every field except the last needs a fixed size. Variable length fields
+/// such as `String` take one with `#[gatt(len = N)]`, padding with zeros or
+/// truncating on encode.
+///
+/// ```ignore
+/// #[derive(GattValue)]
+/// struct Reading {
+///     temperature: i16,
+///     humidity: u16,
+///     #[gatt(len = 8)]
+///     location: String,
+///     raw: Vec<u8>,
+/// }
+/// ```
+#[proc_macro_derive(GattValue, attributes(gatt))]
+pub fn derive_gatt_value(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    gatt_value::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Declare a GATT service as a struct whose fields are the initial values of
+/// its characteristics. Each field type must implement `GattValue`.
+///
+/// Generates `into_service()` for `GattApplication1::register_new()`, and a
+/// `<Name>Handle` with a `TypedCharacteristicHandle` per field, found with
+/// `handle()` or `find()` once registered. UUIDs are 16/32-bit assigned
+/// numbers or full UUID strings.
+///
+/// ```ignore
+/// #[gatt_service(uuid = 0x180f)]
+/// struct Battery {
+///     #[characteristic(uuid = 0x2a19, flags(read, notify))]
+///     level: BatteryLevel,
+/// }
+///
+/// let app = GattApplication1::register_new(path, conn, vec![
+///     Battery { level: BatteryLevel(100) }.into_service(),
+/// ])
+/// .await?;
+/// Battery::find(&app).unwrap().level.notify(&BatteryLevel(90)).await?;
+/// ```
+#[proc_macro_attribute]
+pub fn gatt_service(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemStruct);
+    gatt_service::expand(args.into(), item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
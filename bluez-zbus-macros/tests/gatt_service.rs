@@ -0,0 +1,117 @@
+use bluez_zbus::codec::BatteryLevel;
+use bluez_zbus::interface::gatt::{gatt_service, GattApplication1};
+use bluez_zbus::testing::{MockBluez, TestBus};
+use bluez_zbus::uuid::Uuid;
+use bluez_zbus::uuids::uuid_from_u32;
+use zbus::fdo::ManagedObjects;
+use zbus::zvariant::{OwnedValue, Value};
+
+#[gatt_service(uuid = 0x180f)]
+struct Battery {
+    #[characteristic(uuid = 0x2a19, flags(read, notify))]
+    level: BatteryLevel,
+}
+
+#[gatt_service(uuid = "12345678-1234-5678-1234-56789abcdef0", secondary)]
+struct Sensor {
+    #[characteristic(uuid = 0x2a6e, flags(read))]
+    temperature: i16,
+    #[characteristic(
+        uuid = "12345678-1234-5678-1234-56789abcdef1",
+        flags(write, write_without_response)
+    )]
+    setpoint: u16,
+}
+
+const SENSOR: Uuid = Uuid::from_u128(0x12345678_1234_5678_1234_56789abcdef0);
+const SETPOINT: Uuid = Uuid::from_u128(0x12345678_1234_5678_1234_56789abcdef1);
+
+/// The properties of the object with `interface` and `UUID` `uuid`
+fn object<'a>(
+    objects: &'a ManagedObjects,
+    interface: &str,
+    uuid: Uuid,
+) -> &'a std::collections::HashMap<String, OwnedValue> {
+    objects
+        .values()
+        .filter_map(|interfaces| {
+            let (_, props) = interfaces.iter().find(|(name, _)| name.as_str() == interface)?;
+            Some(props)
+        })
+        .find(|props| {
+            matches!(&*props["UUID"], Value::Str(s) if s.as_str() == uuid.to_string())
+        })
+        .unwrap_or_else(|| panic!("no {interface} {uuid}"))
+}
+
+fn flags(props: &std::collections::HashMap<String, OwnedValue>) -> Vec<String> {
+    Vec::<String>::try_from(props["Flags"].try_clone().unwrap()).unwrap()
+}
+
+#[test]
+fn attribute_declares_the_service() {
+    assert_eq!(Battery::UUID, uuid_from_u32(0x180f));
+    assert_eq!(Sensor::UUID, SENSOR);
+    let (_, characteristics) = Sensor {
+        temperature: 0,
+        setpoint: 0,
+    }
+    .into_service();
+    assert_eq!(characteristics.len(), 2);
+    assert!(characteristics.iter().all(|(_, descriptors)| descriptors.is_empty()));
+}
+
+#[test]
+fn registered_service_has_uuids_and_flags() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let app = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            vec![
+                Battery {
+                    level: BatteryLevel(80),
+                }
+                .into_service(),
+                Sensor {
+                    temperature: -5,
+                    setpoint: 300,
+                }
+                .into_service(),
+            ],
+        )
+        .await?;
+
+        let applications = bluez.applications(&adapter).await?;
+        let objects = &applications[0].objects;
+        // Two services, three characteristics, no descriptors
+        assert_eq!(objects.len(), 5);
+
+        let battery = object(objects, "org.bluez.GattService1", Battery::UUID);
+        assert_eq!(&*battery["Primary"], &Value::Bool(true));
+        let sensor = object(objects, "org.bluez.GattService1", SENSOR);
+        assert_eq!(&*sensor["Primary"], &Value::Bool(false));
+
+        let level = object(objects, "org.bluez.GattCharacteristic1", uuid_from_u32(0x2a19));
+        assert_eq!(flags(level), ["read", "notify"]);
+        let temperature =
+            object(objects, "org.bluez.GattCharacteristic1", uuid_from_u32(0x2a6e));
+        assert_eq!(flags(temperature), ["read"]);
+        let setpoint = object(objects, "org.bluez.GattCharacteristic1", SETPOINT);
+        assert_eq!(flags(setpoint), ["write", "write-without-response"]);
+
+        let handle = Battery::find(&app).expect("registered Battery");
+        assert_eq!(handle.level.value().await?, BatteryLevel(80));
+        let handle = Sensor::find(&app).expect("registered Sensor");
+        assert_eq!(handle.temperature.value().await?, -5);
+        assert_eq!(handle.setpoint.value().await?, 300);
+
+        app.unregister().await?;
+        Ok(())
+    })
+}
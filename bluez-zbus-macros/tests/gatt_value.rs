@@ -0,0 +1,75 @@
+use bluez_zbus::codec::{BatteryLevel, GattValue};
+
+#[derive(Debug, PartialEq, GattValue)]
+struct Reading {
+    temperature: i16,
+    humidity: u16,
+    valid: bool,
+    #[gatt(len = 8)]
+    location: String,
+    raw: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, GattValue)]
+struct Pair(u8, BatteryLevel);
+
+fn reading() -> Reading {
+    Reading {
+        temperature: -1234,
+        humidity: 0x0102,
+        valid: true,
+        location: "lab".to_owned(),
+        raw: vec![9, 8, 7],
+    }
+}
+
+#[test]
+fn named_fields_round_trip() {
+    let bytes = reading().to_bytes();
+    assert_eq!(
+        bytes,
+        [0x2e, 0xfb, 0x02, 0x01, 1, b'l', b'a', b'b', 0, 0, 0, 0, 0, 9, 8, 7]
+    );
+    assert_eq!(Reading::from_bytes(&bytes).unwrap(), reading());
+}
+
+#[test]
+fn last_field_takes_the_rest() {
+    let mut bytes = reading().to_bytes();
+    bytes.truncate(13);
+    let decoded = Reading::from_bytes(&bytes).unwrap();
+    assert!(decoded.raw.is_empty());
+}
+
+#[test]
+fn fixed_len_field_is_truncated() {
+    let mut long = reading();
+    long.location = "a very long location".to_owned();
+    let decoded = Reading::from_bytes(&long.to_bytes()).unwrap();
+    assert_eq!(decoded.location, "a very l");
+}
+
+#[test]
+fn tuple_struct_round_trips() {
+    assert_eq!(<Pair as GattValue>::SIZE, Some(2));
+    let pair = Pair(7, BatteryLevel(42));
+    assert_eq!(pair.to_bytes(), [7, 42]);
+    assert_eq!(Pair::from_bytes(&[7, 42]).unwrap(), pair);
+}
+
+#[test]
+fn size_is_none_with_a_variable_field() {
+    assert_eq!(<Reading as GattValue>::SIZE, None);
+}
+
+#[test]
+fn truncated_value_is_rejected() {
+    let err = Reading::from_bytes(&[0x2e, 0xfb, 0x02]).unwrap_err();
+    assert!(err.to_string().contains("Reading::humidity"), "{err}");
+}
+
+#[test]
+fn compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,9 @@
+use bluez_zbus::codec::GattValue;
+
+#[derive(GattValue)]
+struct Reading {
+    name: String,
+    level: u8,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use bluez_zbus::codec::GattValue;
+
+struct NotAValue;
+
+#[derive(GattValue)]
+struct Reading {
+    level: u8,
+    other: NotAValue,
+}
+
+fn main() {}
@@ -0,0 +1,335 @@
+//! Generate `zbus` proxies from the introspection XML in `xml/`.
+//!
+//! Interfaces named in a `#[proxy(interface = ...)]` attribute under
+//! `src/proxy` are skipped, those use the crate's own types for properties.
+//! Everything else becomes a module of `crate::proxy`, named after the
+//! interface like the hand-written ones. The XML is read with `zbus_xml`, the
+//! parser `BluezCapabilities` uses for introspection at runtime.
+
+use std::collections::BTreeSet;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use zbus_xml::{Arg, ArgDirection, Interface, Node};
+
+fn main() {
+    println!("cargo:rerun-if-changed=xml");
+    println!("cargo:rerun-if-changed=src/proxy");
+
+    let mut hand_written = BTreeSet::new();
+    proxy_interfaces(Path::new("src/proxy"), &mut hand_written);
+
+    let mut files: Vec<_> = fs::read_dir("xml")
+        .expect("xml/ is missing")
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "xml"))
+        .collect();
+    files.sort();
+
+    let mut out = String::new();
+    for file in files {
+        let xml = fs::read_to_string(&file).unwrap_or_else(|e| panic!("{file:?}: {e}"));
+        let node = Node::try_from(xml.as_str()).unwrap_or_else(|e| panic!("{file:?}: {e}"));
+        for interface in node.interfaces() {
+            let name = interface.name().to_string();
+            if hand_written.contains(&name) {
+                continue;
+            }
+            generate(&mut out, &module_name(&name), interface)
+                .unwrap_or_else(|e| panic!("{file:?}: {name}: {e}"));
+        }
+    }
+
+    let dest = Path::new(&env::var("OUT_DIR").unwrap()).join("proxies.rs");
+    fs::write(dest, out).unwrap();
+}
+
+/// Collect the interfaces of the `#[proxy(...)]` attributes in the sources
+/// under `dir`
+fn proxy_interfaces(dir: &Path, interfaces: &mut BTreeSet<String>) {
+    let entries = fs::read_dir(dir).unwrap_or_else(|e| panic!("{dir:?}: {e}"));
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            proxy_interfaces(&path, interfaces);
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            let source = fs::read_to_string(&path).unwrap_or_else(|e| panic!("{path:?}: {e}"));
+            for attr in source.split("#[proxy(").skip(1) {
+                let attr = attr.split(")]").next().unwrap_or_default();
+                interfaces.extend(attr_value(attr, "interface"));
+            }
+        }
+    }
+}
+
+/// Value of `name = "..."` in the arguments of an attribute
+fn attr_value(attr: &str, name: &str) -> Option<String> {
+    let pattern = format!("{name} = \"");
+    let start = attr.find(&pattern)? + pattern.len();
+    let len = attr[start..].find('"')?;
+    Some(attr[start..start + len].to_owned())
+}
+
+/// `org.bluez.DeviceSet1` is `device_set1`, `org.bluez.obex.Session1` is
+/// `obex_session1`
+fn module_name(interface: &str) -> String {
+    let name = interface.strip_prefix("org.bluez.").unwrap_or(interface);
+    name.split('.')
+        .map(snake_case)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+fn trait_name(interface: &str) -> String {
+    let name = interface.strip_prefix("org.bluez.").unwrap_or(interface);
+    name.replace('.', "")
+}
+
+/// `ServiceAllowList` is `service_allow_list`, `UUIDs` is `uuids`
+fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next = chars.get(i + 1).copied();
+            // An acronym ends before a capital followed by lowercase, unless
+            // that is only a plural `s`
+            let acronym_end = prev.is_uppercase()
+                && next.is_some_and(|n| n.is_lowercase())
+                && !(next == Some('s') && chars.get(i + 2).is_none_or(|n| n.is_uppercase()));
+            if prev.is_lowercase() || prev.is_ascii_digit() || acronym_end {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+/// How zbus derives a member name from a snake case function name
+fn pascal_case(snake: &str) -> String {
+    snake
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn ident(name: &str) -> String {
+    let name = snake_case(name);
+    match name.as_str() {
+        "type" | "ref" | "match" | "move" | "mod" | "impl" | "self" | "in" | "fn" | "use" => {
+            format!("{name}_")
+        }
+        _ => name,
+    }
+}
+
+/// `#[zbus(...)]` attributes, with a `name` when zbus would not derive the
+/// D-Bus name from `function`
+fn zbus_attr(kind: Option<&str>, function: &str, name: &str) -> String {
+    let mut attrs: Vec<String> = kind.into_iter().map(str::to_owned).collect();
+    if pascal_case(function) != name {
+        attrs.push(format!("name = \"{name}\""));
+    }
+    if attrs.is_empty() {
+        String::new()
+    } else {
+        format!("    #[zbus({})]\n", attrs.join(", "))
+    }
+}
+
+/// Split a signature into its complete types
+fn split_signature(signature: &str) -> Result<Vec<&str>, String> {
+    let mut types = Vec::new();
+    let mut rest = signature;
+    while !rest.is_empty() {
+        let len = type_len(rest)?;
+        types.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+    Ok(types)
+}
+
+/// Length of the first complete type of `signature`
+fn type_len(signature: &str) -> Result<usize, String> {
+    let bytes = signature.as_bytes();
+    match bytes.first() {
+        Some(b'a') => Ok(1 + type_len(&signature[1..])?),
+        Some(&open @ (b'(' | b'{')) => {
+            let close = if open == b'(' { b')' } else { b'}' };
+            let mut len = 1;
+            while bytes.get(len) != Some(&close) {
+                if len >= bytes.len() {
+                    return Err(format!("unterminated '{signature}'"));
+                }
+                len += type_len(&signature[len..])?;
+            }
+            Ok(len + 1)
+        }
+        Some(b'b' | b'y' | b'n' | b'q' | b'i' | b'u' | b'x' | b't' | b'd' | b's' | b'o')
+        | Some(b'g' | b'v' | b'h') => Ok(1),
+        _ => Err(format!("invalid signature '{signature}'")),
+    }
+}
+
+/// Rust type of a single complete type. `arg` types are what a caller passes
+/// at the top level, everything else is owned.
+fn rust_type(signature: &str, arg: bool) -> Result<String, String> {
+    let inner = |signature| rust_type(signature, false);
+    let ty = match signature {
+        "b" => "bool".to_owned(),
+        "y" => "u8".to_owned(),
+        "n" => "i16".to_owned(),
+        "q" => "u16".to_owned(),
+        "i" => "i32".to_owned(),
+        "u" => "u32".to_owned(),
+        "x" => "i64".to_owned(),
+        "t" => "u64".to_owned(),
+        "d" => "f64".to_owned(),
+        "s" if arg => "&str".to_owned(),
+        "s" => "String".to_owned(),
+        "o" if arg => "&zbus::zvariant::ObjectPath<'_>".to_owned(),
+        "o" => "zbus::zvariant::OwnedObjectPath".to_owned(),
+        "g" if arg => "&zbus::zvariant::Signature".to_owned(),
+        "g" => "zbus::zvariant::Signature".to_owned(),
+        "v" if arg => "&zbus::zvariant::Value<'_>".to_owned(),
+        "v" => "zbus::zvariant::OwnedValue".to_owned(),
+        "h" if arg => "zbus::zvariant::Fd<'_>".to_owned(),
+        "h" => "zbus::zvariant::OwnedFd".to_owned(),
+        "a{sv}" if arg => "std::collections::HashMap<&str, zbus::zvariant::Value<'_>>".to_owned(),
+        _ if signature.starts_with("a{") => {
+            let types = split_signature(&signature[2..signature.len() - 1])?;
+            let [key, value] = types[..] else {
+                return Err(format!("invalid dict '{signature}'"));
+            };
+            format!(
+                "std::collections::HashMap<{}, {}>",
+                inner(key)?,
+                inner(value)?
+            )
+        }
+        _ if signature.starts_with('a') && arg => {
+            let element = match &signature[1..] {
+                "s" => "&str".to_owned(),
+                "o" => "zbus::zvariant::ObjectPath<'_>".to_owned(),
+                element => inner(element)?,
+            };
+            format!("&[{element}]")
+        }
+        _ if signature.starts_with('a') => format!("Vec<{}>", inner(&signature[1..])?),
+        _ if signature.starts_with('(') => {
+            let fields = split_signature(&signature[1..signature.len() - 1])?
+                .into_iter()
+                .map(inner)
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("({},)", fields.join(", "))
+        }
+        _ => return Err(format!("invalid signature '{signature}'")),
+    };
+    Ok(ty)
+}
+
+/// Return type of a method from its out args
+fn return_type(args: &[&Arg]) -> Result<String, String> {
+    let types = args
+        .iter()
+        .map(|arg| rust_type(&arg.ty().to_string(), false))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(match types.len() {
+        0 => "()".to_owned(),
+        1 => types[0].clone(),
+        _ => format!("({})", types.join(", ")),
+    })
+}
+
+/// Parameters of a method, or of a signal which are received so owned
+fn params(args: &[&Arg], signal: bool) -> Result<String, String> {
+    let mut params = String::from("&self");
+    for (i, arg) in args.iter().enumerate() {
+        let name = arg.name().map_or_else(|| format!("arg_{i}"), ident);
+        let ty = rust_type(&arg.ty().to_string(), !signal)?;
+        write!(params, ", {name}: {ty}").unwrap();
+    }
+    Ok(params)
+}
+
+fn generate(out: &mut String, module: &str, interface: &Interface<'_>) -> Result<(), String> {
+    writeln!(out, "pub mod {module} {{").unwrap();
+    writeln!(out, "    use zbus::proxy;\n").unwrap();
+    writeln!(
+        out,
+        "    #[proxy(\n        interface = \"{}\",\n        default_service = \"org.bluez\",\n        assume_defaults = true\n    )]",
+        interface.name()
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    pub trait {} {{",
+        trait_name(&interface.name().to_string())
+    )
+    .unwrap();
+
+    let mut items = Vec::new();
+    for method in interface.methods() {
+        let name = method.name().to_string();
+        let function = ident(&name);
+        let (outputs, inputs): (Vec<&Arg>, Vec<&Arg>) = method
+            .args()
+            .iter()
+            .partition(|arg| arg.direction() == Some(ArgDirection::Out));
+        items.push(format!(
+            "    /// {name} method\n{}    fn {function}({}) -> zbus::Result<{}>;\n",
+            zbus_attr(None, &function, &name),
+            params(&inputs, false)?,
+            return_type(&outputs)?,
+        ));
+    }
+    for signal in interface.signals() {
+        let name = signal.name().to_string();
+        let function = ident(&name);
+        let args: Vec<&Arg> = signal.args().iter().collect();
+        items.push(format!(
+            "    /// {name} signal\n{}    fn {function}({}) -> zbus::Result<()>;\n",
+            zbus_attr(Some("signal"), &function, &name),
+            params(&args, true)?,
+        ));
+    }
+    for property in interface.properties() {
+        let name = property.name().to_string();
+        let signature = property.ty().to_string();
+        let function = ident(&name);
+        let attrs = zbus_attr(Some("property"), &function, &name);
+        let mut item = format!(
+            "    /// {name} property\n{attrs}    fn {function}(&self) -> zbus::Result<{}>;\n",
+            rust_type(&signature, false)?,
+        );
+        if property.access().write() {
+            writeln!(
+                item,
+                "{attrs}    fn set_{function}(&self, value: {}) -> zbus::Result<()>;",
+                rust_type(&signature, true)?,
+            )
+            .unwrap();
+        }
+        items.push(item);
+    }
+
+    // Indent the trait items one more level inside the module
+    for item in items.join("\n").lines() {
+        if item.is_empty() {
+            out.push('\n');
+        } else {
+            writeln!(out, "    {item}").unwrap();
+        }
+    }
+    writeln!(out, "    }}\n}}\n").unwrap();
+    Ok(())
+}
@@ -1,13 +1,11 @@
-use std::collections::HashMap;
 use std::io::Write;
 use std::time::Duration;
 
+use bluez_zbus::advertising::blocking::AdvertisingManager;
 use bluez_zbus::interface::{AdvertisementType, LEAdvertisement1};
 use bluez_zbus::proxy::adapter1::Adapter1ProxyBlocking;
-use bluez_zbus::proxy::le_advertising_manager1::LEAdvertisingManager1ProxyBlocking;
-use log::{error, info};
+use log::info;
 use zbus::blocking::Connection;
-use zbus::zvariant::ObjectPath;
 
 fn power_on(adaptor: &Adapter1ProxyBlocking) -> Result<(), zbus::Error> {
     if !adaptor.powered()? {
@@ -51,42 +49,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         advert.max_interval = Some(Duration::from_secs(1));
     }
 
-    connection
-        .object_server()
-        .at(
-            &ObjectPath::from_str_unchecked(
-                "/org/bluez/testing/advertisement/5935e8220fd3461f95a73ea26b4628ef",
-            ),
-            advert,
-        )
-        .map_err(|err| {
-            error!("{}: add_to_server {}", "path", err);
-            err
-        })?;
-
-    let advertising = LEAdvertisingManager1ProxyBlocking::builder(&connection)
-        .path("/org/bluez/hci0")?
-        .build()?;
-
-    // advertise
-    //  - Set name
-    //  - Set advertise
-    // gatt
-    //  - register service 00000000-4fc7-4d40-8e54-3956e5e4ffb9
-    //  - register characteristic 0000afb1-4fc7-4d40-8e54-3956e5e4ffb9 + props
-    //  - register application
-
-    dbg!(advertising.active_instances()?);
-    dbg!(advertising.supported_includes()?);
-    dbg!(advertising.supported_instances()?);
+    let advertising = AdvertisingManager::new(&connection, "/org/bluez/hci0")?;
+    dbg!(advertising.proxy().supported_includes()?);
     println!("Registering");
-    advertising.register_advertisement(
-        &ObjectPath::from_static_str_unchecked(
-            "/org/bluez/testing/advertisement/5935e8220fd3461f95a73ea26b4628ef",
-        ),
-        HashMap::default(),
-    )?;
-    dbg!(advertising.active_instances()?);
+    let handle = advertising.register(advert)?;
+    dbg!(handle.path());
+    dbg!(advertising.proxy().active_instances()?);
 
     let mut count_down = 30;
     while count_down != 0 {
@@ -95,10 +63,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     println!("Unregistering");
-    advertising.unregister_advertisement(&ObjectPath::from_static_str_unchecked(
-        "/org/bluez/testing/advertisement/5935e8220fd3461f95a73ea26b4628ef",
-    ))?;
-    dbg!(advertising.active_instances()?);
+    handle.unregister()?;
+    dbg!(advertising.proxy().active_instances()?);
     // toggle_pairable(&adaptor)?;
     Ok(())
 }
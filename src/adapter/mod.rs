@@ -0,0 +1,205 @@
+//! # Adapter helpers
+//!
+//! Typed values of the `org.bluez.Adapter1` properties.
+
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+use uuid::Uuid;
+use zbus::zvariant::{OwnedValue, Signature, Type, Value};
+
+use crate::enum_impl_to_from_str;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod power;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use power::*;
+
+enum_impl_to_from_str! {
+    PowerState, {
+        On : "on",
+        Off : "off",
+        OffEnabling : "off-enabling",
+        OnDisabling : "on-disabling",
+        OffBlocked : "off-blocked",
+    }
+}
+
+impl PowerState {
+    /// The adapter is off because of an rfkill block
+    pub fn is_blocked(&self) -> bool {
+        matches!(self, PowerState::OffBlocked)
+    }
+}
+
+impl Type for PowerState {
+    const SIGNATURE: &'static Signature = &Signature::Str;
+}
+
+impl TryFrom<OwnedValue> for PowerState {
+    type Error = zbus::Error;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        let state: String = value.try_into()?;
+        Ok(PowerState::from_str(&state)?)
+    }
+}
+
+enum_impl_to_from_str! {
+    AdapterRole, {
+        Central : "central",
+        Peripheral : "peripheral",
+        CentralPeripheral : "central-peripheral",
+    }
+}
+
+/// The roles supported by an adapter, from the `Roles` property
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AdapterRoles {
+    central: bool,
+    peripheral: bool,
+    central_peripheral: bool,
+}
+
+impl AdapterRoles {
+    pub fn contains(&self, role: AdapterRole) -> bool {
+        match role {
+            AdapterRole::Central => self.central,
+            AdapterRole::Peripheral => self.peripheral,
+            AdapterRole::CentralPeripheral => self.central_peripheral,
+        }
+    }
+
+    /// GATT applications and advertisements can be registered
+    pub fn supports_peripheral(&self) -> bool {
+        self.peripheral || self.central_peripheral
+    }
+
+    fn insert(&mut self, role: AdapterRole) {
+        match role {
+            AdapterRole::Central => self.central = true,
+            AdapterRole::Peripheral => self.peripheral = true,
+            AdapterRole::CentralPeripheral => self.central_peripheral = true,
+        }
+    }
+}
+
+impl FromIterator<AdapterRole> for AdapterRoles {
+    fn from_iter<I: IntoIterator<Item = AdapterRole>>(iter: I) -> Self {
+        let mut roles = Self::default();
+        iter.into_iter().for_each(|role| roles.insert(role));
+        roles
+    }
+}
+
+impl Type for AdapterRoles {
+    const SIGNATURE: &'static Signature = <Vec<String> as Type>::SIGNATURE;
+}
+
+impl TryFrom<OwnedValue> for AdapterRoles {
+    type Error = zbus::Error;
+
+    /// Roles unknown to this crate are skipped
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        let roles: Vec<String> = value.try_into()?;
+        Ok(roles
+            .iter()
+            .filter_map(|role| AdapterRole::from_str(role).ok())
+            .collect())
+    }
+}
+
+/// The UUIDs of the bluez experimental features enabled on an adapter, from
+/// the `ExperimentalFeatures` property
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExperimentalFeatures(BTreeSet<Uuid>);
+
+impl ExperimentalFeatures {
+    pub fn contains(&self, feature: &Uuid) -> bool {
+        self.0.contains(feature)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Uuid> {
+        self.0.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Type for ExperimentalFeatures {
+    const SIGNATURE: &'static Signature = <Vec<String> as Type>::SIGNATURE;
+}
+
+impl TryFrom<OwnedValue> for ExperimentalFeatures {
+    type Error = zbus::Error;
+
+    fn try_from(value: OwnedValue) -> Result<Self, Self::Error> {
+        let features: Vec<String> = value.try_into()?;
+        features
+            .iter()
+            .map(|feature| {
+                Uuid::parse_str(feature).map_err(|e| {
+                    zbus::Error::Failure(format!("Invalid experimental feature {feature}: {e}"))
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+enum_impl_to_from_str! {
+    DiscoveryTransport, {
+        Auto : "auto",
+        BrEdr : "bredr",
+        Le : "le",
+    }
+}
+
+/// Filter for `Adapter1.SetDiscoveryFilter`. Unset fields are left to bluez.
+#[derive(Debug, Default, Clone)]
+pub struct DiscoveryFilter {
+    /// Only report devices advertising one of these service UUIDs
+    pub uuids: Vec<Uuid>,
+    /// Only report devices with a stronger RSSI, in dBm
+    pub rssi: Option<i16>,
+    /// Only report devices with a lower pathloss, in dB
+    pub pathloss: Option<u16>,
+    pub transport: Option<DiscoveryTransport>,
+    /// Report every advertisement instead of only changes
+    pub duplicate_data: Option<bool>,
+    /// Only report discoverable devices
+    pub discoverable: Option<bool>,
+    /// Only report devices whose address or name starts with this
+    pub pattern: Option<String>,
+}
+
+impl DiscoveryFilter {
+    pub fn to_map(&self) -> HashMap<&'static str, Value<'static>> {
+        let mut filter = HashMap::new();
+        if !self.uuids.is_empty() {
+            let uuids: Vec<String> = self.uuids.iter().map(Uuid::to_string).collect();
+            filter.insert("UUIDs", Value::from(uuids));
+        }
+        if let Some(rssi) = self.rssi {
+            filter.insert("RSSI", Value::from(rssi));
+        }
+        if let Some(pathloss) = self.pathloss {
+            filter.insert("Pathloss", Value::from(pathloss));
+        }
+        if let Some(transport) = self.transport {
+            filter.insert("Transport", Value::from(<&str>::from(transport)));
+        }
+        if let Some(duplicate_data) = self.duplicate_data {
+            filter.insert("DuplicateData", Value::from(duplicate_data));
+        }
+        if let Some(discoverable) = self.discoverable {
+            filter.insert("Discoverable", Value::from(discoverable));
+        }
+        if let Some(pattern) = &self.pattern {
+            filter.insert("Pattern", Value::from(pattern.clone()));
+        }
+        filter
+    }
+}
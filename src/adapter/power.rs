@@ -0,0 +1,16 @@
+use futures_lite::{Stream, StreamExt};
+
+use super::PowerState;
+use crate::proxy::adapter1::Adapter1Proxy;
+
+/// Stream of the adapter's `PowerState`, including transitions and rfkill
+/// blocks
+pub async fn power_state_changes(
+    proxy: &Adapter1Proxy<'static>,
+) -> impl Stream<Item = PowerState> + use<> {
+    proxy
+        .receive_power_state_changed()
+        .await
+        .then(|changed| async move { changed.get().await.ok() })
+        .filter_map(|state| state)
+}
@@ -0,0 +1,154 @@
+//! # Bluetooth device addresses
+
+use std::fmt;
+use std::str::FromStr;
+
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+/// A Bluetooth device address, stored most significant byte first as it is
+/// written, e.g. `00:11:22:33:44:55`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BDAddr([u8; 6]);
+
+impl BDAddr {
+    pub const fn new(bytes: [u8; 6]) -> Self {
+        Self(bytes)
+    }
+
+    /// The address bytes, most significant first
+    pub fn as_bytes(&self) -> &[u8; 6] {
+        &self.0
+    }
+
+    /// The address bytes in over-the-air (little endian) order
+    pub fn to_le_bytes(&self) -> [u8; 6] {
+        let mut bytes = self.0;
+        bytes.reverse();
+        bytes
+    }
+
+    /// Parse the `dev_XX_XX_XX_XX_XX_XX` object path segment bluez uses for
+    /// devices
+    pub fn from_path_segment(segment: &str) -> Result<Self, zbus::fdo::Error> {
+        segment
+            .strip_prefix("dev_")
+            .ok_or_else(|| {
+                zbus::fdo::Error::InvalidArgs(format!("{segment} is not a device path segment"))
+            })?
+            .replace('_', ":")
+            .parse()
+    }
+
+    /// The `dev_XX_XX_XX_XX_XX_XX` object path segment of the device
+    pub fn to_path_segment(&self) -> String {
+        format!("dev_{}", self.to_string().replace(':', "_"))
+    }
+
+    /// Parse the address from a device object path such as
+    /// `/org/bluez/hci0/dev_00_11_22_33_44_55`
+    pub fn from_device_path(path: &ObjectPath<'_>) -> Result<Self, zbus::fdo::Error> {
+        Self::from_path_segment(path.as_str().rsplit('/').next().unwrap_or_default())
+    }
+
+    /// The object path of the device below `adapter_path`
+    pub fn device_path(&self, adapter_path: &ObjectPath<'_>) -> OwnedObjectPath {
+        // Both parts are valid path elements, so this can't fail
+        OwnedObjectPath::try_from(format!(
+            "{}/{}",
+            adapter_path.as_str(),
+            self.to_path_segment()
+        ))
+        .unwrap_or_default()
+    }
+}
+
+impl fmt::Display for BDAddr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{a:02X}:{b:02X}:{c:02X}:{d:02X}:{e:02X}:{g:02X}")
+    }
+}
+
+impl FromStr for BDAddr {
+    type Err = zbus::fdo::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || zbus::fdo::Error::InvalidArgs(format!("{s} is an invalid address"));
+        let mut bytes = [0; 6];
+        let mut parts = s.split(':');
+        for byte in bytes.iter_mut() {
+            let part = parts
+                .next()
+                .filter(|p| p.len() == 2 && p.bytes().all(|b| b.is_ascii_hexdigit()))
+                .ok_or_else(invalid)?;
+            *byte = u8::from_str_radix(part, 16).map_err(|_| invalid())?;
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(Self(bytes))
+    }
+}
+
+impl From<[u8; 6]> for BDAddr {
+    fn from(bytes: [u8; 6]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl From<BDAddr> for [u8; 6] {
+    fn from(addr: BDAddr) -> Self {
+        addr.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDR: BDAddr = BDAddr::new([0x00, 0x11, 0x22, 0xaa, 0xbb, 0xcc]);
+
+    #[test]
+    fn parse_and_display() {
+        assert_eq!("00:11:22:AA:BB:CC".parse::<BDAddr>().unwrap(), ADDR);
+        assert_eq!("00:11:22:aa:bb:cc".parse::<BDAddr>().unwrap(), ADDR);
+        assert_eq!(ADDR.to_string(), "00:11:22:AA:BB:CC");
+        assert_eq!(ADDR.to_le_bytes(), [0xcc, 0xbb, 0xaa, 0x22, 0x11, 0x00]);
+    }
+
+    #[test]
+    fn invalid_addresses() {
+        for addr in [
+            "",
+            "00:11:22:AA:BB",
+            "00:11:22:AA:BB:CC:DD",
+            "00:11:22:AA:BB:C",
+            "00:11:22:AA:BB:CCC",
+            "00-11-22-AA-BB-CC",
+            "00:11:22:AA:BB:GG",
+            "00:11:22:AA:BB:+C",
+        ] {
+            assert!(addr.parse::<BDAddr>().is_err(), "{addr}");
+        }
+    }
+
+    #[test]
+    fn path_segments() {
+        assert_eq!(ADDR.to_path_segment(), "dev_00_11_22_AA_BB_CC");
+        assert_eq!(
+            BDAddr::from_path_segment("dev_00_11_22_AA_BB_CC").unwrap(),
+            ADDR
+        );
+        assert!(BDAddr::from_path_segment("00_11_22_AA_BB_CC").is_err());
+        assert!(BDAddr::from_path_segment("dev_00_11_22_AA_BB").is_err());
+    }
+
+    #[test]
+    fn device_paths() {
+        let adapter = ObjectPath::from_static_str_unchecked("/org/bluez/hci0");
+        let path = ADDR.device_path(&adapter);
+        assert_eq!(path.as_str(), "/org/bluez/hci0/dev_00_11_22_AA_BB_CC");
+        assert_eq!(BDAddr::from_device_path(&path).unwrap(), ADDR);
+        assert!(BDAddr::from_device_path(&adapter).is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+
+use bitflags::bitflags;
+use uuid::Uuid;
+use zbus::zvariant::OwnedValue;
+
+use crate::uuids::{uuid_from_u16, uuid_from_u32};
+
+/// AD type numbers from the Bluetooth SIG assigned numbers
+pub mod ad_type {
+    pub const FLAGS: u8 = 0x01;
+    pub const INCOMPLETE_UUIDS_16: u8 = 0x02;
+    pub const COMPLETE_UUIDS_16: u8 = 0x03;
+    pub const INCOMPLETE_UUIDS_32: u8 = 0x04;
+    pub const COMPLETE_UUIDS_32: u8 = 0x05;
+    pub const INCOMPLETE_UUIDS_128: u8 = 0x06;
+    pub const COMPLETE_UUIDS_128: u8 = 0x07;
+    pub const SHORTENED_LOCAL_NAME: u8 = 0x08;
+    pub const COMPLETE_LOCAL_NAME: u8 = 0x09;
+    pub const TX_POWER_LEVEL: u8 = 0x0a;
+    pub const CLASS_OF_DEVICE: u8 = 0x0d;
+    pub const SOLICIT_UUIDS_16: u8 = 0x14;
+    pub const SOLICIT_UUIDS_128: u8 = 0x15;
+    pub const SERVICE_DATA_16: u8 = 0x16;
+    pub const APPEARANCE: u8 = 0x19;
+    pub const SOLICIT_UUIDS_32: u8 = 0x1f;
+    pub const SERVICE_DATA_32: u8 = 0x20;
+    pub const SERVICE_DATA_128: u8 = 0x21;
+    pub const URI: u8 = 0x24;
+    pub const MANUFACTURER_DATA: u8 = 0xff;
+}
+
+bitflags! {
+    /// The Flags AD type, bluez reports it as the `AdvertisingFlags` of a
+    /// device
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct AdvFlags: u8 {
+        const LE_LIMITED_DISCOVERABLE = 1 << 0;
+        const LE_GENERAL_DISCOVERABLE = 1 << 1;
+        const BR_EDR_NOT_SUPPORTED = 1 << 2;
+        const LE_BR_EDR_CONTROLLER = 1 << 3;
+        const LE_BR_EDR_HOST = 1 << 4;
+    }
+}
+
+impl AdvFlags {
+    /// Decode the flags octet of an AD structure payload, reserved bits are
+    /// kept
+    pub fn from_bytes(data: &[u8]) -> Self {
+        data.first()
+            .map_or_else(Self::empty, |flags| Self::from_bits_retain(*flags))
+    }
+
+    /// Either of the discoverable modes is set
+    pub fn is_discoverable(&self) -> bool {
+        self.intersects(Self::LE_LIMITED_DISCOVERABLE | Self::LE_GENERAL_DISCOVERABLE)
+    }
+}
+
+/// A single decoded AD structure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdStructure {
+    Flags(AdvFlags),
+    /// Service class UUIDs, `complete` is false when the device has more
+    /// services than listed
+    ServiceUuids {
+        complete: bool,
+        uuids: Vec<Uuid>,
+    },
+    ShortenedLocalName(String),
+    CompleteLocalName(String),
+    /// Transmit power in dBm
+    TxPower(i8),
+    ClassOfDevice(u32),
+    SolicitUuids(Vec<Uuid>),
+    ServiceData(Uuid, Vec<u8>),
+    Appearance(u16),
+    Uri(String),
+    /// Data keyed by company identifier
+    ManufacturerData(u16, Vec<u8>),
+    /// Any type without a decoder
+    Unknown(u8, Vec<u8>),
+}
+
+impl AdStructure {
+    /// Decode the payload `data` of an AD structure of type `ad_type`
+    pub fn parse(ad_type: u8, data: &[u8]) -> Result<Self, zbus::Error> {
+        let res = match ad_type {
+            ad_type::FLAGS => AdStructure::Flags(AdvFlags::from_bytes(&fixed::<1>(ad_type, data)?)),
+            ad_type::INCOMPLETE_UUIDS_16
+            | ad_type::COMPLETE_UUIDS_16
+            | ad_type::INCOMPLETE_UUIDS_32
+            | ad_type::COMPLETE_UUIDS_32
+            | ad_type::INCOMPLETE_UUIDS_128
+            | ad_type::COMPLETE_UUIDS_128 => AdStructure::ServiceUuids {
+                complete: ad_type % 2 == 1,
+                uuids: uuid_list(ad_type, data)?,
+            },
+            ad_type::SHORTENED_LOCAL_NAME => {
+                AdStructure::ShortenedLocalName(String::from_utf8_lossy(data).into_owned())
+            }
+            ad_type::COMPLETE_LOCAL_NAME => {
+                AdStructure::CompleteLocalName(String::from_utf8_lossy(data).into_owned())
+            }
+            ad_type::TX_POWER_LEVEL => {
+                AdStructure::TxPower(i8::from_le_bytes(fixed::<1>(ad_type, data)?))
+            }
+            ad_type::CLASS_OF_DEVICE => {
+                let [a, b, c] = fixed::<3>(ad_type, data)?;
+                AdStructure::ClassOfDevice(u32::from_le_bytes([
+                    a, b, c, 0,
+                ]))
+            }
+            ad_type::SOLICIT_UUIDS_16 | ad_type::SOLICIT_UUIDS_32 | ad_type::SOLICIT_UUIDS_128 => {
+                AdStructure::SolicitUuids(uuid_list(ad_type, data)?)
+            }
+            ad_type::SERVICE_DATA_16 | ad_type::SERVICE_DATA_32 | ad_type::SERVICE_DATA_128 => {
+                let len = uuid_len(ad_type);
+                if data.len() < len {
+                    return Err(too_short(ad_type, data));
+                }
+                let (uuid, data) = data.split_at(len);
+                AdStructure::ServiceData(uuid_from_le(uuid), data.to_vec())
+            }
+            ad_type::APPEARANCE => {
+                AdStructure::Appearance(u16::from_le_bytes(fixed::<2>(ad_type, data)?))
+            }
+            ad_type::URI => AdStructure::Uri(String::from_utf8_lossy(data).into_owned()),
+            ad_type::MANUFACTURER_DATA => {
+                if data.len() < 2 {
+                    return Err(too_short(ad_type, data));
+                }
+                let (company, data) = data.split_at(2);
+                AdStructure::ManufacturerData(
+                    u16::from_le_bytes([
+                        company[0], company[1],
+                    ]),
+                    data.to_vec(),
+                )
+            }
+            _ => AdStructure::Unknown(ad_type, data.to_vec()),
+        };
+        Ok(res)
+    }
+}
+
+/// Decode a raw advertising or scan response payload, a sequence of
+/// length-type-data AD structures
+pub fn parse_ad_structures(mut data: &[u8]) -> Result<Vec<AdStructure>, zbus::Error> {
+    let mut structures = Vec::new();
+    while let Some((&len, rest)) = data.split_first() {
+        let len = len as usize;
+        // Zero length marks the start of the padding
+        if len == 0 {
+            break;
+        }
+        if rest.len() < len {
+            return Err(zbus::Error::Failure(format!(
+                "AD structure of length {len} overruns the {} remaining bytes",
+                rest.len()
+            )));
+        }
+        let (field, next) = rest.split_at(len);
+        structures.push(AdStructure::parse(field[0], &field[1..])?);
+        data = next;
+    }
+    Ok(structures)
+}
+
+/// Decode the per-type map of `Device1::AdvertisingData`, or the `Data` map
+/// of an `LEAdvertisement1`
+pub fn parse_advertising_data(
+    data: &HashMap<u8, OwnedValue>,
+) -> Result<Vec<AdStructure>, zbus::Error> {
+    let mut types: Vec<&u8> = data.keys().collect();
+    types.sort();
+    types
+        .into_iter()
+        .map(|ad_type| {
+            let bytes = Vec::<u8>::try_from(data[ad_type].try_clone()?)?;
+            AdStructure::parse(*ad_type, &bytes)
+        })
+        .collect()
+}
+
+fn too_short(ad_type: u8, data: &[u8]) -> zbus::Error {
+    zbus::Error::Failure(format!(
+        "AD type {ad_type:#04x}: {} bytes is too short",
+        data.len()
+    ))
+}
+
+fn fixed<const N: usize>(ad_type: u8, data: &[u8]) -> Result<[u8; N], zbus::Error> {
+    data.get(..N)
+        .and_then(|data| data.try_into().ok())
+        .ok_or_else(|| too_short(ad_type, data))
+}
+
+fn uuid_len(ad_type: u8) -> usize {
+    match ad_type {
+        ad_type::INCOMPLETE_UUIDS_16
+        | ad_type::COMPLETE_UUIDS_16
+        | ad_type::SOLICIT_UUIDS_16
+        | ad_type::SERVICE_DATA_16 => 2,
+        ad_type::INCOMPLETE_UUIDS_32
+        | ad_type::COMPLETE_UUIDS_32
+        | ad_type::SOLICIT_UUIDS_32
+        | ad_type::SERVICE_DATA_32 => 4,
+        _ => 16,
+    }
+}
+
+/// UUIDs are sent little endian, 16 and 32-bit ones relative to the
+/// Bluetooth base UUID
+fn uuid_from_le(bytes: &[u8]) -> Uuid {
+    match *bytes {
+        [a, b] => uuid_from_u16(u16::from_le_bytes([a, b])),
+        [a, b, c, d] => uuid_from_u32(u32::from_le_bytes([
+            a, b, c, d,
+        ])),
+        _ => {
+            let mut be = [0; 16];
+            be.copy_from_slice(bytes);
+            be.reverse();
+            Uuid::from_bytes(be)
+        }
+    }
+}
+
+fn uuid_list(ad_type: u8, data: &[u8]) -> Result<Vec<Uuid>, zbus::Error> {
+    let len = uuid_len(ad_type);
+    if !data.len().is_multiple_of(len) {
+        return Err(zbus::Error::Failure(format!(
+            "AD type {ad_type:#04x}: {} bytes is not a list of {len} byte UUIDs",
+            data.len()
+        )));
+    }
+    Ok(data.chunks_exact(len).map(uuid_from_le).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_sequence() {
+        let data = [
+            0x02, 0x01, 0x06, // Flags
+            0x05, 0x09, b'T', b'e', b's', b't', // Complete Local Name
+            0x02, 0x0a, 0xf4, // TX power -12 dBm
+        ];
+        assert_eq!(
+            parse_ad_structures(&data).unwrap(),
+            [
+                AdStructure::Flags(
+                    AdvFlags::LE_GENERAL_DISCOVERABLE | AdvFlags::BR_EDR_NOT_SUPPORTED
+                ),
+                AdStructure::CompleteLocalName("Test".to_owned()),
+                AdStructure::TxPower(-12),
+            ]
+        );
+    }
+
+    #[test]
+    fn zero_length_ends_the_data() {
+        let data = [0x02, 0x01, 0x06, 0x00, 0x03, 0x09];
+        assert_eq!(
+            parse_ad_structures(&data).unwrap(),
+            [AdStructure::Flags(
+                AdvFlags::LE_GENERAL_DISCOVERABLE | AdvFlags::BR_EDR_NOT_SUPPORTED
+            )]
+        );
+        assert!(parse_ad_structures(&[0x00; 4]).unwrap().is_empty());
+        assert!(parse_ad_structures(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn length_overrunning_the_buffer_is_rejected() {
+        assert!(parse_ad_structures(&[0x05, 0x09, b'a', b'b']).is_err());
+        assert!(parse_ad_structures(&[0x02, 0x01, 0x06, 0x03]).is_err());
+    }
+
+    #[test]
+    fn uuid_lists() {
+        let uuids16 = [0x05, 0x03, 0x0f, 0x18, 0x0d, 0x18];
+        assert_eq!(
+            parse_ad_structures(&uuids16).unwrap(),
+            [AdStructure::ServiceUuids {
+                complete: true,
+                uuids: vec![uuid_from_u16(0x180f), uuid_from_u16(0x180d)],
+            }]
+        );
+
+        let uuids32 = [0x05, 0x04, 0x78, 0x56, 0x34, 0x12];
+        assert_eq!(
+            parse_ad_structures(&uuids32).unwrap(),
+            [AdStructure::ServiceUuids {
+                complete: false,
+                uuids: vec![uuid_from_u32(0x1234_5678)],
+            }]
+        );
+
+        let uuid128 = Uuid::from_u128(0x0011_2233_4455_6677_8899_aabb_ccdd_eeff);
+        let mut uuids128 = vec![0x11, 0x07];
+        uuids128.extend(uuid128.as_bytes().iter().rev());
+        assert_eq!(
+            parse_ad_structures(&uuids128).unwrap(),
+            [AdStructure::ServiceUuids {
+                complete: true,
+                uuids: vec![uuid128],
+            }]
+        );
+    }
+
+    #[test]
+    fn partial_uuid_is_rejected() {
+        assert!(AdStructure::parse(ad_type::COMPLETE_UUIDS_16, &[0x0f, 0x18, 0x0d]).is_err());
+        assert!(AdStructure::parse(ad_type::COMPLETE_UUIDS_128, &[0; 15]).is_err());
+    }
+
+    #[test]
+    fn manufacturer_data() {
+        assert_eq!(
+            AdStructure::parse(ad_type::MANUFACTURER_DATA, &[0x4c, 0x00, 0x02, 0x15]).unwrap(),
+            AdStructure::ManufacturerData(0x004c, vec![0x02, 0x15])
+        );
+        assert_eq!(
+            AdStructure::parse(ad_type::MANUFACTURER_DATA, &[0x4c, 0x00]).unwrap(),
+            AdStructure::ManufacturerData(0x004c, Vec::new())
+        );
+        assert!(AdStructure::parse(ad_type::MANUFACTURER_DATA, &[0x4c]).is_err());
+        assert!(AdStructure::parse(ad_type::MANUFACTURER_DATA, &[]).is_err());
+    }
+
+    #[test]
+    fn service_data() {
+        assert_eq!(
+            AdStructure::parse(ad_type::SERVICE_DATA_16, &[0x0f, 0x18, 0x64]).unwrap(),
+            AdStructure::ServiceData(uuid_from_u16(0x180f), vec![0x64])
+        );
+        assert!(AdStructure::parse(ad_type::SERVICE_DATA_32, &[0x0f, 0x18, 0x00]).is_err());
+    }
+
+    #[test]
+    fn fixed_size_types() {
+        assert!(AdStructure::parse(ad_type::FLAGS, &[]).is_err());
+        assert_eq!(
+            AdStructure::parse(ad_type::CLASS_OF_DEVICE, &[0x0c, 0x02, 0x5a]).unwrap(),
+            AdStructure::ClassOfDevice(0x5a020c)
+        );
+        assert!(AdStructure::parse(ad_type::APPEARANCE, &[0x40]).is_err());
+        assert_eq!(
+            AdStructure::parse(0x2a, &[1, 2]).unwrap(),
+            AdStructure::Unknown(0x2a, vec![1, 2])
+        );
+    }
+}
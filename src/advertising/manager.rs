@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use log::{debug, error};
+use zbus::names::OwnedBusName;
+use zbus::object_server::InterfaceRef;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::Connection;
+
+use super::ADVERTISEMENT_BASE_PATH;
+use crate::bus::BluezBus;
+use crate::interface::LEAdvertisement1;
+use crate::proxy::le_advertising_manager1::LEAdvertisingManager1Proxy;
+use crate::restart::{Registered, Registration};
+
+/// Handle to an advert registered through `AdvertisingManager`
+pub struct AdvertisementHandle {
+    connection: Connection,
+    destination: OwnedBusName,
+    adapter_path: OwnedObjectPath,
+    path: OwnedObjectPath,
+    interface: InterfaceRef<LEAdvertisement1>,
+    registered: Arc<Registered>,
+}
+
+impl AdvertisementHandle {
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// The adapter the advert is registered with
+    pub fn adapter_path(&self) -> &ObjectPath<'_> {
+        &self.adapter_path
+    }
+
+    pub fn zbus(&self) -> &InterfaceRef<LEAdvertisement1> {
+        &self.interface
+    }
+
+    /// For `RestartWatcher::track()`
+    pub fn registration(&self) -> Registration {
+        Registration::new(&self.registered)
+    }
+
+    /// Unregister the advert from bluez and remove it from the object server,
+    /// even if bluez fails to unregister it
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        let res = async {
+            let proxy = LEAdvertisingManager1Proxy::builder(&self.connection)
+                .destination(self.destination.clone())?
+                .path(self.adapter_path.clone())?
+                .build()
+                .await?;
+            proxy.unregister_advertisement(&self.path).await
+        }
+        .await;
+
+        let removed = self
+            .connection
+            .object_server()
+            .remove::<LEAdvertisement1, _>(&self.path)
+            .await;
+        res.and(removed.map(|_| ()))
+    }
+}
+
+/// Registers adverts against the `LEAdvertisingManager1` of one adapter
+pub struct AdvertisingManager {
+    connection: Connection,
+    proxy: LEAdvertisingManager1Proxy<'static>,
+    adapter_path: OwnedObjectPath,
+    base_path: OwnedObjectPath,
+    experimental: bool,
+}
+
+impl AdvertisingManager {
+    /// Create a manager for the adapter at `adapter_path`, e.g.
+    /// `/org/bluez/hci0`
+    pub async fn new(bus: impl Into<BluezBus>, adapter_path: &str) -> Result<Self, zbus::Error> {
+        let bus = bus.into();
+        let connection = bus.connection().clone();
+        let adapter_path = OwnedObjectPath::try_from(adapter_path)?;
+        let proxy = LEAdvertisingManager1Proxy::builder(&connection)
+            .destination(bus.destination().clone())?
+            .path(adapter_path.clone())?
+            .build()
+            .await?;
+        Ok(Self {
+            connection,
+            proxy,
+            adapter_path,
+            base_path: OwnedObjectPath::try_from(ADVERTISEMENT_BASE_PATH)?,
+            experimental: bus.experimental(),
+        })
+    }
+
+    /// Use a different base path for exported adverts than
+    /// `ADVERTISEMENT_BASE_PATH`
+    pub fn with_base_path(mut self, base_path: &str) -> Result<Self, zbus::Error> {
+        self.base_path = OwnedObjectPath::try_from(base_path)?;
+        Ok(self)
+    }
+
+    pub fn proxy(&self) -> &LEAdvertisingManager1Proxy<'static> {
+        &self.proxy
+    }
+
+    /// Export the advert at a free object path and register it with bluez.
+    ///
+    /// Fails early if the adapter has no free advertising instance left.
+    /// Experimental values are left out when `BluezBus::experimental()` is
+    /// `false`.
+    pub async fn register(
+        &self,
+        mut advertisement: LEAdvertisement1,
+    ) -> Result<AdvertisementHandle, zbus::Error> {
+        // SupportedInstances counts the instances still free, not the total
+        let active = self.proxy.active_instances().await?;
+        let free = self.proxy.supported_instances().await?;
+        debug!("AdvertisingManager: {active} instances active, {free} free");
+        if free == 0 {
+            return Err(zbus::Error::Failure(format!(
+                "{}: no free advertising instances ({active} active)",
+                self.adapter_path.as_str()
+            )));
+        }
+
+        if !self.experimental {
+            advertisement.clear_experimental();
+        }
+        let path = self.free_path().await?;
+        self.connection
+            .object_server()
+            .at(&path, advertisement)
+            .await
+            .map_err(|err| {
+                error!("{}: add_to_server {}", path.as_str(), err);
+                err
+            })?;
+        let interface = self
+            .connection
+            .object_server()
+            .interface::<_, LEAdvertisement1>(&path)
+            .await?;
+
+        if let Err(err) = self
+            .proxy
+            .register_advertisement(&path, HashMap::default())
+            .await
+        {
+            self.connection
+                .object_server()
+                .remove::<LEAdvertisement1, _>(&path)
+                .await
+                .ok();
+            return Err(err);
+        }
+
+        Ok(AdvertisementHandle {
+            connection: self.connection.clone(),
+            destination: self.proxy.inner().destination().to_owned().into(),
+            adapter_path: self.adapter_path.clone(),
+            registered: Arc::new(Registered::Advertisement {
+                adapter_path: self.adapter_path.clone(),
+                path: path.clone(),
+            }),
+            path,
+            interface,
+        })
+    }
+
+    /// Find the first `{base_path}/advertisementN` not already in use
+    async fn free_path(&self) -> Result<OwnedObjectPath, zbus::Error> {
+        let mut count = 0;
+        loop {
+            let path = OwnedObjectPath::try_from(format!(
+                "{}/advertisement{count}",
+                self.base_path.as_str()
+            ))?;
+            if self
+                .connection
+                .object_server()
+                .interface::<_, LEAdvertisement1>(&path)
+                .await
+                .is_err()
+            {
+                return Ok(path);
+            }
+            count += 1;
+        }
+    }
+}
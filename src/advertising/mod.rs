@@ -0,0 +1,22 @@
+//! # Advertising helpers
+//!
+//! Wraps `LEAdvertisingManager1` so that an `LEAdvertisement1` can be exported
+//! and registered with bluez in a single call. The returned
+//! `AdvertisementHandle` is used to unregister the advert again.
+//!
+//! `parse_ad_structures()` and `parse_advertising_data()` decode the raw
+//! advertising data seen while scanning.
+
+mod data;
+pub use data::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod manager;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use manager::*;
+
+#[cfg(feature = "blocking-api")]
+pub mod blocking;
+
+/// Default object path under which adverts are exported
+pub const ADVERTISEMENT_BASE_PATH: &str = "/org/bluez_zbus/advertisement";
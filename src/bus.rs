@@ -0,0 +1,96 @@
+//! # Bus and destination of the bluez daemon
+//!
+//! The registration helpers take a `BluezBus`, or anything converting into
+//! one such as a `Connection`. By default they talk to `org.bluez` over that
+//! connection, `with_destination()` points them at another bus name, e.g. a
+//! mock service on a session or peer-to-peer bus in tests.
+//!
+//! With the `experimental` feature the helpers send the experimental bluez
+//! properties too. If bluetoothd may run without `--experimental`, probe it
+//! with `BluezCapabilities::probe()` and pass the result to
+//! `with_experimental()`.
+
+use zbus::names::{BusName, OwnedBusName, WellKnownName};
+use zbus::Connection;
+
+/// The well-known name of bluetoothd on the system bus
+pub const BLUEZ_DESTINATION: &str = "org.bluez";
+
+/// A connection and the bus name bluez calls are sent to
+#[derive(Debug, Clone)]
+pub struct BluezBus {
+    connection: Connection,
+    destination: OwnedBusName,
+    experimental: bool,
+}
+
+impl BluezBus {
+    pub fn new(connection: Connection) -> Self {
+        Self {
+            connection,
+            destination: BusName::from(WellKnownName::from_static_str_unchecked(BLUEZ_DESTINATION))
+                .into(),
+            experimental: true,
+        }
+    }
+
+    /// Send calls to `destination` instead of `org.bluez`
+    pub fn with_destination(mut self, destination: &str) -> Result<Self, zbus::Error> {
+        self.destination = OwnedBusName::try_from(destination)?;
+        Ok(self)
+    }
+
+    /// Whether bluez runs with `--experimental`. When it doesn't, adverts and
+    /// GATT applications registered through this bus leave out the
+    /// experimental values bluez would reject.
+    pub fn with_experimental(mut self, experimental: bool) -> Self {
+        self.experimental = experimental;
+        self
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn destination(&self) -> &OwnedBusName {
+        &self.destination
+    }
+
+    /// Experimental values are sent, always `false` without the
+    /// `experimental` feature
+    pub fn experimental(&self) -> bool {
+        cfg!(feature = "experimental") && self.experimental
+    }
+}
+
+impl From<Connection> for BluezBus {
+    fn from(connection: Connection) -> Self {
+        Self::new(connection)
+    }
+}
+
+impl From<&Connection> for BluezBus {
+    fn from(connection: &Connection) -> Self {
+        Self::new(connection.clone())
+    }
+}
+
+impl From<&BluezBus> for BluezBus {
+    fn from(bus: &BluezBus) -> Self {
+        bus.clone()
+    }
+}
+
+#[cfg(feature = "blocking-api")]
+impl From<zbus::blocking::Connection> for BluezBus {
+    fn from(connection: zbus::blocking::Connection) -> Self {
+        Self::new(connection.into_inner())
+    }
+}
+
+#[cfg(feature = "blocking-api")]
+impl From<&zbus::blocking::Connection> for BluezBus {
+    fn from(connection: &zbus::blocking::Connection) -> Self {
+        Self::new(connection.inner().clone())
+    }
+}
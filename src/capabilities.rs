@@ -0,0 +1,273 @@
+//! # Runtime detection of bluez features
+//!
+//! The `experimental` feature compiles in the experimental bluez API, but
+//! bluetoothd only serves it when started with `--experimental`, and bluez
+//! hides experimental properties from introspection otherwise.
+//! `BluezCapabilities::probe()` introspects an adapter to find out what the
+//! running daemon supports. Passing `experimental()` to
+//! `BluezBus::with_experimental()` makes the registration helpers using that
+//! bus drop experimental values such as `LEAdvertisement1.Data` or a GATT
+//! `Handle` when bluez would reject them.
+//!
+//! `CapabilityReport::probe()` adds the daemon version and what the
+//! controller can do, for logging or picking a code path.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+use zbus::fdo::PropertiesProxy;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+use zbus::names::InterfaceName;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+use zbus::zvariant::OwnedValue;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+use crate::adapter::AdapterRoles;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+use crate::bus::BluezBus;
+
+/// Properties bluez only shows when running with `--experimental`
+pub const EXPERIMENTAL_PROPERTIES: &[(&str, &str)] = &[
+    ("org.bluez.Adapter1", "PowerState"),
+    ("org.bluez.LEAdvertisingManager1", "SupportedFeatures"),
+    ("org.bluez.LEAdvertisingManager1", "SupportedCapabilities"),
+];
+
+/// The interfaces and properties an adapter of the running daemon exports
+#[derive(Debug, Clone, Default)]
+pub struct BluezCapabilities {
+    interfaces: HashMap<String, BTreeSet<String>>,
+}
+
+impl BluezCapabilities {
+    /// Introspect the adapter at `adapter`, e.g. `/org/bluez/hci0`
+    #[cfg(any(feature = "async-io", feature = "tokio"))]
+    pub async fn probe(bus: impl Into<BluezBus>, adapter: &str) -> Result<Self, zbus::Error> {
+        let bus = bus.into();
+        let xml = zbus::fdo::IntrospectableProxy::builder(bus.connection())
+            .destination(bus.destination().clone())?
+            .path(adapter)?
+            .build()
+            .await?
+            .introspect()
+            .await?;
+        let capabilities = Self::from_introspection(&xml)?;
+        if !capabilities.has_interface("org.bluez.Adapter1") {
+            return Err(zbus::Error::Failure(format!("{adapter}: not an adapter")));
+        }
+        Ok(capabilities)
+    }
+
+    /// Read the interfaces and their properties from introspection XML.
+    /// Interfaces of child nodes are skipped.
+    pub fn from_introspection(xml: &str) -> Result<Self, zbus::Error> {
+        let node = zbus_xml::Node::try_from(xml)
+            .map_err(|e| zbus::Error::Failure(format!("invalid introspection XML: {e}")))?;
+        let interfaces = node
+            .interfaces()
+            .iter()
+            .map(|interface| {
+                let properties = interface
+                    .properties()
+                    .iter()
+                    .map(|property| property.name().to_string())
+                    .collect();
+                (interface.name().to_string(), properties)
+            })
+            .collect();
+        Ok(Self { interfaces })
+    }
+
+    pub fn has_interface(&self, interface: &str) -> bool {
+        self.interfaces.contains_key(interface)
+    }
+
+    pub fn has_property(&self, interface: &str, property: &str) -> bool {
+        self.interfaces
+            .get(interface)
+            .is_some_and(|properties| properties.contains(property))
+    }
+
+    /// Properties of `interface`, empty if the adapter does not export it
+    pub fn properties(&self, interface: &str) -> impl Iterator<Item = &str> {
+        self.interfaces
+            .get(interface)
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+    }
+
+    /// bluez runs with `--experimental`, so it takes the experimental advert
+    /// properties (`Data`, `Discoverable`, intervals, `TxPower`) and GATT
+    /// handles
+    pub fn experimental(&self) -> bool {
+        EXPERIMENTAL_PROPERTIES
+            .iter()
+            .any(|(interface, property)| self.has_property(interface, property))
+    }
+
+    /// The adapter has `PowerState`
+    pub fn power_state(&self) -> bool {
+        self.has_property("org.bluez.Adapter1", "PowerState")
+    }
+
+    /// The adapter can register GATT applications
+    pub fn gatt_server(&self) -> bool {
+        self.has_interface("org.bluez.GattManager1")
+    }
+
+    /// The adapter can register adverts
+    pub fn advertising(&self) -> bool {
+        self.has_interface("org.bluez.LEAdvertisingManager1")
+    }
+}
+
+/// Version of bluetoothd
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BluezVersion {
+    pub major: u8,
+    pub minor: u8,
+}
+
+impl BluezVersion {
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+
+    /// Read the version from an adapter `Modalias` as set by bluetoothd,
+    /// `usb:v1D6Bp0246d0548` for 5.72. `None` if the modalias was set by the
+    /// device id in `main.conf` instead.
+    pub fn from_modalias(modalias: &str) -> Option<Self> {
+        let device = modalias
+            .strip_prefix("usb:v1D6Bp0246d")
+            .or_else(|| modalias.strip_prefix("usb:v1d6bp0246d"))?;
+        let device = u16::from_str_radix(device.get(..4)?, 16).ok()?;
+        Some(Self::new((device >> 8) as u8, device as u8))
+    }
+}
+
+impl fmt::Display for BluezVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// What the daemon and one adapter support
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapabilityReport {
+    /// `None` if the `Modalias` does not carry the bluez version
+    pub version: Option<BluezVersion>,
+    /// bluez runs with `--experimental`
+    pub experimental: bool,
+    /// The adapter has the peripheral role, so GATT applications and adverts
+    /// can be registered
+    pub le_peripheral: bool,
+    /// The controller advertises on secondary channels or with more than
+    /// 31 bytes of data
+    pub extended_advertising: bool,
+    /// Number of adverts that can be registered at once
+    pub advertising_instances: u8,
+    /// Advertisement monitors are offloaded to the controller
+    pub adv_monitor_offload: bool,
+}
+
+impl CapabilityReport {
+    /// Probe the adapter at `adapter` like `BluezCapabilities::probe()` and
+    /// read the properties the report is built from. Missing properties count
+    /// as unsupported.
+    #[cfg(any(feature = "async-io", feature = "tokio"))]
+    pub async fn probe(bus: impl Into<BluezBus>, adapter: &str) -> Result<Self, zbus::Error> {
+        let bus = bus.into();
+        let capabilities = BluezCapabilities::probe(bus.clone(), adapter).await?;
+        let proxy = PropertiesProxy::builder(bus.connection())
+            .destination(bus.destination().clone())?
+            .path(adapter)?
+            .build()
+            .await?;
+
+        let adapter1 = get_all(&proxy, "org.bluez.Adapter1").await;
+        let version = adapter1
+            .get("Modalias")
+            .and_then(|value| <&str>::try_from(value).ok())
+            .and_then(BluezVersion::from_modalias);
+        let le_peripheral = adapter1
+            .get("Roles")
+            .and_then(|value| AdapterRoles::try_from(value.try_clone().ok()?).ok())
+            .is_some_and(|roles| roles.supports_peripheral());
+
+        let advertising = if capabilities.advertising() {
+            get_all(&proxy, "org.bluez.LEAdvertisingManager1").await
+        } else {
+            HashMap::new()
+        };
+        let advertising_instances = advertising
+            .get("SupportedInstances")
+            .and_then(|value| u8::try_from(value).ok())
+            .unwrap_or_default();
+        let secondary_channels = advertising
+            .get("SupportedSecondaryChannels")
+            .and_then(|value| <Vec<String>>::try_from(value.try_clone().ok()?).ok())
+            .is_some_and(|channels| !channels.is_empty());
+        let max_adv_len = advertising
+            .get("SupportedCapabilities")
+            .and_then(|value| <HashMap<String, OwnedValue>>::try_from(value.try_clone().ok()?).ok())
+            .and_then(|caps| u8::try_from(caps.get("MaxAdvLen")?).ok())
+            .unwrap_or_default();
+
+        let adv_monitor_offload =
+            if capabilities.has_interface("org.bluez.AdvertisementMonitorManager1") {
+                get_all(&proxy, "org.bluez.AdvertisementMonitorManager1")
+                    .await
+                    .get("SupportedFeatures")
+                    .and_then(|value| <Vec<String>>::try_from(value.try_clone().ok()?).ok())
+                    .is_some_and(|features| features.iter().any(|f| f == "controller-patterns"))
+            } else {
+                false
+            };
+
+        Ok(Self {
+            version,
+            experimental: capabilities.experimental(),
+            le_peripheral,
+            extended_advertising: secondary_channels || max_adv_len > 31,
+            advertising_instances,
+            adv_monitor_offload,
+        })
+    }
+}
+
+impl fmt::Display for CapabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.version {
+            Some(version) => write!(f, "bluez {version}")?,
+            None => write!(f, "bluez (unknown version)")?,
+        }
+        let yes_no = |supported| if supported { "yes" } else { "no" };
+        write!(
+            f,
+            ", experimental: {}, LE peripheral: {}, extended advertising: {}, \
+             advertising instances: {}, monitor offload: {}",
+            yes_no(self.experimental),
+            yes_no(self.le_peripheral),
+            yes_no(self.extended_advertising),
+            self.advertising_instances,
+            yes_no(self.adv_monitor_offload),
+        )
+    }
+}
+
+/// All properties of `interface`, empty if they can not be read
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+async fn get_all(
+    proxy: &PropertiesProxy<'_>,
+    interface: &'static str,
+) -> HashMap<String, OwnedValue> {
+    proxy
+        .get_all(InterfaceName::from_static_str_unchecked(interface))
+        .await
+        .unwrap_or_else(|e| {
+            log::debug!("{interface}: GetAll failed: {e}");
+            HashMap::new()
+        })
+}
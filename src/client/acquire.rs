@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use std::io;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_lite::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use zbus::zvariant::ObjectPath;
+
+use super::{command_chunk, Pacer, WriteOptions, WritePacing, WriteType, DEFAULT_MTU};
+use crate::bus::BluezBus;
+use crate::proxy::gatt_characteristic1::GattCharacteristic1Proxy;
+use crate::rt::{sleep, AsyncStream};
+
+/// Reader over the socket returned by `AcquireNotify` on a remote
+/// characteristic.
+///
+/// The socket is packet based, so each read returns at most one notification.
+/// Reads return `0` once bluez or the remote device closes the socket.
+pub struct NotifyReader {
+    stream: AsyncStream,
+    mtu: u16,
+}
+
+impl NotifyReader {
+    /// The MTU negotiated for the notification socket
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    /// Receive the next notification. An empty value means the socket closed.
+    pub async fn recv(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; self.mtu as usize];
+        let len = self.stream.read(&mut buf).await?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+}
+
+impl AsyncRead for NotifyReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+/// Call `AcquireNotify` on the remote characteristic at `path`
+pub async fn acquire_notify(
+    bus: impl Into<BluezBus>,
+    path: &ObjectPath<'_>,
+) -> Result<NotifyReader, zbus::Error> {
+    let bus = bus.into();
+    let proxy = GattCharacteristic1Proxy::builder(bus.connection())
+        .destination(bus.destination().clone())?
+        .path(path.to_owned())?
+        .build()
+        .await?;
+    let (fd, mtu) = proxy.acquire_notify(HashMap::default()).await?;
+    let stream = AsyncStream::new(UnixStream::from(OwnedFd::from(fd)))?;
+    Ok(NotifyReader { stream, mtu })
+}
+
+/// Writer over the socket returned by `AcquireWrite` on a remote
+/// characteristic.
+///
+/// Every write sends at most one MTU sized packet, which bluez forwards as a
+/// single write-without-response. Larger buffers are split across writes.
+pub struct AcquiredWriter {
+    stream: AsyncStream,
+    mtu: u16,
+}
+
+impl AcquiredWriter {
+    /// The MTU negotiated for the write socket
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    /// Send all of `value`, chunked to the MTU
+    pub async fn send(&mut self, value: &[u8]) -> io::Result<()> {
+        for chunk in value.chunks(self.mtu.max(1) as usize) {
+            self.stream.write_all(chunk).await?;
+        }
+        Ok(())
+    }
+}
+
+impl AsyncWrite for AcquiredWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let len = buf.len().min(self.mtu.max(1) as usize);
+        Pin::new(&mut self.stream).poll_write(cx, &buf[..len])
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_close(cx)
+    }
+}
+
+/// Call `AcquireWrite` on the remote characteristic at `path`
+pub async fn acquire_write(
+    bus: impl Into<BluezBus>,
+    path: &ObjectPath<'_>,
+) -> Result<AcquiredWriter, zbus::Error> {
+    let bus = bus.into();
+    let proxy = GattCharacteristic1Proxy::builder(bus.connection())
+        .destination(bus.destination().clone())?
+        .path(path.to_owned())?
+        .build()
+        .await?;
+    let (fd, mtu) = proxy.acquire_write(HashMap::default()).await?;
+    let stream = AsyncStream::new(UnixStream::from(OwnedFd::from(fd)))?;
+    Ok(AcquiredWriter { stream, mtu })
+}
+
+enum PacedSink {
+    Acquired(AcquiredWriter),
+    Command(GattCharacteristic1Proxy<'static>),
+}
+
+/// Stream of write-without-response packets to a remote characteristic,
+/// either over the `AcquireWrite` socket or as `WriteValue` calls of type
+/// `command`. Values are split into MTU sized packets, sent no faster than
+/// the `WritePacing` allows.
+pub struct PacedWriter {
+    sink: PacedSink,
+    payload: usize,
+    pacer: Pacer,
+}
+
+impl PacedWriter {
+    /// Pace the packets of an `AcquireWrite` socket
+    pub fn acquired(writer: AcquiredWriter, pacing: WritePacing) -> Self {
+        Self {
+            payload: writer.mtu.max(1) as usize,
+            sink: PacedSink::Acquired(writer),
+            pacer: Pacer::new(pacing),
+        }
+    }
+
+    /// Pace `WriteValue` calls of type `command` on `proxy`, sized to its
+    /// `MTU`
+    pub async fn command(proxy: GattCharacteristic1Proxy<'static>, pacing: WritePacing) -> Self {
+        let mtu = proxy.mtu().await.unwrap_or(DEFAULT_MTU);
+        Self {
+            payload: command_chunk(mtu),
+            sink: PacedSink::Command(proxy),
+            pacer: Pacer::new(pacing),
+        }
+    }
+
+    /// Largest value sent in one packet
+    pub fn payload(&self) -> usize {
+        self.payload
+    }
+
+    /// The writes go over an `AcquireWrite` socket
+    pub fn is_acquired(&self) -> bool {
+        matches!(self.sink, PacedSink::Acquired(_))
+    }
+
+    /// Send all of `value`, waiting whenever the budget of the current
+    /// interval is used up
+    pub async fn send(&mut self, value: &[u8]) -> Result<(), zbus::Error> {
+        let options = WriteOptions {
+            type_: Some(WriteType::Command),
+            ..Default::default()
+        };
+        for chunk in value.chunks(self.payload) {
+            if let Some(delay) = self.pacer.next_packet() {
+                sleep(delay).await;
+            }
+            match &mut self.sink {
+                PacedSink::Acquired(writer) => writer.stream.write_all(chunk).await?,
+                PacedSink::Command(proxy) => proxy.write_value(chunk, options.to_map()).await?,
+            }
+        }
+        Ok(())
+    }
+}
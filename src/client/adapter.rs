@@ -0,0 +1,264 @@
+use std::time::Duration;
+
+use log::warn;
+use zbus::fdo::ObjectManagerProxy;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::Connection;
+
+use super::{is_child, with_retry, with_timeout, DiscoveredDevice, RetryPolicy};
+use crate::address::BDAddr;
+use crate::bus::BluezBus;
+use crate::proxy::adapter1::Adapter1Proxy;
+use crate::proxy::object_manager::BluezDevice;
+use crate::trace;
+
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+
+/// A device of an adapter, by object path or by address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceId {
+    Path(OwnedObjectPath),
+    Address(BDAddr),
+}
+
+impl DeviceId {
+    /// The object path of the device below `adapter`
+    pub fn path(&self, adapter: &ObjectPath<'_>) -> OwnedObjectPath {
+        match self {
+            DeviceId::Path(path) => path.clone(),
+            DeviceId::Address(address) => address.device_path(adapter),
+        }
+    }
+}
+
+impl From<OwnedObjectPath> for DeviceId {
+    fn from(path: OwnedObjectPath) -> Self {
+        DeviceId::Path(path)
+    }
+}
+
+impl From<&ObjectPath<'_>> for DeviceId {
+    fn from(path: &ObjectPath<'_>) -> Self {
+        DeviceId::Path(path.to_owned().into())
+    }
+}
+
+impl From<BDAddr> for DeviceId {
+    fn from(address: BDAddr) -> Self {
+        DeviceId::Address(address)
+    }
+}
+
+/// What `default_adapter_with()` does when no adapter is powered
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AdapterFallback {
+    /// Use the first adapter as is
+    #[default]
+    FirstAdapter,
+    /// Power on the first adapter
+    PowerOn,
+    /// Fail
+    Error,
+}
+
+/// High level wrapper around a local `org.bluez.Adapter1`
+pub struct Adapter {
+    bus: BluezBus,
+    proxy: Adapter1Proxy<'static>,
+    retry: Option<RetryPolicy>,
+    timeout: Option<Duration>,
+}
+
+impl Adapter {
+    pub async fn new(
+        bus: impl Into<BluezBus>,
+        path: &ObjectPath<'_>,
+    ) -> Result<Self, zbus::Error> {
+        let bus = bus.into();
+        let proxy = Adapter1Proxy::builder(bus.connection())
+            .destination(bus.destination().clone())?
+            .path(path.to_owned())?
+            .build()
+            .await?;
+        Ok(Self {
+            bus,
+            proxy,
+            retry: None,
+            timeout: None,
+        })
+    }
+
+    /// Retry powering and starting discovery while bluez reports the
+    /// operation as in progress
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    pub(crate) fn retry_policy(&self) -> Option<&RetryPolicy> {
+        self.retry.as_ref()
+    }
+
+    /// Fail powering and starting discovery after `timeout` instead of
+    /// waiting for bluez to give up
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        self.proxy.inner().path()
+    }
+
+    pub fn connection(&self) -> &Connection {
+        self.bus.connection()
+    }
+
+    /// The connection and bluez bus name the adapter was created with
+    pub fn bus(&self) -> &BluezBus {
+        &self.bus
+    }
+
+    pub fn proxy(&self) -> &Adapter1Proxy<'static> {
+        &self.proxy
+    }
+
+    pub async fn is_powered(&self) -> Result<bool, zbus::Error> {
+        self.proxy.powered().await
+    }
+
+    pub async fn set_powered(&self, powered: bool) -> Result<(), zbus::Error> {
+        with_timeout(
+            self.timeout,
+            format!("{}: set powered", self.path()),
+            with_retry(self.retry.as_ref(), || {
+                trace::call(
+                    self.proxy.inner(),
+                    "Set(Powered)",
+                    self.proxy.set_powered(powered),
+                )
+            }),
+        )
+        .await
+    }
+
+    /// The devices of this adapter bluez knows about, discovered or paired
+    pub async fn devices(&self) -> Result<Vec<DiscoveredDevice>, zbus::Error> {
+        let objects = ObjectManagerProxy::builder(self.bus.connection())
+            .destination(self.bus.destination().clone())?
+            .path("/")?
+            .build()
+            .await?
+            .get_managed_objects()
+            .await?;
+        Ok(objects
+            .into_iter()
+            .filter(|(path, _)| is_child(self.path(), path))
+            .filter_map(|(path, interfaces)| {
+                let data = BluezDevice::from(interfaces.get(DEVICE_INTERFACE)?);
+                Some(DiscoveredDevice::new(path, data))
+            })
+            .collect())
+    }
+
+    /// Remove a device and its pairing from bluez. It is disconnected first
+    /// if connected.
+    pub async fn remove_device(&self, device: impl Into<DeviceId>) -> Result<(), zbus::Error> {
+        let path = device.into().path(self.path());
+        with_timeout(
+            self.timeout,
+            format!("{}: remove {path}", self.path()),
+            with_retry(self.retry.as_ref(), || {
+                trace::call(
+                    self.proxy.inner(),
+                    "RemoveDevice",
+                    self.proxy.remove_device(&path),
+                )
+            }),
+        )
+        .await
+    }
+
+    /// Remove every device matching `predicate` and return their paths.
+    /// Failures are logged and skipped, e.g. a device bluez dropped in the
+    /// meantime.
+    pub async fn forget_matching(
+        &self,
+        predicate: impl Fn(&DiscoveredDevice) -> bool,
+    ) -> Result<Vec<OwnedObjectPath>, zbus::Error> {
+        let mut removed = Vec::new();
+        for device in self.devices().await?.iter().filter(|d| predicate(d)) {
+            let path = OwnedObjectPath::from(device.path().to_owned());
+            match self.remove_device(path.clone()).await {
+                Ok(()) => removed.push(path),
+                Err(err) => warn!("{path}: remove {err}"),
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove the devices that are neither paired nor connected, e.g. those
+    /// left over from discovery
+    pub async fn forget_unpaired(&self) -> Result<Vec<OwnedObjectPath>, zbus::Error> {
+        self.forget_matching(|device| !device.data().paired() && !device.data().connected())
+            .await
+    }
+}
+
+/// The first powered adapter, or the first adapter if none are powered
+pub async fn default_adapter(bus: impl Into<BluezBus>) -> Result<Adapter, zbus::Error> {
+    default_adapter_with(bus, AdapterFallback::default()).await
+}
+
+/// The first powered adapter, using `fallback` if none are powered. Adapters
+/// are ordered by path, so `hci0` comes before `hci1`.
+pub async fn default_adapter_with(
+    bus: impl Into<BluezBus>,
+    fallback: AdapterFallback,
+) -> Result<Adapter, zbus::Error> {
+    let bus = bus.into();
+    let objects = ObjectManagerProxy::builder(bus.connection())
+        .destination(bus.destination().clone())?
+        .path("/")?
+        .build()
+        .await?
+        .get_managed_objects()
+        .await?;
+    let mut adapters: Vec<(&OwnedObjectPath, bool)> = objects
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            let props = interfaces.get(ADAPTER_INTERFACE)?;
+            let powered = props
+                .get("Powered")
+                .and_then(|powered| bool::try_from(powered).ok())
+                .unwrap_or_default();
+            Some((path, powered))
+        })
+        .collect();
+    adapters.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+    if let Some((path, _)) = adapters.iter().find(|(_, powered)| *powered) {
+        return Adapter::new(bus, path).await;
+    }
+    let Some((path, _)) = adapters.first() else {
+        return Err(zbus::Error::Failure(
+            "No bluetooth adapter found".to_owned(),
+        ));
+    };
+    match fallback {
+        AdapterFallback::FirstAdapter => Adapter::new(bus, path).await,
+        AdapterFallback::PowerOn => {
+            let adapter = Adapter::new(bus, path).await?;
+            adapter.set_powered(true).await?;
+            Ok(adapter)
+        }
+        AdapterFallback::Error => Err(zbus::Error::Failure(
+            "No powered bluetooth adapter found".to_owned(),
+        )),
+    }
+}
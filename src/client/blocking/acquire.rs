@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+
+use zbus::blocking::Connection;
+use zbus::zvariant::ObjectPath;
+
+use crate::bus::BluezBus;
+use crate::client::{command_chunk, Pacer, WriteOptions, WritePacing, WriteType, DEFAULT_MTU};
+use crate::proxy::gatt_characteristic1::GattCharacteristic1ProxyBlocking;
+
+/// Reader over the socket returned by `AcquireNotify` on a remote
+/// characteristic.
+///
+/// The socket is packet based, so each read returns at most one notification.
+/// Reads return `0` once bluez or the remote device closes the socket.
+pub struct NotifyReader {
+    stream: UnixStream,
+    mtu: u16,
+}
+
+impl NotifyReader {
+    /// The MTU negotiated for the notification socket
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    /// Receive the next notification. An empty value means the socket closed.
+    pub fn recv(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; self.mtu as usize];
+        let len = self.stream.read(&mut buf)?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+}
+
+impl Read for NotifyReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+/// Call `AcquireNotify` on the remote characteristic at `path`
+pub fn acquire_notify(
+    bus: impl Into<BluezBus>,
+    path: &ObjectPath<'_>,
+) -> Result<NotifyReader, zbus::Error> {
+    let bus = bus.into();
+    let connection = Connection::from(bus.connection().clone());
+    let proxy = GattCharacteristic1ProxyBlocking::builder(&connection)
+        .destination(bus.destination().clone())?
+        .path(path.to_owned())?
+        .build()?;
+    let (fd, mtu) = proxy.acquire_notify(HashMap::default())?;
+    let stream = UnixStream::from(OwnedFd::from(fd));
+    Ok(NotifyReader { stream, mtu })
+}
+
+/// Writer over the socket returned by `AcquireWrite` on a remote
+/// characteristic.
+///
+/// Every write sends at most one MTU sized packet, which bluez forwards as a
+/// single write-without-response. Larger buffers are split across writes.
+pub struct AcquiredWriter {
+    stream: UnixStream,
+    mtu: u16,
+}
+
+impl AcquiredWriter {
+    /// The MTU negotiated for the write socket
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    /// Send all of `value`, chunked to the MTU
+    pub fn send(&mut self, value: &[u8]) -> io::Result<()> {
+        for chunk in value.chunks(self.mtu.max(1) as usize) {
+            self.stream.write_all(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for AcquiredWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.mtu.max(1) as usize);
+        self.stream.write(&buf[..len])
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Call `AcquireWrite` on the remote characteristic at `path`
+pub fn acquire_write(
+    bus: impl Into<BluezBus>,
+    path: &ObjectPath<'_>,
+) -> Result<AcquiredWriter, zbus::Error> {
+    let bus = bus.into();
+    let connection = Connection::from(bus.connection().clone());
+    let proxy = GattCharacteristic1ProxyBlocking::builder(&connection)
+        .destination(bus.destination().clone())?
+        .path(path.to_owned())?
+        .build()?;
+    let (fd, mtu) = proxy.acquire_write(HashMap::default())?;
+    let stream = UnixStream::from(OwnedFd::from(fd));
+    Ok(AcquiredWriter { stream, mtu })
+}
+
+enum PacedSink {
+    Acquired(AcquiredWriter),
+    Command(GattCharacteristic1ProxyBlocking<'static>),
+}
+
+/// Stream of write-without-response packets to a remote characteristic,
+/// either over the `AcquireWrite` socket or as `WriteValue` calls of type
+/// `command`. Values are split into MTU sized packets, sent no faster than
+/// the `WritePacing` allows.
+pub struct PacedWriter {
+    sink: PacedSink,
+    payload: usize,
+    pacer: Pacer,
+}
+
+impl PacedWriter {
+    /// Pace the packets of an `AcquireWrite` socket
+    pub fn acquired(writer: AcquiredWriter, pacing: WritePacing) -> Self {
+        Self {
+            payload: writer.mtu.max(1) as usize,
+            sink: PacedSink::Acquired(writer),
+            pacer: Pacer::new(pacing),
+        }
+    }
+
+    /// Pace `WriteValue` calls of type `command` on `proxy`, sized to its
+    /// `MTU`
+    pub fn command(proxy: GattCharacteristic1ProxyBlocking<'static>, pacing: WritePacing) -> Self {
+        let mtu = proxy.mtu().unwrap_or(DEFAULT_MTU);
+        Self {
+            payload: command_chunk(mtu),
+            sink: PacedSink::Command(proxy),
+            pacer: Pacer::new(pacing),
+        }
+    }
+
+    /// Largest value sent in one packet
+    pub fn payload(&self) -> usize {
+        self.payload
+    }
+
+    /// The writes go over an `AcquireWrite` socket
+    pub fn is_acquired(&self) -> bool {
+        matches!(self.sink, PacedSink::Acquired(_))
+    }
+
+    /// Send all of `value`, waiting whenever the budget of the current
+    /// interval is used up
+    pub fn send(&mut self, value: &[u8]) -> Result<(), zbus::Error> {
+        let options = WriteOptions {
+            type_: Some(WriteType::Command),
+            ..Default::default()
+        };
+        for chunk in value.chunks(self.payload) {
+            if let Some(delay) = self.pacer.next_packet() {
+                std::thread::sleep(delay);
+            }
+            match &mut self.sink {
+                PacedSink::Acquired(writer) => writer.stream.write_all(chunk)?,
+                PacedSink::Command(proxy) => proxy.write_value(chunk, options.to_map())?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+
+use uuid::Uuid;
+use zbus::blocking::fdo::ObjectManagerProxy;
+use zbus::blocking::Connection;
+use zbus::zvariant::ObjectPath;
+
+use crate::bus::BluezBus;
+use crate::client::{gatt_tree, RemoteService};
+use crate::proxy::device1::Device1ProxyBlocking;
+
+/// Wait for `ServicesResolved` on the device at `device_path`, then return its
+/// GATT database keyed by service UUID
+pub fn discover_gatt(
+    bus: impl Into<BluezBus>,
+    device_path: &ObjectPath<'_>,
+) -> Result<BTreeMap<Uuid, RemoteService>, zbus::Error> {
+    let bus = bus.into();
+    let connection = Connection::from(bus.connection().clone());
+    let device = Device1ProxyBlocking::builder(&connection)
+        .destination(bus.destination().clone())?
+        .path(device_path.to_owned())?
+        .build()?;
+    let mut resolved = device.receive_services_resolved_changed();
+    while !device.services_resolved()? {
+        match resolved.next() {
+            Some(changed) if changed.get()? => break,
+            Some(_) => continue,
+            None => {
+                return Err(zbus::Error::Failure(format!(
+                    "{device_path}: device removed before services resolved"
+                )))
+            }
+        }
+    }
+
+    let objects = ObjectManagerProxy::builder(&connection)
+        .destination(bus.destination().clone())?
+        .path("/")?
+        .build()?
+        .get_managed_objects()?;
+    Ok(gatt_tree(&objects, device_path))
+}
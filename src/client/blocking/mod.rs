@@ -0,0 +1,11 @@
+mod acquire;
+pub use acquire::*;
+
+mod discover;
+pub use discover::*;
+
+mod long;
+pub use long::*;
+
+mod notify;
+pub use notify::*;
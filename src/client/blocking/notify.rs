@@ -0,0 +1,67 @@
+use log::warn;
+use zbus::blocking::fdo::{PropertiesChangedIterator, PropertiesProxy};
+use zbus::blocking::Connection;
+use zbus::zvariant::ObjectPath;
+
+use crate::bus::BluezBus;
+use crate::client::changed_value;
+use crate::proxy::gatt_characteristic1::GattCharacteristic1ProxyBlocking;
+
+/// Iterator over values notified by a remote characteristic.
+///
+/// Created by `subscribe()`. Dropping the iterator calls `StopNotify`.
+pub struct Notifications {
+    proxy: GattCharacteristic1ProxyBlocking<'static>,
+    changed: PropertiesChangedIterator,
+}
+
+impl Notifications {
+    pub fn proxy(&self) -> &GattCharacteristic1ProxyBlocking<'static> {
+        &self.proxy
+    }
+}
+
+impl Iterator for Notifications {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for signal in self.changed.by_ref() {
+            let Ok(args) = signal.args() else {
+                continue;
+            };
+            if let Some(value) = changed_value(&args.interface_name, &args.changed_properties) {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+impl Drop for Notifications {
+    fn drop(&mut self) {
+        if let Err(err) = self.proxy.stop_notify() {
+            warn!("{}: StopNotify {}", self.proxy.inner().path(), err);
+        }
+    }
+}
+
+/// Start notifications on the remote characteristic at `path` and iterate the
+/// values as they arrive.
+pub fn subscribe(
+    bus: impl Into<BluezBus>,
+    path: &ObjectPath<'_>,
+) -> Result<Notifications, zbus::Error> {
+    let bus = bus.into();
+    let connection = Connection::from(bus.connection().clone());
+    let proxy = GattCharacteristic1ProxyBlocking::builder(&connection)
+        .destination(bus.destination().clone())?
+        .path(path.to_owned())?
+        .build()?;
+    let changed = PropertiesProxy::builder(&connection)
+        .destination(bus.destination().clone())?
+        .path(path.to_owned())?
+        .build()?
+        .receive_properties_changed()?;
+    proxy.start_notify()?;
+    Ok(Notifications { proxy, changed })
+}
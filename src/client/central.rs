@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{ready, Context, Poll};
+use std::time::{Duration, Instant};
+
+use async_lock::Semaphore;
+use futures_lite::Stream;
+use log::warn;
+use uuid::Uuid;
+use zbus::fdo::{InterfacesAddedStream, ObjectManagerProxy, PropertiesChanged};
+use zbus::message::Type as MessageType;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+use zbus::{MatchRule, MessageStream};
+
+use super::discovery::is_child;
+use super::{
+    changed_value, with_retry, with_timeout, Adapter, Device, DeviceMatcher, DiscoveredDevice,
+};
+use crate::adapter::DiscoveryFilter;
+use crate::proxy::object_manager::BluezDevice;
+use crate::trace;
+
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+
+/// Something that happened on one of the adapter's devices
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum CentralEvent {
+    /// A new device was found while scanning
+    DeviceDiscovered(DiscoveredDevice),
+    Connected(OwnedObjectPath),
+    Disconnected(OwnedObjectPath),
+    /// The remote GATT database of the device has been discovered
+    ServicesResolved(OwnedObjectPath),
+    /// A subscribed characteristic of `device` notified a new value
+    Notification {
+        device: OwnedObjectPath,
+        characteristic: OwnedObjectPath,
+        value: Vec<u8>,
+    },
+}
+
+/// Manages scanning and the connections to any number of remote devices on
+/// one adapter, the central-role counterpart of `Peripheral`.
+///
+/// Connected devices are kept as shared `Device`s, each caching its own GATT
+/// database. Events for all of them are received from `events()`.
+pub struct Central {
+    adapter: Adapter,
+    devices: Mutex<HashMap<OwnedObjectPath, Arc<Device>>>,
+    /// Bounds the `Connect` and `Pair` calls in flight
+    link_limit: Option<Semaphore>,
+    /// Passed to `Device::with_auto_trust()`
+    auto_trust: Option<bool>,
+}
+
+impl Central {
+    pub fn new(adapter: Adapter) -> Self {
+        Self {
+            adapter,
+            devices: Mutex::new(HashMap::new()),
+            link_limit: None,
+            auto_trust: None,
+        }
+    }
+
+    /// Run at most `limit` connects or pairings at once, further calls wait
+    /// for one to finish. Controllers tend to fail when asked to open many
+    /// links at the same time.
+    pub fn with_link_limit(mut self, limit: usize) -> Self {
+        self.link_limit = Some(Semaphore::new(limit.max(1)));
+        self
+    }
+
+    /// Trust devices once `pair()` succeeds, see `Device::with_auto_trust()`
+    pub fn with_auto_trust(mut self, wake_allowed: bool) -> Self {
+        self.auto_trust = Some(wake_allowed);
+        self
+    }
+
+    /// Run `fut` once the link limit allows
+    async fn limited<T>(
+        &self,
+        fut: impl Future<Output = Result<T, zbus::Error>>,
+    ) -> Result<T, zbus::Error> {
+        let _permit = match &self.link_limit {
+            Some(limit) => Some(limit.acquire().await),
+            None => None,
+        };
+        fut.await
+    }
+
+    pub fn adapter(&self) -> &Adapter {
+        &self.adapter
+    }
+
+    /// Start discovery with `filter`, new devices are reported as
+    /// `CentralEvent::DeviceDiscovered`
+    pub async fn start_scan(&self, filter: DiscoveryFilter) -> Result<(), zbus::Error> {
+        let proxy = self.adapter.proxy();
+        proxy.set_discovery_filter(filter.to_map()).await?;
+        with_timeout(
+            self.adapter.timeout(),
+            format!("{}: start discovery", self.adapter.path()),
+            with_retry(self.adapter.retry_policy(), || {
+                trace::call(proxy.inner(), "StartDiscovery", proxy.start_discovery())
+            }),
+        )
+        .await
+    }
+
+    pub async fn stop_scan(&self) -> Result<(), zbus::Error> {
+        self.adapter.proxy().stop_discovery().await
+    }
+
+    /// The shared `Device` for `path`, created with the adapter's retry
+    /// policy and timeout, and the auto-trust setting, on first use
+    pub async fn device(&self, path: &ObjectPath<'_>) -> Result<Arc<Device>, zbus::Error> {
+        let key = OwnedObjectPath::from(path.to_owned());
+        if let Some(device) = self.devices.lock().ok().and_then(|d| d.get(&key).cloned()) {
+            return Ok(device);
+        }
+        let mut device = Device::new(self.adapter.bus(), path).await?;
+        if let Some(policy) = self.adapter.retry_policy() {
+            device = device.with_retry_policy(*policy);
+        }
+        if let Some(timeout) = self.adapter.timeout() {
+            device = device.with_timeout(timeout);
+        }
+        if let Some(wake_allowed) = self.auto_trust {
+            device = device.with_auto_trust(wake_allowed);
+        }
+        let device = Arc::new(device);
+        if let Ok(mut devices) = self.devices.lock() {
+            return Ok(devices.entry(key).or_insert(device).clone());
+        }
+        Ok(device)
+    }
+
+    /// Connect to the device at `path`
+    pub async fn connect(&self, path: &ObjectPath<'_>) -> Result<Arc<Device>, zbus::Error> {
+        let device = self.device(path).await?;
+        self.limited(device.connect()).await?;
+        Ok(device)
+    }
+
+    /// Pair with the device at `path`, with whichever agent is registered
+    pub async fn pair(&self, path: &ObjectPath<'_>) -> Result<Arc<Device>, zbus::Error> {
+        let device = self.device(path).await?;
+        self.limited(device.pair()).await?;
+        Ok(device)
+    }
+
+    /// Discover the first device advertising `uuid`, connect to it and wait
+    /// for its services to resolve, all within `timeout`. Discovery stops
+    /// once the device is found.
+    pub async fn connect_first_advertising(
+        &self,
+        uuid: Uuid,
+        timeout: Duration,
+    ) -> Result<Arc<Device>, zbus::Error> {
+        let deadline = Instant::now() + timeout;
+        let found = self
+            .adapter
+            .wait_for_device(DeviceMatcher::ServiceUuid(uuid), timeout)
+            .await?;
+        let device = self.device(found.path()).await?;
+        self.limited(
+            device.connect_with_timeout(Some(deadline.saturating_duration_since(Instant::now()))),
+        )
+        .await?;
+        device
+            .wait_services_resolved(Some(deadline.saturating_duration_since(Instant::now())))
+            .await?;
+        Ok(device)
+    }
+
+    /// Disconnect the device at `path` and forget its cached GATT database.
+    /// Fails for a path this `Central` holds no `Device` for.
+    pub async fn disconnect(&self, path: &ObjectPath<'_>) -> Result<(), zbus::Error> {
+        let key = OwnedObjectPath::from(path.to_owned());
+        let Some(device) = self.devices.lock().ok().and_then(|d| d.get(&key).cloned()) else {
+            return Err(zbus::Error::Failure(format!("{path}: not a device of this central")));
+        };
+        device.disconnect().await?;
+        if let Ok(mut devices) = self.devices.lock() {
+            devices.remove(&key);
+        }
+        Ok(())
+    }
+
+    /// The devices connected through this `Central`
+    pub fn devices(&self) -> Vec<Arc<Device>> {
+        self.devices
+            .lock()
+            .map(|devices| devices.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Stream the events of every device on the adapter
+    pub async fn events(&self) -> Result<CentralEvents, zbus::Error> {
+        let bus = self.adapter.bus();
+        let added = ObjectManagerProxy::builder(bus.connection())
+            .destination(bus.destination().clone())?
+            .path("/")?
+            .build()
+            .await?
+            .receive_interfaces_added()
+            .await?;
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(bus.destination().as_str())?
+            .interface("org.freedesktop.DBus.Properties")?
+            .member("PropertiesChanged")?
+            .path_namespace(self.adapter.path().to_owned())?
+            .build();
+        let changed = MessageStream::for_match_rule(rule, bus.connection(), None).await?;
+        Ok(CentralEvents {
+            adapter: self.adapter.path().to_owned().into(),
+            added,
+            changed,
+            pending: Vec::new(),
+        })
+    }
+}
+
+/// Stream of `CentralEvent`s, created by `Central::events()`
+pub struct CentralEvents {
+    adapter: OwnedObjectPath,
+    added: InterfacesAddedStream,
+    changed: MessageStream,
+    /// A single `PropertiesChanged` can carry more than one event
+    pending: Vec<CentralEvent>,
+}
+
+/// The device a GATT object path belongs to, e.g.
+/// `/org/bluez/hci0/dev_XX/service000a/char000b` to `/org/bluez/hci0/dev_XX`
+fn device_of(path: &ObjectPath<'_>) -> Option<OwnedObjectPath> {
+    let path = path.as_str();
+    let start = path.find("/dev_")? + 1;
+    let end = path[start..].find('/').map_or(path.len(), |i| start + i);
+    OwnedObjectPath::try_from(&path[..end]).ok()
+}
+
+fn device_events(path: &OwnedObjectPath, changed: &HashMap<&str, OwnedValue>) -> Vec<CentralEvent> {
+    let flag = |name| {
+        changed
+            .get(name)
+            .and_then(|value| bool::try_from(value).ok())
+    };
+    let mut events = Vec::new();
+    match flag("Connected") {
+        Some(true) => events.push(CentralEvent::Connected(path.clone())),
+        Some(false) => events.push(CentralEvent::Disconnected(path.clone())),
+        None => {}
+    }
+    if flag("ServicesResolved") == Some(true) {
+        events.push(CentralEvent::ServicesResolved(path.clone()));
+    }
+    events
+}
+
+impl CentralEvents {
+    fn poll_added(&mut self, cx: &mut Context<'_>) -> Poll<Option<CentralEvent>> {
+        while let Poll::Ready(signal) = Pin::new(&mut self.added).poll_next(cx) {
+            let Some(signal) = signal else {
+                return Poll::Ready(None);
+            };
+            let Ok(args) = signal.args() else {
+                continue;
+            };
+            if !is_child(&self.adapter, &args.object_path) {
+                continue;
+            }
+            let Some(properties) = args.interfaces_and_properties.get(DEVICE_INTERFACE) else {
+                continue;
+            };
+            let properties: HashMap<String, OwnedValue> = properties
+                .iter()
+                .filter_map(|(key, value)| Some((key.to_string(), value.try_to_owned().ok()?)))
+                .collect();
+            return Poll::Ready(Some(CentralEvent::DeviceDiscovered(DiscoveredDevice::new(
+                args.object_path.to_owned().into(),
+                BluezDevice::from(&properties),
+            ))));
+        }
+        Poll::Pending
+    }
+
+    fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<Option<CentralEvent>> {
+        loop {
+            let Some(msg) = ready!(Pin::new(&mut self.changed).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(err) => {
+                    warn!("CentralEvents: {err}");
+                    continue;
+                }
+            };
+            let Some(signal) = PropertiesChanged::from_message(msg) else {
+                continue;
+            };
+            let header = signal.message().header();
+            let (Some(path), Ok(args)) = (header.path(), signal.args()) else {
+                continue;
+            };
+            if args.interface_name == DEVICE_INTERFACE {
+                let changed: HashMap<&str, OwnedValue> = args
+                    .changed_properties
+                    .iter()
+                    .filter_map(|(key, value)| Some((*key, value.try_to_owned().ok()?)))
+                    .collect();
+                self.pending = device_events(&path.to_owned().into(), &changed);
+                self.pending.reverse();
+                if let Some(event) = self.pending.pop() {
+                    return Poll::Ready(Some(event));
+                }
+            } else if let Some(value) =
+                changed_value(&args.interface_name, &args.changed_properties)
+                && let Some(device) = device_of(path)
+            {
+                return Poll::Ready(Some(CentralEvent::Notification {
+                    device,
+                    characteristic: path.to_owned().into(),
+                    value,
+                }));
+            }
+        }
+    }
+}
+
+impl Stream for CentralEvents {
+    type Item = CentralEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(event) = self.pending.pop() {
+            return Poll::Ready(Some(event));
+        }
+        if let Poll::Ready(event) = self.poll_added(cx) {
+            return Poll::Ready(event);
+        }
+        self.poll_changed(cx)
+    }
+}
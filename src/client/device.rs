@@ -0,0 +1,540 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures_lite::{future, StreamExt};
+use log::{debug, warn};
+use uuid::Uuid;
+use zbus::proxy::PropertyStream;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::Connection;
+
+use super::{
+    acquire_write, discover_gatt, read_long, with_retry, with_timeout, with_timeout_abort,
+    write_long, PacedWriter, ReadOptions, RemoteService, RetryPolicy, WriteOptions, WritePacing,
+};
+use crate::advertising::AdvFlags;
+use crate::bus::BluezBus;
+use crate::interface::{Agent1, AgentCapability, AgentHandler};
+use crate::proxy::device1::Device1Proxy;
+use crate::proxy::gatt_characteristic1::GattCharacteristic1Proxy;
+use crate::proxy::gatt_descriptor1::GattDescriptor1Proxy;
+use crate::proxy::object_manager::manufacturer_data;
+use crate::rt::sleep;
+use crate::trace;
+
+/// Numbers the temporary agents of `Device::pair_with_agent()`, so pairings
+/// running at once never share an object path
+static PAIRING_AGENTS: AtomicUsize = AtomicUsize::new(0);
+
+/// High level wrapper around a remote `org.bluez.Device1`.
+///
+/// The GATT database is discovered on first use and cached, call
+/// `refresh_gatt()` if the remote services change. Reads and writes of
+/// characteristics and descriptors are sent one at a time, also when called
+/// from several tasks at once.
+pub struct Device {
+    bus: BluezBus,
+    proxy: Device1Proxy<'static>,
+    gatt: Mutex<Option<Arc<BTreeMap<Uuid, RemoteService>>>>,
+    retry: Option<RetryPolicy>,
+    timeout: Option<Duration>,
+    /// `Some(wake_allowed)` to trust the device after `pair()`
+    auto_trust: Option<bool>,
+    /// Serializes GATT requests, bluez fails overlapping ATT requests with
+    /// `InProgress`
+    gatt_queue: async_lock::Mutex<()>,
+}
+
+impl Device {
+    pub async fn new(
+        bus: impl Into<BluezBus>,
+        path: &ObjectPath<'_>,
+    ) -> Result<Self, zbus::Error> {
+        let bus = bus.into();
+        let proxy = Device1Proxy::builder(bus.connection())
+            .destination(bus.destination().clone())?
+            .path(path.to_owned())?
+            .build()
+            .await?;
+        Ok(Self {
+            bus,
+            proxy,
+            gatt: Mutex::new(None),
+            retry: None,
+            timeout: None,
+            auto_trust: None,
+            gatt_queue: async_lock::Mutex::new(()),
+        })
+    }
+
+    /// Retry pairing and characteristic access while bluez reports the
+    /// operation as in progress
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Fail connecting, pairing and characteristic access after `timeout`
+    /// instead of waiting for bluez to give up
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Mark the device `Trusted` once `pair()` succeeds, so it can reconnect
+    /// without authorization. With `wake_allowed` it may also wake the host.
+    /// `pair()` fails if either can't be set, the device stays paired.
+    pub fn with_auto_trust(mut self, wake_allowed: bool) -> Self {
+        self.auto_trust = Some(wake_allowed);
+        self
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        self.proxy.inner().path()
+    }
+
+    pub fn connection(&self) -> &Connection {
+        self.bus.connection()
+    }
+
+    /// The connection and bluez bus name the device was created with
+    pub fn bus(&self) -> &BluezBus {
+        &self.bus
+    }
+
+    pub fn proxy(&self) -> &Device1Proxy<'static> {
+        &self.proxy
+    }
+
+    /// Manufacturer specific advertising data keyed by company identifier
+    pub async fn manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, zbus::Error> {
+        manufacturer_data(self.proxy.manufacturer_data().await?)
+    }
+
+    /// The flags of the last advert seen from the device
+    pub async fn advertising_flags(&self) -> Result<AdvFlags, zbus::Error> {
+        Ok(AdvFlags::from_bytes(&self.proxy.advertising_flags().await?))
+    }
+
+    /// Service advertising data keyed by service UUID
+    pub async fn service_data(&self) -> Result<HashMap<Uuid, Vec<u8>>, zbus::Error> {
+        self.proxy
+            .service_data()
+            .await?
+            .into_iter()
+            .map(|(uuid, data)| {
+                let uuid = Uuid::parse_str(&uuid).map_err(|e| {
+                    zbus::Error::Failure(format!(
+                        "{}: invalid service UUID {uuid}: {e}",
+                        self.path()
+                    ))
+                })?;
+                Ok((uuid, Vec::<u8>::try_from(data)?))
+            })
+            .collect()
+    }
+
+    fn cached_gatt(&self) -> Option<Arc<BTreeMap<Uuid, RemoteService>>> {
+        self.gatt
+            .lock()
+            .map(|gatt| gatt.clone())
+            .unwrap_or_default()
+    }
+
+    /// The remote GATT database, discovered once services are resolved
+    pub async fn gatt(&self) -> Result<Arc<BTreeMap<Uuid, RemoteService>>, zbus::Error> {
+        if let Some(gatt) = self.cached_gatt() {
+            return Ok(gatt);
+        }
+        self.refresh_gatt().await
+    }
+
+    /// Discard the cached GATT database and discover it again
+    pub async fn refresh_gatt(&self) -> Result<Arc<BTreeMap<Uuid, RemoteService>>, zbus::Error> {
+        let gatt = Arc::new(discover_gatt(&self.bus, self.path()).await?);
+        if let Ok(mut cached) = self.gatt.lock() {
+            *cached = Some(gatt.clone());
+        }
+        Ok(gatt)
+    }
+
+    /// Resolve the object path of a characteristic from the GATT database
+    pub async fn characteristic_path(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+    ) -> Result<OwnedObjectPath, zbus::Error> {
+        self.gatt()
+            .await?
+            .get(&service_uuid)
+            .and_then(|service| service.characteristics().get(&char_uuid))
+            .map(|char| char.path().to_owned().into())
+            .ok_or_else(|| {
+                zbus::Error::Failure(format!(
+                    "{}: no characteristic {char_uuid} in service {service_uuid}",
+                    self.path()
+                ))
+            })
+    }
+
+    async fn characteristic(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+    ) -> Result<GattCharacteristic1Proxy<'static>, zbus::Error> {
+        GattCharacteristic1Proxy::builder(self.bus.connection())
+            .destination(self.bus.destination().clone())?
+            .path(self.characteristic_path(service_uuid, char_uuid).await?)?
+            .build()
+            .await
+    }
+
+    async fn descriptor(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+        desc_uuid: Uuid,
+    ) -> Result<GattDescriptor1Proxy<'static>, zbus::Error> {
+        let path = self
+            .gatt()
+            .await?
+            .get(&service_uuid)
+            .and_then(|service| service.characteristics().get(&char_uuid))
+            .and_then(|char| char.descriptors().get(&desc_uuid))
+            .map(|desc| desc.path().to_owned())
+            .ok_or_else(|| {
+                zbus::Error::Failure(format!(
+                    "{}: no descriptor {desc_uuid} in characteristic {char_uuid}",
+                    self.path()
+                ))
+            })?;
+        GattDescriptor1Proxy::builder(self.bus.connection())
+            .destination(self.bus.destination().clone())?
+            .path(path)?
+            .build()
+            .await
+    }
+
+    /// Run a GATT request once the earlier ones to this device are done,
+    /// failing after the default timeout. The wait in the queue counts
+    /// towards the timeout.
+    async fn queued<T>(
+        &self,
+        what: String,
+        fut: impl Future<Output = Result<T, zbus::Error>>,
+    ) -> Result<T, zbus::Error> {
+        with_timeout(self.timeout, what, async {
+            let _queue = self.gatt_queue.lock().await;
+            fut.await
+        })
+        .await
+    }
+
+    /// Read the value of a remote characteristic
+    pub async fn read_characteristic(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+        options: ReadOptions,
+    ) -> Result<Vec<u8>, zbus::Error> {
+        let char = self.characteristic(service_uuid, char_uuid).await?;
+        self.queued(
+            format!("{}: read {char_uuid}", self.path()),
+            with_retry(self.retry.as_ref(), || {
+                trace::call(char.inner(), "ReadValue", char.read_value(options.to_map()))
+            }),
+        )
+        .await
+    }
+
+    /// Write the value of a remote characteristic
+    pub async fn write_characteristic(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+        value: &[u8],
+        options: WriteOptions,
+    ) -> Result<(), zbus::Error> {
+        let char = self.characteristic(service_uuid, char_uuid).await?;
+        self.queued(
+            format!("{}: write {char_uuid}", self.path()),
+            with_retry(self.retry.as_ref(), || {
+                trace::call(
+                    char.inner(),
+                    "WriteValue",
+                    char.write_value(value, options.to_map()),
+                )
+            }),
+        )
+        .await
+    }
+
+    /// Read a remote characteristic value longer than the MTU
+    pub async fn read_characteristic_long(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+    ) -> Result<Vec<u8>, zbus::Error> {
+        let char = self.characteristic(service_uuid, char_uuid).await?;
+        self.queued(
+            format!("{}: read {char_uuid}", self.path()),
+            with_retry(self.retry.as_ref(), || read_long(&char)),
+        )
+        .await
+    }
+
+    /// Write a remote characteristic value longer than the MTU
+    pub async fn write_characteristic_long(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+        value: &[u8],
+        options: WriteOptions,
+    ) -> Result<(), zbus::Error> {
+        let char = self.characteristic(service_uuid, char_uuid).await?;
+        self.queued(
+            format!("{}: write {char_uuid}", self.path()),
+            with_retry(self.retry.as_ref(), || write_long(&char, value, options)),
+        )
+        .await
+    }
+
+    /// Read the value of a remote descriptor
+    pub async fn read_descriptor(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+        desc_uuid: Uuid,
+    ) -> Result<Vec<u8>, zbus::Error> {
+        let desc = self.descriptor(service_uuid, char_uuid, desc_uuid).await?;
+        self.queued(
+            format!("{}: read {desc_uuid}", self.path()),
+            with_retry(self.retry.as_ref(), || {
+                trace::call(desc.inner(), "ReadValue", desc.read_value(HashMap::new()))
+            }),
+        )
+        .await
+    }
+
+    /// Write the value of a remote descriptor
+    pub async fn write_descriptor(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+        desc_uuid: Uuid,
+        value: &[u8],
+    ) -> Result<(), zbus::Error> {
+        let desc = self.descriptor(service_uuid, char_uuid, desc_uuid).await?;
+        self.queued(
+            format!("{}: write {desc_uuid}", self.path()),
+            with_retry(self.retry.as_ref(), || {
+                trace::call(
+                    desc.inner(),
+                    "WriteValue",
+                    desc.write_value(value, HashMap::new()),
+                )
+            }),
+        )
+        .await
+    }
+
+    /// Stream write-without-response packets to a remote characteristic,
+    /// over `AcquireWrite` if bluez allows it and as `WriteValue` commands
+    /// otherwise. Not serialized with the other GATT operations, the packets
+    /// are not answered.
+    pub async fn paced_writer(
+        &self,
+        service_uuid: Uuid,
+        char_uuid: Uuid,
+        pacing: WritePacing,
+    ) -> Result<PacedWriter, zbus::Error> {
+        let path = self.characteristic_path(service_uuid, char_uuid).await?;
+        match acquire_write(&self.bus, &path).await {
+            Ok(writer) => Ok(PacedWriter::acquired(writer, pacing)),
+            Err(e) => {
+                debug!("{path}: AcquireWrite failed, writing commands: {e}");
+                let char = self.characteristic(service_uuid, char_uuid).await?;
+                Ok(PacedWriter::command(char, pacing).await)
+            }
+        }
+    }
+
+    /// Connect using the default timeout
+    pub async fn connect(&self) -> Result<(), zbus::Error> {
+        self.connect_with_timeout(self.timeout).await
+    }
+
+    /// Connect, giving up after `timeout` instead of the default. A timed out
+    /// attempt is aborted with `Disconnect`.
+    pub async fn connect_with_timeout(&self, timeout: Option<Duration>) -> Result<(), zbus::Error> {
+        with_timeout_abort(
+            timeout,
+            format!("{}: connect", self.path()),
+            with_retry(self.retry.as_ref(), || {
+                trace::call(self.proxy.inner(), "Connect", self.proxy.connect())
+            }),
+            || async {
+                self.proxy.disconnect().await.ok();
+            },
+        )
+        .await
+    }
+
+    pub async fn disconnect(&self) -> Result<(), zbus::Error> {
+        with_timeout(
+            self.timeout,
+            format!("{}: disconnect", self.path()),
+            trace::call(self.proxy.inner(), "Disconnect", self.proxy.disconnect()),
+        )
+        .await
+    }
+
+    /// Wait until the device is connected, for at most `timeout`
+    pub async fn wait_connected(&self, timeout: Option<Duration>) -> Result<(), zbus::Error> {
+        let changes = self.proxy.receive_connected_changed().await;
+        let connected = self.proxy.connected().await?;
+        self.wait_for("connected", timeout, changes, connected, true)
+            .await
+    }
+
+    /// Wait until the device is disconnected, for at most `timeout`
+    pub async fn wait_disconnected(&self, timeout: Option<Duration>) -> Result<(), zbus::Error> {
+        let changes = self.proxy.receive_connected_changed().await;
+        let connected = self.proxy.connected().await?;
+        self.wait_for("disconnected", timeout, changes, connected, false)
+            .await
+    }
+
+    /// Wait until bluez has resolved the device's services, for at most
+    /// `timeout`
+    pub async fn wait_services_resolved(
+        &self,
+        timeout: Option<Duration>,
+    ) -> Result<(), zbus::Error> {
+        let changes = self.proxy.receive_services_resolved_changed().await;
+        let resolved = self.proxy.services_resolved().await?;
+        self.wait_for("services resolved", timeout, changes, resolved, true)
+            .await
+    }
+
+    /// Wait for a boolean property to become `wanted`. The stream is
+    /// subscribed before `current` is read so no change is missed.
+    async fn wait_for(
+        &self,
+        what: &str,
+        timeout: Option<Duration>,
+        mut changes: PropertyStream<'static, bool>,
+        current: bool,
+        wanted: bool,
+    ) -> Result<(), zbus::Error> {
+        if current == wanted {
+            return Ok(());
+        }
+        with_timeout(
+            timeout,
+            format!("{}: wait for {what}", self.path()),
+            async {
+                while let Some(changed) = changes.next().await {
+                    if changed.get().await? == wanted {
+                        return Ok(());
+                    }
+                }
+                Err(zbus::Error::Failure(format!(
+                    "{}: property stream ended waiting for {what}",
+                    self.path()
+                )))
+            },
+        )
+        .await
+    }
+
+    /// Pair using the default timeout, with whichever agent is registered
+    pub async fn pair(&self) -> Result<(), zbus::Error> {
+        self.pair_with_timeout(self.timeout).await
+    }
+
+    /// Pair, giving up after `timeout` instead of the default. A timed out
+    /// attempt is aborted with `CancelPairing`.
+    pub async fn pair_with_timeout(&self, timeout: Option<Duration>) -> Result<(), zbus::Error> {
+        with_timeout_abort(
+            timeout,
+            format!("{}: pair", self.path()),
+            with_retry(self.retry.as_ref(), || {
+                trace::call(self.proxy.inner(), "Pair", self.proxy.pair())
+            }),
+            || async {
+                self.proxy.cancel_pairing().await.ok();
+            },
+        )
+        .await?;
+        match self.auto_trust {
+            Some(wake_allowed) => self.trust(wake_allowed).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Set `Trusted`, and `WakeAllowed` if `wake_allowed`
+    async fn trust(&self, wake_allowed: bool) -> Result<(), zbus::Error> {
+        self.proxy.set_trusted(true).await?;
+        if wake_allowed {
+            self.proxy.set_wake_allowed(true).await?;
+        }
+        Ok(())
+    }
+
+    async fn pair_and_trust(&self) -> Result<(), zbus::Error> {
+        let mut paired_changed = self.proxy.receive_paired_changed().await;
+        if !self.proxy.paired().await? {
+            with_retry(self.retry.as_ref(), || {
+                trace::call(self.proxy.inner(), "Pair", self.proxy.pair())
+            })
+            .await?;
+            while !self.proxy.paired().await? {
+                if paired_changed.next().await.is_none() {
+                    return Err(zbus::Error::Failure(format!(
+                        "{}: device removed while pairing",
+                        self.path()
+                    )));
+                }
+            }
+        }
+        self.trust(self.auto_trust.unwrap_or_default()).await
+    }
+
+    /// Pair with the device using a temporary `agent`, then mark it trusted.
+    ///
+    /// The agent is registered with `capability` for the duration of the
+    /// call only. Pairing is canceled if it does not complete within
+    /// `timeout`.
+    pub async fn pair_with_agent<H: AgentHandler>(
+        &self,
+        agent: H,
+        capability: AgentCapability,
+        timeout: Duration,
+    ) -> Result<(), zbus::Error> {
+        let segment = self.path().as_str().rsplit('/').next().unwrap_or_default();
+        let count = PAIRING_AGENTS.fetch_add(1, Ordering::Relaxed);
+        let agent_path = format!("/org/bluez_zbus/agent/pair{count}_{segment}");
+        let handle = Agent1::new(agent)
+            .register(&agent_path, capability, false, &self.bus)
+            .await?;
+
+        let res = future::or(self.pair_and_trust(), async {
+            sleep(timeout).await;
+            self.proxy.cancel_pairing().await.ok();
+            Err(zbus::Error::Failure(format!(
+                "{}: pairing timed out",
+                self.path()
+            )))
+        })
+        .await;
+
+        if let Err(err) = handle.unregister().await {
+            warn!("{agent_path}: unregister agent {err}");
+        }
+        res
+    }
+}
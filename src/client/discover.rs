@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+use futures_lite::StreamExt;
+use uuid::Uuid;
+use zbus::fdo::ObjectManagerProxy;
+use zbus::zvariant::ObjectPath;
+
+use super::{gatt_tree, RemoteService};
+use crate::bus::BluezBus;
+use crate::proxy::device1::Device1Proxy;
+
+/// Wait for `ServicesResolved` on the device at `device_path`, then return its
+/// GATT database keyed by service UUID
+pub async fn discover_gatt(
+    bus: impl Into<BluezBus>,
+    device_path: &ObjectPath<'_>,
+) -> Result<BTreeMap<Uuid, RemoteService>, zbus::Error> {
+    let bus = bus.into();
+    let device = Device1Proxy::builder(bus.connection())
+        .destination(bus.destination().clone())?
+        .path(device_path.to_owned())?
+        .build()
+        .await?;
+    let mut resolved = device.receive_services_resolved_changed().await;
+    while !device.services_resolved().await? {
+        match resolved.next().await {
+            Some(changed) if changed.get().await? => break,
+            Some(_) => continue,
+            None => {
+                return Err(zbus::Error::Failure(format!(
+                    "{device_path}: device removed before services resolved"
+                )))
+            }
+        }
+    }
+
+    let objects = ObjectManagerProxy::builder(bus.connection())
+        .destination(bus.destination().clone())?
+        .path("/")?
+        .build()
+        .await?
+        .get_managed_objects()
+        .await?;
+    Ok(gatt_tree(&objects, device_path))
+}
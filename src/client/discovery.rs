@@ -0,0 +1,378 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::pin::Pin;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_lite::{future, Stream, StreamExt};
+use log::warn;
+use uuid::Uuid;
+use zbus::fdo::{InterfacesAddedStream, ObjectManagerProxy, PropertiesChanged};
+use zbus::message::Type as MessageType;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{MatchRule, MessageStream};
+
+use super::{with_retry, with_timeout, Adapter, Device};
+use crate::adapter::DiscoveryFilter;
+use crate::address::BDAddr;
+use crate::proxy::adapter1::Adapter1Proxy;
+use crate::proxy::device1::Device1Proxy;
+use crate::proxy::object_manager::{service_data_map, uuid_list, BluezDevice};
+use crate::rt::sleep;
+use crate::trace;
+
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+
+/// A device found by a `DiscoverySession`
+#[derive(Debug, Clone)]
+pub struct DiscoveredDevice {
+    path: OwnedObjectPath,
+    data: BluezDevice,
+}
+
+impl DiscoveredDevice {
+    pub(crate) fn new(path: OwnedObjectPath, data: BluezDevice) -> Self {
+        Self { path, data }
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    pub fn data(&self) -> &BluezDevice {
+        &self.data
+    }
+}
+
+/// Selects the device `Adapter::wait_for_device()` waits for
+#[derive(Debug, Clone)]
+pub enum DeviceMatcher {
+    Address(BDAddr),
+    /// The device name starts with this
+    NamePrefix(String),
+    /// The device advertises this service
+    ServiceUuid(Uuid),
+}
+
+impl DeviceMatcher {
+    async fn matches(&self, device: &Device1Proxy<'_>) -> bool {
+        match self {
+            DeviceMatcher::Address(address) => device
+                .address()
+                .await
+                .is_ok_and(|addr| BDAddr::from_str(&addr).is_ok_and(|addr| addr == *address)),
+            DeviceMatcher::NamePrefix(prefix) => device
+                .name()
+                .await
+                .is_ok_and(|name| name.starts_with(prefix.as_str())),
+            DeviceMatcher::ServiceUuid(uuid) => device.uuids().await.is_ok_and(|uuids| {
+                uuids
+                    .iter()
+                    .any(|u| Uuid::parse_str(u).is_ok_and(|u| u == *uuid))
+            }),
+        }
+    }
+
+    /// Whether an update with these values can change the outcome of
+    /// `matches()`
+    fn affected_by(
+        &self,
+        name: Option<&str>,
+        uuids: &[Uuid],
+        service_data: &HashMap<Uuid, Vec<u8>>,
+    ) -> bool {
+        match self {
+            DeviceMatcher::Address(_) => false,
+            DeviceMatcher::NamePrefix(_) => name.is_some(),
+            DeviceMatcher::ServiceUuid(_) => !uuids.is_empty() || !service_data.is_empty(),
+        }
+    }
+
+    /// Discovery filter narrowing the search to matching devices
+    fn filter(&self) -> DiscoveryFilter {
+        match self {
+            DeviceMatcher::Address(address) => DiscoveryFilter {
+                pattern: Some(address.to_string()),
+                ..Default::default()
+            },
+            DeviceMatcher::NamePrefix(prefix) => DiscoveryFilter {
+                pattern: Some(prefix.clone()),
+                ..Default::default()
+            },
+            DeviceMatcher::ServiceUuid(uuid) => DiscoveryFilter {
+                uuids: vec![*uuid],
+                ..Default::default()
+            },
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum DiscoveryEvent {
+    /// A device was seen for the first time in this session
+    DeviceDiscovered(DiscoveredDevice),
+    /// A device was seen again, with the values that changed. `name` is set
+    /// when the name arrived or changed, e.g. from a scan response, `uuids`
+    /// is empty unless the service UUIDs changed.
+    DeviceUpdated {
+        path: OwnedObjectPath,
+        rssi: Option<i16>,
+        name: Option<String>,
+        uuids: Vec<Uuid>,
+        service_data: HashMap<Uuid, Vec<u8>>,
+    },
+}
+
+/// Stream of the devices found while discovering, starting with the devices
+/// bluez already knows about. Each device is discovered once, later
+/// advertisements are reported as updates.
+///
+/// Created by `Adapter::discover()`. Dropping the session stops discovery.
+pub struct DiscoverySession {
+    proxy: Adapter1Proxy<'static>,
+    known: VecDeque<DiscoveredDevice>,
+    seen: HashSet<OwnedObjectPath>,
+    added: InterfacesAddedStream,
+    changed: MessageStream,
+}
+
+impl DiscoverySession {
+    /// Device properties from an `InterfacesAdded` signal, if the object is a
+    /// device of this adapter
+    fn device_added(
+        &self,
+        path: &ObjectPath<'_>,
+        properties: &HashMap<&str, Value<'_>>,
+    ) -> Option<DiscoveredDevice> {
+        if !is_child(self.proxy.inner().path(), path) {
+            return None;
+        }
+        let properties: HashMap<String, OwnedValue> = properties
+            .iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value.try_to_owned().ok()?)))
+            .collect();
+        Some(DiscoveredDevice {
+            path: path.to_owned().into(),
+            data: BluezDevice::from(&properties),
+        })
+    }
+}
+
+/// Build an update from a device's `PropertiesChanged`, if the RSSI, name,
+/// service UUIDs or service data changed
+fn device_updated(
+    path: OwnedObjectPath,
+    changed: &HashMap<&str, Value<'_>>,
+) -> Option<DiscoveryEvent> {
+    let owned = |key| changed.get(key).and_then(|value| value.try_to_owned().ok());
+    let rssi = changed
+        .get("RSSI")
+        .and_then(|rssi| i16::try_from(rssi).ok());
+    let name = changed
+        .get("Name")
+        .and_then(|name| <&str>::try_from(name).ok())
+        .map(str::to_owned);
+    let uuids = uuid_list(owned("UUIDs").as_ref());
+    let service_data = service_data_map(owned("ServiceData").as_ref());
+    if rssi.is_none() && name.is_none() && uuids.is_empty() && service_data.is_empty() {
+        return None;
+    }
+    Some(DiscoveryEvent::DeviceUpdated {
+        path,
+        rssi,
+        name,
+        uuids,
+        service_data,
+    })
+}
+
+pub(super) fn is_child(adapter: &ObjectPath<'_>, device: &ObjectPath<'_>) -> bool {
+    device
+        .as_str()
+        .strip_prefix(adapter.as_str())
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+impl DiscoverySession {
+    fn discovered(&mut self, device: DiscoveredDevice) -> Option<DiscoveryEvent> {
+        self.seen
+            .insert(device.path.clone())
+            .then_some(DiscoveryEvent::DeviceDiscovered(device))
+    }
+
+    fn poll_added(&mut self, cx: &mut Context<'_>) -> Poll<Option<DiscoveryEvent>> {
+        while let Poll::Ready(signal) = Pin::new(&mut self.added).poll_next(cx) {
+            let Some(signal) = signal else {
+                return Poll::Ready(None);
+            };
+            let Ok(args) = signal.args() else {
+                continue;
+            };
+            let Some(properties) = args.interfaces_and_properties.get(DEVICE_INTERFACE) else {
+                continue;
+            };
+            if let Some(event) = self
+                .device_added(&args.object_path, properties)
+                .and_then(|device| self.discovered(device))
+            {
+                return Poll::Ready(Some(event));
+            }
+        }
+        Poll::Pending
+    }
+
+    fn poll_changed(&mut self, cx: &mut Context<'_>) -> Poll<Option<DiscoveryEvent>> {
+        while let Poll::Ready(msg) = Pin::new(&mut self.changed).poll_next(cx) {
+            let Some(Ok(msg)) = msg else {
+                return Poll::Ready(None);
+            };
+            let Some(signal) = PropertiesChanged::from_message(msg) else {
+                continue;
+            };
+            let header = signal.message().header();
+            let (Some(path), Ok(args)) = (header.path(), signal.args()) else {
+                continue;
+            };
+            if let Some(event) = device_updated(path.to_owned().into(), &args.changed_properties) {
+                return Poll::Ready(Some(event));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+impl Stream for DiscoverySession {
+    type Item = DiscoveryEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        while let Some(device) = self.known.pop_front() {
+            if let Some(event) = self.discovered(device) {
+                return Poll::Ready(Some(event));
+            }
+        }
+        if let Poll::Ready(event) = self.poll_added(cx) {
+            return Poll::Ready(event);
+        }
+        self.poll_changed(cx)
+    }
+}
+
+impl Drop for DiscoverySession {
+    fn drop(&mut self) {
+        let proxy = self.proxy.clone();
+        self.proxy
+            .inner()
+            .connection()
+            .executor()
+            .spawn(
+                async move {
+                    if let Err(err) = proxy.stop_discovery().await {
+                        warn!("{}: StopDiscovery {}", proxy.inner().path(), err);
+                    }
+                },
+                "bluez-zbus stop_discovery",
+            )
+            .detach();
+    }
+}
+
+impl Adapter {
+    /// Start discovery with `filter` and stream the devices found
+    pub async fn discover(&self, filter: DiscoveryFilter) -> Result<DiscoverySession, zbus::Error> {
+        let object_manager = ObjectManagerProxy::builder(self.connection())
+            .destination(self.bus().destination().clone())?
+            .path("/")?
+            .build()
+            .await?;
+        // Subscribe first so devices added in between aren't missed
+        let added = object_manager.receive_interfaces_added().await?;
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(self.bus().destination().as_str())?
+            .interface("org.freedesktop.DBus.Properties")?
+            .member("PropertiesChanged")?
+            .path_namespace(self.path().to_owned())?
+            .arg(0, DEVICE_INTERFACE)?
+            .build();
+        let changed = MessageStream::for_match_rule(rule, self.connection(), None).await?;
+        let known = object_manager
+            .get_managed_objects()
+            .await?
+            .into_iter()
+            .filter(|(path, _)| is_child(self.path(), path))
+            .filter_map(|(path, interfaces)| {
+                let data = BluezDevice::from(interfaces.get(DEVICE_INTERFACE)?);
+                Some(DiscoveredDevice { path, data })
+            })
+            .collect();
+
+        self.proxy().set_discovery_filter(filter.to_map()).await?;
+        with_timeout(
+            self.timeout(),
+            format!("{}: start discovery", self.path()),
+            with_retry(self.retry_policy(), || {
+                trace::call(
+                    self.proxy().inner(),
+                    "StartDiscovery",
+                    self.proxy().start_discovery(),
+                )
+            }),
+        )
+        .await?;
+        Ok(DiscoverySession {
+            proxy: self.proxy().clone(),
+            known,
+            seen: HashSet::new(),
+            added,
+            changed,
+        })
+    }
+}
+
+impl Adapter {
+    async fn find_device(&self, matcher: &DeviceMatcher) -> Result<Device, zbus::Error> {
+        let mut session = self.discover(matcher.filter()).await?;
+        while let Some(event) = session.next().await {
+            let path = match event {
+                DiscoveryEvent::DeviceDiscovered(device) => device.path,
+                // The name and services may only arrive after the device was
+                // found, and devices bluez already knew start out with stale
+                // values
+                DiscoveryEvent::DeviceUpdated {
+                    path,
+                    name,
+                    uuids,
+                    service_data,
+                    ..
+                } if matcher.affected_by(name.as_deref(), &uuids, &service_data) => path,
+                DiscoveryEvent::DeviceUpdated { .. } => continue,
+            };
+            let device = Device::new(self.bus(), &path).await?;
+            if matcher.matches(device.proxy()).await {
+                return Ok(device);
+            }
+        }
+        Err(zbus::Error::Failure(format!(
+            "{}: discovery ended",
+            self.path()
+        )))
+    }
+
+    /// Discover until a device matching `matcher` is found, or fail after
+    /// `timeout`
+    pub async fn wait_for_device(
+        &self,
+        matcher: DeviceMatcher,
+        timeout: Duration,
+    ) -> Result<Device, zbus::Error> {
+        future::or(self.find_device(&matcher), async {
+            sleep(timeout).await;
+            Err(zbus::Error::Failure(format!(
+                "{}: no device matching {matcher:?} found",
+                self.path()
+            )))
+        })
+        .await
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+use uuid::Uuid;
+use zbus::fdo::ManagedObjects;
+use zbus::names::OwnedInterfaceName;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+use crate::enum_impl_to_from_str;
+use crate::interface::gatt::{CharacteristicFlags, GattDescriptorFlags};
+
+type Properties = HashMap<String, OwnedValue>;
+
+/// A descriptor found on a remote device
+#[derive(Debug, Clone)]
+pub struct RemoteDescriptor {
+    path: OwnedObjectPath,
+    uuid: Uuid,
+    flags: Vec<GattDescriptorFlags>,
+}
+
+impl RemoteDescriptor {
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn flags(&self) -> &[GattDescriptorFlags] {
+        &self.flags
+    }
+}
+
+/// A characteristic found on a remote device, with its descriptors
+#[derive(Debug, Clone)]
+pub struct RemoteCharacteristic {
+    path: OwnedObjectPath,
+    uuid: Uuid,
+    flags: Vec<CharacteristicFlags>,
+    descriptors: BTreeMap<Uuid, RemoteDescriptor>,
+}
+
+impl RemoteCharacteristic {
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn flags(&self) -> &[CharacteristicFlags] {
+        &self.flags
+    }
+
+    pub fn descriptors(&self) -> &BTreeMap<Uuid, RemoteDescriptor> {
+        &self.descriptors
+    }
+}
+
+/// A service found on a remote device, with its characteristics
+#[derive(Debug, Clone)]
+pub struct RemoteService {
+    path: OwnedObjectPath,
+    uuid: Uuid,
+    primary: bool,
+    characteristics: BTreeMap<Uuid, RemoteCharacteristic>,
+}
+
+impl RemoteService {
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn primary(&self) -> bool {
+        self.primary
+    }
+
+    pub fn characteristics(&self) -> &BTreeMap<Uuid, RemoteCharacteristic> {
+        &self.characteristics
+    }
+}
+
+fn interface<'a>(
+    interfaces: &'a HashMap<OwnedInterfaceName, Properties>,
+    name: &str,
+) -> Option<&'a Properties> {
+    interfaces
+        .iter()
+        .find(|(iface, _)| iface.as_str() == name)
+        .map(|(_, props)| props)
+}
+
+fn uuid(props: &Properties) -> Option<Uuid> {
+    props
+        .get("UUID")
+        .and_then(|v| <&str>::try_from(v).ok())
+        .and_then(|v| Uuid::parse_str(v).ok())
+}
+
+fn parent(props: &Properties, name: &str) -> Option<OwnedObjectPath> {
+    props
+        .get(name)
+        .and_then(|v| OwnedObjectPath::try_from(v.try_clone().ok()?).ok())
+}
+
+fn flags<T: FromStr>(props: &Properties) -> Vec<T> {
+    props
+        .get("Flags")
+        .and_then(|v| Vec::<String>::try_from(v.try_clone().ok()?).ok())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|f| T::from_str(f).ok())
+        .collect()
+}
+
+/// Build the GATT tree of the device at `device_path` from the bluez managed
+/// objects
+pub(crate) fn gatt_tree(
+    objects: &ManagedObjects,
+    device_path: &ObjectPath<'_>,
+) -> BTreeMap<Uuid, RemoteService> {
+    let prefix = format!("{}/", device_path.as_str());
+    let objects: Vec<_> = objects
+        .iter()
+        .filter(|(path, _)| path.as_str().starts_with(&prefix))
+        .collect();
+
+    let mut descriptors: HashMap<OwnedObjectPath, BTreeMap<Uuid, RemoteDescriptor>> =
+        HashMap::new();
+    for (path, interfaces) in &objects {
+        let Some(props) = interface(interfaces, "org.bluez.GattDescriptor1") else {
+            continue;
+        };
+        let (Some(uuid), Some(char_path)) = (uuid(props), parent(props, "Characteristic")) else {
+            continue;
+        };
+        descriptors.entry(char_path).or_default().insert(
+            uuid,
+            RemoteDescriptor {
+                path: (*path).clone(),
+                uuid,
+                flags: flags(props),
+            },
+        );
+    }
+
+    let mut characteristics: HashMap<OwnedObjectPath, BTreeMap<Uuid, RemoteCharacteristic>> =
+        HashMap::new();
+    for (path, interfaces) in &objects {
+        let Some(props) = interface(interfaces, "org.bluez.GattCharacteristic1") else {
+            continue;
+        };
+        let (Some(uuid), Some(service_path)) = (uuid(props), parent(props, "Service")) else {
+            continue;
+        };
+        characteristics.entry(service_path).or_default().insert(
+            uuid,
+            RemoteCharacteristic {
+                path: (*path).clone(),
+                uuid,
+                flags: flags(props),
+                descriptors: descriptors.remove(*path).unwrap_or_default(),
+            },
+        );
+    }
+
+    let mut services = BTreeMap::new();
+    for (path, interfaces) in &objects {
+        let Some(props) = interface(interfaces, "org.bluez.GattService1") else {
+            continue;
+        };
+        let Some(uuid) = uuid(props) else {
+            continue;
+        };
+        services.insert(
+            uuid,
+            RemoteService {
+                path: (*path).clone(),
+                uuid,
+                primary: props
+                    .get("Primary")
+                    .map(|b| bool::try_from(b).unwrap_or_default())
+                    .unwrap_or_default(),
+                characteristics: characteristics.remove(*path).unwrap_or_default(),
+            },
+        );
+    }
+    services
+}
+
+enum_impl_to_from_str! {
+    WriteType, {
+        Command : "command",
+        Request : "request",
+        Reliable : "reliable",
+    }
+}
+
+/// Options for `ReadValue` on a remote characteristic or descriptor
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ReadOptions {
+    /// Offset to start reading from
+    pub offset: Option<u16>,
+}
+
+impl ReadOptions {
+    pub fn to_map(&self) -> HashMap<&'static str, Value<'static>> {
+        let mut options = HashMap::new();
+        if let Some(offset) = self.offset {
+            options.insert("offset", Value::from(offset));
+        }
+        options
+    }
+}
+
+/// Options for `WriteValue` on a remote characteristic or descriptor
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WriteOptions {
+    /// Offset to start writing at
+    pub offset: Option<u16>,
+    /// Write procedure to use. Bluez picks one from the flags if unset.
+    pub type_: Option<WriteType>,
+}
+
+impl WriteOptions {
+    pub fn to_map(&self) -> HashMap<&'static str, Value<'static>> {
+        let mut options = HashMap::new();
+        if let Some(offset) = self.offset {
+            options.insert("offset", Value::from(offset));
+        }
+        if let Some(type_) = self.type_ {
+            options.insert("type", Value::from(<&str>::from(type_)));
+        }
+        options
+    }
+}
@@ -0,0 +1,34 @@
+use super::{long_write_parts, ReadOptions, WriteOptions, DEFAULT_MTU};
+use crate::proxy::gatt_characteristic1::GattCharacteristic1Proxy;
+
+async fn mtu(proxy: &GattCharacteristic1Proxy<'_>) -> u16 {
+    proxy.mtu().await.unwrap_or(DEFAULT_MTU)
+}
+
+/// Read a characteristic value longer than the MTU.
+///
+/// bluez reads the rest of the value with `Read Blob Request`s itself once
+/// the first response fills the MTU, so a single `ReadValue` returns all of
+/// it. Reading again at the returned length would only cost a round trip.
+pub async fn read_long(proxy: &GattCharacteristic1Proxy<'_>) -> Result<Vec<u8>, zbus::Error> {
+    proxy.read_value(ReadOptions::default().to_map()).await
+}
+
+/// Write a characteristic value longer than the MTU by splitting it into
+/// chunks written at increasing offsets. Values ending past the 512 byte
+/// attribute limit fail with `InvalidValueLength` before anything is written.
+pub async fn write_long(
+    proxy: &GattCharacteristic1Proxy<'_>,
+    value: &[u8],
+    options: WriteOptions,
+) -> Result<(), zbus::Error> {
+    let base = options.offset.unwrap_or_default();
+    for (offset, part) in long_write_parts(value, base, mtu(proxy).await)? {
+        let options = WriteOptions {
+            offset: Some(offset),
+            ..options
+        };
+        proxy.write_value(part, options.to_map()).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+//! # Client helpers
+//!
+//! Convenience wrappers around the `org.bluez.GattCharacteristic1` and
+//! `org.bluez.GattDescriptor1` proxies, and client-role profiles, for
+//! applications acting in the central role.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::Value;
+
+use crate::error::BluezError;
+
+mod gatt;
+pub use gatt::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod acquire;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use acquire::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod adapter;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use adapter::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod central;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use central::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod device;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use device::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod discover;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use discover::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod discovery;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use discovery::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod long;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use long::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod notify;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use notify::*;
+
+mod pacing;
+pub use pacing::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod reconnect;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use reconnect::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod retry;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use retry::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod session;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use session::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod spp;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use spp::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod timeout;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+use timeout::*;
+
+#[cfg(feature = "blocking-api")]
+pub mod blocking;
+
+const CHARACTERISTIC_INTERFACE: &str = "org.bluez.GattCharacteristic1";
+
+/// ATT MTU used when the characteristic doesn't report one
+pub(crate) const DEFAULT_MTU: u16 = 23;
+/// Largest attribute value allowed by the core spec
+pub(crate) const MAX_ATTRIBUTE_LEN: usize = 512;
+
+/// Payload of a `Write Command` for the given ATT MTU
+fn command_chunk(mtu: u16) -> usize {
+    (mtu.max(DEFAULT_MTU) - 3) as usize
+}
+
+/// Payload of a `Prepare Write Request` for the given ATT MTU
+fn long_write_chunk(mtu: u16) -> usize {
+    (mtu.max(DEFAULT_MTU) - 5) as usize
+}
+
+/// Split `value`, written at `base`, into `Prepare Write Request` sized parts
+/// with their offsets. Fails if the value would end past `MAX_ATTRIBUTE_LEN`.
+fn long_write_parts(
+    value: &[u8],
+    base: u16,
+    mtu: u16,
+) -> Result<Vec<(u16, &[u8])>, zbus::Error> {
+    let too_long = || {
+        zbus::Error::from(BluezError::InvalidValueLength(format!(
+            "{} bytes at offset {base} exceed the {MAX_ATTRIBUTE_LEN} byte attribute limit",
+            value.len()
+        )))
+    };
+    if base as usize + value.len() > MAX_ATTRIBUTE_LEN {
+        return Err(too_long());
+    }
+    let chunk = long_write_chunk(mtu);
+    value
+        .chunks(chunk)
+        .enumerate()
+        .map(|(count, part)| {
+            count
+                .checked_mul(chunk)
+                .and_then(|offset| u16::try_from(offset).ok())
+                .and_then(|offset| base.checked_add(offset))
+                .map(|offset| (offset, part))
+                .ok_or_else(too_long)
+        })
+        .collect()
+}
+
+/// Pull a new characteristic `Value` out of a `PropertiesChanged` signal
+fn changed_value(interface: &str, changed: &HashMap<&str, Value<'_>>) -> Option<Vec<u8>> {
+    if interface != CHARACTERISTIC_INTERFACE {
+        return None;
+    }
+    changed
+        .get("Value")
+        .and_then(|value| Vec::<u8>::try_from(value.try_clone().ok()?).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_write_offsets() {
+        let value = [0u8; 50];
+        let parts = long_write_parts(&value, 4, DEFAULT_MTU).unwrap();
+        let offsets: Vec<u16> = parts.iter().map(|(offset, _)| *offset).collect();
+        assert_eq!(offsets, [4, 22, 40]);
+        assert_eq!(parts.iter().map(|(_, part)| part.len()).sum::<usize>(), 50);
+        assert!(long_write_parts(&[], 0, DEFAULT_MTU).unwrap().is_empty());
+    }
+
+    #[test]
+    fn long_write_past_the_attribute_limit() {
+        assert_eq!(long_write_parts(&[0; 512], 0, 247).unwrap().len(), 3);
+        assert_eq!(long_write_parts(&[0; 2], 510, 247).unwrap(), [(510, &[0, 0][..])]);
+        for (len, base) in [(513, 0), (3, 510), (1, u16::MAX), (70_000, 0)] {
+            match long_write_parts(&vec![0; len], base, DEFAULT_MTU) {
+                Err(err) => assert!(matches!(
+                    BluezError::from(err),
+                    BluezError::InvalidValueLength(_)
+                )),
+                Ok(_) => panic!("{len} bytes at {base} accepted"),
+            }
+        }
+    }
+}
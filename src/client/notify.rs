@@ -0,0 +1,85 @@
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_lite::Stream;
+use log::warn;
+use zbus::fdo::{PropertiesChangedStream, PropertiesProxy};
+use zbus::zvariant::ObjectPath;
+
+use super::changed_value;
+use crate::bus::BluezBus;
+use crate::proxy::gatt_characteristic1::GattCharacteristic1Proxy;
+
+/// Stream of values notified by a remote characteristic.
+///
+/// Created by `subscribe()`. Dropping the stream calls `StopNotify`.
+pub struct Notifications {
+    proxy: GattCharacteristic1Proxy<'static>,
+    changed: PropertiesChangedStream,
+}
+
+impl Notifications {
+    pub fn proxy(&self) -> &GattCharacteristic1Proxy<'static> {
+        &self.proxy
+    }
+}
+
+impl Stream for Notifications {
+    type Item = Vec<u8>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let Some(signal) = ready!(Pin::new(&mut self.changed).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            let Ok(args) = signal.args() else {
+                continue;
+            };
+            if let Some(value) = changed_value(&args.interface_name, &args.changed_properties) {
+                return Poll::Ready(Some(value));
+            }
+        }
+    }
+}
+
+impl Drop for Notifications {
+    fn drop(&mut self) {
+        let proxy = self.proxy.clone();
+        self.proxy
+            .inner()
+            .connection()
+            .executor()
+            .spawn(
+                async move {
+                    if let Err(err) = proxy.stop_notify().await {
+                        warn!("{}: StopNotify {}", proxy.inner().path(), err);
+                    }
+                },
+                "bluez-zbus stop_notify",
+            )
+            .detach();
+    }
+}
+
+/// Start notifications on the remote characteristic at `path` and stream the
+/// values as they arrive.
+pub async fn subscribe(
+    bus: impl Into<BluezBus>,
+    path: &ObjectPath<'_>,
+) -> Result<Notifications, zbus::Error> {
+    let bus = bus.into();
+    let proxy = GattCharacteristic1Proxy::builder(bus.connection())
+        .destination(bus.destination().clone())?
+        .path(path.to_owned())?
+        .build()
+        .await?;
+    let changed = PropertiesProxy::builder(bus.connection())
+        .destination(bus.destination().clone())?
+        .path(path.to_owned())?
+        .build()
+        .await?
+        .receive_properties_changed()
+        .await?;
+    proxy.start_notify().await?;
+    Ok(Notifications { proxy, changed })
+}
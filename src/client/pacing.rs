@@ -0,0 +1,58 @@
+use std::time::{Duration, Instant};
+
+/// Budget for write-without-response packets. They are not acknowledged, so
+/// sending faster than the link drains them overflows the controller's queue
+/// and packets get lost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WritePacing {
+    /// Packets sent per interval, at least one
+    pub packets: u32,
+    pub interval: Duration,
+}
+
+impl WritePacing {
+    pub const fn new(packets: u32, interval: Duration) -> Self {
+        Self { packets, interval }
+    }
+}
+
+impl Default for WritePacing {
+    /// 4 packets per 15ms connection interval
+    fn default() -> Self {
+        Self::new(4, Duration::from_millis(15))
+    }
+}
+
+/// Counts the packets sent in the current interval of a `WritePacing`
+#[derive(Debug)]
+pub(crate) struct Pacer {
+    pacing: WritePacing,
+    start: Instant,
+    sent: u32,
+}
+
+impl Pacer {
+    pub(crate) fn new(pacing: WritePacing) -> Self {
+        Self {
+            pacing,
+            start: Instant::now(),
+            sent: 0,
+        }
+    }
+
+    /// Count the next packet, and return how long to wait before sending it
+    pub(crate) fn next_packet(&mut self) -> Option<Duration> {
+        let elapsed = self.start.elapsed();
+        if elapsed >= self.pacing.interval {
+            self.start = Instant::now();
+            self.sent = 0;
+        }
+        if self.sent < self.pacing.packets.max(1) {
+            self.sent += 1;
+            return None;
+        }
+        self.start += self.pacing.interval;
+        self.sent = 1;
+        Some(self.pacing.interval.saturating_sub(elapsed))
+    }
+}
@@ -0,0 +1,144 @@
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_channel::{mpsc, oneshot};
+use futures_lite::{future, Stream, StreamExt};
+use log::{debug, warn};
+use zbus::zvariant::OwnedObjectPath;
+
+use super::Device;
+use crate::rt::sleep;
+
+/// Link state changes reported by a `ReconnectWatchdog`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReconnectEvent {
+    /// The link dropped, reconnecting starts
+    Disconnected(OwnedObjectPath),
+    /// Attempt number `attempt` is made after waiting `delay`
+    Reconnecting {
+        device: OwnedObjectPath,
+        attempt: u32,
+        delay: Duration,
+    },
+    Reconnected(OwnedObjectPath),
+    /// `max_attempts` ran out, the device is no longer watched
+    GaveUp(OwnedObjectPath),
+}
+
+/// Reconnect a device whenever its link drops, waiting `backoff` before the
+/// first attempt and doubling the wait after each failed one up to
+/// `max_backoff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Give up after this many failed attempts in a row, `None` retries
+    /// forever
+    pub max_attempts: Option<u32>,
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: None,
+            backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Watch `Connected` on `device` until the returned watchdog is dropped.
+    /// Drop it before disconnecting on purpose, or the link is brought back.
+    pub fn watch(&self, device: Arc<Device>) -> ReconnectWatchdog {
+        let (sender, events) = mpsc::unbounded();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let policy = *self;
+        let executor = device.connection().executor().clone();
+        executor
+            .spawn(
+                future::or(policy.run(device, sender), async {
+                    stopped.await.ok();
+                }),
+                "reconnect watchdog",
+            )
+            .detach();
+        ReconnectWatchdog {
+            events,
+            _stop: stop,
+        }
+    }
+
+    async fn run(self, device: Arc<Device>, events: mpsc::UnboundedSender<ReconnectEvent>) {
+        let path = OwnedObjectPath::from(device.path().to_owned());
+        let mut connected = device.proxy().receive_connected_changed().await;
+        while let Some(change) = connected.next().await {
+            if change.get().await.unwrap_or(true) {
+                continue;
+            }
+            events
+                .unbounded_send(ReconnectEvent::Disconnected(path.clone()))
+                .ok();
+            if !self.reconnect(&device, &path, &events).await {
+                events
+                    .unbounded_send(ReconnectEvent::GaveUp(path.clone()))
+                    .ok();
+                return;
+            }
+        }
+    }
+
+    /// Returns false if the attempts ran out
+    async fn reconnect(
+        &self,
+        device: &Device,
+        path: &OwnedObjectPath,
+        events: &mpsc::UnboundedSender<ReconnectEvent>,
+    ) -> bool {
+        let mut delay = self.backoff;
+        let mut attempt = 1;
+        while self.max_attempts.is_none_or(|max| attempt <= max) {
+            events
+                .unbounded_send(ReconnectEvent::Reconnecting {
+                    device: path.clone(),
+                    attempt,
+                    delay,
+                })
+                .ok();
+            sleep(delay).await;
+            match device.connect().await {
+                Ok(()) => {
+                    events
+                        .unbounded_send(ReconnectEvent::Reconnected(path.clone()))
+                        .ok();
+                    return true;
+                }
+                Err(err) => debug!("{path}: reconnect attempt {attempt} failed: {err}"),
+            }
+            delay = delay.saturating_mul(2).min(self.max_backoff);
+            attempt += 1;
+        }
+        warn!(
+            "{path}: giving up reconnecting after {} attempts",
+            attempt - 1
+        );
+        false
+    }
+}
+
+/// Stream of `ReconnectEvent`s for one device, created by
+/// `ReconnectPolicy::watch()`. Dropping it stops the watchdog.
+pub struct ReconnectWatchdog {
+    events: mpsc::UnboundedReceiver<ReconnectEvent>,
+    _stop: oneshot::Sender<()>,
+}
+
+impl Stream for ReconnectWatchdog {
+    type Item = ReconnectEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
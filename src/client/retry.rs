@@ -0,0 +1,72 @@
+use std::future::Future;
+use std::time::Duration;
+
+use log::debug;
+
+use crate::error::BluezError;
+use crate::rt::sleep;
+
+/// Retry calls that bluez rejects with `org.bluez.Error.InProgress`, or
+/// `Failed("Operation already in progress")`, because another operation on
+/// the same object hasn't finished yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total number of calls, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each retry after it
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Run `op` until it succeeds, fails with another error, or the attempts
+    /// run out
+    pub(crate) async fn run<T, F, Fut>(&self, mut op: F) -> Result<T, zbus::Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, zbus::Error>>,
+    {
+        let mut backoff = self.backoff;
+        let mut attempt = 1;
+        loop {
+            match op().await {
+                Err(err)
+                    if attempt < self.max_attempts
+                        && BluezError::from(err.clone()).is_in_progress() =>
+                {
+                    debug!(
+                        "retry {attempt}/{} after {backoff:?}: {err}",
+                        self.max_attempts
+                    );
+                    sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+/// Run `op` under `policy`, or once if there is none
+pub(crate) async fn with_retry<T, F, Fut>(
+    policy: Option<&RetryPolicy>,
+    mut op: F,
+) -> Result<T, zbus::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, zbus::Error>>,
+{
+    match policy {
+        Some(policy) => policy.run(op).await,
+        None => op().await,
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc;
+use futures_lite::{Stream, StreamExt};
+use log::warn;
+use zbus::fdo::{InterfacesAdded, InterfacesRemoved, ObjectManagerProxy, PropertiesChanged};
+use zbus::message::Type as MessageType;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+use zbus::{MatchRule, Message, MessageStream, Task};
+
+use crate::address::BDAddr;
+use crate::bus::BluezBus;
+use crate::proxy::object_manager::{BluezAdapter, BluezDevice};
+
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+
+type Properties = HashMap<String, OwnedValue>;
+type Objects = HashMap<OwnedObjectPath, HashMap<String, Properties>>;
+type Subscribers = Arc<Mutex<Vec<mpsc::UnboundedSender<ConnectionEvent>>>>;
+
+/// A device connected or disconnected, or was removed while connected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionEvent {
+    pub device: OwnedObjectPath,
+    pub connected: bool,
+}
+
+/// Stream of the connection changes of every device, from
+/// `BluezSession::connection_events()`
+pub struct ConnectionEvents {
+    events: mpsc::UnboundedReceiver<ConnectionEvent>,
+}
+
+impl Stream for ConnectionEvents {
+    type Item = ConnectionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+/// In-memory mirror of every object bluez exports.
+///
+/// The objects are fetched once, then kept up to date from the
+/// `InterfacesAdded`, `InterfacesRemoved` and `PropertiesChanged` signals, so
+/// lookups don't need a D-Bus round trip. Updates stop when the session is
+/// dropped.
+pub struct BluezSession {
+    objects: Arc<Mutex<Objects>>,
+    subscribers: Subscribers,
+    _task: Task<()>,
+}
+
+impl BluezSession {
+    pub async fn new(bus: impl Into<BluezBus>) -> Result<Self, zbus::Error> {
+        let bus = bus.into();
+        let connection = bus.connection();
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender(bus.destination().as_str())?
+            .build();
+        // Subscribe first so changes made while fetching aren't missed
+        let mut signals = MessageStream::for_match_rule(rule, connection, None).await?;
+        let objects: Objects = ObjectManagerProxy::builder(connection)
+            .destination(bus.destination().clone())?
+            .path("/")?
+            .build()
+            .await?
+            .get_managed_objects()
+            .await?
+            .into_iter()
+            .map(|(path, interfaces)| {
+                let interfaces = interfaces
+                    .into_iter()
+                    .map(|(name, props)| (name.to_string(), props))
+                    .collect();
+                (path, interfaces)
+            })
+            .collect();
+
+        let objects = Arc::new(Mutex::new(objects));
+        let subscribers = Subscribers::default();
+        let mirror = objects.clone();
+        let senders = subscribers.clone();
+        let task = connection.executor().spawn(
+            async move {
+                while let Some(msg) = signals.next().await {
+                    match msg {
+                        Ok(msg) => {
+                            for event in apply_signal(&mirror, msg) {
+                                send_event(&senders, event);
+                            }
+                        }
+                        Err(err) => warn!("BluezSession: {err}"),
+                    }
+                }
+            },
+            "bluez-zbus session",
+        );
+        Ok(Self {
+            objects,
+            subscribers,
+            _task: task,
+        })
+    }
+
+    /// Connects and disconnects of all devices as the session sees them,
+    /// without a proxy per device. Ends when the session is dropped.
+    pub fn connection_events(&self) -> ConnectionEvents {
+        let (tx, rx) = mpsc::unbounded();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(tx);
+        }
+        ConnectionEvents { events: rx }
+    }
+
+    fn with_objects<T>(&self, f: impl FnOnce(&Objects) -> T) -> T {
+        match self.objects.lock() {
+            Ok(objects) => f(&objects),
+            Err(poisoned) => f(&poisoned.into_inner()),
+        }
+    }
+
+    /// Properties of `interface` on the object at `path`
+    pub fn properties(&self, path: &ObjectPath<'_>, interface: &str) -> Option<Properties> {
+        let path = OwnedObjectPath::from(path.to_owned());
+        self.with_objects(|objects| objects.get(&path)?.get(interface).cloned())
+    }
+
+    /// The adapters, sorted by path
+    pub fn adapters(&self) -> Vec<(OwnedObjectPath, BluezAdapter)> {
+        let mut adapters: Vec<(OwnedObjectPath, BluezAdapter)> = self.with_objects(|objects| {
+            objects
+                .iter()
+                .filter_map(|(path, interfaces)| {
+                    let adapter = BluezAdapter::from(interfaces.get(ADAPTER_INTERFACE)?);
+                    Some((path.clone(), adapter))
+                })
+                .collect()
+        });
+        adapters.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        adapters
+    }
+
+    /// All devices known to bluez, on every adapter
+    pub fn devices(&self) -> Vec<(OwnedObjectPath, BluezDevice)> {
+        self.with_objects(|objects| {
+            objects
+                .iter()
+                .filter_map(|(path, interfaces)| {
+                    let device = BluezDevice::from(interfaces.get(DEVICE_INTERFACE)?);
+                    Some((path.clone(), device))
+                })
+                .collect()
+        })
+    }
+
+    pub fn device_by_address(&self, address: BDAddr) -> Option<(OwnedObjectPath, BluezDevice)> {
+        self.with_objects(|objects| {
+            objects.iter().find_map(|(path, interfaces)| {
+                let device = BluezDevice::from(interfaces.get(DEVICE_INTERFACE)?);
+                (device.bd_addr()? == address).then(|| (path.clone(), device))
+            })
+        })
+    }
+}
+
+fn send_event(
+    subscribers: &Mutex<Vec<mpsc::UnboundedSender<ConnectionEvent>>>,
+    event: ConnectionEvent,
+) {
+    if let Ok(mut subscribers) = subscribers.lock() {
+        subscribers.retain(|tx| tx.unbounded_send(event.clone()).is_ok());
+    }
+}
+
+fn is_connected(props: &Properties) -> bool {
+    props
+        .get("Connected")
+        .and_then(|connected| bool::try_from(connected).ok())
+        .unwrap_or_default()
+}
+
+/// Update `objects` from a signal, returning the connection changes it
+/// caused
+fn apply_signal(objects: &Mutex<Objects>, msg: Message) -> Vec<ConnectionEvent> {
+    let mut events = Vec::new();
+    let Ok(mut objects) = objects.lock() else {
+        return events;
+    };
+    if let Some(signal) = InterfacesAdded::from_message(msg.clone())
+        && let Ok(args) = signal.args()
+    {
+        let interfaces = objects
+            .entry(args.object_path.to_owned().into())
+            .or_default();
+        for (name, props) in args.interfaces_and_properties {
+            let props: Properties = props
+                .iter()
+                .filter_map(|(k, v)| Some((k.to_string(), v.try_to_owned().ok()?)))
+                .collect();
+            if name.as_str() == DEVICE_INTERFACE && is_connected(&props) {
+                events.push(ConnectionEvent {
+                    device: args.object_path.to_owned().into(),
+                    connected: true,
+                });
+            }
+            interfaces.insert(name.to_string(), props);
+        }
+    } else if let Some(signal) = InterfacesRemoved::from_message(msg.clone())
+        && let Ok(args) = signal.args()
+    {
+        let path: OwnedObjectPath = args.object_path.to_owned().into();
+        if let Some(interfaces) = objects.get_mut(&path) {
+            for name in args.interfaces.iter() {
+                if let Some(props) = interfaces.remove(name.as_str())
+                    && name.as_str() == DEVICE_INTERFACE
+                    && is_connected(&props)
+                {
+                    events.push(ConnectionEvent {
+                        device: path.clone(),
+                        connected: false,
+                    });
+                }
+            }
+            if interfaces.is_empty() {
+                objects.remove(&path);
+            }
+        }
+    } else if let Some(signal) = PropertiesChanged::from_message(msg.clone())
+        && let Ok(args) = signal.args()
+        && let Some(path) = msg.header().path()
+        && let Some(props) = objects
+            .get_mut(&OwnedObjectPath::from(path.to_owned()))
+            .and_then(|interfaces| interfaces.get_mut(args.interface_name.as_str()))
+    {
+        let was_connected = is_connected(props);
+        for (key, value) in args.changed_properties.iter() {
+            if let Ok(value) = value.try_to_owned() {
+                props.insert(key.to_string(), value);
+            }
+        }
+        for key in args.invalidated_properties.iter() {
+            props.remove(*key);
+        }
+        let connected = is_connected(props);
+        if args.interface_name.as_str() == DEVICE_INTERFACE && connected != was_connected {
+            events.push(ConnectionEvent {
+                device: path.to_owned().into(),
+                connected,
+            });
+        }
+    }
+    events
+}
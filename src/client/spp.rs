@@ -0,0 +1,120 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_lite::{AsyncRead, AsyncWrite, StreamExt};
+use log::debug;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+use crate::bus::BluezBus;
+use crate::interface::{
+    Profile1, ProfileEvent, ProfileEvents, ProfileHandle, ProfileOptions, ProfileRole,
+};
+use crate::proxy::device1::Device1Proxy;
+use crate::rt::AsyncStream;
+
+/// The Serial Port Profile UUID
+pub const SPP_UUID: &str = "00001101-0000-1000-8000-00805f9b34fb";
+
+const SPP_CLIENT_PATH: &str = "/org/bluez_zbus/profile/spp_client";
+
+/// Connected RFCOMM socket to a remote serial port
+pub struct SppStream {
+    device: OwnedObjectPath,
+    stream: AsyncStream,
+}
+
+impl SppStream {
+    pub fn device(&self) -> &ObjectPath<'_> {
+        &self.device
+    }
+}
+
+impl AsyncRead for SppStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SppStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_close(cx)
+    }
+}
+
+/// Serial Port Profile in the client role, connecting to serial ports on
+/// remote devices
+pub struct SppClient {
+    bus: BluezBus,
+    profile: ProfileHandle,
+    events: ProfileEvents,
+}
+
+impl SppClient {
+    /// Register the client-role SPP profile with bluez
+    pub async fn register(bus: impl Into<BluezBus>) -> Result<Self, zbus::Error> {
+        let bus = bus.into();
+        let (profile, events) = Profile1::new();
+        let options = ProfileOptions {
+            name: Some("Serial Port".to_owned()),
+            role: Some(ProfileRole::Client),
+            ..Default::default()
+        };
+        let profile = profile
+            .register(SPP_CLIENT_PATH, SPP_UUID, options, &bus)
+            .await?;
+        Ok(Self {
+            bus,
+            profile,
+            events,
+        })
+    }
+
+    /// Connect to the serial port of the device at `device`
+    pub async fn connect(&mut self, device: &ObjectPath<'_>) -> Result<SppStream, zbus::Error> {
+        Device1Proxy::builder(self.bus.connection())
+            .destination(self.bus.destination().clone())?
+            .path(device.to_owned())?
+            .build()
+            .await?
+            .connect_profile(SPP_UUID)
+            .await?;
+
+        while let Some(event) = self.events.next().await {
+            match event {
+                ProfileEvent::NewConnection(conn) if conn.device() == device => {
+                    return Ok(SppStream {
+                        device: device.to_owned().into(),
+                        stream: AsyncStream::new(conn.into_socket())?,
+                    });
+                }
+                ProfileEvent::Release => break,
+                event => debug!("SppClient: ignoring {event:?}"),
+            }
+        }
+        Err(zbus::Error::Failure(format!(
+            "{}: SPP profile released before connecting",
+            device.as_str()
+        )))
+    }
+
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        self.profile.unregister().await
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use futures_lite::future;
+
+use crate::rt::sleep;
+
+/// Fail with `what timed out` if `fut` doesn't complete within `timeout`.
+/// Without a timeout `fut` runs until bluez replies.
+pub(crate) async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    what: impl Display,
+    fut: impl Future<Output = Result<T, zbus::Error>>,
+) -> Result<T, zbus::Error> {
+    with_timeout_abort(timeout, what, fut, || async {}).await
+}
+
+/// Like `with_timeout()`, but run `abort` once the timeout fires, e.g. to
+/// cancel what bluez is still doing. Errors from `fut` itself don't run it.
+pub(crate) async fn with_timeout_abort<T, A: Future<Output = ()>>(
+    timeout: Option<Duration>,
+    what: impl Display,
+    fut: impl Future<Output = Result<T, zbus::Error>>,
+    abort: impl FnOnce() -> A,
+) -> Result<T, zbus::Error> {
+    let Some(timeout) = timeout else {
+        return fut.await;
+    };
+    let res = future::or(async { Some(fut.await) }, async {
+        sleep(timeout).await;
+        None
+    })
+    .await;
+    match res {
+        Some(res) => res,
+        None => {
+            abort().await;
+            Err(zbus::Error::Failure(format!(
+                "{what} timed out after {timeout:?}"
+            )))
+        }
+    }
+}
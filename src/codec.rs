@@ -0,0 +1,977 @@
+//! # Characteristic value codecs
+//!
+//! Encoding and decoding of the standard characteristic formats, for use both
+//! when serving a characteristic and when reading one from a remote device.
+//! Multi-byte fields are little endian and medical values use the IEEE-11073
+//! `SFLOAT` and `FLOAT` types, which are converted to and from `f32`.
+
+#[cfg(feature = "macros")]
+pub use bluez_zbus_macros::GattValue;
+
+/// A type that can be stored in a characteristic or descriptor value. With
+/// the `macros` feature it can be derived for structs of `GattValue` fields.
+pub trait GattValue: Sized {
+    /// The encoded length, if every value has the same length
+    const SIZE: Option<usize> = None;
+
+    fn to_bytes(&self) -> Vec<u8>;
+
+    fn from_bytes(data: &[u8]) -> Result<Self, zbus::Error>;
+}
+
+macro_rules! impl_gatt_value_le {
+    ($($int:ty,)*) => {
+        $(
+            impl GattValue for $int {
+                const SIZE: Option<usize> = Some(std::mem::size_of::<$int>());
+
+                fn to_bytes(&self) -> Vec<u8> {
+                    self.to_le_bytes().to_vec()
+                }
+
+                fn from_bytes(data: &[u8]) -> Result<Self, zbus::Error> {
+                    Ok(<$int>::from_le_bytes(Reader::new(stringify!($int), data).take()?))
+                }
+            }
+        )*
+    };
+}
+
+impl_gatt_value_le! {
+    u8, u16, u32, u64, i8, i16, i32, i64, f32, f64,
+}
+
+impl GattValue for bool {
+    const SIZE: Option<usize> = Some(1);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self, zbus::Error> {
+        Ok(Reader::new("bool", data).u8()? != 0)
+    }
+}
+
+impl GattValue for String {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    /// Trailing NUL padding is dropped
+    fn from_bytes(data: &[u8]) -> Result<Self, zbus::Error> {
+        let len = data.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+        String::from_utf8(data[..len].to_vec())
+            .map_err(|e| zbus::Error::Failure(format!("String: value is not UTF-8: {e}")))
+    }
+}
+
+impl GattValue for Vec<u8> {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self, zbus::Error> {
+        Ok(data.to_vec())
+    }
+}
+
+macro_rules! impl_gatt_value_codec {
+    ($($codec:ty,)*) => {
+        $(
+            impl GattValue for $codec {
+                fn to_bytes(&self) -> Vec<u8> {
+                    self.encode()
+                }
+
+                fn from_bytes(data: &[u8]) -> Result<Self, zbus::Error> {
+                    Self::decode(data)
+                }
+            }
+        )*
+    };
+}
+
+impl GattValue for BatteryLevel {
+    const SIZE: Option<usize> = Some(1);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self, zbus::Error> {
+        Self::decode(data)
+    }
+}
+
+impl_gatt_value_codec! {
+    TemperatureMeasurement,
+    HeartRateMeasurement,
+    BloodPressureMeasurement,
+    CscMeasurement,
+}
+
+/// Support code for `#[derive(GattValue)]`
+#[doc(hidden)]
+pub mod __derive {
+    pub use zbus::Error;
+
+    /// Split the next `len` bytes of a field off `data`
+    pub fn take<'a>(data: &mut &'a [u8], len: usize, field: &str) -> Result<&'a [u8], Error> {
+        let Some((head, rest)) = data.split_at_checked(len) else {
+            return Err(Error::Failure(format!(
+                "{field}: value is truncated, expected {len} bytes"
+            )));
+        };
+        *data = rest;
+        Ok(head)
+    }
+
+    /// Pad or truncate a field to its declared length
+    pub fn fit(mut bytes: Vec<u8>, len: usize) -> Vec<u8> {
+        bytes.resize(len, 0);
+        bytes
+    }
+
+    /// The size of a struct, if all fields have a size
+    pub const fn sum(sizes: &[Option<usize>]) -> Option<usize> {
+        let mut total = 0;
+        let mut i = 0;
+        while i < sizes.len() {
+            match sizes[i] {
+                Some(size) => total += size,
+                None => return None,
+            }
+            i += 1;
+        }
+        Some(total)
+    }
+}
+
+/// Reads the fields of a characteristic value in order
+struct Reader<'a> {
+    what: &'static str,
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(what: &'static str, data: &'a [u8]) -> Self {
+        Self { what, data }
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], zbus::Error> {
+        let Some((field, rest)) = self.data.split_first_chunk::<N>() else {
+            return Err(zbus::Error::Failure(format!(
+                "{}: value is truncated",
+                self.what
+            )));
+        };
+        self.data = rest;
+        Ok(*field)
+    }
+
+    fn u8(&mut self) -> Result<u8, zbus::Error> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, zbus::Error> {
+        Ok(u16::from_le_bytes(self.take()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, zbus::Error> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    fn sfloat(&mut self) -> Result<f32, zbus::Error> {
+        Ok(sfloat_to_f32(self.u16()?))
+    }
+
+    fn float(&mut self) -> Result<f32, zbus::Error> {
+        Ok(float_to_f32(self.u32()?))
+    }
+
+    fn date_time(&mut self) -> Result<DateTime, zbus::Error> {
+        Ok(DateTime {
+            year: self.u16()?,
+            month: self.u8()?,
+            day: self.u8()?,
+            hours: self.u8()?,
+            minutes: self.u8()?,
+            seconds: self.u8()?,
+        })
+    }
+}
+
+/// Scale `value` to a mantissa and decimal exponent, using the smallest
+/// exponent whose mantissa fits
+fn to_mantissa(
+    value: f32,
+    max: i32,
+    exponents: std::ops::RangeInclusive<i32>,
+) -> Option<(i32, i32)> {
+    exponents
+        .map(|exp| (((value as f64) / 10f64.powi(exp)).round(), exp))
+        .find(|(mantissa, _)| mantissa.abs() <= max as f64)
+        .map(|(mantissa, exp)| (mantissa as i32, exp))
+}
+
+/// Convert an IEEE-11073 16-bit `SFLOAT`
+pub fn sfloat_to_f32(raw: u16) -> f32 {
+    match raw {
+        0x07ff..=0x0801 => f32::NAN,
+        0x07fe => f32::INFINITY,
+        0x0802 => f32::NEG_INFINITY,
+        _ => {
+            let mantissa = ((raw << 4) as i16) >> 4;
+            let exponent = (raw as i16) >> 12;
+            (mantissa as f64 * 10f64.powi(exponent as i32)) as f32
+        }
+    }
+}
+
+/// Convert to an IEEE-11073 16-bit `SFLOAT`, out of range values become
+/// infinity
+pub fn f32_to_sfloat(value: f32) -> u16 {
+    if value.is_nan() {
+        return 0x07ff;
+    }
+    match to_mantissa(value, 0x07fd, -8..=7) {
+        Some((mantissa, exp)) => ((exp as u16 & 0x0f) << 12) | (mantissa as u16 & 0x0fff),
+        None if value > 0.0 => 0x07fe,
+        None => 0x0802,
+    }
+}
+
+/// Convert an IEEE-11073 32-bit `FLOAT`
+pub fn float_to_f32(raw: u32) -> f32 {
+    match raw {
+        0x007f_ffff..=0x0080_0001 => f32::NAN,
+        0x007f_fffe => f32::INFINITY,
+        0x0080_0002 => f32::NEG_INFINITY,
+        _ => {
+            let mantissa = ((raw << 8) as i32) >> 8;
+            let exponent = (raw as i32) >> 24;
+            (mantissa as f64 * 10f64.powi(exponent)) as f32
+        }
+    }
+}
+
+/// Convert to an IEEE-11073 32-bit `FLOAT`, out of range values become
+/// infinity
+pub fn f32_to_float(value: f32) -> u32 {
+    if value.is_nan() {
+        return 0x007f_ffff;
+    }
+    match to_mantissa(value, 0x007f_fffd, -8..=127) {
+        Some((mantissa, exp)) => ((exp as u32 & 0xff) << 24) | (mantissa as u32 & 0x00ff_ffff),
+        None if value > 0.0 => 0x007f_fffe,
+        None => 0x0080_0002,
+    }
+}
+
+/// The 7 byte Date Time characteristic format. Zero fields are unknown.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+}
+
+impl DateTime {
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.year.to_le_bytes());
+        buf.extend_from_slice(&[
+            self.month, self.day, self.hours, self.minutes, self.seconds,
+        ]);
+    }
+}
+
+/// Battery Level (0x2A19), in percent
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryLevel(pub u8);
+
+impl BatteryLevel {
+    pub fn encode(&self) -> Vec<u8> {
+        vec![self.0]
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, zbus::Error> {
+        let level = Reader::new("Battery Level", data).u8()?;
+        if level > 100 {
+            return Err(zbus::Error::Failure(format!(
+                "Battery Level: {level}% is out of range"
+            )));
+        }
+        Ok(Self(level))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+/// Temperature Measurement (0x2A1C) of the Health Thermometer service
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TemperatureMeasurement {
+    pub value: f32,
+    pub unit: TemperatureUnit,
+    pub timestamp: Option<DateTime>,
+    /// Where the temperature was taken, see the Temperature Type
+    /// characteristic
+    pub temperature_type: Option<u8>,
+}
+
+impl TemperatureMeasurement {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.unit == TemperatureUnit::Fahrenheit {
+            flags |= 0x01;
+        }
+        if self.timestamp.is_some() {
+            flags |= 0x02;
+        }
+        if self.temperature_type.is_some() {
+            flags |= 0x04;
+        }
+        let mut buf = vec![flags];
+        buf.extend_from_slice(&f32_to_float(self.value).to_le_bytes());
+        if let Some(timestamp) = &self.timestamp {
+            timestamp.encode_into(&mut buf);
+        }
+        if let Some(temperature_type) = self.temperature_type {
+            buf.push(temperature_type);
+        }
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, zbus::Error> {
+        let mut reader = Reader::new("Temperature Measurement", data);
+        let flags = reader.u8()?;
+        Ok(Self {
+            value: reader.float()?,
+            unit: if flags & 0x01 != 0 {
+                TemperatureUnit::Fahrenheit
+            } else {
+                TemperatureUnit::Celsius
+            },
+            timestamp: if flags & 0x02 != 0 {
+                Some(reader.date_time()?)
+            } else {
+                None
+            },
+            temperature_type: if flags & 0x04 != 0 {
+                Some(reader.u8()?)
+            } else {
+                None
+            },
+        })
+    }
+}
+
+/// Heart Rate Measurement (0x2A37)
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HeartRateMeasurement {
+    /// Beats per minute
+    pub bpm: u16,
+    /// Whether the sensor detects skin contact, `None` if the sensor can't
+    /// tell
+    pub sensor_contact: Option<bool>,
+    /// Energy expended in kilojoules since the last reset
+    pub energy_expended: Option<u16>,
+    /// Intervals between beats in 1/1024 seconds
+    pub rr_intervals: Vec<u16>,
+}
+
+impl HeartRateMeasurement {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.bpm > u8::MAX as u16 {
+            flags |= 0x01;
+        }
+        match self.sensor_contact {
+            Some(true) => flags |= 0x06,
+            Some(false) => flags |= 0x04,
+            None => {}
+        }
+        if self.energy_expended.is_some() {
+            flags |= 0x08;
+        }
+        if !self.rr_intervals.is_empty() {
+            flags |= 0x10;
+        }
+        let mut buf = vec![flags];
+        if flags & 0x01 != 0 {
+            buf.extend_from_slice(&self.bpm.to_le_bytes());
+        } else {
+            buf.push(self.bpm as u8);
+        }
+        if let Some(energy) = self.energy_expended {
+            buf.extend_from_slice(&energy.to_le_bytes());
+        }
+        for interval in &self.rr_intervals {
+            buf.extend_from_slice(&interval.to_le_bytes());
+        }
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, zbus::Error> {
+        let mut reader = Reader::new("Heart Rate Measurement", data);
+        let flags = reader.u8()?;
+        let bpm = if flags & 0x01 != 0 {
+            reader.u16()?
+        } else {
+            reader.u8()? as u16
+        };
+        let sensor_contact = (flags & 0x04 != 0).then_some(flags & 0x02 != 0);
+        let energy_expended = if flags & 0x08 != 0 {
+            Some(reader.u16()?)
+        } else {
+            None
+        };
+        let mut rr_intervals = Vec::new();
+        if flags & 0x10 != 0 {
+            while !reader.data.is_empty() {
+                rr_intervals.push(reader.u16()?);
+            }
+        }
+        Ok(Self {
+            bpm,
+            sensor_contact,
+            energy_expended,
+            rr_intervals,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PressureUnit {
+    #[default]
+    MmHg,
+    KPa,
+}
+
+/// Blood Pressure Measurement (0x2A35)
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BloodPressureMeasurement {
+    pub systolic: f32,
+    pub diastolic: f32,
+    pub mean_arterial_pressure: f32,
+    pub unit: PressureUnit,
+    pub timestamp: Option<DateTime>,
+    /// Beats per minute
+    pub pulse_rate: Option<f32>,
+    pub user_id: Option<u8>,
+    /// Measurement Status bit field
+    pub status: Option<u16>,
+}
+
+impl BloodPressureMeasurement {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.unit == PressureUnit::KPa {
+            flags |= 0x01;
+        }
+        if self.timestamp.is_some() {
+            flags |= 0x02;
+        }
+        if self.pulse_rate.is_some() {
+            flags |= 0x04;
+        }
+        if self.user_id.is_some() {
+            flags |= 0x08;
+        }
+        if self.status.is_some() {
+            flags |= 0x10;
+        }
+        let mut buf = vec![flags];
+        for value in [
+            self.systolic,
+            self.diastolic,
+            self.mean_arterial_pressure,
+        ] {
+            buf.extend_from_slice(&f32_to_sfloat(value).to_le_bytes());
+        }
+        if let Some(timestamp) = &self.timestamp {
+            timestamp.encode_into(&mut buf);
+        }
+        if let Some(pulse_rate) = self.pulse_rate {
+            buf.extend_from_slice(&f32_to_sfloat(pulse_rate).to_le_bytes());
+        }
+        if let Some(user_id) = self.user_id {
+            buf.push(user_id);
+        }
+        if let Some(status) = self.status {
+            buf.extend_from_slice(&status.to_le_bytes());
+        }
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, zbus::Error> {
+        let mut reader = Reader::new("Blood Pressure Measurement", data);
+        let flags = reader.u8()?;
+        Ok(Self {
+            systolic: reader.sfloat()?,
+            diastolic: reader.sfloat()?,
+            mean_arterial_pressure: reader.sfloat()?,
+            unit: if flags & 0x01 != 0 {
+                PressureUnit::KPa
+            } else {
+                PressureUnit::MmHg
+            },
+            timestamp: if flags & 0x02 != 0 {
+                Some(reader.date_time()?)
+            } else {
+                None
+            },
+            pulse_rate: if flags & 0x04 != 0 {
+                Some(reader.sfloat()?)
+            } else {
+                None
+            },
+            user_id: if flags & 0x08 != 0 {
+                Some(reader.u8()?)
+            } else {
+                None
+            },
+            status: if flags & 0x10 != 0 {
+                Some(reader.u16()?)
+            } else {
+                None
+            },
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WheelRevolutions {
+    pub cumulative: u32,
+    /// Time of the last wheel event in 1/1024 seconds
+    pub last_event_time: u16,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CrankRevolutions {
+    pub cumulative: u16,
+    /// Time of the last crank event in 1/1024 seconds
+    pub last_event_time: u16,
+}
+
+/// CSC Measurement (0x2A5B) of the Cycling Speed and Cadence service
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CscMeasurement {
+    pub wheel: Option<WheelRevolutions>,
+    pub crank: Option<CrankRevolutions>,
+}
+
+impl CscMeasurement {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.wheel.is_some() {
+            flags |= 0x01;
+        }
+        if self.crank.is_some() {
+            flags |= 0x02;
+        }
+        let mut buf = vec![flags];
+        if let Some(wheel) = &self.wheel {
+            buf.extend_from_slice(&wheel.cumulative.to_le_bytes());
+            buf.extend_from_slice(&wheel.last_event_time.to_le_bytes());
+        }
+        if let Some(crank) = &self.crank {
+            buf.extend_from_slice(&crank.cumulative.to_le_bytes());
+            buf.extend_from_slice(&crank.last_event_time.to_le_bytes());
+        }
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, zbus::Error> {
+        let mut reader = Reader::new("CSC Measurement", data);
+        let flags = reader.u8()?;
+        Ok(Self {
+            wheel: if flags & 0x01 != 0 {
+                Some(WheelRevolutions {
+                    cumulative: reader.u32()?,
+                    last_event_time: reader.u16()?,
+                })
+            } else {
+                None
+            },
+            crank: if flags & 0x02 != 0 {
+                Some(CrankRevolutions {
+                    cumulative: reader.u16()?,
+                    last_event_time: reader.u16()?,
+                })
+            } else {
+                None
+            },
+        })
+    }
+}
+
+/// Format types of the Characteristic Presentation Format descriptor
+pub mod format {
+    pub const BOOLEAN: u8 = 0x01;
+    pub const UINT2: u8 = 0x02;
+    pub const UINT4: u8 = 0x03;
+    pub const UINT8: u8 = 0x04;
+    pub const UINT12: u8 = 0x05;
+    pub const UINT16: u8 = 0x06;
+    pub const UINT24: u8 = 0x07;
+    pub const UINT32: u8 = 0x08;
+    pub const UINT48: u8 = 0x09;
+    pub const UINT64: u8 = 0x0a;
+    pub const UINT128: u8 = 0x0b;
+    pub const SINT8: u8 = 0x0c;
+    pub const SINT12: u8 = 0x0d;
+    pub const SINT16: u8 = 0x0e;
+    pub const SINT24: u8 = 0x0f;
+    pub const SINT32: u8 = 0x10;
+    pub const SINT48: u8 = 0x11;
+    pub const SINT64: u8 = 0x12;
+    pub const SINT128: u8 = 0x13;
+    pub const FLOAT32: u8 = 0x14;
+    pub const FLOAT64: u8 = 0x15;
+    /// IEEE-11073 16-bit `SFLOAT`
+    pub const SFLOAT: u8 = 0x16;
+    /// IEEE-11073 32-bit `FLOAT`
+    pub const FLOAT: u8 = 0x17;
+    pub const DUINT16: u8 = 0x18;
+    pub const UTF8S: u8 = 0x19;
+    pub const UTF16S: u8 = 0x1a;
+    pub const STRUCT: u8 = 0x1b;
+}
+
+/// Common units from the Bluetooth SIG assigned numbers
+pub mod unit {
+    pub const UNITLESS: u16 = 0x2700;
+    pub const METRE: u16 = 0x2701;
+    pub const KILOGRAM: u16 = 0x2702;
+    pub const SECOND: u16 = 0x2703;
+    pub const AMPERE: u16 = 0x2704;
+    pub const KELVIN: u16 = 0x2705;
+    pub const HERTZ: u16 = 0x2722;
+    pub const NEWTON: u16 = 0x2723;
+    pub const PASCAL: u16 = 0x2724;
+    pub const JOULE: u16 = 0x2725;
+    pub const WATT: u16 = 0x2726;
+    pub const VOLT: u16 = 0x2728;
+    pub const OHM: u16 = 0x272a;
+    pub const DEGREE_CELSIUS: u16 = 0x272f;
+    pub const PERCENTAGE: u16 = 0x27ad;
+    pub const BEATS_PER_MINUTE: u16 = 0x27af;
+    pub const DEGREE_FAHRENHEIT: u16 = 0x27ac;
+    pub const MILLIMETRE_OF_MERCURY: u16 = 0x2781;
+    pub const DECIBEL: u16 = 0x27c3;
+}
+
+/// Namespace of the `description` field for Bluetooth SIG descriptions
+pub const NAMESPACE_BLUETOOTH_SIG: u8 = 0x01;
+
+/// Characteristic Presentation Format (0x2904), describing how to display
+/// the characteristic value. The value is `raw * 10^exponent` in `unit`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PresentationFormat {
+    /// One of the `format` constants
+    pub format: u8,
+    pub exponent: i8,
+    /// One of the `unit` constants, or another assigned unit UUID
+    pub unit: u16,
+    pub namespace: u8,
+    /// Meaning depends on `namespace`, 0 is unknown
+    pub description: u16,
+}
+
+impl PresentationFormat {
+    /// A format with no exponent in the Bluetooth SIG namespace
+    pub fn new(format: u8, unit: u16) -> Self {
+        Self {
+            format,
+            exponent: 0,
+            unit,
+            namespace: NAMESPACE_BLUETOOTH_SIG,
+            description: 0,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![
+            self.format, self.exponent as u8,
+        ];
+        buf.extend_from_slice(&self.unit.to_le_bytes());
+        buf.push(self.namespace);
+        buf.extend_from_slice(&self.description.to_le_bytes());
+        buf
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, zbus::Error> {
+        let mut reader = Reader::new("Presentation Format", data);
+        Ok(Self {
+            format: reader.u8()?,
+            exponent: reader.u8()? as i8,
+            unit: reader.u16()?,
+            namespace: reader.u8()?,
+            description: reader.u16()?,
+        })
+    }
+}
+
+impl GattValue for PresentationFormat {
+    const SIZE: Option<usize> = Some(7);
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self, zbus::Error> {
+        Self::decode(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sfloat_special_values() {
+        assert!(sfloat_to_f32(0x07ff).is_nan());
+        // NRes and the reserved value
+        assert!(sfloat_to_f32(0x0800).is_nan());
+        assert!(sfloat_to_f32(0x0801).is_nan());
+        assert_eq!(sfloat_to_f32(0x07fe), f32::INFINITY);
+        assert_eq!(sfloat_to_f32(0x0802), f32::NEG_INFINITY);
+        assert_eq!(f32_to_sfloat(f32::NAN), 0x07ff);
+        assert_eq!(f32_to_sfloat(f32::INFINITY), 0x07fe);
+        assert_eq!(f32_to_sfloat(f32::NEG_INFINITY), 0x0802);
+        assert_eq!(f32_to_sfloat(1e20), 0x07fe);
+        assert_eq!(f32_to_sfloat(-1e20), 0x0802);
+    }
+
+    #[test]
+    fn sfloat_vectors() {
+        // 120 mmHg, exponent 0
+        assert_eq!(sfloat_to_f32(0x0078), 120.0);
+        // 36.4, mantissa 364 and exponent -1
+        assert_eq!(sfloat_to_f32(0xf16c), 36.4);
+        // -0.5, mantissa -500 and exponent -3
+        assert_eq!(sfloat_to_f32(0xde0c), -0.5);
+        // 2000000, mantissa 2 and exponent 6
+        assert_eq!(sfloat_to_f32(0x6002), 2_000_000.0);
+        assert_eq!(f32_to_sfloat(-0.5), 0xde0c);
+    }
+
+    #[test]
+    fn sfloat_round_trips() {
+        for value in [0.0, 1.0, -1.0, 36.4, 120.0, -0.5, 2045.0, -2045.0, 0.001, 1e6] {
+            let decoded = sfloat_to_f32(f32_to_sfloat(value));
+            assert!((decoded - value).abs() <= value.abs() * 1e-3, "{value} -> {decoded}");
+        }
+    }
+
+    #[test]
+    fn float_special_values() {
+        assert!(float_to_f32(0x007f_ffff).is_nan());
+        assert!(float_to_f32(0x0080_0000).is_nan());
+        assert!(float_to_f32(0x0080_0001).is_nan());
+        assert_eq!(float_to_f32(0x007f_fffe), f32::INFINITY);
+        assert_eq!(float_to_f32(0x0080_0002), f32::NEG_INFINITY);
+        assert_eq!(f32_to_float(f32::NAN), 0x007f_ffff);
+        assert_eq!(f32_to_float(f32::INFINITY), 0x007f_fffe);
+        assert_eq!(f32_to_float(f32::NEG_INFINITY), 0x0080_0002);
+    }
+
+    #[test]
+    fn float_vectors() {
+        // 36.4, mantissa 364 and exponent -1
+        assert_eq!(float_to_f32(0xff00_016c), 36.4);
+        // -273.15, mantissa -27315 and exponent -2
+        assert_eq!(float_to_f32(0xfeff_954d), -273.15);
+        assert_eq!(float_to_f32(0x0000_0000), 0.0);
+    }
+
+    #[test]
+    fn float_round_trips() {
+        for value in [0.0, 1.0, -1.0, 36.4, -273.15, 98.6, 8_388_605.0, 1e-6] {
+            let decoded = float_to_f32(f32_to_float(value));
+            assert!((decoded - value).abs() <= value.abs() * 1e-6, "{value} -> {decoded}");
+        }
+    }
+
+    #[test]
+    fn heart_rate_vector() {
+        // 72 bpm, contact detected, one RR interval of one second
+        let hrm = HeartRateMeasurement::decode(&[0x16, 0x48, 0x00, 0x04]).unwrap();
+        assert_eq!(
+            hrm,
+            HeartRateMeasurement {
+                bpm: 72,
+                sensor_contact: Some(true),
+                energy_expended: None,
+                rr_intervals: vec![1024],
+            }
+        );
+        // Contact not supported
+        let hrm = HeartRateMeasurement::decode(&[0x00, 0x3c]).unwrap();
+        assert_eq!(hrm.bpm, 60);
+        assert_eq!(hrm.sensor_contact, None);
+    }
+
+    #[test]
+    fn heart_rate_round_trips() {
+        let hrm = HeartRateMeasurement {
+            bpm: 300,
+            sensor_contact: Some(false),
+            energy_expended: Some(1234),
+            rr_intervals: vec![800, 810],
+        };
+        let bytes = hrm.encode();
+        assert_eq!(bytes[0], 0x1d);
+        assert_eq!(HeartRateMeasurement::decode(&bytes).unwrap(), hrm);
+    }
+
+    #[test]
+    fn heart_rate_truncated() {
+        assert!(HeartRateMeasurement::decode(&[]).is_err());
+        // 16-bit bpm with one byte
+        assert!(HeartRateMeasurement::decode(&[0x01, 0x48]).is_err());
+        // Energy expended flagged but missing
+        assert!(HeartRateMeasurement::decode(&[0x08, 0x48]).is_err());
+        // Half an RR interval
+        assert!(HeartRateMeasurement::decode(&[0x10, 0x48, 0x00, 0x04, 0x01]).is_err());
+    }
+
+    #[test]
+    fn temperature_round_trips() {
+        let measurement = TemperatureMeasurement {
+            value: 98.6,
+            unit: TemperatureUnit::Fahrenheit,
+            timestamp: Some(DateTime {
+                year: 2024,
+                month: 5,
+                day: 17,
+                hours: 8,
+                minutes: 30,
+                seconds: 0,
+            }),
+            temperature_type: Some(2),
+        };
+        let bytes = measurement.encode();
+        assert_eq!(bytes.len(), 13);
+        assert_eq!(bytes[0], 0x07);
+        assert_eq!(TemperatureMeasurement::decode(&bytes).unwrap(), measurement);
+        assert!(TemperatureMeasurement::decode(&bytes[..12]).is_err());
+    }
+
+    #[test]
+    fn blood_pressure_vector() {
+        // 120/80 mmHg, MAP 93, pulse 72, user 1
+        let bytes = [
+            0x0c, 0x78, 0x00, 0x50, 0x00, 0x5d, 0x00, 0x48, 0x00, 0x01,
+        ];
+        let measurement = BloodPressureMeasurement::decode(&bytes).unwrap();
+        assert_eq!(
+            measurement,
+            BloodPressureMeasurement {
+                systolic: 120.0,
+                diastolic: 80.0,
+                mean_arterial_pressure: 93.0,
+                unit: PressureUnit::MmHg,
+                timestamp: None,
+                pulse_rate: Some(72.0),
+                user_id: Some(1),
+                status: None,
+            }
+        );
+        assert!(BloodPressureMeasurement::decode(&bytes[..9]).is_err());
+    }
+
+    #[test]
+    fn blood_pressure_round_trips() {
+        let measurement = BloodPressureMeasurement {
+            systolic: 16.0,
+            diastolic: 10.7,
+            mean_arterial_pressure: 12.4,
+            unit: PressureUnit::KPa,
+            timestamp: Some(DateTime::default()),
+            pulse_rate: None,
+            user_id: None,
+            status: Some(0x0004),
+        };
+        let decoded = BloodPressureMeasurement::decode(&measurement.encode()).unwrap();
+        assert_eq!(decoded, measurement);
+    }
+
+    #[test]
+    fn blood_pressure_nan_fields() {
+        // An unavailable mean arterial pressure is sent as NaN
+        let bytes = [0x00, 0x78, 0x00, 0x50, 0x00, 0xff, 0x07];
+        let measurement = BloodPressureMeasurement::decode(&bytes).unwrap();
+        assert!(measurement.mean_arterial_pressure.is_nan());
+    }
+
+    #[test]
+    fn csc_round_trips() {
+        let measurement = CscMeasurement {
+            wheel: Some(WheelRevolutions {
+                cumulative: 0x0102_0304,
+                last_event_time: 0x0506,
+            }),
+            crank: Some(CrankRevolutions {
+                cumulative: 0x0708,
+                last_event_time: 0x090a,
+            }),
+        };
+        let bytes = measurement.encode();
+        assert_eq!(
+            bytes,
+            [0x03, 0x04, 0x03, 0x02, 0x01, 0x06, 0x05, 0x08, 0x07, 0x0a, 0x09]
+        );
+        assert_eq!(CscMeasurement::decode(&bytes).unwrap(), measurement);
+    }
+
+    #[test]
+    fn csc_layout_follows_flags() {
+        // Crank data only starts right after the flags
+        let measurement = CscMeasurement::decode(&[0x02, 0x08, 0x07, 0x0a, 0x09]).unwrap();
+        assert_eq!(measurement.wheel, None);
+        assert_eq!(
+            measurement.crank,
+            Some(CrankRevolutions {
+                cumulative: 0x0708,
+                last_event_time: 0x090a,
+            })
+        );
+        assert!(CscMeasurement::decode(&[0x01, 0x04, 0x03, 0x02, 0x01, 0x06]).is_err());
+    }
+
+    #[test]
+    fn presentation_format_vector() {
+        // sint16 in degrees Celsius with exponent -2
+        let bytes = [0x0e, 0xfe, 0x2f, 0x27, 0x01, 0x00, 0x00];
+        let format = PresentationFormat::decode(&bytes).unwrap();
+        assert_eq!(
+            format,
+            PresentationFormat {
+                format: format::SINT16,
+                exponent: -2,
+                unit: unit::DEGREE_CELSIUS,
+                namespace: NAMESPACE_BLUETOOTH_SIG,
+                description: 0,
+            }
+        );
+        assert_eq!(format.encode(), bytes);
+        assert!(PresentationFormat::decode(&bytes[..6]).is_err());
+    }
+
+    #[test]
+    fn battery_level_range() {
+        assert_eq!(BatteryLevel::decode(&[100]).unwrap(), BatteryLevel(100));
+        assert!(BatteryLevel::decode(&[101]).is_err());
+        assert!(BatteryLevel::decode(&[]).is_err());
+    }
+}
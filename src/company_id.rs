@@ -0,0 +1,156 @@
+//! # Company identifiers
+//!
+//! Bluetooth SIG assigned company identifiers, as used for the keys of
+//! `ManufacturerData` in advertisements and discovery results.
+//!
+//! The table below is a hand-picked subset of the several thousand assigned
+//! numbers, covering the commonly seen companies. Anything else is kept as
+//! `CompanyId::Other` without a name. Equality and hashing go by the
+//! assigned number, so `CompanyId::Other(0x004c)` equals `CompanyId::Apple`.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+macro_rules! company_ids {
+    ($($variant:ident = $id:literal : $name:literal,)*) => {
+        #[derive(Debug, Clone, Copy)]
+        pub enum CompanyId {
+            $($variant,)*
+            /// An identifier not in the table
+            Other(u16),
+        }
+
+        impl CompanyId {
+            /// The assigned number
+            pub const fn id(self) -> u16 {
+                match self {
+                    $(CompanyId::$variant => $id,)*
+                    CompanyId::Other(id) => id,
+                }
+            }
+
+            /// The registered company name, if known
+            pub const fn name(self) -> Option<&'static str> {
+                match self {
+                    $(CompanyId::$variant => Some($name),)*
+                    CompanyId::Other(_) => None,
+                }
+            }
+        }
+
+        impl From<u16> for CompanyId {
+            fn from(id: u16) -> Self {
+                match id {
+                    $($id => CompanyId::$variant,)*
+                    id => CompanyId::Other(id),
+                }
+            }
+        }
+    };
+}
+
+company_ids! {
+    Ericsson = 0x0000 : "Ericsson AB",
+    Nokia = 0x0001 : "Nokia Mobile Phones",
+    Intel = 0x0002 : "Intel Corp.",
+    Ibm = 0x0003 : "IBM Corp.",
+    Toshiba = 0x0004 : "Toshiba Corp.",
+    Microsoft = 0x0006 : "Microsoft",
+    Motorola = 0x0008 : "Motorola",
+    Infineon = 0x0009 : "Infineon Technologies AG",
+    QualcommInternational = 0x000a : "Qualcomm Technologies International, Ltd. (QTIL)",
+    TexasInstruments = 0x000d : "Texas Instruments Inc.",
+    Broadcom = 0x000f : "Broadcom Corporation",
+    Atmel = 0x0013 : "Atmel Corporation",
+    Qualcomm = 0x001d : "Qualcomm",
+    Nxp = 0x0025 : "NXP Semiconductors (formerly Philips Semiconductors)",
+    StMicroelectronics = 0x0030 : "ST Microelectronics",
+    MediaTek = 0x0046 : "MediaTek, Inc.",
+    Marvell = 0x0048 : "Marvell Technology Group Ltd.",
+    Apple = 0x004c : "Apple, Inc.",
+    Harman = 0x0057 : "Harman International Industries, Inc.",
+    Nordic = 0x0059 : "Nordic Semiconductor ASA",
+    Realtek = 0x005d : "Realtek Semiconductor Corporation",
+    Samsung = 0x0075 : "Samsung Electronics Co. Ltd.",
+    Nike = 0x0078 : "Nike, Inc.",
+    Garmin = 0x0087 : "Garmin International, Inc.",
+    Bose = 0x009e : "Bose Corporation",
+    Lg = 0x00c4 : "LG Electronics",
+    Dialog = 0x00d2 : "Dialog Semiconductor B.V.",
+    Google = 0x00e0 : "Google",
+    Cypress = 0x0131 : "Cypress Semiconductor",
+    Huami = 0x0157 : "Anhui Huami Information Technology Co., Ltd.",
+    Amazon = 0x0171 : "Amazon.com Services, LLC",
+    Logitech = 0x01da : "Logitech International SA",
+    Huawei = 0x027d : "HUAWEI Technologies Co., Ltd.",
+    Espressif = 0x02e5 : "Espressif Systems (Shanghai) Co., Ltd.",
+    Xiaomi = 0x038f : "Xiaomi Inc.",
+    Ruuvi = 0x0499 : "Ruuvi Innovations Ltd.",
+    Adafruit = 0x0822 : "Adafruit Industries",
+    Testing = 0xffff : "Reserved for internal use and interoperability tests",
+}
+
+impl PartialEq for CompanyId {
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for CompanyId {}
+
+impl Hash for CompanyId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
+impl From<CompanyId> for u16 {
+    fn from(company: CompanyId) -> Self {
+        company.id()
+    }
+}
+
+impl fmt::Display for CompanyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "{:#06x}", self.id()),
+        }
+    }
+}
+
+/// The registered company name for the assigned number `id`, if known
+pub fn company_name(id: u16) -> Option<&'static str> {
+    CompanyId::from(id).name()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn known_ids_map_to_variants() {
+        assert!(matches!(CompanyId::from(0x004c), CompanyId::Apple));
+        assert_eq!(CompanyId::Apple.id(), 0x004c);
+        assert_eq!(CompanyId::Apple.to_string(), "Apple, Inc.");
+        assert_eq!(company_name(0x0059), Some("Nordic Semiconductor ASA"));
+    }
+
+    #[test]
+    fn unknown_ids_are_other() {
+        assert!(matches!(CompanyId::from(0x1234), CompanyId::Other(0x1234)));
+        assert_eq!(CompanyId::from(0x1234).name(), None);
+        assert_eq!(CompanyId::from(0x1234).to_string(), "0x1234");
+    }
+
+    #[test]
+    fn compares_by_id() {
+        assert_eq!(CompanyId::Other(0x004c), CompanyId::Apple);
+        assert_ne!(CompanyId::Other(0x004d), CompanyId::Apple);
+        let ids = HashSet::from([CompanyId::Apple, CompanyId::Other(0x004c)]);
+        assert_eq!(ids.len(), 1);
+        assert!(ids.contains(&CompanyId::from(0x004c)));
+    }
+}
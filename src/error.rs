@@ -0,0 +1,68 @@
+//! # Errors returned by bluez
+//!
+//! Method calls on bluez objects fail with `org.bluez.Error.*` errors. Convert
+//! the `zbus::Error` from a proxy call with `BluezError::from` to match on the
+//! reason; anything else ends up in `BluezError::ZBus`.
+
+/// `org.bluez.Error.*` errors, with bluez's description of the failure
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.bluez.Error")]
+pub enum BluezError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    Failed(String),
+    InProgress(String),
+    InvalidArguments(String),
+    InvalidLength(String),
+    InvalidOffset(String),
+    InvalidValueLength(String),
+    NotReady(String),
+    NotSupported(String),
+    NotAuthorized(String),
+    NotAvailable(String),
+    NotConnected(String),
+    NotPermitted(String),
+    NotPaired(String),
+    AlreadyConnected(String),
+    AlreadyExists(String),
+    DoesNotExist(String),
+    AuthenticationCanceled(String),
+    AuthenticationFailed(String),
+    AuthenticationRejected(String),
+    AuthenticationTimeout(String),
+    ConnectionAttemptFailed(String),
+    Rejected(String),
+    Canceled(String),
+    ImproperlyConfigured(String),
+}
+
+impl BluezError {
+    /// Bluez is busy with the same operation, retrying later may succeed
+    pub fn is_in_progress(&self) -> bool {
+        match self {
+            BluezError::InProgress(_) => true,
+            BluezError::Failed(desc) => desc.contains("already in progress"),
+            _ => false,
+        }
+    }
+}
+
+impl From<BluezError> for zbus::Error {
+    /// The error as if bluez had replied with it, for failures found before
+    /// calling bluez. `BluezError::from` gives the same variant back.
+    fn from(error: BluezError) -> Self {
+        use zbus::DBusError;
+
+        if let BluezError::ZBus(error) = error {
+            return error;
+        }
+        let reply = zbus::Message::method_call("/", "Call")
+            .and_then(|call| call.build(&()))
+            .and_then(|call| zbus::Message::error(&call.header(), error.name()))
+            .and_then(|reply| reply.build(&(error.description().unwrap_or_default(),)));
+        match reply {
+            Ok(reply) => zbus::Error::from(reply),
+            Err(err) => err,
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use log::error;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::Connection;
+
+use super::gatt::exported;
+use super::AdvertisementMonitor1;
+use crate::proxy::advertisement_monitor_manager1::AdvertisementMonitorManager1Proxy;
+
+/// A set of advertisement monitors registered with
+/// `AdvertisementMonitorManager1`, exported under `path` next to an object
+/// manager
+pub struct AdvertisementMonitorApp {
+    connection: Connection,
+    adapter_path: OwnedObjectPath,
+    path: OwnedObjectPath,
+    monitors: Vec<OwnedObjectPath>,
+}
+
+impl AdvertisementMonitorApp {
+    pub async fn register_new(
+        path: &str,
+        adapter_path: &str,
+        connection: &Connection,
+        monitors: Vec<AdvertisementMonitor1>,
+    ) -> Result<Self, zbus::Error> {
+        let path = OwnedObjectPath::try_from(path)?;
+        let adapter_path = OwnedObjectPath::try_from(adapter_path)?;
+        let server = connection.object_server();
+        let added = server
+            .at(&path, zbus::fdo::ObjectManager)
+            .await
+            .map_err(|err| {
+                error!("{}: add_to_server {}", path.as_str(), err);
+                err
+            })?;
+        exported(&path, added)?;
+
+        let mut monitor_paths = Vec::new();
+        let res = async {
+            for (count, monitor) in monitors.into_iter().enumerate() {
+                let monitor_path =
+                    OwnedObjectPath::try_from(format!("{}/monitor{count}", path.as_str()))?;
+                let added = server.at(&monitor_path, monitor).await.map_err(|err| {
+                    error!("{}: add_to_server {}", monitor_path.as_str(), err);
+                    err
+                })?;
+                exported(&monitor_path, added)?;
+                monitor_paths.push(monitor_path);
+            }
+
+            let proxy = AdvertisementMonitorManager1Proxy::builder(connection)
+                .path(adapter_path.clone())?
+                .build()
+                .await?;
+            proxy.register_monitor(&path).await
+        }
+        .await;
+        if let Err(err) = res {
+            remove_objects(connection, &path, &monitor_paths).await.ok();
+            return Err(err);
+        }
+
+        Ok(Self {
+            connection: connection.clone(),
+            adapter_path,
+            path,
+            monitors: monitor_paths,
+        })
+    }
+
+    pub fn monitors(&self) -> &[OwnedObjectPath] {
+        &self.monitors
+    }
+
+    /// Unregister the monitors from bluez and remove them from the object
+    /// server, even if bluez fails to unregister them. Consumes the handle.
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        let res = async {
+            let proxy = AdvertisementMonitorManager1Proxy::builder(&self.connection)
+                .path(self.adapter_path.clone())?
+                .build()
+                .await?;
+            proxy.unregister_monitor(&self.path).await
+        }
+        .await;
+        remove_objects(&self.connection, &self.path, &self.monitors).await?;
+        res
+    }
+}
+
+/// Remove the monitors, then the object manager at `path`
+async fn remove_objects(
+    connection: &Connection,
+    path: &OwnedObjectPath,
+    monitors: &[OwnedObjectPath],
+) -> Result<(), zbus::Error> {
+    let server = connection.object_server();
+    for monitor in monitors {
+        server.remove::<AdvertisementMonitor1, _>(monitor).await?;
+    }
+    server.remove::<zbus::fdo::ObjectManager, _>(path).await?;
+    Ok(())
+}
@@ -0,0 +1,279 @@
+//! # AdvertisementMonitor1 implementation
+//!
+//! Bluez matches advertisements against the monitor's patterns, optionally
+//! filtered by RSSI, and reports devices coming into and going out of range.
+//! The reports go to a `MonitorEvents` stream.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_channel::mpsc;
+use futures_lite::Stream;
+use log::debug;
+use zbus::interface;
+use zbus::message::Header;
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::trace;
+use crate::unused_property;
+
+/// Lowest RSSI threshold accepted by bluez, in dBm
+pub const RSSI_THRESHOLD_MIN: i16 = -127;
+/// Highest RSSI threshold accepted by bluez, in dBm
+pub const RSSI_THRESHOLD_MAX: i16 = 20;
+/// Longest RSSI timeout accepted by bluez
+pub const RSSI_TIMEOUT_MAX: Duration = Duration::from_secs(300);
+/// Longest RSSI sampling period accepted by bluez. The next step, 0xFF,
+/// means "first advertisement only", see `RssiFilter::first_only()`.
+pub const RSSI_SAMPLING_PERIOD_MAX: Duration = Duration::from_millis(25_400);
+/// `RSSISamplingPeriod` value that propagates only the first advertisement
+const RSSI_SAMPLING_FIRST_ONLY: u16 = 0xFF;
+
+/// RSSI filtering of an `AdvertisementMonitor1`.
+///
+/// A device is found once its RSSI stays at or above the high threshold for
+/// the high timeout, and lost once it stays below the low threshold for the
+/// low timeout.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RssiFilter {
+    high: Option<(i16, u16)>,
+    low: Option<(i16, u16)>,
+    sampling_period: Option<u16>,
+}
+
+impl RssiFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn check_threshold(threshold: i16) -> zbus::fdo::Result<()> {
+        if !(RSSI_THRESHOLD_MIN..=RSSI_THRESHOLD_MAX).contains(&threshold) {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "RSSI threshold {threshold} dBm outside {RSSI_THRESHOLD_MIN}..={RSSI_THRESHOLD_MAX}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_timeout(timeout: Duration) -> zbus::fdo::Result<u16> {
+        let secs = timeout.as_secs();
+        if secs == 0 || timeout > RSSI_TIMEOUT_MAX {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "RSSI timeout {timeout:?} outside 1..=300 seconds"
+            )));
+        }
+        Ok(secs as u16)
+    }
+
+    fn check_order(high: Option<(i16, u16)>, low: Option<(i16, u16)>) -> zbus::fdo::Result<()> {
+        if let (Some((high, _)), Some((low, _))) = (high, low)
+            && low > high
+        {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "RSSI low threshold {low} dBm above high threshold {high} dBm"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Report a device once its RSSI is at least `threshold` dBm for
+    /// `timeout`, in whole seconds from 1 to 300
+    pub fn high(mut self, threshold: i16, timeout: Duration) -> zbus::fdo::Result<Self> {
+        Self::check_threshold(threshold)?;
+        let high = Some((threshold, Self::check_timeout(timeout)?));
+        Self::check_order(high, self.low)?;
+        self.high = high;
+        Ok(self)
+    }
+
+    /// Report a device lost once its RSSI is below `threshold` dBm for
+    /// `timeout`, in whole seconds from 1 to 300
+    pub fn low(mut self, threshold: i16, timeout: Duration) -> zbus::fdo::Result<Self> {
+        Self::check_threshold(threshold)?;
+        let low = Some((threshold, Self::check_timeout(timeout)?));
+        Self::check_order(self.high, low)?;
+        self.low = low;
+        Ok(self)
+    }
+
+    /// Propagate advertisements at most once per `period`, in steps of 100ms
+    /// up to 25.4s. Zero propagates every advertisement.
+    pub fn sampling_period(mut self, period: Duration) -> zbus::fdo::Result<Self> {
+        if period > RSSI_SAMPLING_PERIOD_MAX {
+            return Err(zbus::fdo::Error::InvalidArgs(format!(
+                "RSSI sampling period {period:?} above {RSSI_SAMPLING_PERIOD_MAX:?}"
+            )));
+        }
+        self.sampling_period = Some((period.as_millis() / 100) as u16);
+        Ok(self)
+    }
+
+    /// Propagate only the first advertisement of a device while it stays
+    /// found, instead of sampling periodically
+    pub fn first_only(mut self) -> Self {
+        self.sampling_period = Some(RSSI_SAMPLING_FIRST_ONLY);
+        self
+    }
+}
+
+/// An advertising data pattern: `content` must appear in the AD structure of
+/// `ad_type` at byte `start_position`
+#[derive(Debug, Clone)]
+pub struct MonitorPattern {
+    pub start_position: u8,
+    pub ad_type: u8,
+    pub content: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub enum MonitorEvent {
+    /// Bluez started monitoring
+    Activate,
+    /// Bluez stopped monitoring, e.g. the monitor was invalid
+    Release,
+    /// A device matching the monitor came into range
+    DeviceFound(OwnedObjectPath),
+    /// A device matching the monitor went out of range
+    DeviceLost(OwnedObjectPath),
+}
+
+/// Stream of the events received by an `AdvertisementMonitor1`
+pub struct MonitorEvents {
+    events: mpsc::UnboundedReceiver<MonitorEvent>,
+}
+
+impl Stream for MonitorEvents {
+    type Item = MonitorEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+/// A monitor matching any of its patterns ("or_patterns")
+pub struct AdvertisementMonitor1 {
+    pub patterns: Vec<MonitorPattern>,
+    pub rssi: RssiFilter,
+    events: mpsc::UnboundedSender<MonitorEvent>,
+}
+
+impl AdvertisementMonitor1 {
+    pub fn new(patterns: Vec<MonitorPattern>, rssi: RssiFilter) -> (Self, MonitorEvents) {
+        let (tx, rx) = mpsc::unbounded();
+        (
+            Self {
+                patterns,
+                rssi,
+                events: tx,
+            },
+            MonitorEvents { events: rx },
+        )
+    }
+
+    fn send(&self, event: MonitorEvent) {
+        self.events.unbounded_send(event).ok();
+    }
+}
+
+#[interface(name = "org.bluez.AdvertisementMonitor1")]
+impl AdvertisementMonitor1 {
+    /// Activate method
+    fn activate(&self, #[zbus(header)] header: Header<'_>) {
+        let _span = trace::enter(&header, "Activate");
+        debug!("AdvertisementMonitor1: activate");
+        self.send(MonitorEvent::Activate);
+    }
+
+    /// DeviceFound method
+    fn device_found(&self, #[zbus(header)] header: Header<'_>, device: OwnedObjectPath) {
+        let _span = trace::enter(&header, "DeviceFound");
+        debug!("AdvertisementMonitor1: device_found: {}", device.as_str());
+        self.send(MonitorEvent::DeviceFound(device));
+    }
+
+    /// DeviceLost method
+    fn device_lost(&self, #[zbus(header)] header: Header<'_>, device: OwnedObjectPath) {
+        let _span = trace::enter(&header, "DeviceLost");
+        debug!("AdvertisementMonitor1: device_lost: {}", device.as_str());
+        self.send(MonitorEvent::DeviceLost(device));
+    }
+
+    /// Release method
+    fn release(&self, #[zbus(header)] header: Header<'_>) {
+        let _span = trace::enter(&header, "Release");
+        debug!("AdvertisementMonitor1: release");
+        self.send(MonitorEvent::Release);
+    }
+
+    /// Patterns property
+    #[zbus(property)]
+    fn patterns(&self) -> zbus::fdo::Result<Vec<(u8, u8, Vec<u8>)>> {
+        Ok(self
+            .patterns
+            .iter()
+            .map(|p| (p.start_position, p.ad_type, p.content.clone()))
+            .collect())
+    }
+
+    /// RSSIHighThreshold property
+    #[zbus(property, name = "RSSIHighThreshold")]
+    fn rssi_high_threshold(&self) -> zbus::fdo::Result<i16> {
+        self.rssi.high.map_or_else(
+            || {
+                unused_property!("rssi_high_threshold", "AdvertisementMonitor1");
+            },
+            |(threshold, _)| Ok(threshold),
+        )
+    }
+
+    /// RSSIHighTimeout property
+    #[zbus(property, name = "RSSIHighTimeout")]
+    fn rssi_high_timeout(&self) -> zbus::fdo::Result<u16> {
+        self.rssi.high.map_or_else(
+            || {
+                unused_property!("rssi_high_timeout", "AdvertisementMonitor1");
+            },
+            |(_, timeout)| Ok(timeout),
+        )
+    }
+
+    /// RSSILowThreshold property
+    #[zbus(property, name = "RSSILowThreshold")]
+    fn rssi_low_threshold(&self) -> zbus::fdo::Result<i16> {
+        self.rssi.low.map_or_else(
+            || {
+                unused_property!("rssi_low_threshold", "AdvertisementMonitor1");
+            },
+            |(threshold, _)| Ok(threshold),
+        )
+    }
+
+    /// RSSILowTimeout property
+    #[zbus(property, name = "RSSILowTimeout")]
+    fn rssi_low_timeout(&self) -> zbus::fdo::Result<u16> {
+        self.rssi.low.map_or_else(
+            || {
+                unused_property!("rssi_low_timeout", "AdvertisementMonitor1");
+            },
+            |(_, timeout)| Ok(timeout),
+        )
+    }
+
+    /// RSSISamplingPeriod property
+    #[zbus(property, name = "RSSISamplingPeriod")]
+    fn rssi_sampling_period(&self) -> zbus::fdo::Result<u16> {
+        self.rssi.sampling_period.map_or_else(
+            || {
+                unused_property!("rssi_sampling_period", "AdvertisementMonitor1");
+            },
+            Ok,
+        )
+    }
+
+    /// Type property
+    #[zbus(property, name = "Type")]
+    fn type_(&self) -> zbus::fdo::Result<String> {
+        Ok("or_patterns".to_owned())
+    }
+}
@@ -0,0 +1,115 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use log::error;
+use zbus::names::OwnedBusName;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::Connection;
+
+use super::gatt::exported;
+use super::{Agent1, AgentCapability, AgentHandler};
+use crate::bus::BluezBus;
+use crate::proxy::agent_manager1::AgentManager1Proxy;
+use crate::restart::{Registered, Registration};
+
+/// Handle to an agent registered with `AgentManager1`
+pub struct AgentHandle<H> {
+    connection: Connection,
+    destination: OwnedBusName,
+    path: OwnedObjectPath,
+    registered: Arc<Registered>,
+    handler: PhantomData<fn() -> H>,
+}
+
+impl<H: AgentHandler> AgentHandle<H> {
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    /// For `RestartWatcher::track()`
+    pub fn registration(&self) -> Registration {
+        Registration::new(&self.registered)
+    }
+
+    /// Unregister the agent from bluez and remove it from the object server
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        agent_manager(&self.connection, &self.destination)
+            .await?
+            .unregister_agent(&self.path)
+            .await?;
+        self.connection
+            .object_server()
+            .remove::<Agent1<H>, _>(&self.path)
+            .await?;
+        Ok(())
+    }
+}
+
+async fn agent_manager(
+    connection: &Connection,
+    destination: &OwnedBusName,
+) -> Result<AgentManager1Proxy<'static>, zbus::Error> {
+    AgentManager1Proxy::builder(connection)
+        .destination(destination.clone())?
+        .path("/org/bluez")?
+        .build()
+        .await
+}
+
+impl<H: AgentHandler> Agent1<H> {
+    /// Export the agent at `path` and register it with `capability`. If
+    /// `default` is set the agent is also requested as the system default
+    /// agent.
+    pub async fn register(
+        mut self,
+        path: &str,
+        capability: AgentCapability,
+        default: bool,
+        bus: impl Into<BluezBus>,
+    ) -> Result<AgentHandle<H>, zbus::Error> {
+        let bus = bus.into();
+        let connection = bus.connection();
+        let path = OwnedObjectPath::try_from(path)?;
+        self.bus = Some(bus.clone());
+        let added = connection
+            .object_server()
+            .at(&path, self)
+            .await
+            .map_err(|err| {
+                error!("{}: add_to_server {}", path.as_str(), err);
+                err
+            })?;
+        exported(&path, added)?;
+
+        let res = async {
+            let proxy = agent_manager(connection, bus.destination()).await?;
+            proxy.register_agent(&path, capability.into()).await?;
+            if default && let Err(err) = proxy.request_default_agent(&path).await {
+                proxy.unregister_agent(&path).await.ok();
+                return Err(err);
+            }
+            Ok(())
+        }
+        .await;
+        if let Err(err) = res {
+            connection
+                .object_server()
+                .remove::<Agent1<H>, _>(&path)
+                .await
+                .ok();
+            return Err(err);
+        }
+
+        Ok(AgentHandle {
+            connection: connection.clone(),
+            destination: bus.destination().clone(),
+            registered: Arc::new(Registered::Agent {
+                path: path.clone(),
+                capability,
+                default,
+            }),
+            path,
+            handler: PhantomData,
+        })
+    }
+}
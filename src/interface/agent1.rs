@@ -0,0 +1,392 @@
+//! # Agent1 implementation
+//!
+//! Bluez calls the agent while pairing and when a device asks to use a
+//! service. The requests are forwarded to the `AgentHandler` the agent was
+//! created with; the defaults reject everything.
+
+use std::future::Future;
+use std::sync::Arc;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+use std::time::Duration;
+
+use log::{debug, warn};
+use zbus::interface;
+use zbus::message::Header;
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::bus::BluezBus;
+use crate::enum_impl_to_from_str;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+use crate::proxy::device1::Device1Proxy;
+use crate::trace;
+
+enum_impl_to_from_str! {
+    AgentCapability, {
+        DisplayOnly : "DisplayOnly",
+        DisplayYesNo : "DisplayYesNo",
+        KeyboardOnly : "KeyboardOnly",
+        NoInputNoOutput : "NoInputNoOutput",
+        KeyboardDisplay : "KeyboardDisplay",
+    }
+}
+
+/// Errors an agent replies to bluez with
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.bluez.Error")]
+pub enum AgentError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    /// The request was rejected
+    Rejected(String),
+    /// The request was canceled
+    Canceled(String),
+}
+
+/// Application callbacks for an `Agent1`
+pub trait AgentHandler: Send + Sync + 'static {
+    /// Bluez unregistered the agent
+    fn release(&self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Return the PIN code (1-16 characters) to pair with `device`
+    fn request_pin_code(
+        &self,
+        _device: OwnedObjectPath,
+    ) -> impl Future<Output = Result<String, AgentError>> + Send {
+        async { Err(AgentError::Rejected("PIN code not supported".to_owned())) }
+    }
+
+    /// Show `pincode` so it can be entered on `device`
+    fn display_pin_code(
+        &self,
+        _device: OwnedObjectPath,
+        _pincode: String,
+    ) -> impl Future<Output = Result<(), AgentError>> + Send {
+        async { Err(AgentError::Rejected("PIN code not supported".to_owned())) }
+    }
+
+    /// Return the passkey (0-999999) to pair with `device`
+    fn request_passkey(
+        &self,
+        _device: OwnedObjectPath,
+    ) -> impl Future<Output = Result<u32, AgentError>> + Send {
+        async { Err(AgentError::Rejected("Passkey not supported".to_owned())) }
+    }
+
+    /// Show `passkey`, `entered` is the number of digits typed on `device` so
+    /// far
+    fn display_passkey(
+        &self,
+        _device: OwnedObjectPath,
+        _passkey: u32,
+        _entered: u16,
+    ) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Confirm that `passkey` is the one shown on `device`
+    fn request_confirmation(
+        &self,
+        _device: OwnedObjectPath,
+        _passkey: u32,
+    ) -> impl Future<Output = Result<(), AgentError>> + Send {
+        async {
+            Err(AgentError::Rejected(
+                "Confirmation not supported".to_owned(),
+            ))
+        }
+    }
+
+    /// Authorize pairing with `device` when no passkey is involved
+    fn request_authorization(
+        &self,
+        _device: OwnedObjectPath,
+    ) -> impl Future<Output = Result<(), AgentError>> + Send {
+        async {
+            Err(AgentError::Rejected(
+                "Authorization not supported".to_owned(),
+            ))
+        }
+    }
+
+    /// Authorize `device` to connect to the service `uuid`
+    fn authorize_service(
+        &self,
+        _device: OwnedObjectPath,
+        _uuid: String,
+    ) -> impl Future<Output = Result<(), AgentError>> + Send {
+        async { Err(AgentError::Rejected("Service not authorized".to_owned())) }
+    }
+
+    /// The last request was canceled by bluez, e.g. on timeout
+    fn cancel(&self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Auto-trust ran for `device` after it paired, see
+    /// `Agent1::with_auto_trust()`. The default logs a failure.
+    fn trusted(
+        &self,
+        device: OwnedObjectPath,
+        result: Result<(), zbus::Error>,
+    ) -> impl Future<Output = ()> + Send {
+        async move {
+            if let Err(err) = result {
+                warn!("Agent1: {}: auto-trust {err}", device.as_str());
+            }
+        }
+    }
+}
+
+/// How long to wait for a device the agent approved to become `Paired`
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+const PAIRED_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct Agent1<H> {
+    handler: Arc<H>,
+    /// `Some(wake_allowed)` to trust devices once they pair
+    auto_trust: Option<bool>,
+    /// Set by `register()`
+    pub(super) bus: Option<BluezBus>,
+}
+
+impl<H: AgentHandler> Agent1<H> {
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler: Arc::new(handler),
+            auto_trust: None,
+            bus: None,
+        }
+    }
+
+    /// Mark a device `Trusted` once it pairs through this agent, so it can
+    /// reconnect without authorization. Every pairing method counts, from
+    /// legacy PIN codes to just works, as long as the handler accepted it.
+    /// With `wake_allowed` it may also wake the host. The outcome is passed
+    /// to `AgentHandler::trusted()`.
+    pub fn with_auto_trust(mut self, wake_allowed: bool) -> Self {
+        self.auto_trust = Some(wake_allowed);
+        self
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+
+    /// If auto-trust is on, trust `device` once bluez reports it `Paired`.
+    /// Accepting a request only lets pairing go on, it can still fail, so
+    /// nothing is trusted before the bond exists.
+    fn trust_once_paired(&self, device: &OwnedObjectPath) {
+        #[cfg(any(feature = "async-io", feature = "tokio"))]
+        if let (Some(wake_allowed), Some(bus)) = (self.auto_trust, &self.bus) {
+            let handler = self.handler.clone();
+            let task = trust_once_paired(bus.clone(), device.clone(), wake_allowed, handler);
+            bus.connection()
+                .executor()
+                .spawn(task, "bluez-zbus agent auto-trust")
+                .detach();
+        }
+        #[cfg(not(any(feature = "async-io", feature = "tokio")))]
+        let _ = device;
+    }
+}
+
+/// Wait for `device` to become `Paired`, then set `Trusted` and, if
+/// `wake_allowed`, `WakeAllowed`. Gives up quietly if the device doesn't pair
+/// within `PAIRED_TIMEOUT` or goes away.
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+async fn trust_once_paired<H: AgentHandler>(
+    bus: BluezBus,
+    device: OwnedObjectPath,
+    wake_allowed: bool,
+    handler: Arc<H>,
+) {
+    use futures_lite::{StreamExt, future};
+
+    let proxy = match device_proxy(&bus, &device).await {
+        Ok(proxy) => proxy,
+        Err(err) => return handler.trusted(device, Err(err)).await,
+    };
+    let mut changes = proxy.receive_paired_changed().await;
+    let paired = future::or(
+        async {
+            while let Some(changed) = changes.next().await {
+                if changed.get().await.unwrap_or_default() {
+                    return true;
+                }
+            }
+            false
+        },
+        async {
+            crate::rt::sleep(PAIRED_TIMEOUT).await;
+            false
+        },
+    )
+    .await;
+    if !paired {
+        debug!("Agent1: {} did not pair, not trusted", device.as_str());
+        return;
+    }
+    let mut result = proxy.set_trusted(true).await;
+    if result.is_ok() && wake_allowed {
+        result = proxy.set_wake_allowed(true).await;
+    }
+    handler.trusted(device, result).await;
+}
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+async fn device_proxy(
+    bus: &BluezBus,
+    device: &OwnedObjectPath,
+) -> Result<Device1Proxy<'static>, zbus::Error> {
+    Device1Proxy::builder(bus.connection())
+        .destination(bus.destination().clone())?
+        .path(device.clone())?
+        .build()
+        .await
+}
+
+#[interface(name = "org.bluez.Agent1")]
+impl<H: AgentHandler> Agent1<H> {
+    /// AuthorizeService method
+    async fn authorize_service(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+        uuid: String,
+    ) -> Result<(), AgentError> {
+        trace::handle(&header, "AuthorizeService", async move {
+            debug!("Agent1: authorize_service: {} {uuid}", device.as_str());
+            self.handler.authorize_service(device, uuid).await
+        })
+        .await
+    }
+
+    /// Cancel method
+    async fn cancel(&self, #[zbus(header)] header: Header<'_>) {
+        trace::handle(&header, "Cancel", async move {
+            debug!("Agent1: cancel");
+            self.handler.cancel().await;
+        })
+        .await
+    }
+
+    /// DisplayPasskey method
+    async fn display_passkey(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+        passkey: u32,
+        entered: u16,
+    ) {
+        trace::handle(&header, "DisplayPasskey", async move {
+            debug!("Agent1: display_passkey: {}", device.as_str());
+            // Called again for every digit typed, watch only once
+            if entered == 0 {
+                self.trust_once_paired(&device);
+            }
+            self.handler.display_passkey(device, passkey, entered).await;
+        })
+        .await
+    }
+
+    /// DisplayPinCode method
+    async fn display_pin_code(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+        pincode: String,
+    ) -> Result<(), AgentError> {
+        trace::handle(&header, "DisplayPinCode", async move {
+            debug!("Agent1: display_pin_code: {}", device.as_str());
+            let res = self.handler.display_pin_code(device.clone(), pincode).await;
+            if res.is_ok() {
+                self.trust_once_paired(&device);
+            }
+            res
+        })
+        .await
+    }
+
+    /// Release method
+    async fn release(&self, #[zbus(header)] header: Header<'_>) {
+        trace::handle(&header, "Release", async move {
+            debug!("Agent1: release");
+            self.handler.release().await;
+        })
+        .await
+    }
+
+    /// RequestAuthorization method
+    async fn request_authorization(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+    ) -> Result<(), AgentError> {
+        trace::handle(&header, "RequestAuthorization", async move {
+            debug!("Agent1: request_authorization: {}", device.as_str());
+            let res = self.handler.request_authorization(device.clone()).await;
+            if res.is_ok() {
+                self.trust_once_paired(&device);
+            }
+            res
+        })
+        .await
+    }
+
+    /// RequestConfirmation method
+    async fn request_confirmation(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+        passkey: u32,
+    ) -> Result<(), AgentError> {
+        trace::handle(&header, "RequestConfirmation", async move {
+            debug!("Agent1: request_confirmation: {}", device.as_str());
+            let res = self
+                .handler
+                .request_confirmation(device.clone(), passkey)
+                .await;
+            if res.is_ok() {
+                self.trust_once_paired(&device);
+            }
+            res
+        })
+        .await
+    }
+
+    /// RequestPasskey method
+    async fn request_passkey(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+    ) -> Result<u32, AgentError> {
+        trace::handle(&header, "RequestPasskey", async move {
+            debug!("Agent1: request_passkey: {}", device.as_str());
+            let res = self.handler.request_passkey(device.clone()).await;
+            if res.is_ok() {
+                self.trust_once_paired(&device);
+            }
+            res
+        })
+        .await
+    }
+
+    /// RequestPinCode method
+    async fn request_pin_code(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+    ) -> Result<String, AgentError> {
+        trace::handle(&header, "RequestPinCode", async move {
+            debug!("Agent1: request_pin_code: {}", device.as_str());
+            let res = self.handler.request_pin_code(device.clone()).await;
+            if res.is_ok() {
+                self.trust_once_paired(&device);
+            }
+            res
+        })
+        .await
+    }
+}
@@ -0,0 +1,96 @@
+use std::collections::HashSet;
+
+use log::info;
+use zbus::zvariant::OwnedObjectPath;
+
+use super::{AgentError, AgentHandler};
+use crate::address::BDAddr;
+
+/// An `AgentHandler` for headless devices that accepts every pairing and
+/// service authorization request, optionally only from allowed addresses.
+///
+/// Register the agent with `AgentCapability::NoInputNoOutput` so bluez uses
+/// "Just Works" pairing.
+#[derive(Debug, Default, Clone)]
+pub struct NoInputNoOutputAgent {
+    allow_list: Option<HashSet<BDAddr>>,
+}
+
+impl NoInputNoOutputAgent {
+    /// Accept requests from every device
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept requests only from devices with one of the `addresses`
+    pub fn with_allow_list(addresses: impl IntoIterator<Item = BDAddr>) -> Self {
+        Self {
+            allow_list: Some(addresses.into_iter().collect()),
+        }
+    }
+
+    fn check(&self, device: &OwnedObjectPath) -> Result<(), AgentError> {
+        let Some(allow_list) = &self.allow_list else {
+            return Ok(());
+        };
+        match BDAddr::from_device_path(device) {
+            Ok(address) if allow_list.contains(&address) => Ok(()),
+            _ => Err(AgentError::Rejected(format!(
+                "{} is not in the allow list",
+                device.as_str()
+            ))),
+        }
+    }
+}
+
+impl AgentHandler for NoInputNoOutputAgent {
+    async fn request_confirmation(
+        &self,
+        device: OwnedObjectPath,
+        _passkey: u32,
+    ) -> Result<(), AgentError> {
+        self.check(&device)?;
+        info!(
+            "NoInputNoOutputAgent: confirmed pairing with {}",
+            device.as_str()
+        );
+        Ok(())
+    }
+
+    async fn request_authorization(&self, device: OwnedObjectPath) -> Result<(), AgentError> {
+        self.check(&device)?;
+        info!(
+            "NoInputNoOutputAgent: authorized pairing with {}",
+            device.as_str()
+        );
+        Ok(())
+    }
+
+    async fn authorize_service(
+        &self,
+        device: OwnedObjectPath,
+        uuid: String,
+    ) -> Result<(), AgentError> {
+        self.check(&device)?;
+        info!(
+            "NoInputNoOutputAgent: authorized {uuid} for {}",
+            device.as_str()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+impl NoInputNoOutputAgent {
+    /// Export the agent at `path` and register it as the default agent with
+    /// `AgentCapability::NoInputNoOutput`
+    pub async fn register(
+        self,
+        path: &str,
+        bus: impl Into<crate::bus::BluezBus>,
+    ) -> Result<super::AgentHandle<Self>, zbus::Error> {
+        super::Agent1::new(self)
+            .register(path, super::AgentCapability::NoInputNoOutput, true, bus)
+            .await
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use log::{debug, error};
+use zbus::object_server::InterfaceRef;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::Connection;
+
+use super::BatteryProvider1;
+use crate::proxy::battery_provider_manager1::BatteryProviderManager1Proxy;
+
+/// Handle to one battery exported by a `BatteryProvider`
+pub struct BatteryHandle {
+    interface: InterfaceRef<BatteryProvider1>,
+    connection: Connection,
+    path: OwnedObjectPath,
+}
+
+impl BatteryHandle {
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    pub fn zbus(&self) -> &InterfaceRef<BatteryProvider1> {
+        &self.interface
+    }
+
+    /// Update the battery level and notify bluez of the change
+    pub async fn set_percentage(&self, percentage: u8) -> Result<(), zbus::Error> {
+        let mut battery = self.interface.get_mut().await;
+        battery.percentage = percentage;
+        battery
+            .percentage_changed(self.interface.signal_emitter())
+            .await
+    }
+
+    /// Remove the battery, bluez drops it from the device
+    pub async fn remove(self) -> Result<(), zbus::Error> {
+        self.connection
+            .object_server()
+            .remove::<BatteryProvider1, _>(&self.path)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A battery provider registered with `BatteryProviderManager1`
+pub struct BatteryProvider {
+    connection: Connection,
+    adapter_path: OwnedObjectPath,
+    path: OwnedObjectPath,
+    count: AtomicUsize,
+}
+
+impl BatteryProvider {
+    /// Export an object manager at `path` and register it as a battery
+    /// provider on the adapter at `adapter_path`
+    pub async fn register_new(
+        path: &str,
+        adapter_path: &str,
+        connection: &Connection,
+    ) -> Result<Self, zbus::Error> {
+        let path = OwnedObjectPath::try_from(path)?;
+        let adapter_path = OwnedObjectPath::try_from(adapter_path)?;
+        connection
+            .object_server()
+            .at(&path, zbus::fdo::ObjectManager)
+            .await
+            .map_err(|err| {
+                error!("{}: add_to_server {}", path.as_str(), err);
+                err
+            })?;
+
+        let proxy = BatteryProviderManager1Proxy::builder(connection)
+            .path(adapter_path.clone())?
+            .build()
+            .await?;
+        proxy.register_battery_provider(&path).await?;
+
+        Ok(Self {
+            connection: connection.clone(),
+            adapter_path,
+            path,
+            count: Default::default(),
+        })
+    }
+
+    /// Export a battery, `battery.device` selects the device it belongs to
+    pub async fn add_battery(
+        &self,
+        battery: BatteryProvider1,
+    ) -> Result<BatteryHandle, zbus::Error> {
+        let count = self.count.fetch_add(1, Ordering::Relaxed);
+        let path = OwnedObjectPath::try_from(format!("{}/battery{count}", self.path.as_str()))?;
+        debug!(
+            "BatteryProvider1: Added battery for {}",
+            battery.device.as_str()
+        );
+        self.connection
+            .object_server()
+            .at(&path, battery)
+            .await
+            .map_err(|err| {
+                error!("{}: add_to_server {}", path.as_str(), err);
+                err
+            })?;
+        let interface = self
+            .connection
+            .object_server()
+            .interface::<_, BatteryProvider1>(&path)
+            .await?;
+        Ok(BatteryHandle {
+            interface,
+            connection: self.connection.clone(),
+            path,
+        })
+    }
+
+    /// Unregister the battery provider from bluez, consuming the handle
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        let proxy = BatteryProviderManager1Proxy::builder(&self.connection)
+            .path(self.adapter_path.clone())?
+            .build()
+            .await?;
+        proxy.unregister_battery_provider(&self.path).await
+    }
+}
@@ -0,0 +1,59 @@
+//! # BatteryProvider1 implementation
+//!
+//! A battery provider is an object tree rooted at a path implementing
+//! `org.freedesktop.DBus.ObjectManager`, with one `org.bluez.BatteryProvider1`
+//! object per device battery. Bluez follows the tree through
+//! `InterfacesAdded`/`InterfacesRemoved` and `PropertiesChanged`, and exposes
+//! the values as `org.bluez.Battery1` on the matching device.
+//!
+//! ```ignore
+//! -> /com/example/battery
+//!   |   - org.freedesktop.DBus.ObjectManager
+//!   |
+//!   -> /com/example/battery/battery0
+//!       - org.freedesktop.DBus.Properties
+//!       - org.bluez.BatteryProvider1
+//! ```
+
+use log::debug;
+use zbus::interface;
+use zbus::zvariant::OwnedObjectPath;
+
+use crate::unused_property;
+
+#[derive(Debug, Default)]
+pub struct BatteryProvider1 {
+    /// The percentage of battery left as an unsigned 8-bit integer
+    pub percentage: u8,
+    /// Describes where the battery information comes from, e.g. "HFP 1.7"
+    pub source: Option<String>,
+    /// The object path of the device that has this battery
+    pub device: OwnedObjectPath,
+}
+
+#[interface(name = "org.bluez.BatteryProvider1")]
+impl BatteryProvider1 {
+    /// Device property
+    #[zbus(property)]
+    fn device(&self) -> zbus::fdo::Result<OwnedObjectPath> {
+        Ok(self.device.clone())
+    }
+
+    /// Percentage property
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::fdo::Result<u8> {
+        debug!("BatteryProvider1: percentage: {}", self.percentage);
+        Ok(self.percentage)
+    }
+
+    /// Source property
+    #[zbus(property)]
+    fn source(&self) -> zbus::fdo::Result<String> {
+        self.source.as_ref().map_or_else(
+            || {
+                unused_property!("source", "BatteryProvider1");
+            },
+            |source| Ok(source.clone()),
+        )
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use uuid::Uuid;
+use zbus::zvariant::{self, ObjectPath};
+
+use super::{device, GattError, GattOperation};
+
+/// Decides which devices may use a characteristic or descriptor, e.g. only
+/// paired and trusted ones or those on an application allow-list. `device`
+/// is `None` if bluez did not say which device sent the request. A denied
+/// request fails with `GattError::NotAuthorized`.
+///
+/// The policy runs while the request is being served, so it should answer
+/// from state the application already has instead of asking bluez.
+pub trait AccessPolicy: Send + Sync + 'static {
+    /// Whether `device` may perform `operation` on the characteristic or
+    /// descriptor `uuid`
+    fn allow(&self, device: Option<&ObjectPath<'_>>, operation: GattOperation, uuid: Uuid) -> bool;
+}
+
+impl<F> AccessPolicy for F
+where
+    F: Fn(Option<&ObjectPath<'_>>, GattOperation, Uuid) -> bool + Send + Sync + 'static,
+{
+    fn allow(&self, device: Option<&ObjectPath<'_>>, operation: GattOperation, uuid: Uuid) -> bool {
+        self(device, operation, uuid)
+    }
+}
+
+/// The optional `AccessPolicy` of a characteristic or descriptor
+#[derive(Clone, Default)]
+pub(crate) struct AccessHook(Option<Arc<dyn AccessPolicy>>);
+
+impl fmt::Debug for AccessHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("AccessHook")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl AccessHook {
+    pub(crate) fn new(policy: Arc<dyn AccessPolicy>) -> Self {
+        Self(Some(policy))
+    }
+
+    /// Ask the policy about the device in `options`
+    pub(crate) fn check(
+        &self,
+        uuid: Uuid,
+        operation: GattOperation,
+        options: &HashMap<&str, zvariant::Value<'_>>,
+    ) -> Result<(), GattError> {
+        let Some(policy) = &self.0 else {
+            return Ok(());
+        };
+        let device = device(options);
+        if policy.allow(device.as_deref(), operation, uuid) {
+            Ok(())
+        } else {
+            Err(GattError::NotAuthorized(format!(
+                "{operation:?} on {uuid} denied"
+            )))
+        }
+    }
+}
@@ -1,136 +1,386 @@
 // GattApplication1
 
 use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use log::error;
+use uuid::Uuid;
 use zbus::interface;
-use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+use zbus::names::OwnedBusName;
+use zbus::object_server::InterfaceRef;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
 use zbus::Connection;
 
 use super::characteristic1::GattCharacteristic1;
 use super::service1::{GattService1, GattServiceHandle};
-use super::GattDescriptor1;
+use super::{exported, ChildPaths, GattDescriptor1, PropertyMap};
+use crate::bus::BluezBus;
 use crate::proxy::gatt_manager1::GattManager1Proxy;
+use crate::restart::{Registered, Registration};
 
-/// Mapped values to properties under this service
-type Properties = HashMap<String, OwnedValue>;
 /// Map of all the services under this path
-type Services = HashMap<String, Properties>;
+type Services = HashMap<String, PropertyMap>;
 /// Map of all the pathes used by this application for gatts
 type ManagedObjects = HashMap<OwnedObjectPath, Services>;
+/// The `GetManagedObjects` reply, shared by the application and its handle.
+/// Replies hold the inner `Arc` so changes only copy the map while one is
+/// being sent.
+type SharedObjects = Arc<Mutex<Arc<ManagedObjects>>>;
+
+/// The objects exported for `service`, with their interfaces and properties
+fn service_objects(service: &GattServiceHandle) -> Vec<(OwnedObjectPath, Services)> {
+    let object = |interface: &str, props| HashMap::from([(interface.to_string(), props)]);
+    let mut objects = vec![(
+        service.owned_path(),
+        object("org.bluez.GattService1", service.property_map().clone()),
+    )];
+    for char in service.characteristics().values() {
+        objects.push((
+            char.owned_path(),
+            object("org.bluez.GattCharacteristic1", char.property_map().clone()),
+        ));
+        for desc in char.descriptors().values() {
+            objects.push((
+                desc.owned_path(),
+                object("org.bluez.GattDescriptor1", desc.property_map().clone()),
+            ));
+        }
+    }
+    objects
+}
+
+/// Remove one object listed by `service_objects()` from `server`. An object
+/// that is already gone counts as removed.
+async fn remove_object(
+    server: &zbus::ObjectServer,
+    path: &OwnedObjectPath,
+    interfaces: &Services,
+) -> Result<(), zbus::Error> {
+    let res = match interfaces.keys().next().map(String::as_str) {
+        Some("org.bluez.GattService1") => server.remove::<GattService1, _>(path).await,
+        Some("org.bluez.GattCharacteristic1") => {
+            server.remove::<GattCharacteristic1, _>(path).await
+        }
+        _ => server.remove::<GattDescriptor1, _>(path).await,
+    };
+    match res {
+        Ok(_) | Err(zbus::Error::InterfaceNotFound) => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Clear the handles of a service, bluez only takes them with
+/// `--experimental`
+fn clear_handles(
+    service: &mut GattService1,
+    characteristics: &mut [(GattCharacteristic1, Vec<GattDescriptor1>)],
+) {
+    service.handle = None;
+    for (char, descriptors) in characteristics {
+        char.handle = None;
+        for desc in descriptors {
+            desc.handle = None;
+        }
+    }
+}
 
 pub struct GattApplicationHandle {
     connection: Connection,
+    destination: OwnedBusName,
     services: Vec<GattServiceHandle>,
     path: OwnedObjectPath,
+    adapter_path: OwnedObjectPath,
+    interface: InterfaceRef<GattApplication1>,
+    registered: Arc<Registered>,
+    managed_objects: SharedObjects,
+    paths: ChildPaths,
+    experimental: bool,
 }
 
 impl GattApplicationHandle {
-    // TODO: use stored device path + actual used path for self
-    pub async fn unregister(&self) -> Result<(), zbus::Error> {
-        let proxy = GattManager1Proxy::builder(&self.connection)
-            .path("/org/bluez/hci0")?
-            .build()
-            .await?;
-        proxy.unregister_application(&self.path).await
+    /// Unregister the application from bluez and remove its objects from the
+    /// object server, even if bluez fails to unregister it. Consumes the
+    /// handle, so a `RestartWatcher` tracking it stops registering it again.
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        let res = async {
+            let proxy = GattManager1Proxy::builder(&self.connection)
+                .destination(self.destination.clone())?
+                .path(self.adapter_path.clone())?
+                .build()
+                .await?;
+            proxy.unregister_application(&self.path).await
+        }
+        .await;
+
+        // Keep going past failures so one stuck object doesn't leave the
+        // rest exported, and report the first error
+        let server = self.connection.object_server();
+        let mut removed = Ok(());
+        for service in &self.services {
+            for (path, interfaces) in service_objects(service).iter().rev() {
+                removed = removed.and(remove_object(server, path, interfaces).await);
+            }
+        }
+        let app = server.remove::<GattApplication1, _>(&self.path).await;
+        res.and(removed).and(app.map(|_| ()))
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    /// The adapter the application is registered with
+    pub fn adapter_path(&self) -> &ObjectPath<'_> {
+        &self.adapter_path
+    }
+
+    /// The `ObjectManager` of the application
+    pub fn zbus(&self) -> &InterfaceRef<GattApplication1> {
+        &self.interface
     }
 
     pub fn services(&self) -> &[GattServiceHandle] {
         &self.services
     }
+
+    /// For `RestartWatcher::track()`
+    pub fn registration(&self) -> Registration {
+        Registration::new(&self.registered)
+    }
+
+    /// Export another service and announce it with `InterfacesAdded`
+    pub async fn add_service(
+        &mut self,
+        mut service: GattService1,
+        mut characteristics: Vec<(GattCharacteristic1, Vec<GattDescriptor1>)>,
+    ) -> Result<&GattServiceHandle, zbus::Error> {
+        if !self.experimental {
+            clear_handles(&mut service, &mut characteristics);
+        }
+        let service_path = self
+            .paths
+            .next(self.services.len(), service.name.as_deref())?;
+        let handle = match service
+            .register(characteristics, &self.connection, service_path.clone())
+            .await
+        {
+            Ok(handle) => handle,
+            Err(err) => {
+                self.paths.release(&service_path);
+                return Err(err);
+            }
+        };
+
+        let objects = service_objects(&handle);
+        if let Ok(mut managed_objects) = self.managed_objects.lock() {
+            Arc::make_mut(&mut managed_objects).extend(objects.iter().cloned());
+        }
+        if let Err(err) = self.announce(&objects).await {
+            if let Ok(mut managed_objects) = self.managed_objects.lock() {
+                let managed_objects = Arc::make_mut(&mut managed_objects);
+                for (path, _) in &objects {
+                    managed_objects.remove(path);
+                }
+            }
+            handle.unexport(&self.connection).await.ok();
+            self.paths.release(&service_path);
+            return Err(err);
+        }
+
+        self.services.push(handle);
+        Ok(&self.services[self.services.len() - 1])
+    }
+
+    /// Emit `InterfacesAdded` for `objects`
+    async fn announce(&self, objects: &[(OwnedObjectPath, Services)]) -> Result<(), zbus::Error> {
+        let emitter = SignalEmitter::new(&self.connection, &self.path)?;
+        for (path, interfaces) in objects {
+            GattApplication1::interfaces_added(&emitter, path, interfaces).await?;
+        }
+        Ok(())
+    }
+
+    /// Remove the first service with `uuid` and announce it with
+    /// `InterfacesRemoved`
+    pub async fn remove_service(&mut self, uuid: Uuid) -> Result<(), zbus::Error> {
+        let Some(index) = self.services.iter().position(|s| s.uuid() == uuid) else {
+            return Err(zbus::Error::Failure(format!(
+                "{}: no service {uuid}",
+                self.path
+            )));
+        };
+        let objects = service_objects(&self.services[index]);
+        let server = self.connection.object_server();
+        let emitter = SignalEmitter::new(&self.connection, &self.path)?;
+        let mut res = Ok(());
+        // Children first, so bluez never sees an attribute without its parent
+        for (path, interfaces) in objects.iter().rev() {
+            if let Err(err) = remove_object(server, path, interfaces).await {
+                res = res.and(Err(err));
+                continue;
+            }
+            if let Ok(mut managed_objects) = self.managed_objects.lock() {
+                Arc::make_mut(&mut managed_objects).remove(path);
+            }
+            let interfaces: Vec<&str> = interfaces.keys().map(String::as_str).collect();
+            res = res.and(GattApplication1::interfaces_removed(&emitter, path, &interfaces).await);
+        }
+
+        // Forget the service only once all of it is gone, so calling this
+        // again or `unregister()` removes what is left
+        if res.is_ok() {
+            let handle = self.services.remove(index);
+            self.paths.release(&handle.owned_path());
+        }
+        res
+    }
 }
 
 #[derive(Debug)]
 pub struct GattApplication1 {
     connection: Connection,
-    // TODO: store base path
-    managed_objects: ManagedObjects,
+    managed_objects: SharedObjects,
 }
 
 impl GattApplication1 {
+    /// Register the application with the `GattManager1` of `hci0`
     #[allow(clippy::type_complexity)]
     pub async fn register_new(
         path: &str,
-        connection: Connection,
+        bus: impl Into<BluezBus>,
+        services: Vec<(
+            GattService1,
+            Vec<(GattCharacteristic1, Vec<GattDescriptor1>)>,
+        )>,
+    ) -> Result<GattApplicationHandle, zbus::Error> {
+        Self::register_on(path, "/org/bluez/hci0", bus, services).await
+    }
+
+    /// Register the application with the `GattManager1` of the adapter at
+    /// `adapter_path`
+    #[allow(clippy::type_complexity)]
+    pub async fn register_on(
+        path: &str,
+        adapter_path: &str,
+        bus: impl Into<BluezBus>,
         services: Vec<(
             GattService1,
             Vec<(GattCharacteristic1, Vec<GattDescriptor1>)>,
         )>,
     ) -> Result<GattApplicationHandle, zbus::Error> {
+        let bus = bus.into();
         let path = OwnedObjectPath::try_from(path)?;
-        let mut application = Self {
-            connection,
-            managed_objects: HashMap::default(),
+        let adapter_path = OwnedObjectPath::try_from(adapter_path)?;
+        let application = Self {
+            connection: bus.connection().clone(),
+            managed_objects: SharedObjects::default(),
         };
 
         let connection = application.connection.clone();
         let mut serv_handles = Vec::new();
-        for (count, serv) in services.into_iter().enumerate() {
-            serv_handles.push(
-                serv.0
-                    .register(
-                        serv.1,
-                        &application.connection,
-                        OwnedObjectPath::try_from(format!("{path}/service{count}"))?,
-                    )
-                    .await?,
-            );
-        }
-
-        // This exists just to build the property list
-        // TODO: make this better, move it somewhere else etc.
-        for serv in &serv_handles {
-            let props = serv.property_map();
-            let mut service = HashMap::new();
-            service.insert("org.bluez.GattService1".to_string(), props);
-            application
-                .managed_objects
-                .insert(serv.owned_path(), service);
-
-            for (_, char) in &mut serv.characteristics().iter() {
-                let props = char.property_map();
-                let mut service = HashMap::new();
-                service.insert("org.bluez.GattCharacteristic1".to_string(), props);
-                application
-                    .managed_objects
-                    .insert(char.owned_path(), service);
-                for (_, desc) in char.descriptors().iter() {
-                    let props = desc.property_map();
-                    let mut descriptor = HashMap::new();
-                    descriptor.insert("org.bluez.GattDescriptor1".to_string(), props);
-                    application
-                        .managed_objects
-                        .insert(desc.owned_path(), descriptor);
+        let mut paths = ChildPaths::new(path.as_str(), "service");
+        for (count, mut serv) in services.into_iter().enumerate() {
+            if !bus.experimental() {
+                clear_handles(&mut serv.0, &mut serv.1);
+            }
+            let registered = match paths.next(count, serv.0.name.as_deref()) {
+                Ok(service_path) => {
+                    serv.0
+                        .register(serv.1, &application.connection, service_path)
+                        .await
+                }
+                Err(err) => Err(err),
+            };
+            match registered {
+                Ok(handle) => serv_handles.push(handle),
+                Err(err) => {
+                    unexport_services(&serv_handles, &connection).await;
+                    return Err(err);
                 }
             }
         }
 
-        connection
-            .object_server()
-            .at(&path, application)
-            .await
-            .map_err(|err| {
-                error!("{}: add_to_server {}", path, err);
-                err
-            })?;
-
-        let proxy = GattManager1Proxy::builder(&connection)
-            .path("/org/bluez/hci0")?
-            .build()
-            .await?;
-        // proxy.call_method("RegisterApplication", &{})?;
-        proxy
-            .register_application(&path, HashMap::default())
-            .await?;
+        let managed_objects = application.managed_objects.clone();
+        if let Ok(mut objects) = managed_objects.lock() {
+            *objects = Arc::new(serv_handles.iter().flat_map(service_objects).collect());
+        }
+
+        let interface = match application.export(&path, &adapter_path, &bus).await {
+            Ok(interface) => interface,
+            Err(err) => {
+                unexport_services(&serv_handles, &connection).await;
+                return Err(err);
+            }
+        };
 
         Ok(GattApplicationHandle {
             services: serv_handles,
             connection,
+            destination: bus.destination().clone(),
+            registered: Arc::new(Registered::Application {
+                adapter_path: adapter_path.clone(),
+                path: path.clone(),
+            }),
             path,
+            adapter_path,
+            interface,
+            managed_objects,
+            paths,
+            experimental: bus.experimental(),
         })
     }
 }
 
+impl GattApplication1 {
+    /// Export the application at `path` and register it with the adapter. If
+    /// bluez rejects it the application object is removed again.
+    async fn export(
+        self,
+        path: &OwnedObjectPath,
+        adapter_path: &OwnedObjectPath,
+        bus: &BluezBus,
+    ) -> Result<InterfaceRef<GattApplication1>, zbus::Error> {
+        let connection = self.connection.clone();
+        let server = connection.object_server();
+        let added = server.at(path, self).await.map_err(|err| {
+            error!("{}: add_to_server {}", path, err);
+            err
+        })?;
+        exported(path, added)?;
+
+        let res = async {
+            let interface = server.interface::<_, GattApplication1>(path).await?;
+            let proxy = GattManager1Proxy::builder(&connection)
+                .destination(bus.destination().clone())?
+                .path(adapter_path.clone())?
+                .build()
+                .await?;
+            proxy
+                .register_application(path, HashMap::default())
+                .await?;
+            Ok(interface)
+        }
+        .await;
+        if res.is_err() {
+            server.remove::<GattApplication1, _>(path).await.ok();
+        }
+        res
+    }
+}
+
+/// Remove the services of an application that failed to register. The
+/// registration error is what gets reported, so removal errors are ignored.
+async fn unexport_services(services: &[GattServiceHandle], connection: &Connection) {
+    for service in services {
+        service.unexport(connection).await.ok();
+    }
+}
+
 #[interface(interface = "org.freedesktop.DBus.ObjectManager")]
 impl GattApplication1 {
     /// Includes property
@@ -138,7 +388,24 @@ impl GattApplication1 {
     /// Array of object paths representing the included services of this
     /// service.
     #[zbus(name = "GetManagedObjects")]
-    fn get_managed_objects(&self) -> zbus::fdo::Result<ManagedObjects> {
-        Ok(self.managed_objects.clone())
+    fn get_managed_objects(&self) -> zbus::fdo::Result<Arc<ManagedObjects>> {
+        self.managed_objects
+            .lock()
+            .map(|objects| objects.clone())
+            .map_err(|e| zbus::fdo::Error::Failed(format!("Could not lock objects: {e}")))
     }
+
+    #[zbus(signal)]
+    async fn interfaces_added(
+        emitter: &SignalEmitter<'_>,
+        object_path: &ObjectPath<'_>,
+        interfaces_and_properties: &Services,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn interfaces_removed(
+        emitter: &SignalEmitter<'_>,
+        object_path: &ObjectPath<'_>,
+        interfaces: &[&str],
+    ) -> zbus::Result<()>;
 }
@@ -0,0 +1,100 @@
+//! # Battery Service
+//!
+//! The standard Battery Service (0x180F) with a readable, notifying Battery
+//! Level characteristic, ready to pass to `GattApplication1::register_new()`.
+
+use super::characteristic1::{GattCharacteristic1, GattCharacteristicHandle};
+use super::service1::GattService1;
+use super::{CharacteristicFlags, GattApplicationHandle, GattDescriptor1};
+use crate::codec::BatteryLevel;
+use crate::uuids::{characteristic, service};
+
+/// Builder for the Battery Service
+#[derive(Debug, Clone)]
+pub struct BatteryService {
+    level: u8,
+    primary: bool,
+}
+
+impl Default for BatteryService {
+    fn default() -> Self {
+        Self {
+            level: 100,
+            primary: true,
+        }
+    }
+}
+
+impl BatteryService {
+    /// Start with the battery at `level` percent, capped at 100
+    pub fn new(level: u8) -> Self {
+        Self {
+            level: level.min(100),
+            ..Default::default()
+        }
+    }
+
+    /// Expose the service as a secondary service
+    pub fn secondary(mut self) -> Self {
+        self.primary = false;
+        self
+    }
+
+    /// The service and its Battery Level characteristic
+    pub fn build(
+        self,
+    ) -> (
+        GattService1,
+        Vec<(GattCharacteristic1, Vec<GattDescriptor1>)>,
+    ) {
+        let level = GattCharacteristic1::new(
+            characteristic::BATTERY_LEVEL,
+            Some(BatteryLevel(self.level).encode()),
+            vec![
+                CharacteristicFlags::Read,
+                CharacteristicFlags::Notify,
+            ],
+        );
+        (
+            GattService1::new(service::BATTERY, self.primary),
+            vec![(level, Vec::new())],
+        )
+    }
+}
+
+/// Handle to the Battery Level of a registered Battery Service
+pub struct BatteryServiceHandle<'a> {
+    level: &'a GattCharacteristicHandle,
+}
+
+impl<'a> BatteryServiceHandle<'a> {
+    /// Find the Battery Service among the services of `application`. A
+    /// Battery Level in any other service is not a match.
+    pub fn find(application: &'a GattApplicationHandle) -> Option<Self> {
+        application
+            .services()
+            .iter()
+            .filter(|service| service.uuid() == service::BATTERY)
+            .find_map(|service| {
+                service
+                    .characteristics()
+                    .get(&characteristic::BATTERY_LEVEL)
+            })
+            .map(|level| Self { level })
+    }
+
+    /// The current battery level in percent
+    pub async fn level(&self) -> u8 {
+        BatteryLevel::decode(&self.level.value().await)
+            .unwrap_or_default()
+            .0
+    }
+
+    /// Update the battery level, capped at 100, and notify subscribed
+    /// clients
+    pub async fn set_level(&self, level: u8) -> Result<(), zbus::Error> {
+        self.level
+            .notify(BatteryLevel(level.min(100)).encode())
+            .await
+    }
+}
@@ -1,26 +1,35 @@
 use std::collections::{BTreeMap, HashMap};
-use std::sync::{Arc, Mutex};
+use std::sync::{mpsc, Arc, Mutex};
 
-use log::error;
+use log::{debug, error};
 use uuid::Uuid;
 use zbus::blocking::object_server::InterfaceRef;
 use zbus::blocking::Connection;
-use zbus::fdo::Error as ZbusError;
+use zbus::message::Header;
+use zbus::object_server::SignalEmitter;
 use zbus::zvariant::{Array, ObjectPath, OwnedObjectPath, OwnedValue, Str};
 use zbus::{interface, zvariant};
 
-use super::{GattDescriptor1, GattDescriptorHandle};
-use crate::interface::gatt::CharacteristicFlags;
-use crate::unused_property;
+use super::{notify_fd, GattDescriptor1, GattDescriptorHandle, NotifyWriter, NotifyWriters};
+use crate::interface::gatt::{
+    exported, lock, notify_value, path_segment, read_value, write_value, AccessHook, AccessPolicy,
+    CharacteristicFlags, ChildPaths, GattError, GattOperation, Metrics, MetricsHook, PropertyMap,
+    Subscriptions,
+};
+use crate::trace;
+use crate::{experimental_property, unused_property};
 
 /// The `GattCharacteristicHandle` provides a handle to the registered
 /// `GattCharacteristic1` which is consumed by the zbus interface
 pub struct GattCharacteristicHandle {
+    uuid: Uuid,
     data: Arc<Mutex<Vec<u8>>>,
     interface: InterfaceRef<GattCharacteristic1>,
-    property_map: HashMap<String, OwnedValue>,
+    property_map: PropertyMap,
     path: OwnedObjectPath,
     descriptors: BTreeMap<Uuid, GattDescriptorHandle>,
+    metrics: MetricsHook,
+    subscriptions: Subscriptions,
 }
 
 impl GattCharacteristicHandle {
@@ -32,8 +41,12 @@ impl GattCharacteristicHandle {
         &self.interface
     }
 
-    pub(crate) fn property_map(&self) -> HashMap<String, OwnedValue> {
-        self.property_map.clone()
+    pub(crate) fn property_map(&self) -> &PropertyMap {
+        &self.property_map
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
     }
 
     pub(crate) fn owned_path(&self) -> OwnedObjectPath {
@@ -43,6 +56,36 @@ impl GattCharacteristicHandle {
     pub fn descriptors(&self) -> &BTreeMap<Uuid, GattDescriptorHandle> {
         &self.descriptors
     }
+
+    /// Who is subscribed to notifications or indications
+    pub fn subscriptions(&self) -> &Subscriptions {
+        &self.subscriptions
+    }
+
+    /// Store `value` and send it to subscribed clients as a notification
+    pub fn notify(&self, value: Vec<u8>) -> Result<(), zbus::Error> {
+        let len = value.len();
+        if let Ok(mut data) = self.data.lock() {
+            data.clone_from(&value);
+        }
+        let res = zbus::block_on(notify_value(self.interface.signal_emitter(), &value));
+        self.metrics.notify(self.uuid, len, &res);
+        res
+    }
+}
+
+/// Iterator over the values of a `GattCharacteristic1` after each write by
+/// a client
+pub struct CharacteristicWrites {
+    events: mpsc::Receiver<Vec<u8>>,
+}
+
+impl Iterator for CharacteristicWrites {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.recv().ok()
+    }
 }
 
 pub struct GattCharacteristic1 {
@@ -54,6 +97,14 @@ pub struct GattCharacteristic1 {
     write_acquired: Option<bool>,
     descriptors: Vec<OwnedObjectPath>,
     service_path: OwnedObjectPath,
+    metrics: MetricsHook,
+    access: AccessHook,
+    writes: Option<mpsc::Sender<Vec<u8>>>,
+    notify_writers: Option<mpsc::Sender<NotifyWriter>>,
+    subscriptions: Subscriptions,
+    pub(crate) handle: Option<u16>,
+    properties: PropertyMap,
+    pub(crate) name: Option<String>,
 }
 
 impl GattCharacteristic1 {
@@ -67,9 +118,62 @@ impl GattCharacteristic1 {
             write_acquired: None,
             descriptors: Vec::default(),
             service_path: Default::default(),
+            metrics: MetricsHook::default(),
+            access: AccessHook::default(),
+            writes: None,
+            notify_writers: None,
+            subscriptions: Subscriptions::default(),
+            handle: None,
+            properties: PropertyMap::default(),
+            name: None,
         }
     }
 
+    /// Receive the value after each write by a client
+    pub fn with_writes(mut self) -> (Self, CharacteristicWrites) {
+        let (tx, rx) = mpsc::channel();
+        self.writes = Some(tx);
+        (self, CharacteristicWrites { events: rx })
+    }
+
+    /// Let bluez acquire a socket for notifications with `AcquireNotify`
+    /// instead of waiting for a `PropertiesChanged` signal per value, and
+    /// receive a `NotifyWriter` for each subscription
+    pub fn with_notify_fd(mut self) -> (Self, NotifyWriters) {
+        let (tx, rx) = mpsc::channel();
+        self.notify_writers = Some(tx);
+        self.notify_acquired = Some(false);
+        (self, NotifyWriters { events: rx })
+    }
+
+    /// Ask bluez to place the characteristic declaration at ATT `handle`, so
+    /// clients caching the database keep finding it. Needs the
+    /// `experimental` feature and bluez running with `--experimental`.
+    pub fn with_handle(mut self, handle: u16) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Export under `name` instead of `characteristicN`. `name` is made a
+    /// valid path element with `path_segment()`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(path_segment(name));
+        self
+    }
+
+    /// Report reads, writes, subscriptions and errors to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = MetricsHook::new(metrics);
+        self
+    }
+
+    /// Serve reads, writes and `AcquireNotify` only to the devices `policy`
+    /// allows.
+    pub fn with_access_policy(mut self, policy: Arc<dyn AccessPolicy>) -> Self {
+        self.access = AccessHook::new(policy);
+        self
+    }
+
     fn property_map(&self) -> HashMap<String, OwnedValue> {
         let mut props = HashMap::new();
 
@@ -109,6 +213,10 @@ impl GattCharacteristic1 {
             props.insert("Flags".to_string(), flags);
         }
         props.insert("Primary".to_string(), OwnedValue::from(true));
+        #[cfg(feature = "experimental")]
+        if let Some(handle) = self.handle {
+            props.insert("Handle".to_string(), OwnedValue::from(handle));
+        }
 
         props
     }
@@ -136,12 +244,17 @@ impl GattCharacteristic1 {
         sys_connection: &Connection,
     ) -> Result<GattCharacteristicHandle, zbus::Error> {
         self.service_path = service_path.clone();
-        let property_map = self.property_map();
+        self.properties = PropertyMap::new(self.property_map());
+        let property_map = self.properties.clone();
         let data = self.data.clone();
+        let uuid = self.uuid;
+        let metrics = self.metrics.clone();
+        let subscriptions = self.subscriptions.clone();
         let mut descriptor_handles = BTreeMap::default();
 
+        let mut paths = ChildPaths::new(path.as_str(), "descriptor");
         for (count, descriptor) in descriptors.into_iter().enumerate() {
-            let descriptor_path = OwnedObjectPath::try_from(format!("{path}/descriptor{count}"))?;
+            let descriptor_path = paths.next(count, descriptor.name.as_deref())?;
             self.descriptors.push(descriptor_path.clone());
             descriptor_handles.insert(
                 descriptor.uuid,
@@ -150,43 +263,107 @@ impl GattCharacteristic1 {
         }
 
         log::debug!("GattCharacteristic1: Added UUID: {}", self.uuid);
-        sys_connection
+        let added = sys_connection
             .object_server()
             .at(&path, self)
             .map_err(|err| {
                 error!("{}: add_to_server {}", "path", err);
                 err
             })?;
+        exported(&path, added)?;
 
         let interface = Self::get_characteristic_interface(&path, sys_connection)?;
         Ok(GattCharacteristicHandle {
+            uuid,
             data,
             interface,
             property_map,
             path,
             descriptors: descriptor_handles,
+            metrics,
+            subscriptions,
         })
     }
 }
 
+/// Set `NotifyAcquired` to whether a notification socket is still open and
+/// announce the change, from a task of `connection`
+fn refresh_notify_acquired(connection: zbus::Connection, path: OwnedObjectPath) {
+    let server = connection.clone();
+    connection
+        .executor()
+        .spawn(
+            async move {
+                let res = async {
+                    let interface = server
+                        .object_server()
+                        .interface::<_, GattCharacteristic1>(&path)
+                        .await?;
+                    let mut char = interface.get_mut().await;
+                    let acquired = char.subscriptions.has_sockets();
+                    if char.notify_acquired.is_some_and(|current| current != acquired) {
+                        char.notify_acquired = Some(acquired);
+                        char.notify_acquired_changed(interface.signal_emitter())
+                            .await?;
+                    }
+                    Ok::<_, zbus::Error>(())
+                }
+                .await;
+                // The characteristic may be gone already
+                if let Err(err) = res {
+                    debug!("{path}: NotifyAcquired {err}");
+                }
+            },
+            "bluez-zbus notify_acquired",
+        )
+        .detach();
+}
+
 #[interface(interface = "org.bluez.GattCharacteristic1")]
 impl GattCharacteristic1 {
     /// AcquireNotify method
+    ///
+    /// `NotifyAcquired` turns `true` once this returns and stays so until the
+    /// last `NotifyWriter` sees its socket close.
     fn acquire_notify(
         &self,
-        _options: std::collections::HashMap<&str, zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<(zvariant::OwnedFd, u16)> {
-        Err(ZbusError::NotSupported(
-            "AcquireNotify not supported on GattCharacteristic1".to_string(),
-        ))
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        options: std::collections::HashMap<&str, zvariant::Value<'_>>,
+    ) -> Result<(zvariant::OwnedFd, u16), GattError> {
+        let _span = trace::enter(&header, "AcquireNotify");
+        let Some(writers) = &self.notify_writers else {
+            return Err(GattError::NotSupported(
+                "AcquireNotify not supported on GattCharacteristic1".to_string(),
+            ));
+        };
+        let connection = emitter.connection().clone();
+        let path = OwnedObjectPath::from(emitter.path().to_owned());
+        let on_close = {
+            let (connection, path) = (connection.clone(), path.clone());
+            Box::new(move || refresh_notify_acquired(connection, path))
+        };
+        let res = self
+            .access
+            .check(self.uuid, GattOperation::Subscribe, &options)
+            .and_then(|()| {
+                notify_fd::acquire_notify(writers, &self.subscriptions, &options, on_close)
+            });
+        if res.is_ok() {
+            self.metrics.subscribe(self.uuid);
+            refresh_notify_acquired(connection, path);
+        }
+        res
     }
 
     /// AcquireWrite method
     fn acquire_write(
         &self,
+        #[zbus(header)] header: Header<'_>,
         _options: std::collections::HashMap<&str, zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<(zvariant::OwnedFd, u16)> {
-        Err(ZbusError::NotSupported(
+    ) -> Result<(zvariant::OwnedFd, u16), GattError> {
+        let _span = trace::enter(&header, "AcquireWrite");
+        Err(GattError::NotSupported(
             "AcquireWrite not supported on GattCharacteristic1".to_string(),
         ))
     }
@@ -195,7 +372,8 @@ impl GattCharacteristic1 {
     ///
     /// This method doesn't expect a reply so it is just a confirmation that
     /// value was received. Possible Errors: `org.bluez.Error.Failed`
-    fn confirm(&self) -> zbus::fdo::Result<()> {
+    fn confirm(&self, #[zbus(header)] header: Header<'_>) -> Result<(), GattError> {
+        let _span = trace::enter(&header, "Confirm");
         // TODO: record that the client recieved something
         Ok(())
     }
@@ -210,22 +388,17 @@ impl GattCharacteristic1 {
     /// 		  "device": Object Device (Server only)
     fn read_value(
         &self,
+        #[zbus(header)] header: Header<'_>,
         options: std::collections::HashMap<&str, zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<Vec<u8>> {
-        let data = self
-            .data
-            .lock()
-            .map_err(|e| zbus::fdo::Error::Failed(format!("Could not lock data: {e}")))?;
-        let offset = if let Some(zvariant::Value::U16(ofs)) = options.get("offset") {
-            if *ofs as usize >= data.len() - 1 {
-                return Err(ZbusError::InvalidArgs("InvalidOffset".to_owned()));
-            }
-            *ofs
-        } else {
-            0
-        } as usize;
-        let data: Vec<u8> = data[offset..].to_vec();
-        Ok(data)
+    ) -> Result<Vec<u8>, GattError> {
+        let _span = trace::enter(&header, "ReadValue");
+        let res = self
+            .access
+            .check(self.uuid, GattOperation::Read, &options)
+            .and_then(|()| lock(&self.data))
+            .and_then(|data| read_value(&data, &options));
+        self.metrics.read(self.uuid, &res);
+        res
     }
 
     /// StartNotify method
@@ -238,8 +411,10 @@ impl GattCharacteristic1 {
     ///             org.bluez.Error.InProgress
     ///             org.bluez.Error.NotConnected
     ///             org.bluez.Error.NotSupported
-    fn start_notify(&self) -> zbus::fdo::Result<()> {
-        // TODO: wire up the notification stuff
+    fn start_notify(&self, #[zbus(header)] header: Header<'_>) -> Result<(), GattError> {
+        let _span = trace::enter(&header, "StartNotify");
+        self.subscriptions.start();
+        self.metrics.subscribe(self.uuid);
         Ok(())
     }
 
@@ -251,8 +426,10 @@ impl GattCharacteristic1 {
     /// calling StopNotify will release a single session.
     ///
     /// Possible Errors: org.bluez.Error.Failed
-    fn stop_notify(&self) -> zbus::fdo::Result<()> {
-        // TODO: wire up the notification stuff
+    fn stop_notify(&self, #[zbus(header)] header: Header<'_>) -> Result<(), GattError> {
+        let _span = trace::enter(&header, "StopNotify");
+        self.subscriptions.stop();
+        self.metrics.unsubscribe(self.uuid);
         Ok(())
     }
 
@@ -261,40 +438,30 @@ impl GattCharacteristic1 {
     /// Issues a request to write the value of the characteristic.
     fn write_value(
         &mut self,
+        #[zbus(header)] header: Header<'_>,
         value: &[u8],
         options: std::collections::HashMap<&str, zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<()> {
-        let mut data = self
-            .data
-            .lock()
-            .map_err(|e| zbus::fdo::Error::Failed(format!("Could not lock data: {e}")))?;
-        let offset = if let Some(zvariant::Value::U16(ofs)) = options.get("offset") {
-            // if *ofs as usize >= data.len() || data.len() - (*ofs as usize) >= data.len()
-            // {     return
-            // Err(ZbusError::InvalidArgs("InvalidOffset".to_owned())); }
-            *ofs
-        } else {
-            0
-        } as usize;
-
-        let data_len = data.len();
-        let value_len = value.len();
-        if offset + value_len > data_len {
-            let max_len = if value_len < data_len {
-                data_len
-            } else {
-                value_len
-            };
-            let mut new_data = vec![0; offset + max_len];
-            new_data[..data_len].copy_from_slice(&data);
-            new_data[offset..].copy_from_slice(value);
-            *data = new_data;
-        } else {
-            data.truncate(value_len);
-            data[offset..].copy_from_slice(value);
+    ) -> Result<(), GattError> {
+        let _span = trace::enter(&header, "WriteValue");
+        // Taken under the write's lock, locking again could see the value of
+        // a later write or `set_value()`
+        let mut written = None;
+        let res = self
+            .access
+            .check(self.uuid, GattOperation::Write, &options)
+            .and_then(|()| lock(&self.data))
+            .and_then(|mut data| {
+                write_value(&mut data, value, &options)?;
+                written = Some(data.clone());
+                Ok(())
+            });
+        self.metrics.write(self.uuid, value.len(), &res);
+        if let Some(writes) = &self.writes
+            && let Some(written) = written
+        {
+            writes.send(written).ok();
         }
-
-        Ok(())
+        res
     }
 
     /// Descriptors property
@@ -366,7 +533,6 @@ impl GattCharacteristic1 {
     /// Service property
     ///
     /// Object path of the GATT service the characteristic belongs to.
-    // TODO:
     #[zbus(property)]
     fn service(&self) -> zbus::fdo::Result<zvariant::OwnedObjectPath> {
         Ok(self.service_path.clone())
@@ -394,4 +560,34 @@ impl GattCharacteristic1 {
             Ok,
         )
     }
+
+    /// Handle property
+    ///
+    /// The ATT handle of the attribute. A value set before registering asks
+    /// bluez for that handle, bluez writes back the handle it assigned.
+    #[zbus(property)]
+    fn handle(&self) -> zbus::fdo::Result<u16> {
+        experimental_property!("handle", "GattCharacteristic1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle.map_or_else(
+                || {
+                    unused_property!("handle", "GattCharacteristic1");
+                },
+                Ok,
+            )
+        }
+    }
+
+    #[cfg_attr(not(feature = "experimental"), allow(unused_variables))]
+    #[zbus(property)]
+    fn set_handle(&mut self, handle: u16) -> zbus::fdo::Result<()> {
+        experimental_property!("handle", "GattCharacteristic1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle = Some(handle);
+            self.properties.set("Handle", OwnedValue::from(handle));
+            Ok(())
+        }
+    }
 }
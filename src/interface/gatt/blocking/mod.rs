@@ -7,5 +7,8 @@ pub use characteristic1::*;
 mod descriptor;
 pub use descriptor::*;
 
+mod notify_fd;
+pub use notify_fd::*;
+
 mod service1;
 pub use service1::*;
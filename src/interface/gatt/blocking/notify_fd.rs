@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+use std::sync::mpsc;
+
+use zbus::zvariant::{self, ObjectPath, OwnedObjectPath};
+
+use crate::interface::gatt::{
+    device, is_closed_error, notify_payload, notify_socket, GattError, OnNotifyClose,
+    Subscriptions,
+};
+
+/// Writer over the socket a `GattCharacteristic1` hands to bluez on
+/// `AcquireNotify`. Each packet written is sent to the subscribed client as
+/// one notification, without a D-Bus round trip.
+///
+/// Writes block while the socket is full. Bluez closes the socket once the
+/// client unsubscribes or disconnects, sends fail with `BrokenPipe` after
+/// that.
+pub struct NotifyWriter {
+    stream: UnixStream,
+    mtu: u16,
+    device: Option<OwnedObjectPath>,
+    subscriptions: Subscriptions,
+    closed: bool,
+    on_close: Option<OnNotifyClose>,
+}
+
+impl NotifyWriter {
+    /// The MTU bluez negotiated with the client
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    /// Largest value sent in one notification
+    pub fn payload(&self) -> usize {
+        notify_payload(self.mtu)
+    }
+
+    /// The device that subscribed, if bluez said
+    pub fn device(&self) -> Option<&ObjectPath<'_>> {
+        self.device.as_deref()
+    }
+
+    /// Bluez closed the socket
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Send all of `value`, one notification per `payload()` sized chunk
+    pub fn send(&mut self, value: &[u8]) -> io::Result<()> {
+        for chunk in value.chunks(self.payload()) {
+            let res = self.stream.write_all(chunk);
+            self.check(res)?;
+        }
+        Ok(())
+    }
+
+    /// Send `value` as one notification if the socket has room for it.
+    /// `Ok(false)` if it is full and nothing was sent.
+    pub fn try_send(&mut self, value: &[u8]) -> io::Result<bool> {
+        if value.len() > self.payload() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} bytes exceed the MTU", value.len()),
+            ));
+        }
+        self.stream.set_nonblocking(true)?;
+        let res = self.stream.write(value);
+        self.stream.set_nonblocking(false)?;
+        match self.check(res) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Block until bluez closes the socket
+    pub fn closed(&mut self) {
+        let mut buf = [0; 1];
+        while !self.closed {
+            if !matches!(self.stream.read(&mut buf), Ok(1..)) {
+                self.close();
+            }
+        }
+    }
+
+    fn check<T>(&mut self, res: io::Result<T>) -> io::Result<T> {
+        if let Err(e) = &res
+            && is_closed_error(e)
+        {
+            self.close();
+        }
+        res
+    }
+
+    fn close(&mut self) {
+        if self.closed {
+            return;
+        }
+        self.closed = true;
+        if let Some(device) = &self.device {
+            self.subscriptions.remove(device);
+        }
+        self.subscriptions.released();
+        if let Some(on_close) = self.on_close.take() {
+            on_close();
+        }
+    }
+}
+
+impl Drop for NotifyWriter {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl Write for NotifyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.payload());
+        let res = self.stream.write(&buf[..len]);
+        self.check(res)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// Iterator over a `NotifyWriter` for each `AcquireNotify` by bluez
+pub struct NotifyWriters {
+    pub(super) events: mpsc::Receiver<NotifyWriter>,
+}
+
+impl Iterator for NotifyWriters {
+    type Item = NotifyWriter;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.events.recv().ok()
+    }
+}
+
+/// Answer `AcquireNotify`: keep one end of a new socket pair in a
+/// `NotifyWriter` and return the other end for bluez
+pub(super) fn acquire_notify(
+    writers: &mpsc::Sender<NotifyWriter>,
+    subscriptions: &Subscriptions,
+    options: &HashMap<&str, zvariant::Value<'_>>,
+    on_close: OnNotifyClose,
+) -> Result<(zvariant::OwnedFd, u16), GattError> {
+    let (stream, remote, mtu) = notify_socket(options)?;
+    let writer = NotifyWriter {
+        stream,
+        mtu,
+        device: device(options),
+        subscriptions: subscriptions.clone(),
+        closed: false,
+        on_close: Some(on_close),
+    };
+    subscriptions.acquired(options);
+    writers
+        .send(writer)
+        .map_err(|_| GattError::NotSupported("Nobody sends notifications".to_owned()))?;
+    Ok((remote, mtu))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::fd::OwnedFd;
+
+    use super::*;
+
+    const DEVICE: &str = "/org/bluez/hci0/dev_00_11_22_33_44_55";
+
+    #[test]
+    fn sends_one_packet_per_notification_until_closed() {
+        let (tx, rx) = mpsc::channel();
+        let mut writers = NotifyWriters { events: rx };
+        let subscriptions = Subscriptions::default();
+        let options = HashMap::from([
+            ("mtu", zvariant::Value::U16(23)),
+            (
+                "device",
+                zvariant::Value::from(ObjectPath::try_from(DEVICE).unwrap()),
+            ),
+        ]);
+
+        let (remote, mtu) = acquire_notify(&tx, &subscriptions, &options, Box::new(|| {})).unwrap();
+        assert_eq!(mtu, 23);
+        let mut writer = writers.next().unwrap();
+        assert_eq!(writer.payload(), 20);
+        assert!(subscriptions.contains(&ObjectPath::try_from(DEVICE).unwrap()));
+
+        let mut remote = UnixStream::from(OwnedFd::from(remote));
+        writer.send(&[7; 30]).unwrap();
+        let mut buf = [0; 64];
+        assert_eq!(remote.read(&mut buf).unwrap(), 20);
+        assert_eq!(remote.read(&mut buf).unwrap(), 10);
+        assert!(writer.try_send(&[0; 21]).is_err());
+
+        drop(remote);
+        writer.closed();
+        assert!(writer.is_closed());
+        assert!(!subscriptions.is_notifying());
+    }
+}
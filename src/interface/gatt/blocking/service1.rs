@@ -40,28 +40,50 @@ use std::collections::{BTreeMap, HashMap};
 
 use log::error;
 use uuid::Uuid;
+use zbus::blocking::object_server::InterfaceRef;
 use zbus::blocking::Connection;
 use zbus::interface;
-use zbus::zvariant::{OwnedObjectPath, OwnedValue, Str};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Str};
 
 use super::characteristic1::{GattCharacteristic1, GattCharacteristicHandle};
 use super::GattDescriptor1;
+use crate::experimental_property;
+use crate::interface::gatt::{exported, path_segment, ChildPaths, PropertyMap};
+#[cfg(feature = "experimental")]
+use crate::unused_property;
 
 pub struct GattServiceHandle {
     characteristics: BTreeMap<Uuid, GattCharacteristicHandle>,
-    _uuid: Uuid,
-    _primary: bool,
-    property_map: HashMap<String, OwnedValue>,
+    uuid: Uuid,
+    primary: bool,
+    interface: InterfaceRef<GattService1>,
+    property_map: PropertyMap,
     path: OwnedObjectPath,
 }
 
 impl GattServiceHandle {
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn primary(&self) -> bool {
+        self.primary
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    pub fn zbus(&self) -> &InterfaceRef<GattService1> {
+        &self.interface
+    }
+
     pub fn characteristics(&self) -> &BTreeMap<Uuid, GattCharacteristicHandle> {
         &self.characteristics
     }
 
-    pub(crate) fn property_map(&self) -> HashMap<String, OwnedValue> {
-        self.property_map.clone()
+    pub(crate) fn property_map(&self) -> &PropertyMap {
+        &self.property_map
     }
 
     pub(crate) fn owned_path(&self) -> OwnedObjectPath {
@@ -73,11 +95,35 @@ impl GattServiceHandle {
 pub struct GattService1 {
     uuid: Uuid,
     primary: bool,
+    pub(crate) handle: Option<u16>,
+    properties: PropertyMap,
+    pub(crate) name: Option<String>,
 }
 
 impl GattService1 {
     pub fn new(uuid: Uuid, primary: bool) -> Self {
-        Self { uuid, primary }
+        Self {
+            uuid,
+            primary,
+            handle: None,
+            properties: PropertyMap::default(),
+            name: None,
+        }
+    }
+
+    /// Ask bluez to place the service at ATT `handle`, so clients caching the
+    /// database keep finding it. Needs the `experimental` feature and bluez
+    /// running with `--experimental`.
+    pub fn with_handle(mut self, handle: u16) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Export under `name` instead of `serviceN`. `name` is made a
+    /// valid path element with `path_segment()`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(path_segment(name));
+        self
     }
 
     fn property_map(&self) -> HashMap<String, OwnedValue> {
@@ -89,6 +135,10 @@ impl GattService1 {
             OwnedValue::from(Str::from(self.uuid.to_string())),
         );
         props.insert("Primary".to_string(), OwnedValue::from(self.primary));
+        #[cfg(feature = "experimental")]
+        if let Some(handle) = self.handle {
+            props.insert("Handle".to_string(), OwnedValue::from(handle));
+        }
         // if !self.includes.is_empty() {
         //     let includes: Vec<String> = self.includes.iter().cloned().collect();
         //     let includes = Array::from(includes);
@@ -99,42 +149,48 @@ impl GattService1 {
     }
 
     pub fn register(
-        self,
+        mut self,
         characteristics: Vec<(GattCharacteristic1, Vec<GattDescriptor1>)>,
         sys_connection: &Connection,
         service_path: OwnedObjectPath,
     ) -> Result<GattServiceHandle, zbus::Error> {
-        let mut service_handle = GattServiceHandle {
-            characteristics: BTreeMap::new(),
-            _uuid: self.uuid,
-            _primary: self.primary,
-            property_map: self.property_map(),
-            path: service_path.clone(),
-        };
+        self.properties = PropertyMap::new(self.property_map());
+        let property_map = self.properties.clone();
+        let uuid = self.uuid;
+        let primary = self.primary;
+        let mut char_handles = BTreeMap::new();
 
+        let mut paths = ChildPaths::new(service_path.as_str(), "characteristic");
         for (count, (gatt_char, descriptors)) in characteristics.into_iter().enumerate() {
-            service_handle.characteristics.insert(
+            let path = paths.next(count, gatt_char.name.as_deref())?;
+            char_handles.insert(
                 gatt_char.uuid,
-                gatt_char.register(
-                    OwnedObjectPath::try_from(format!("{service_path}/characteristic{count}"))?,
-                    service_path.clone(),
-                    descriptors,
-                    sys_connection,
-                )?,
+                gatt_char.register(path, service_path.clone(), descriptors, sys_connection)?,
             );
             // TODO: push includes paths
         }
 
         log::debug!("GattService1: Added UUID: {}", self.uuid);
-        sys_connection
+        let added = sys_connection
             .object_server()
             .at(&service_path, self)
             .map_err(|err| {
                 error!("{}: add_to_server {}", "path", err);
                 err
             })?;
+        exported(&service_path, added)?;
 
-        Ok(service_handle)
+        let interface = sys_connection
+            .object_server()
+            .interface::<_, GattService1>(&service_path)?;
+        Ok(GattServiceHandle {
+            characteristics: char_handles,
+            uuid,
+            primary,
+            interface,
+            property_map,
+            path: service_path,
+        })
     }
 }
 
@@ -167,4 +223,34 @@ impl GattService1 {
     fn uuid(&self) -> zbus::fdo::Result<String> {
         Ok(self.uuid.to_string())
     }
+
+    /// Handle property
+    ///
+    /// The ATT handle of the attribute. A value set before registering asks
+    /// bluez for that handle, bluez writes back the handle it assigned.
+    #[zbus(property)]
+    fn handle(&self) -> zbus::fdo::Result<u16> {
+        experimental_property!("handle", "GattService1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle.map_or_else(
+                || {
+                    unused_property!("handle", "GattService1");
+                },
+                Ok,
+            )
+        }
+    }
+
+    #[cfg_attr(not(feature = "experimental"), allow(unused_variables))]
+    #[zbus(property)]
+    fn set_handle(&mut self, handle: u16) -> zbus::fdo::Result<()> {
+        experimental_property!("handle", "GattService1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle = Some(handle);
+            self.properties.set("Handle", OwnedValue::from(handle));
+            Ok(())
+        }
+    }
 }
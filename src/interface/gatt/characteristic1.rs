@@ -1,38 +1,86 @@
 use std::collections::{BTreeMap, HashMap};
-use std::sync::{Arc, Mutex};
-
-use log::error;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use async_lock::Mutex;
+use futures_channel::mpsc;
+use futures_lite::Stream;
+use log::{debug, error};
 use uuid::Uuid;
-use zbus::fdo::Error as ZbusError;
-use zbus::object_server::InterfaceRef;
+use zbus::message::Header;
+use zbus::object_server::{InterfaceRef, SignalEmitter};
 use zbus::zvariant::{Array, ObjectPath, OwnedObjectPath, OwnedValue, Str};
 use zbus::Connection;
 use zbus::{interface, zvariant};
 
-use super::{CharacteristicFlags, GattDescriptor1, GattDescriptorHandle};
-use crate::unused_property;
+use super::{
+    exported, notify_fd, notify_value, path_segment, read_value, write_value, AccessHook,
+    AccessPolicy, CharacteristicFlags, ChildPaths, GattDescriptor1, GattDescriptorHandle,
+    GattError, GattOperation, Metrics, MetricsHook, NotifyWriter, NotifyWriters, PropertyMap,
+    Subscriptions,
+};
+use crate::trace;
+use crate::{experimental_property, unused_property};
 
 /// The `GattCharacteristicHandle` provides a handle to the registered
 /// `GattCharacteristic1` which is consumed by the zbus interface
 pub struct GattCharacteristicHandle {
+    uuid: Uuid,
     data: Arc<Mutex<Vec<u8>>>,
     interface: InterfaceRef<GattCharacteristic1>,
-    property_map: HashMap<String, OwnedValue>,
+    property_map: PropertyMap,
     path: OwnedObjectPath,
     descriptors: BTreeMap<Uuid, GattDescriptorHandle>,
+    metrics: MetricsHook,
+    subscriptions: Subscriptions,
 }
 
 impl GattCharacteristicHandle {
+    /// Remove the characteristic and its descriptors from the object server,
+    /// children first
+    pub(crate) async fn unexport(&self, connection: &Connection) -> Result<(), zbus::Error> {
+        // Carry on past a failed child and return the first error
+        let mut res = Ok(());
+        for descriptor in self.descriptors.values() {
+            res = res.and(descriptor.unexport(connection).await);
+        }
+        let removed = connection
+            .object_server()
+            .remove::<GattCharacteristic1, _>(&self.path)
+            .await;
+        res.and(removed.map(|_| ()))
+    }
+
     pub fn data(&self) -> Arc<Mutex<Vec<u8>>> {
         self.data.clone()
     }
 
+    /// The current value
+    pub async fn value(&self) -> Vec<u8> {
+        self.data.lock().await.clone()
+    }
+
+    /// The current value, `None` while a client read or write holds it
+    pub fn try_value(&self) -> Option<Vec<u8>> {
+        self.data.try_lock().map(|data| data.clone())
+    }
+
+    /// Store `value` without notifying clients
+    pub async fn set_value(&self, value: Vec<u8>) {
+        *self.data.lock().await = value;
+    }
+
     pub fn zbus(&self) -> &InterfaceRef<GattCharacteristic1> {
         &self.interface
     }
 
-    pub(crate) fn property_map(&self) -> HashMap<String, OwnedValue> {
-        self.property_map.clone()
+    pub(crate) fn property_map(&self) -> &PropertyMap {
+        &self.property_map
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
     }
 
     pub(crate) fn owned_path(&self) -> OwnedObjectPath {
@@ -42,6 +90,34 @@ impl GattCharacteristicHandle {
     pub fn descriptors(&self) -> &BTreeMap<Uuid, GattDescriptorHandle> {
         &self.descriptors
     }
+
+    /// Who is subscribed to notifications or indications
+    pub fn subscriptions(&self) -> &Subscriptions {
+        &self.subscriptions
+    }
+
+    /// Store `value` and send it to subscribed clients as a notification
+    pub async fn notify(&self, value: Vec<u8>) -> Result<(), zbus::Error> {
+        let len = value.len();
+        self.data.lock().await.clone_from(&value);
+        let res = notify_value(self.interface.signal_emitter(), &value).await;
+        self.metrics.notify(self.uuid, len, &res);
+        res
+    }
+}
+
+/// Stream of the values of a `GattCharacteristic1` after each write by a
+/// client
+pub struct CharacteristicWrites {
+    events: mpsc::UnboundedReceiver<Vec<u8>>,
+}
+
+impl Stream for CharacteristicWrites {
+    type Item = Vec<u8>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
 }
 
 pub struct GattCharacteristic1 {
@@ -53,6 +129,14 @@ pub struct GattCharacteristic1 {
     write_acquired: Option<bool>,
     descriptors: Vec<OwnedObjectPath>,
     service_path: OwnedObjectPath,
+    metrics: MetricsHook,
+    access: AccessHook,
+    writes: Option<mpsc::UnboundedSender<Vec<u8>>>,
+    notify_writers: Option<mpsc::UnboundedSender<NotifyWriter>>,
+    subscriptions: Subscriptions,
+    pub(crate) handle: Option<u16>,
+    properties: PropertyMap,
+    pub(crate) name: Option<String>,
 }
 
 impl GattCharacteristic1 {
@@ -66,9 +150,63 @@ impl GattCharacteristic1 {
             write_acquired: None,
             descriptors: Vec::default(),
             service_path: Default::default(),
+            metrics: MetricsHook::default(),
+            access: AccessHook::default(),
+            writes: None,
+            notify_writers: None,
+            subscriptions: Subscriptions::default(),
+            handle: None,
+            properties: PropertyMap::default(),
+            name: None,
         }
     }
 
+    /// Export under `name` instead of `characteristicN`. `name` is made a
+    /// valid path element with `path_segment()`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(path_segment(name));
+        self
+    }
+
+    /// Ask bluez to place the characteristic declaration at ATT `handle`, so
+    /// clients caching the database keep finding it. Needs the
+    /// `experimental` feature and bluez running with `--experimental`.
+    pub fn with_handle(mut self, handle: u16) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// Receive the value after each write by a client
+    pub fn with_writes(mut self) -> (Self, CharacteristicWrites) {
+        let (tx, rx) = mpsc::unbounded();
+        self.writes = Some(tx);
+        (self, CharacteristicWrites { events: rx })
+    }
+
+    /// Let bluez acquire a socket for notifications with `AcquireNotify`
+    /// instead of waiting for a `PropertiesChanged` signal per value, and
+    /// receive a `NotifyWriter` for each subscription
+    pub fn with_notify_fd(mut self) -> (Self, NotifyWriters) {
+        let (tx, rx) = mpsc::unbounded();
+        self.notify_writers = Some(tx);
+        self.notify_acquired = Some(false);
+        (self, NotifyWriters { events: rx })
+    }
+
+    /// Report reads, writes, subscriptions, notifications and errors to
+    /// `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = MetricsHook::new(metrics);
+        self
+    }
+
+    /// Serve reads, writes and `AcquireNotify` only to the devices `policy`
+    /// allows.
+    pub fn with_access_policy(mut self, policy: Arc<dyn AccessPolicy>) -> Self {
+        self.access = AccessHook::new(policy);
+        self
+    }
+
     fn property_map(&self) -> HashMap<String, OwnedValue> {
         let mut props = HashMap::new();
 
@@ -108,6 +246,10 @@ impl GattCharacteristic1 {
             props.insert("Flags".to_string(), flags);
         }
         props.insert("Primary".to_string(), OwnedValue::from(true));
+        #[cfg(feature = "experimental")]
+        if let Some(handle) = self.handle {
+            props.insert("Handle".to_string(), OwnedValue::from(handle));
+        }
 
         props
     }
@@ -136,60 +278,161 @@ impl GattCharacteristic1 {
         sys_connection: &Connection,
     ) -> Result<GattCharacteristicHandle, zbus::Error> {
         self.service_path = service_path.clone();
-        let property_map = self.property_map();
+        self.properties = PropertyMap::new(self.property_map());
+        let property_map = self.properties.clone();
         let data = self.data.clone();
+        let uuid = self.uuid;
+        let metrics = self.metrics.clone();
+        let subscriptions = self.subscriptions.clone();
         let mut descriptor_handles = BTreeMap::default();
 
+        let mut paths = ChildPaths::new(path.as_str(), "descriptor");
         for (count, descriptor) in descriptors.into_iter().enumerate() {
-            let descriptor_path = OwnedObjectPath::try_from(format!("{path}/descriptor{count}"))?;
+            let descriptor_path = paths.next(count, descriptor.name.as_deref())?;
             self.descriptors.push(descriptor_path.clone());
-            descriptor_handles.insert(
-                descriptor.uuid,
-                descriptor
-                    .register(descriptor_path, path.clone(), sys_connection)
-                    .await?,
-            );
+            let uuid = descriptor.uuid;
+            match descriptor
+                .register(descriptor_path, path.clone(), sys_connection)
+                .await
+            {
+                Ok(handle) => descriptor_handles.insert(uuid, handle),
+                Err(err) => {
+                    unexport_descriptors(&descriptor_handles, sys_connection).await;
+                    return Err(err);
+                }
+            };
         }
 
         log::debug!("GattCharacteristic1: Added UUID: {}", self.uuid);
-        sys_connection
+        let added = sys_connection
             .object_server()
             .at(&path, self)
             .await
             .map_err(|err| {
                 error!("{}: add_to_server {}", "path", err);
                 err
-            })?;
+            })
+            .and_then(|added| exported(&path, added));
+        if let Err(err) = added {
+            unexport_descriptors(&descriptor_handles, sys_connection).await;
+            return Err(err);
+        }
 
-        let interface = Self::get_characteristic_interface(&path, sys_connection).await?;
+        let interface = match Self::get_characteristic_interface(&path, sys_connection).await {
+            Ok(interface) => interface,
+            Err(err) => {
+                let server = sys_connection.object_server();
+                server.remove::<GattCharacteristic1, _>(&path).await.ok();
+                unexport_descriptors(&descriptor_handles, sys_connection).await;
+                return Err(err);
+            }
+        };
         Ok(GattCharacteristicHandle {
+            uuid,
             data,
             interface,
             property_map,
             path,
             descriptors: descriptor_handles,
+            metrics,
+            subscriptions,
         })
     }
 }
 
+/// Remove the descriptors of a characteristic that failed to register. The
+/// registration error is what gets reported, so removal errors are ignored.
+async fn unexport_descriptors(
+    descriptors: &BTreeMap<Uuid, GattDescriptorHandle>,
+    connection: &Connection,
+) {
+    for descriptor in descriptors.values() {
+        descriptor.unexport(connection).await.ok();
+    }
+}
+
+/// Set `NotifyAcquired` to whether a notification socket is still open and
+/// announce the change, from a task of `connection`
+fn refresh_notify_acquired(connection: Connection, path: OwnedObjectPath) {
+    let server = connection.clone();
+    connection
+        .executor()
+        .spawn(
+            async move {
+                let res = async {
+                    let interface = server
+                        .object_server()
+                        .interface::<_, GattCharacteristic1>(&path)
+                        .await?;
+                    let mut char = interface.get_mut().await;
+                    let acquired = char.subscriptions.has_sockets();
+                    if char.notify_acquired.is_some_and(|current| current != acquired) {
+                        char.notify_acquired = Some(acquired);
+                        char.notify_acquired_changed(interface.signal_emitter())
+                            .await?;
+                    }
+                    Ok::<_, zbus::Error>(())
+                }
+                .await;
+                // The characteristic may be gone already
+                if let Err(err) = res {
+                    debug!("{path}: NotifyAcquired {err}");
+                }
+            },
+            "bluez-zbus notify_acquired",
+        )
+        .detach();
+}
+
 #[interface(interface = "org.bluez.GattCharacteristic1")]
 impl GattCharacteristic1 {
     /// AcquireNotify method
-    fn acquire_notify(
-        &self,
-        _options: std::collections::HashMap<&str, zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<(zvariant::OwnedFd, u16)> {
-        Err(ZbusError::NotSupported(
-            "AcquireNotify not supported on GattCharacteristic1".to_string(),
-        ))
+    ///
+    /// `NotifyAcquired` is `true` from here until the last `NotifyWriter`
+    /// sees its socket close.
+    async fn acquire_notify(
+        &mut self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        options: std::collections::HashMap<&str, zvariant::Value<'_>>,
+    ) -> Result<(zvariant::OwnedFd, u16), GattError> {
+        trace::handle(&header, "AcquireNotify", async {
+            let Some(writers) = &self.notify_writers else {
+                return Err(GattError::NotSupported(
+                    "AcquireNotify not supported on GattCharacteristic1".to_string(),
+                ));
+            };
+            let connection = emitter.connection().clone();
+            let path = OwnedObjectPath::from(emitter.path().to_owned());
+            let on_close = Box::new(move || refresh_notify_acquired(connection, path));
+            let res = self
+                .access
+                .check(self.uuid, GattOperation::Subscribe, &options)
+                .and_then(|()| {
+                    notify_fd::acquire_notify(writers, &self.subscriptions, &options, on_close)
+                });
+            if res.is_ok() {
+                self.metrics.subscribe(self.uuid);
+                if self.notify_acquired == Some(false) {
+                    self.notify_acquired = Some(true);
+                    if let Err(err) = self.notify_acquired_changed(&emitter).await {
+                        error!("{}: NotifyAcquired {err}", emitter.path());
+                    }
+                }
+            }
+            res
+        })
+        .await
     }
 
     /// AcquireWrite method
     fn acquire_write(
         &self,
+        #[zbus(header)] header: Header<'_>,
         _options: std::collections::HashMap<&str, zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<(zvariant::OwnedFd, u16)> {
-        Err(ZbusError::NotSupported(
+    ) -> Result<(zvariant::OwnedFd, u16), GattError> {
+        let _span = trace::enter(&header, "AcquireWrite");
+        Err(GattError::NotSupported(
             "AcquireWrite not supported on GattCharacteristic1".to_string(),
         ))
     }
@@ -198,7 +441,8 @@ impl GattCharacteristic1 {
     ///
     /// This method doesn't expect a reply so it is just a confirmation that
     /// value was received. Possible Errors: `org.bluez.Error.Failed`
-    fn confirm(&self) -> zbus::fdo::Result<()> {
+    fn confirm(&self, #[zbus(header)] header: Header<'_>) -> Result<(), GattError> {
+        let _span = trace::enter(&header, "Confirm");
         // TODO: record that the client recieved something
         Ok(())
     }
@@ -211,24 +455,20 @@ impl GattCharacteristic1 {
     /// Possible options: "offset": uint16 offset
     /// 		  "mtu": Exchanged MTU (Server only)
     /// 		  "device": Object Device (Server only)
-    fn read_value(
+    async fn read_value(
         &self,
+        #[zbus(header)] header: Header<'_>,
         options: std::collections::HashMap<&str, zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<Vec<u8>> {
-        let data = self
-            .data
-            .lock()
-            .map_err(|e| zbus::fdo::Error::Failed(format!("Could not lock data: {e}")))?;
-        let offset = if let Some(zvariant::Value::U16(ofs)) = options.get("offset") {
-            if *ofs as usize >= data.len() - 1 {
-                return Err(ZbusError::InvalidArgs("InvalidOffset".to_owned()));
-            }
-            *ofs
-        } else {
-            0
-        } as usize;
-        let data: Vec<u8> = data[offset..].to_vec();
-        Ok(data)
+    ) -> Result<Vec<u8>, GattError> {
+        trace::handle(&header, "ReadValue", async {
+            let res = match self.access.check(self.uuid, GattOperation::Read, &options) {
+                Ok(()) => read_value(&self.data.lock().await, &options),
+                Err(e) => Err(e),
+            };
+            self.metrics.read(self.uuid, &res);
+            res
+        })
+        .await
     }
 
     /// StartNotify method
@@ -241,8 +481,10 @@ impl GattCharacteristic1 {
     ///             org.bluez.Error.InProgress
     ///             org.bluez.Error.NotConnected
     ///             org.bluez.Error.NotSupported
-    fn start_notify(&self) -> zbus::fdo::Result<()> {
-        // TODO: wire up the notification stuff
+    fn start_notify(&self, #[zbus(header)] header: Header<'_>) -> Result<(), GattError> {
+        let _span = trace::enter(&header, "StartNotify");
+        self.subscriptions.start();
+        self.metrics.subscribe(self.uuid);
         Ok(())
     }
 
@@ -254,50 +496,37 @@ impl GattCharacteristic1 {
     /// calling StopNotify will release a single session.
     ///
     /// Possible Errors: org.bluez.Error.Failed
-    fn stop_notify(&self) -> zbus::fdo::Result<()> {
-        // TODO: wire up the notification stuff
+    fn stop_notify(&self, #[zbus(header)] header: Header<'_>) -> Result<(), GattError> {
+        let _span = trace::enter(&header, "StopNotify");
+        self.subscriptions.stop();
+        self.metrics.unsubscribe(self.uuid);
         Ok(())
     }
 
     /// WriteValue method
     ///
     /// Issues a request to write the value of the characteristic.
-    fn write_value(
-        &mut self,
+    async fn write_value(
+        &self,
+        #[zbus(header)] header: Header<'_>,
         value: &[u8],
         options: std::collections::HashMap<&str, zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<()> {
-        let mut data = self
-            .data
-            .lock()
-            .map_err(|e| zbus::fdo::Error::Failed(format!("Could not lock data: {e}")))?;
-        let offset = if let Some(zvariant::Value::U16(ofs)) = options.get("offset") {
-            // if *ofs as usize >= data.len() || data.len() - (*ofs as usize) >= data.len()
-            // {     return
-            // Err(ZbusError::InvalidArgs("InvalidOffset".to_owned())); }
-            *ofs
-        } else {
-            0
-        } as usize;
-
-        let data_len = data.len();
-        let value_len = value.len();
-        if offset + value_len > data_len {
-            let max_len = if value_len < data_len {
-                data_len
-            } else {
-                value_len
-            };
-            let mut new_data = vec![0; offset + max_len];
-            new_data[..data_len].copy_from_slice(&data);
-            new_data[offset..].copy_from_slice(value);
-            *data = new_data;
-        } else {
-            data.truncate(value_len);
-            data[offset..].copy_from_slice(value);
-        }
-
-        Ok(())
+    ) -> Result<(), GattError> {
+        trace::handle(&header, "WriteValue", async {
+            let mut data = self.data.lock().await;
+            let res = self
+                .access
+                .check(self.uuid, GattOperation::Write, &options)
+                .and_then(|()| write_value(&mut data, value, &options));
+            self.metrics.write(self.uuid, value.len(), &res);
+            if res.is_ok()
+                && let Some(writes) = &self.writes
+            {
+                writes.unbounded_send(data.clone()).ok();
+            }
+            res
+        })
+        .await
     }
 
     /// Descriptors property
@@ -369,7 +598,6 @@ impl GattCharacteristic1 {
     /// Service property
     ///
     /// Object path of the GATT service the characteristic belongs to.
-    // TODO:
     #[zbus(property)]
     fn service(&self) -> zbus::fdo::Result<zvariant::OwnedObjectPath> {
         Ok(self.service_path.clone())
@@ -387,6 +615,36 @@ impl GattCharacteristic1 {
         Ok(Vec::default())
     }
 
+    /// Handle property
+    ///
+    /// The ATT handle of the attribute. A value set before registering asks
+    /// bluez for that handle, bluez writes back the handle it assigned.
+    #[zbus(property)]
+    fn handle(&self) -> zbus::fdo::Result<u16> {
+        experimental_property!("handle", "GattCharacteristic1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle.map_or_else(
+                || {
+                    unused_property!("handle", "GattCharacteristic1");
+                },
+                Ok,
+            )
+        }
+    }
+
+    #[cfg_attr(not(feature = "experimental"), allow(unused_variables))]
+    #[zbus(property)]
+    fn set_handle(&mut self, handle: u16) -> zbus::fdo::Result<()> {
+        experimental_property!("handle", "GattCharacteristic1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle = Some(handle);
+            self.properties.set("Handle", OwnedValue::from(handle));
+            Ok(())
+        }
+    }
+
     /// WriteAcquired property
     #[zbus(property)]
     fn write_acquired(&self) -> zbus::fdo::Result<bool> {
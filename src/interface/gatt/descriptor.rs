@@ -1,34 +1,72 @@
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
+use async_lock::Mutex;
 use log::error;
 use uuid::Uuid;
-use zbus::fdo::Error as ZbusError;
 use zbus::interface;
+use zbus::message::Header;
 use zbus::object_server::InterfaceRef;
-use zbus::zvariant::{self, Array, ObjectPath, OwnedObjectPath, OwnedValue, Str};
+use zbus::zvariant::{Array, ObjectPath, OwnedObjectPath, OwnedValue, Str};
 use zbus::Connection;
 
-use super::GattDescriptorFlags;
+use super::{
+    exported, path_segment, read_value, write_value, AccessHook, AccessPolicy, GattDescriptorFlags,
+    GattError, GattOperation, Metrics, MetricsHook, PropertyMap,
+};
+use crate::codec::PresentationFormat;
+use crate::experimental_property;
+use crate::trace;
+#[cfg(feature = "experimental")]
+use crate::unused_property;
+use crate::uuids::descriptor::CHARACTERISTIC_PRESENTATION_FORMAT;
 
 pub struct GattDescriptorHandle {
     data: Arc<Mutex<Vec<u8>>>,
     interface: InterfaceRef<GattDescriptor1>,
-    property_map: HashMap<String, OwnedValue>,
+    property_map: PropertyMap,
     path: OwnedObjectPath,
 }
 
 impl GattDescriptorHandle {
+    /// Remove the descriptor from the object server
+    pub(crate) async fn unexport(&self, connection: &Connection) -> Result<(), zbus::Error> {
+        connection
+            .object_server()
+            .remove::<GattDescriptor1, _>(&self.path)
+            .await?;
+        Ok(())
+    }
+
     pub fn data(&self) -> Arc<Mutex<Vec<u8>>> {
         self.data.clone()
     }
 
+    /// The current value
+    pub async fn value(&self) -> Vec<u8> {
+        self.data.lock().await.clone()
+    }
+
+    /// The current value, `None` while a client read or write holds it
+    pub fn try_value(&self) -> Option<Vec<u8>> {
+        self.data.try_lock().map(|data| data.clone())
+    }
+
+    /// Store `value`
+    pub async fn set_value(&self, value: Vec<u8>) {
+        *self.data.lock().await = value;
+    }
+
     pub fn zbus(&self) -> &InterfaceRef<GattDescriptor1> {
         &self.interface
     }
 
-    pub(crate) fn property_map(&self) -> HashMap<String, OwnedValue> {
-        self.property_map.clone()
+    pub(crate) fn property_map(&self) -> &PropertyMap {
+        &self.property_map
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
     }
 
     pub(crate) fn owned_path(&self) -> OwnedObjectPath {
@@ -41,6 +79,11 @@ pub struct GattDescriptor1 {
     data: Arc<Mutex<Vec<u8>>>,
     flags: Vec<GattDescriptorFlags>,
     char_path: OwnedObjectPath,
+    metrics: MetricsHook,
+    access: AccessHook,
+    pub(crate) handle: Option<u16>,
+    properties: PropertyMap,
+    pub(crate) name: Option<String>,
 }
 
 impl GattDescriptor1 {
@@ -50,14 +93,55 @@ impl GattDescriptor1 {
             data: Arc::new(Mutex::new(data.unwrap_or_default())),
             flags,
             char_path: Default::default(),
+            metrics: MetricsHook::default(),
+            access: AccessHook::default(),
+            handle: None,
+            properties: PropertyMap::default(),
+            name: None,
         }
     }
 
+    /// Export under `name` instead of `descriptorN`. `name` is made a
+    /// valid path element with `path_segment()`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(path_segment(name));
+        self
+    }
+
+    /// Ask bluez to place the descriptor at ATT `handle`, so clients caching the
+    /// database keep finding it. Needs the `experimental` feature and bluez
+    /// running with `--experimental`.
+    pub fn with_handle(mut self, handle: u16) -> Self {
+        self.handle = Some(handle);
+        self
+    }
+
+    /// A read-only Characteristic Presentation Format descriptor
+    pub fn presentation_format(format: &PresentationFormat) -> Self {
+        Self::new(
+            CHARACTERISTIC_PRESENTATION_FORMAT,
+            Some(format.encode()),
+            vec![GattDescriptorFlags::Read],
+        )
+    }
+
+    /// Report reads, writes and errors to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = MetricsHook::new(metrics);
+        self
+    }
+
+    /// Serve reads and writes only to the devices `policy` allows
+    pub fn with_access_policy(mut self, policy: Arc<dyn AccessPolicy>) -> Self {
+        self.access = AccessHook::new(policy);
+        self
+    }
+
     fn data(&self) -> Arc<Mutex<Vec<u8>>> {
         self.data.clone()
     }
 
-    fn property_map(&self) -> HashMap<String, OwnedValue> {
+    async fn property_map(&self) -> HashMap<String, OwnedValue> {
         let mut props = HashMap::new();
 
         // TODO: could use try_from...
@@ -69,16 +153,10 @@ impl GattDescriptor1 {
             "Characteristic".to_string(),
             OwnedValue::from(self.char_path.as_ref()),
         );
-        if let Ok(data) = self
-            .data
-            .lock()
-            .map_err(|e| log::warn!("Could not lock data: {e}"))
+        if let Ok(data) = OwnedValue::try_from(Array::from(&*self.data.lock().await))
+            .map_err(|e| log::warn!("Could not convert data: {e}"))
         {
-            if let Ok(data) = OwnedValue::try_from(Array::from(&*data))
-                .map_err(|e| log::warn!("Could not convert data: {e}"))
-            {
-                props.insert("Value".to_string(), data);
-            }
+            props.insert("Value".to_string(), data);
         }
 
         let flags: Vec<String> = self
@@ -91,6 +169,10 @@ impl GattDescriptor1 {
         {
             props.insert("Flags".to_string(), flags);
         }
+        #[cfg(feature = "experimental")]
+        if let Some(handle) = self.handle {
+            props.insert("Handle".to_string(), OwnedValue::from(handle));
+        }
         props
     }
 
@@ -117,11 +199,12 @@ impl GattDescriptor1 {
         sys_connection: &Connection,
     ) -> Result<GattDescriptorHandle, zbus::Error> {
         self.char_path = characteristic_path;
-        let property_map = self.property_map();
+        self.properties = PropertyMap::new(self.property_map().await);
+        let property_map = self.properties.clone();
         let data = self.data();
 
         log::debug!("GattDescriptor1: Added UUID: {}", self.uuid);
-        sys_connection
+        let added = sys_connection
             .object_server()
             .at(&path, self)
             .await
@@ -129,8 +212,16 @@ impl GattDescriptor1 {
                 error!("{}: add_to_server {}", "path", err);
                 err
             })?;
+        exported(&path, added)?;
 
-        let interface = Self::get_descriptor_interface(&path, sys_connection).await?;
+        let interface = match Self::get_descriptor_interface(&path, sys_connection).await {
+            Ok(interface) => interface,
+            Err(err) => {
+                let server = sys_connection.object_server();
+                server.remove::<GattDescriptor1, _>(&path).await.ok();
+                return Err(err);
+            }
+        };
         Ok(GattDescriptorHandle {
             data,
             interface,
@@ -143,69 +234,46 @@ impl GattDescriptor1 {
 #[interface(interface = "org.bluez.GattDescriptor1")]
 impl GattDescriptor1 {
     /// ReadValue method
-    fn read_value(
+    async fn read_value(
         &self,
+        #[zbus(header)] header: Header<'_>,
         options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<Vec<u8>> {
-        let data = self
-            .data
-            .lock()
-            .map_err(|e| zbus::fdo::Error::Failed(format!("Could not lock data: {e}")))?;
-        let offset = if let Some(zvariant::Value::U16(ofs)) = options.get("offset") {
-            if *ofs as usize >= data.len() - 1 {
-                return Err(ZbusError::InvalidArgs("InvalidOffset".to_owned()));
-            }
-            *ofs
-        } else {
-            0
-        } as usize;
-        let data: Vec<u8> = data[offset..].to_vec();
-        Ok(data)
+    ) -> Result<Vec<u8>, GattError> {
+        trace::handle(&header, "ReadValue", async {
+            let res = match self.access.check(self.uuid, GattOperation::Read, &options) {
+                Ok(()) => read_value(&self.data.lock().await, &options),
+                Err(e) => Err(e),
+            };
+            self.metrics.read(self.uuid, &res);
+            res
+        })
+        .await
     }
 
     /// WriteValue method
-    fn write_value(
+    async fn write_value(
         &self,
+        #[zbus(header)] header: Header<'_>,
         value: &[u8],
         options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
-    ) -> zbus::fdo::Result<()> {
-        let mut data = self
-            .data
-            .lock()
-            .map_err(|e| zbus::fdo::Error::Failed(format!("Could not lock data: {e}")))?;
-        let offset = if let Some(zvariant::Value::U16(ofs)) = options.get("offset") {
-            // if *ofs as usize >= data.len() || data.len() - (*ofs as usize) >= data.len()
-            // {     return
-            // Err(ZbusError::InvalidArgs("InvalidOffset".to_owned())); }
-            *ofs
-        } else {
-            0
-        } as usize;
-
-        let data_len = data.len();
-        let value_len = value.len();
-        if offset + value_len > data_len {
-            let max_len = if value_len < data_len {
-                data_len
-            } else {
-                value_len
+    ) -> Result<(), GattError> {
+        trace::handle(&header, "WriteValue", async {
+            let res = match self.access.check(self.uuid, GattOperation::Write, &options) {
+                Ok(()) => write_value(&mut *self.data.lock().await, value, &options),
+                Err(e) => Err(e),
             };
-            let mut new_data = vec![0; offset + max_len];
-            new_data[..data_len].copy_from_slice(&data);
-            new_data[offset..].copy_from_slice(value);
-            *data = new_data;
-        } else {
-            data.truncate(value_len);
-            data[offset..].copy_from_slice(value);
-        }
-
-        Ok(())
+            self.metrics.write(self.uuid, value.len(), &res);
+            res
+        })
+        .await
     }
 
     /// Characteristic property
+    ///
+    /// Object path of the GATT characteristic the descriptor belongs to.
     #[zbus(property)]
     fn characteristic(&self) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
-        Ok(OwnedObjectPath::default())
+        Ok(self.char_path.clone())
     }
 
     /// Flags property
@@ -229,4 +297,34 @@ impl GattDescriptor1 {
     fn value(&self) -> zbus::fdo::Result<Vec<u8>> {
         Ok(Vec::default())
     }
+
+    /// Handle property
+    ///
+    /// The ATT handle of the attribute. A value set before registering asks
+    /// bluez for that handle, bluez writes back the handle it assigned.
+    #[zbus(property)]
+    fn handle(&self) -> zbus::fdo::Result<u16> {
+        experimental_property!("handle", "GattDescriptor1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle.map_or_else(
+                || {
+                    unused_property!("handle", "GattDescriptor1");
+                },
+                Ok,
+            )
+        }
+    }
+
+    #[cfg_attr(not(feature = "experimental"), allow(unused_variables))]
+    #[zbus(property)]
+    fn set_handle(&mut self, handle: u16) -> zbus::fdo::Result<()> {
+        experimental_property!("handle", "GattDescriptor1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle = Some(handle);
+            self.properties.set("Handle", OwnedValue::from(handle));
+            Ok(())
+        }
+    }
 }
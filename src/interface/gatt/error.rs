@@ -0,0 +1,23 @@
+/// Errors a GATT server replies to bluez with. Bluez turns them into the ATT
+/// error the client receives, e.g. `NotAuthorized` into "Insufficient
+/// Authorization".
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.bluez.Error")]
+pub enum GattError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    /// The operation failed
+    Failed(String),
+    /// Another operation is still running
+    InProgress(String),
+    /// The attribute does not allow the operation
+    NotPermitted(String),
+    /// The device is not allowed to perform the operation
+    NotAuthorized(String),
+    /// The offset is past the end of the value
+    InvalidOffset(String),
+    /// The value has the wrong length
+    InvalidValueLength(String),
+    /// The operation is not supported
+    NotSupported(String),
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use super::GattError;
+
+/// A GATT server operation, e.g. the one an error happened in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GattOperation {
+    Read,
+    Write,
+    Notify,
+    Subscribe,
+    Unsubscribe,
+}
+
+/// Hooks the GATT server calls as clients use a characteristic or
+/// descriptor, e.g. to count requests for a metrics exporter. `uuid` is the
+/// UUID of the characteristic or descriptor. Every hook defaults to doing
+/// nothing.
+pub trait Metrics: Send + Sync + 'static {
+    /// A client read `len` bytes
+    fn read(&self, _uuid: Uuid, _len: usize) {}
+
+    /// A client wrote `len` bytes
+    fn write(&self, _uuid: Uuid, _len: usize) {}
+
+    /// A notification of `len` bytes was sent
+    fn notify(&self, _uuid: Uuid, _len: usize) {}
+
+    /// A client enabled notifications
+    fn subscribe(&self, _uuid: Uuid) {}
+
+    /// A client disabled notifications
+    fn unsubscribe(&self, _uuid: Uuid) {}
+
+    /// `operation` failed with `error`
+    fn error(&self, _uuid: Uuid, _operation: GattOperation, _error: &GattError) {}
+}
+
+/// The optional `Metrics` of a characteristic or descriptor
+#[derive(Clone, Default)]
+pub(crate) struct MetricsHook(Option<Arc<dyn Metrics>>);
+
+impl fmt::Debug for MetricsHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MetricsHook")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+impl MetricsHook {
+    pub(crate) fn new(metrics: Arc<dyn Metrics>) -> Self {
+        Self(Some(metrics))
+    }
+
+    pub(crate) fn read(&self, uuid: Uuid, res: &Result<Vec<u8>, GattError>) {
+        match (&self.0, res) {
+            (Some(metrics), Ok(value)) => metrics.read(uuid, value.len()),
+            (Some(metrics), Err(err)) => metrics.error(uuid, GattOperation::Read, err),
+            (None, _) => {}
+        }
+    }
+
+    pub(crate) fn write(&self, uuid: Uuid, len: usize, res: &Result<(), GattError>) {
+        match (&self.0, res) {
+            (Some(metrics), Ok(())) => metrics.write(uuid, len),
+            (Some(metrics), Err(err)) => metrics.error(uuid, GattOperation::Write, err),
+            (None, _) => {}
+        }
+    }
+
+    pub(crate) fn notify(&self, uuid: Uuid, len: usize, res: &zbus::Result<()>) {
+        match (&self.0, res) {
+            (Some(metrics), Ok(())) => metrics.notify(uuid, len),
+            (Some(metrics), Err(err)) => {
+                metrics.error(uuid, GattOperation::Notify, &GattError::from(err.clone()))
+            }
+            (None, _) => {}
+        }
+    }
+
+    pub(crate) fn subscribe(&self, uuid: Uuid) {
+        if let Some(metrics) = &self.0 {
+            metrics.subscribe(uuid);
+        }
+    }
+
+    pub(crate) fn unsubscribe(&self, uuid: Uuid) {
+        if let Some(metrics) = &self.0 {
+            metrics.unsubscribe(uuid);
+        }
+    }
+}
@@ -1,25 +1,64 @@
+mod access;
+pub use access::*;
+
+mod error;
+pub use error::*;
+
+mod subscriptions;
+pub use subscriptions::*;
+
+mod metrics;
+pub use metrics::*;
+
 mod types_;
 pub use types_::*;
 
-#[cfg(feature = "async-io")]
+mod properties;
+use properties::*;
+
+mod value;
+use value::*;
+
+mod notify_socket;
+use notify_socket::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
 mod application;
-#[cfg(feature = "async-io")]
+#[cfg(any(feature = "async-io", feature = "tokio"))]
 pub use application::*;
 
-#[cfg(feature = "async-io")]
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod battery;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use battery::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
 mod characteristic1;
-#[cfg(feature = "async-io")]
+#[cfg(any(feature = "async-io", feature = "tokio"))]
 pub use characteristic1::*;
 
-#[cfg(feature = "async-io")]
+#[cfg(any(feature = "async-io", feature = "tokio"))]
 mod descriptor;
-#[cfg(feature = "async-io")]
+#[cfg(any(feature = "async-io", feature = "tokio"))]
 pub use descriptor::*;
 
-#[cfg(feature = "async-io")]
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod notify_fd;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use notify_fd::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
 mod service1;
-#[cfg(feature = "async-io")]
+#[cfg(any(feature = "async-io", feature = "tokio"))]
 pub use service1::*;
 
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod typed;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use typed::*;
+
+#[cfg(all(feature = "macros", any(feature = "async-io", feature = "tokio")))]
+pub use bluez_zbus_macros::gatt_service;
+
 #[cfg(feature = "blocking-api")]
 pub mod blocking;
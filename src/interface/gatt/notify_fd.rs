@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+use futures_channel::mpsc;
+use futures_lite::{AsyncReadExt, AsyncWrite, AsyncWriteExt, Stream};
+use zbus::zvariant::{self, ObjectPath, OwnedObjectPath};
+
+use super::{
+    device, is_closed_error, notify_payload, notify_socket, GattError, OnNotifyClose,
+    Subscriptions,
+};
+use crate::rt::AsyncStream;
+
+/// Writer over the socket a `GattCharacteristic1` hands to bluez on
+/// `AcquireNotify`. Each packet written is sent to the subscribed client as
+/// one notification, without a D-Bus round trip.
+///
+/// Writes wait while the socket is full. Bluez closes the socket once the
+/// client unsubscribes or disconnects, sends fail with `BrokenPipe` after
+/// that.
+pub struct NotifyWriter {
+    stream: AsyncStream,
+    mtu: u16,
+    device: Option<OwnedObjectPath>,
+    subscriptions: Subscriptions,
+    closed: bool,
+    on_close: Option<OnNotifyClose>,
+}
+
+impl NotifyWriter {
+    /// The MTU bluez negotiated with the client
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    /// Largest value sent in one notification
+    pub fn payload(&self) -> usize {
+        notify_payload(self.mtu)
+    }
+
+    /// The device that subscribed, if bluez said
+    pub fn device(&self) -> Option<&ObjectPath<'_>> {
+        self.device.as_deref()
+    }
+
+    /// Bluez closed the socket
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    /// Send all of `value`, one notification per `payload()` sized chunk
+    pub async fn send(&mut self, value: &[u8]) -> io::Result<()> {
+        for chunk in value.chunks(self.payload()) {
+            let res = self.stream.write_all(chunk).await;
+            self.check(res)?;
+        }
+        Ok(())
+    }
+
+    /// Send `value` as one notification if the socket has room for it.
+    /// `Ok(false)` if it is full and nothing was sent.
+    pub fn try_send(&mut self, value: &[u8]) -> io::Result<bool> {
+        if value.len() > self.payload() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} bytes exceed the MTU", value.len()),
+            ));
+        }
+        let mut cx = Context::from_waker(Waker::noop());
+        match Pin::new(&mut self.stream).poll_write(&mut cx, value) {
+            Poll::Ready(res) => self.check(res).map(|_| true),
+            Poll::Pending => Ok(false),
+        }
+    }
+
+    /// Wait until bluez closes the socket
+    pub async fn closed(&mut self) {
+        let mut buf = [0; 1];
+        while !self.closed {
+            if !matches!(self.stream.read(&mut buf).await, Ok(1..)) {
+                self.close();
+            }
+        }
+    }
+
+    fn check<T>(&mut self, res: io::Result<T>) -> io::Result<T> {
+        if let Err(e) = &res
+            && is_closed_error(e)
+        {
+            self.close();
+        }
+        res
+    }
+
+    fn close(&mut self) {
+        if self.closed {
+            return;
+        }
+        self.closed = true;
+        if let Some(device) = &self.device {
+            self.subscriptions.remove(device);
+        }
+        self.subscriptions.released();
+        if let Some(on_close) = self.on_close.take() {
+            on_close();
+        }
+    }
+}
+
+impl Drop for NotifyWriter {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl AsyncWrite for NotifyWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let len = buf.len().min(self.payload());
+        let res = Pin::new(&mut self.stream).poll_write(cx, &buf[..len]);
+        res.map(|res| self.check(res))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_close(cx)
+    }
+}
+
+/// Stream of a `NotifyWriter` for each `AcquireNotify` by bluez
+pub struct NotifyWriters {
+    pub(super) events: mpsc::UnboundedReceiver<NotifyWriter>,
+}
+
+impl Stream for NotifyWriters {
+    type Item = NotifyWriter;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+/// Answer `AcquireNotify`: keep one end of a new socket pair in a
+/// `NotifyWriter` and return the other end for bluez
+pub(super) fn acquire_notify(
+    writers: &mpsc::UnboundedSender<NotifyWriter>,
+    subscriptions: &Subscriptions,
+    options: &HashMap<&str, zvariant::Value<'_>>,
+    on_close: OnNotifyClose,
+) -> Result<(zvariant::OwnedFd, u16), GattError> {
+    let (local, remote, mtu) = notify_socket(options)?;
+    let writer = NotifyWriter {
+        stream: AsyncStream::new(local).map_err(|e| GattError::Failed(e.to_string()))?,
+        mtu,
+        device: device(options),
+        subscriptions: subscriptions.clone(),
+        closed: false,
+        on_close: Some(on_close),
+    };
+    subscriptions.acquired(options);
+    writers
+        .unbounded_send(writer)
+        .map_err(|_| GattError::NotSupported("Nobody sends notifications".to_owned()))?;
+    Ok((remote, mtu))
+}
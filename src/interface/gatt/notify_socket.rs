@@ -0,0 +1,64 @@
+//! Socket handling shared by the async and blocking `NotifyWriter`
+
+use std::collections::HashMap;
+use std::io;
+use std::os::fd::{FromRawFd, OwnedFd};
+use std::os::unix::net::UnixStream;
+
+use zbus::zvariant;
+
+use super::GattError;
+use crate::client::DEFAULT_MTU;
+
+/// Run by a `NotifyWriter` once its socket closed
+pub(crate) type OnNotifyClose = Box<dyn FnOnce() + Send + Sync>;
+
+/// Answer `AcquireNotify`: a new socket pair, one end for a `NotifyWriter`
+/// and the other for bluez, and the MTU bluez reported
+pub(crate) fn notify_socket(
+    options: &HashMap<&str, zvariant::Value<'_>>,
+) -> Result<(UnixStream, zvariant::OwnedFd, u16), GattError> {
+    let mtu = match options.get("mtu") {
+        Some(zvariant::Value::U16(mtu)) => *mtu,
+        _ => DEFAULT_MTU,
+    };
+    let (local, remote) = seqpacket_pair().map_err(|e| GattError::Failed(e.to_string()))?;
+    Ok((
+        UnixStream::from(local),
+        zvariant::OwnedFd::from(remote),
+        mtu,
+    ))
+}
+
+/// Largest value sent in one notification at `mtu`
+pub(crate) fn notify_payload(mtu: u16) -> usize {
+    (mtu.max(DEFAULT_MTU) - 3) as usize
+}
+
+/// Whether `error` means bluez closed the socket
+pub(crate) fn is_closed_error(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset
+    )
+}
+
+/// A connected pair of `SOCK_SEQPACKET` unix sockets, which keep the
+/// boundaries between packets like bluez expects
+fn seqpacket_pair() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0; 2];
+    // SAFETY: `fds` has room for the two descriptors socketpair() returns
+    let res = unsafe {
+        libc::socketpair(
+            libc::AF_UNIX,
+            libc::SOCK_SEQPACKET | libc::SOCK_CLOEXEC,
+            0,
+            fds.as_mut_ptr(),
+        )
+    };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: both descriptors were just opened and are owned by nobody else
+    Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+}
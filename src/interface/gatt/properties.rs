@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::{Serialize, Serializer};
+use zbus::zvariant::{OwnedValue, Signature, Type};
+
+type Properties = HashMap<String, OwnedValue>;
+
+/// The properties of one exported attribute as listed by
+/// `GetManagedObjects`. Built once on register and shared by the interface,
+/// its handle and the application, the interface updates it when bluez sets
+/// a property.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PropertyMap(Arc<Mutex<Arc<Properties>>>);
+
+impl PropertyMap {
+    pub(crate) fn new(properties: Properties) -> Self {
+        Self(Arc::new(Mutex::new(Arc::new(properties))))
+    }
+
+    pub(crate) fn get(&self) -> Arc<Properties> {
+        self.0
+            .lock()
+            .map(|properties| properties.clone())
+            .unwrap_or_default()
+    }
+
+    #[cfg_attr(not(feature = "experimental"), allow(dead_code))]
+    pub(crate) fn set(&self, name: &str, value: OwnedValue) {
+        if let Ok(mut properties) = self.0.lock() {
+            Arc::make_mut(&mut properties).insert(name.to_string(), value);
+        }
+    }
+}
+
+impl Serialize for PropertyMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.get().serialize(serializer)
+    }
+}
+
+impl Type for PropertyMap {
+    const SIGNATURE: &'static Signature = Properties::SIGNATURE;
+}
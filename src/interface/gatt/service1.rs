@@ -41,27 +41,63 @@ use std::collections::{BTreeMap, HashMap};
 use log::error;
 use uuid::Uuid;
 use zbus::interface;
-use zbus::zvariant::{OwnedObjectPath, OwnedValue, Str};
+use zbus::object_server::InterfaceRef;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Str};
 use zbus::Connection;
 
 use super::characteristic1::{GattCharacteristic1, GattCharacteristicHandle};
-use super::GattDescriptor1;
+use super::{exported, path_segment, ChildPaths, GattDescriptor1, PropertyMap};
+use crate::experimental_property;
+#[cfg(feature = "experimental")]
+use crate::unused_property;
 
 pub struct GattServiceHandle {
     characteristics: BTreeMap<Uuid, GattCharacteristicHandle>,
-    _uuid: Uuid,
-    _primary: bool,
-    property_map: HashMap<String, OwnedValue>,
+    uuid: Uuid,
+    primary: bool,
+    interface: InterfaceRef<GattService1>,
+    property_map: PropertyMap,
     path: OwnedObjectPath,
 }
 
 impl GattServiceHandle {
+    /// Remove the service, its characteristics and their descriptors from the
+    /// object server, children first
+    pub(crate) async fn unexport(&self, connection: &Connection) -> Result<(), zbus::Error> {
+        // Carry on past a failed child and return the first error
+        let mut res = Ok(());
+        for characteristic in self.characteristics.values() {
+            res = res.and(characteristic.unexport(connection).await);
+        }
+        let removed = connection
+            .object_server()
+            .remove::<GattService1, _>(&self.path)
+            .await;
+        res.and(removed.map(|_| ()))
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn primary(&self) -> bool {
+        self.primary
+    }
+
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    pub fn zbus(&self) -> &InterfaceRef<GattService1> {
+        &self.interface
+    }
+
     pub fn characteristics(&self) -> &BTreeMap<Uuid, GattCharacteristicHandle> {
         &self.characteristics
     }
 
-    pub(crate) fn property_map(&self) -> HashMap<String, OwnedValue> {
-        self.property_map.clone()
+    pub(crate) fn property_map(&self) -> &PropertyMap {
+        &self.property_map
     }
 
     pub(crate) fn owned_path(&self) -> OwnedObjectPath {
@@ -73,11 +109,35 @@ impl GattServiceHandle {
 pub struct GattService1 {
     uuid: Uuid,
     primary: bool,
+    pub(crate) handle: Option<u16>,
+    properties: PropertyMap,
+    pub(crate) name: Option<String>,
 }
 
 impl GattService1 {
     pub fn new(uuid: Uuid, primary: bool) -> Self {
-        Self { uuid, primary }
+        Self {
+            uuid,
+            primary,
+            handle: None,
+            properties: PropertyMap::default(),
+            name: None,
+        }
+    }
+
+    /// Export under `name` instead of `serviceN`. `name` is made a
+    /// valid path element with `path_segment()`.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(path_segment(name));
+        self
+    }
+
+    /// Ask bluez to place the service at ATT `handle`, so clients caching the
+    /// database keep finding it. Needs the `experimental` feature and bluez
+    /// running with `--experimental`.
+    pub fn with_handle(mut self, handle: u16) -> Self {
+        self.handle = Some(handle);
+        self
     }
 
     fn property_map(&self) -> HashMap<String, OwnedValue> {
@@ -89,6 +149,10 @@ impl GattService1 {
             OwnedValue::from(Str::from(self.uuid.to_string())),
         );
         props.insert("Primary".to_string(), OwnedValue::from(self.primary));
+        #[cfg(feature = "experimental")]
+        if let Some(handle) = self.handle {
+            props.insert("Handle".to_string(), OwnedValue::from(handle));
+        }
         // if !self.includes.is_empty() {
         //     let includes: Vec<String> = self.includes.iter().cloned().collect();
         //     let includes = Array::from(includes);
@@ -99,45 +163,82 @@ impl GattService1 {
     }
 
     pub async fn register(
-        self,
+        mut self,
         characteristics: Vec<(GattCharacteristic1, Vec<GattDescriptor1>)>,
         sys_connection: &Connection,
         service_path: OwnedObjectPath,
     ) -> Result<GattServiceHandle, zbus::Error> {
-        let mut service_handle = GattServiceHandle {
-            characteristics: BTreeMap::new(),
-            _uuid: self.uuid,
-            _primary: self.primary,
-            property_map: self.property_map(),
-            path: service_path.clone(),
-        };
+        self.properties = PropertyMap::new(self.property_map());
+        let property_map = self.properties.clone();
+        let uuid = self.uuid;
+        let primary = self.primary;
+        let mut char_handles = BTreeMap::new();
 
+        let mut paths = ChildPaths::new(service_path.as_str(), "characteristic");
         for (count, (gatt_char, descriptors)) in characteristics.into_iter().enumerate() {
-            service_handle.characteristics.insert(
-                gatt_char.uuid,
-                gatt_char
-                    .register(
-                        OwnedObjectPath::try_from(format!("{service_path}/characteristic{count}"))?,
-                        service_path.clone(),
-                        descriptors,
-                        sys_connection,
-                    )
-                    .await?,
-            );
+            let registered = match paths.next(count, gatt_char.name.as_deref()) {
+                Ok(path) => {
+                    let uuid = gatt_char.uuid;
+                    gatt_char
+                        .register(path, service_path.clone(), descriptors, sys_connection)
+                        .await
+                        .map(|handle| (uuid, handle))
+                }
+                Err(err) => Err(err),
+            };
+            match registered {
+                Ok((uuid, handle)) => char_handles.insert(uuid, handle),
+                Err(err) => {
+                    unexport_characteristics(&char_handles, sys_connection).await;
+                    return Err(err);
+                }
+            };
             // TODO: push includes paths
         }
 
         log::debug!("GattService1: Added UUID: {}", self.uuid);
-        sys_connection
+        let added = sys_connection
             .object_server()
             .at(&service_path, self)
             .await
             .map_err(|err| {
                 error!("{}: add_to_server {}", "path", err);
                 err
-            })?;
+            })
+            .and_then(|added| exported(&service_path, added));
+        if let Err(err) = added {
+            unexport_characteristics(&char_handles, sys_connection).await;
+            return Err(err);
+        }
 
-        Ok(service_handle)
+        let server = sys_connection.object_server();
+        let interface = match server.interface::<_, GattService1>(&service_path).await {
+            Ok(interface) => interface,
+            Err(err) => {
+                server.remove::<GattService1, _>(&service_path).await.ok();
+                unexport_characteristics(&char_handles, sys_connection).await;
+                return Err(err);
+            }
+        };
+        Ok(GattServiceHandle {
+            characteristics: char_handles,
+            uuid,
+            primary,
+            interface,
+            property_map,
+            path: service_path,
+        })
+    }
+}
+
+/// Remove the characteristics of a service that failed to register. The
+/// registration error is what gets reported, so removal errors are ignored.
+async fn unexport_characteristics(
+    characteristics: &BTreeMap<Uuid, GattCharacteristicHandle>,
+    connection: &Connection,
+) {
+    for characteristic in characteristics.values() {
+        characteristic.unexport(connection).await.ok();
     }
 }
 
@@ -170,4 +271,34 @@ impl GattService1 {
     fn uuid(&self) -> zbus::fdo::Result<String> {
         Ok(self.uuid.to_string())
     }
+
+    /// Handle property
+    ///
+    /// The ATT handle of the attribute. A value set before registering asks
+    /// bluez for that handle, bluez writes back the handle it assigned.
+    #[zbus(property)]
+    fn handle(&self) -> zbus::fdo::Result<u16> {
+        experimental_property!("handle", "GattService1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle.map_or_else(
+                || {
+                    unused_property!("handle", "GattService1");
+                },
+                Ok,
+            )
+        }
+    }
+
+    #[cfg_attr(not(feature = "experimental"), allow(unused_variables))]
+    #[zbus(property)]
+    fn set_handle(&mut self, handle: u16) -> zbus::fdo::Result<()> {
+        experimental_property!("handle", "GattService1");
+        #[cfg(feature = "experimental")]
+        {
+            self.handle = Some(handle);
+            self.properties.set("Handle", OwnedValue::from(handle));
+            Ok(())
+        }
+    }
 }
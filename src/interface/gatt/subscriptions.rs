@@ -0,0 +1,144 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use zbus::zvariant::{self, ObjectPath, OwnedObjectPath};
+
+/// Who is subscribed to the notifications or indications of one
+/// characteristic.
+///
+/// Bluez serves the Client Characteristic Configuration descriptor itself and
+/// only tells the application when notifications start and stop: with
+/// `StartNotify` and `StopNotify` for all clients at once, and with
+/// `AcquireNotify` for one device.
+#[derive(Debug, Default, Clone)]
+pub struct Subscriptions(Arc<Mutex<State>>);
+
+#[derive(Debug, Default)]
+struct State {
+    /// Between `StartNotify` and `StopNotify`
+    started: bool,
+    /// Devices with an acquired notification socket
+    devices: HashSet<OwnedObjectPath>,
+    /// Notification sockets handed out and not closed yet
+    sockets: usize,
+}
+
+impl Subscriptions {
+    /// Whether any client has notifications or indications enabled
+    pub fn is_notifying(&self) -> bool {
+        self.0
+            .lock()
+            .map(|state| state.started || !state.devices.is_empty())
+            .unwrap_or(false)
+    }
+
+    /// Devices that subscribed with `AcquireNotify`
+    pub fn devices(&self) -> Vec<OwnedObjectPath> {
+        self.0
+            .lock()
+            .map(|state| state.devices.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Whether `device` subscribed with `AcquireNotify`
+    pub fn contains(&self, device: &ObjectPath<'_>) -> bool {
+        self.0
+            .lock()
+            .map(|state| state.devices.iter().any(|path| path.as_ref() == *device))
+            .unwrap_or(false)
+    }
+
+    /// Forget `device`, e.g. once it disconnects
+    pub fn remove(&self, device: &ObjectPath<'_>) {
+        if let Ok(mut state) = self.0.lock() {
+            state.devices.retain(|path| path.as_ref() != *device);
+        }
+    }
+
+    pub(crate) fn start(&self) {
+        if let Ok(mut state) = self.0.lock() {
+            state.started = true;
+        }
+    }
+
+    pub(crate) fn stop(&self) {
+        if let Ok(mut state) = self.0.lock() {
+            state.started = false;
+        }
+    }
+
+    /// Record an `AcquireNotify` from the device in `options`
+    pub(crate) fn acquired(&self, options: &HashMap<&str, zvariant::Value<'_>>) {
+        if let Ok(mut state) = self.0.lock() {
+            state.sockets += 1;
+            if let Some(device) = device(options) {
+                state.devices.insert(device);
+            }
+        }
+    }
+
+    /// Record that a socket from `acquired()` closed
+    pub(crate) fn released(&self) {
+        if let Ok(mut state) = self.0.lock() {
+            state.sockets = state.sockets.saturating_sub(1);
+        }
+    }
+
+    /// Whether a notification socket is still open
+    pub(crate) fn has_sockets(&self) -> bool {
+        self.0.lock().map(|state| state.sockets > 0).unwrap_or(false)
+    }
+}
+
+/// The `device` option bluez passes to server side reads and writes
+pub(super) fn device(options: &HashMap<&str, zvariant::Value<'_>>) -> Option<OwnedObjectPath> {
+    match options.get("device") {
+        Some(zvariant::Value::ObjectPath(path)) => Some(path.to_owned().into()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEVICE: &str = "/org/bluez/hci0/dev_00_11_22_33_44_55";
+
+    fn from(device: &str) -> HashMap<&'static str, zvariant::Value<'static>> {
+        let path = ObjectPath::try_from(device.to_owned()).unwrap();
+        HashMap::from([("device", zvariant::Value::from(path))])
+    }
+
+    #[test]
+    fn start_and_stop_notify() {
+        let subscriptions = Subscriptions::default();
+        assert!(!subscriptions.is_notifying());
+        subscriptions.start();
+        assert!(subscriptions.is_notifying());
+        assert!(subscriptions.devices().is_empty());
+        subscriptions.stop();
+        assert!(!subscriptions.is_notifying());
+    }
+
+    #[test]
+    fn acquire_notify_tracks_device() {
+        let subscriptions = Subscriptions::default();
+        let device = ObjectPath::try_from(DEVICE).unwrap();
+        subscriptions.acquired(&from(DEVICE));
+        assert!(subscriptions.is_notifying());
+        assert!(subscriptions.contains(&device));
+        subscriptions.remove(&device);
+        assert!(!subscriptions.contains(&device));
+        assert!(!subscriptions.is_notifying());
+    }
+
+    #[test]
+    fn acquire_notify_without_device() {
+        let subscriptions = Subscriptions::default();
+        subscriptions.acquired(&HashMap::new());
+        assert!(subscriptions.devices().is_empty());
+        assert!(subscriptions.has_sockets());
+        subscriptions.released();
+        assert!(!subscriptions.has_sockets());
+    }
+}
@@ -0,0 +1,134 @@
+//! # Typed characteristics
+//!
+//! `TypedCharacteristic<T>` wraps a `GattCharacteristic1` whose value is a
+//! `GattValue`, so the application reads, writes and notifies `T` instead of
+//! byte buffers.
+
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures_lite::Stream;
+use log::warn;
+use uuid::Uuid;
+
+use super::characteristic1::{CharacteristicWrites, GattCharacteristic1, GattCharacteristicHandle};
+use super::{CharacteristicFlags, Metrics};
+use crate::codec::GattValue;
+
+/// A `GattCharacteristic1` holding a `T`
+pub struct TypedCharacteristic<T> {
+    characteristic: GattCharacteristic1,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T: GattValue> TypedCharacteristic<T> {
+    pub fn new(uuid: Uuid, value: Option<T>, flags: Vec<CharacteristicFlags>) -> Self {
+        Self {
+            characteristic: GattCharacteristic1::new(
+                uuid,
+                value.map(|value| value.to_bytes()),
+                flags,
+            ),
+            _value: PhantomData,
+        }
+    }
+
+    /// Receive the value after each write by a client
+    pub fn with_writes(self) -> (Self, TypedWrites<T>) {
+        let (characteristic, writes) = self.characteristic.with_writes();
+        (
+            Self {
+                characteristic,
+                _value: PhantomData,
+            },
+            TypedWrites {
+                writes,
+                _value: PhantomData,
+            },
+        )
+    }
+
+    /// Report reads, writes, subscriptions, notifications and errors to
+    /// `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.characteristic = self.characteristic.with_metrics(metrics);
+        self
+    }
+
+    /// See `GattCharacteristic1::with_name()`
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.characteristic = self.characteristic.with_name(name);
+        self
+    }
+
+    /// The characteristic to register with its service
+    pub fn into_inner(self) -> GattCharacteristic1 {
+        self.characteristic
+    }
+}
+
+impl<T> From<TypedCharacteristic<T>> for GattCharacteristic1 {
+    fn from(typed: TypedCharacteristic<T>) -> Self {
+        typed.characteristic
+    }
+}
+
+/// Stream of the values written to a `TypedCharacteristic` by clients.
+/// Writes that don't decode as a `T` are logged and skipped.
+pub struct TypedWrites<T> {
+    writes: CharacteristicWrites,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<T: GattValue> Stream for TypedWrites<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match Pin::new(&mut self.writes).poll_next(cx) {
+                Poll::Ready(Some(data)) => match T::from_bytes(&data) {
+                    Ok(value) => return Poll::Ready(Some(value)),
+                    Err(e) => warn!("TypedWrites: skipping write: {e}"),
+                },
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Typed access to the value of a registered characteristic
+pub struct TypedCharacteristicHandle<'a, T> {
+    handle: &'a GattCharacteristicHandle,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: GattValue> TypedCharacteristicHandle<'a, T> {
+    pub fn new(handle: &'a GattCharacteristicHandle) -> Self {
+        Self {
+            handle,
+            _value: PhantomData,
+        }
+    }
+
+    pub fn handle(&self) -> &GattCharacteristicHandle {
+        self.handle
+    }
+
+    /// Decode the current value
+    pub async fn value(&self) -> Result<T, zbus::Error> {
+        T::from_bytes(&self.handle.value().await)
+    }
+
+    /// Store `value` without notifying clients
+    pub async fn set_value(&self, value: &T) {
+        self.handle.set_value(value.to_bytes()).await;
+    }
+
+    /// Store `value` and send it to subscribed clients as a notification
+    pub async fn notify(&self, value: &T) -> Result<(), zbus::Error> {
+        self.handle.notify(value.to_bytes()).await
+    }
+}
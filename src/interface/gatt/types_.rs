@@ -1,4 +1,8 @@
-use zbus::zvariant::Type;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use bitflags::bitflags;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, Type};
 
 use crate::enum_impl_to_from_str;
 
@@ -30,6 +34,207 @@ enum_impl_to_from_str! {
     }
 }
 
+bitflags! {
+    /// `CharacteristicFlags` as a bit set, for cheap containment checks
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct CharacteristicFlagSet: u32 {
+        const BROADCAST = 1 << 0;
+        const READ = 1 << 1;
+        const WRITE_WITHOUT_RESPONSE = 1 << 2;
+        const WRITE = 1 << 3;
+        const NOTIFY = 1 << 4;
+        const INDICATE = 1 << 5;
+        const AUTHENTICATED_SIGNED_WRITES = 1 << 6;
+        const EXTENDED_PROPERTIES = 1 << 7;
+        const RELIABLE_WRITE = 1 << 8;
+        const WRITABLE_AUXILIARIES = 1 << 9;
+        const ENCRYPT_READ = 1 << 10;
+        const ENCRYPT_WRITE = 1 << 11;
+        const ENCRYPT_NOTIFY = 1 << 12;
+        const ENCRYPT_INDICATE = 1 << 13;
+        const ENCRYPT_AUTHENTICATED_READ = 1 << 14;
+        const ENCRYPT_AUTHENTICATED_WRITE = 1 << 15;
+        const ENCRYPT_AUTHENTICATED_NOTIFY = 1 << 16;
+        const ENCRYPT_AUTHENTICATED_INDICATE = 1 << 17;
+        const SECURE_READ = 1 << 18;
+        const SECURE_WRITE = 1 << 19;
+        const SECURE_NOTIFY = 1 << 20;
+        const SECURE_INDICATE = 1 << 21;
+        const AUTHORIZE = 1 << 22;
+    }
+}
+
+const CHARACTERISTIC_FLAG_BITS: [(CharacteristicFlags, CharacteristicFlagSet); 23] = [
+    (
+        CharacteristicFlags::Broadcast,
+        CharacteristicFlagSet::BROADCAST,
+    ),
+    (CharacteristicFlags::Read, CharacteristicFlagSet::READ),
+    (
+        CharacteristicFlags::WriteWithoutResponse,
+        CharacteristicFlagSet::WRITE_WITHOUT_RESPONSE,
+    ),
+    (CharacteristicFlags::Write, CharacteristicFlagSet::WRITE),
+    (CharacteristicFlags::Notify, CharacteristicFlagSet::NOTIFY),
+    (
+        CharacteristicFlags::Indicate,
+        CharacteristicFlagSet::INDICATE,
+    ),
+    (
+        CharacteristicFlags::AuthenticatedSignedWrites,
+        CharacteristicFlagSet::AUTHENTICATED_SIGNED_WRITES,
+    ),
+    (
+        CharacteristicFlags::ExtendedProperties,
+        CharacteristicFlagSet::EXTENDED_PROPERTIES,
+    ),
+    (
+        CharacteristicFlags::ReliableWrite,
+        CharacteristicFlagSet::RELIABLE_WRITE,
+    ),
+    (
+        CharacteristicFlags::WritableAuxiliaries,
+        CharacteristicFlagSet::WRITABLE_AUXILIARIES,
+    ),
+    (
+        CharacteristicFlags::EncryptRead,
+        CharacteristicFlagSet::ENCRYPT_READ,
+    ),
+    (
+        CharacteristicFlags::EncryptWrite,
+        CharacteristicFlagSet::ENCRYPT_WRITE,
+    ),
+    (
+        CharacteristicFlags::EncryptNotify,
+        CharacteristicFlagSet::ENCRYPT_NOTIFY,
+    ),
+    (
+        CharacteristicFlags::EncryptIndicate,
+        CharacteristicFlagSet::ENCRYPT_INDICATE,
+    ),
+    (
+        CharacteristicFlags::EncryptAuthenticatedRead,
+        CharacteristicFlagSet::ENCRYPT_AUTHENTICATED_READ,
+    ),
+    (
+        CharacteristicFlags::EncryptAuthenticatedWrite,
+        CharacteristicFlagSet::ENCRYPT_AUTHENTICATED_WRITE,
+    ),
+    (
+        CharacteristicFlags::EncryptAuthenticatedNotify,
+        CharacteristicFlagSet::ENCRYPT_AUTHENTICATED_NOTIFY,
+    ),
+    (
+        CharacteristicFlags::EncryptAuthenticatedIndicate,
+        CharacteristicFlagSet::ENCRYPT_AUTHENTICATED_INDICATE,
+    ),
+    (
+        CharacteristicFlags::SecureRead,
+        CharacteristicFlagSet::SECURE_READ,
+    ),
+    (
+        CharacteristicFlags::SecureWrite,
+        CharacteristicFlagSet::SECURE_WRITE,
+    ),
+    (
+        CharacteristicFlags::SecureNotify,
+        CharacteristicFlagSet::SECURE_NOTIFY,
+    ),
+    (
+        CharacteristicFlags::SecureIndicate,
+        CharacteristicFlagSet::SECURE_INDICATE,
+    ),
+    (
+        CharacteristicFlags::Authorize,
+        CharacteristicFlagSet::AUTHORIZE,
+    ),
+];
+
+impl From<CharacteristicFlags> for CharacteristicFlagSet {
+    fn from(flag: CharacteristicFlags) -> Self {
+        match flag {
+            CharacteristicFlags::Broadcast => CharacteristicFlagSet::BROADCAST,
+            CharacteristicFlags::Read => CharacteristicFlagSet::READ,
+            CharacteristicFlags::WriteWithoutResponse => {
+                CharacteristicFlagSet::WRITE_WITHOUT_RESPONSE
+            }
+            CharacteristicFlags::Write => CharacteristicFlagSet::WRITE,
+            CharacteristicFlags::Notify => CharacteristicFlagSet::NOTIFY,
+            CharacteristicFlags::Indicate => CharacteristicFlagSet::INDICATE,
+            CharacteristicFlags::AuthenticatedSignedWrites => {
+                CharacteristicFlagSet::AUTHENTICATED_SIGNED_WRITES
+            }
+            CharacteristicFlags::ExtendedProperties => CharacteristicFlagSet::EXTENDED_PROPERTIES,
+            CharacteristicFlags::ReliableWrite => CharacteristicFlagSet::RELIABLE_WRITE,
+            CharacteristicFlags::WritableAuxiliaries => CharacteristicFlagSet::WRITABLE_AUXILIARIES,
+            CharacteristicFlags::EncryptRead => CharacteristicFlagSet::ENCRYPT_READ,
+            CharacteristicFlags::EncryptWrite => CharacteristicFlagSet::ENCRYPT_WRITE,
+            CharacteristicFlags::EncryptNotify => CharacteristicFlagSet::ENCRYPT_NOTIFY,
+            CharacteristicFlags::EncryptIndicate => CharacteristicFlagSet::ENCRYPT_INDICATE,
+            CharacteristicFlags::EncryptAuthenticatedRead => {
+                CharacteristicFlagSet::ENCRYPT_AUTHENTICATED_READ
+            }
+            CharacteristicFlags::EncryptAuthenticatedWrite => {
+                CharacteristicFlagSet::ENCRYPT_AUTHENTICATED_WRITE
+            }
+            CharacteristicFlags::EncryptAuthenticatedNotify => {
+                CharacteristicFlagSet::ENCRYPT_AUTHENTICATED_NOTIFY
+            }
+            CharacteristicFlags::EncryptAuthenticatedIndicate => {
+                CharacteristicFlagSet::ENCRYPT_AUTHENTICATED_INDICATE
+            }
+            CharacteristicFlags::SecureRead => CharacteristicFlagSet::SECURE_READ,
+            CharacteristicFlags::SecureWrite => CharacteristicFlagSet::SECURE_WRITE,
+            CharacteristicFlags::SecureNotify => CharacteristicFlagSet::SECURE_NOTIFY,
+            CharacteristicFlags::SecureIndicate => CharacteristicFlagSet::SECURE_INDICATE,
+            CharacteristicFlags::Authorize => CharacteristicFlagSet::AUTHORIZE,
+        }
+    }
+}
+
+impl FromIterator<CharacteristicFlags> for CharacteristicFlagSet {
+    fn from_iter<T: IntoIterator<Item = CharacteristicFlags>>(iter: T) -> Self {
+        iter.into_iter()
+            .fold(Self::empty(), |set, flag| set | Self::from(flag))
+    }
+}
+
+impl From<&[CharacteristicFlags]> for CharacteristicFlagSet {
+    fn from(flags: &[CharacteristicFlags]) -> Self {
+        flags.iter().copied().collect()
+    }
+}
+
+impl From<CharacteristicFlagSet> for Vec<CharacteristicFlags> {
+    fn from(set: CharacteristicFlagSet) -> Self {
+        CHARACTERISTIC_FLAG_BITS
+            .iter()
+            .filter(|(_, bit)| set.contains(*bit))
+            .map(|(flag, _)| *flag)
+            .collect()
+    }
+}
+
+impl CharacteristicFlagSet {
+    /// Parse the `Flags` string array of a characteristic
+    pub fn from_strings<'a>(
+        flags: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Self, zbus::fdo::Error> {
+        flags
+            .into_iter()
+            .map(CharacteristicFlags::from_str)
+            .collect()
+    }
+
+    /// The `Flags` string array of a characteristic
+    pub fn to_strings(self) -> Vec<String> {
+        Vec::<CharacteristicFlags>::from(self)
+            .into_iter()
+            .map(|flag| <&str>::from(flag).to_owned())
+            .collect()
+    }
+}
+
 enum_impl_to_from_str! {
     GattDescriptorFlags, {
         Read : "read",
@@ -64,3 +269,86 @@ impl From<&SupportedIncludes> for String {
         }
     }
 }
+
+/// Make `name` a valid object path element, any character other than
+/// `[A-Za-z0-9_]` becomes `_`
+pub fn path_segment(name: &str) -> String {
+    let segment: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if segment.is_empty() {
+        "_".to_owned()
+    } else {
+        segment
+    }
+}
+
+/// Object paths of the children of one attribute, either named or numbered
+/// as `{prefix}{count}`, skipping numbers already taken
+#[derive(Debug)]
+pub(crate) struct ChildPaths {
+    parent: String,
+    prefix: &'static str,
+    used: HashSet<String>,
+}
+
+impl ChildPaths {
+    pub(crate) fn new(parent: &str, prefix: &'static str) -> Self {
+        Self {
+            parent: parent.to_owned(),
+            prefix,
+            used: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn next(
+        &mut self,
+        mut count: usize,
+        name: Option<&str>,
+    ) -> Result<OwnedObjectPath, zbus::Error> {
+        let segment = match name {
+            Some(name) => name.to_owned(),
+            None => loop {
+                let segment = format!("{}{count}", self.prefix);
+                if !self.used.contains(&segment) {
+                    break segment;
+                }
+                count += 1;
+            },
+        };
+        if !self.used.insert(segment.clone()) {
+            return Err(zbus::Error::Failure(format!(
+                "{}: more than one child at '{segment}'",
+                self.parent
+            )));
+        }
+        Ok(OwnedObjectPath::try_from(format!(
+            "{}/{segment}",
+            self.parent
+        ))?)
+    }
+
+    /// Free the segment of a removed child for reuse
+    pub(crate) fn release(&mut self, path: &OwnedObjectPath) {
+        if let Some(segment) = path.as_str().rsplit('/').next() {
+            self.used.remove(segment);
+        }
+    }
+}
+
+/// Fail if `ObjectServer::at()` did not add the interface at `path` because
+/// it was already served there, which would keep the old object
+pub(crate) fn exported(path: &ObjectPath<'_>, added: bool) -> Result<(), zbus::Error> {
+    if added {
+        Ok(())
+    } else {
+        Err(zbus::Error::Failure(format!("{path}: already exported")))
+    }
+}
@@ -0,0 +1,133 @@
+//! Attribute value handling shared by the async and blocking GATT servers
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+#[cfg(feature = "blocking-api")]
+use std::sync::{Mutex, MutexGuard};
+
+use zbus::fdo::Properties;
+use zbus::names::InterfaceName;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant;
+
+use super::GattError;
+
+type Options<'a> = HashMap<&'a str, zvariant::Value<'a>>;
+
+fn offset(options: &Options<'_>) -> usize {
+    match options.get("offset") {
+        Some(zvariant::Value::U16(offset)) => *offset as usize,
+        _ => 0,
+    }
+}
+
+/// Lock the value of a blocking characteristic or descriptor
+#[cfg(feature = "blocking-api")]
+pub(crate) fn lock(data: &Mutex<Vec<u8>>) -> Result<MutexGuard<'_, Vec<u8>>, GattError> {
+    data.lock()
+        .map_err(|e| GattError::Failed(format!("Could not lock data: {e}")))
+}
+
+/// `ReadValue` of a characteristic or descriptor, from the requested offset
+pub(crate) fn read_value(data: &[u8], options: &Options<'_>) -> Result<Vec<u8>, GattError> {
+    let offset = offset(options);
+    if offset > data.len() {
+        return Err(GattError::InvalidOffset(format!(
+            "offset {offset} is past the end"
+        )));
+    }
+    Ok(data[offset..].to_vec())
+}
+
+/// `WriteValue` of a characteristic or descriptor. A write at offset 0
+/// replaces the value, a write at a later offset overwrites from there and
+/// grows the value as needed. Offsets past the end are rejected.
+pub(crate) fn write_value(
+    data: &mut Vec<u8>,
+    value: &[u8],
+    options: &Options<'_>,
+) -> Result<(), GattError> {
+    let offset = offset(options);
+    if offset > data.len() {
+        return Err(GattError::InvalidOffset(format!(
+            "offset {offset} is past the end"
+        )));
+    }
+    if offset == 0 {
+        data.clear();
+    }
+    let end = offset + value.len();
+    data.resize(data.len().max(end), 0);
+    data[offset..end].copy_from_slice(value);
+    Ok(())
+}
+
+/// Emit `value` as a changed `Value`, which bluez sends to subscribed
+/// clients as a notification or indication
+pub(crate) async fn notify_value(
+    emitter: &SignalEmitter<'_>,
+    value: &[u8],
+) -> Result<(), zbus::Error> {
+    let changed = HashMap::from([("Value", zvariant::Value::from(value))]);
+    Properties::properties_changed(
+        emitter,
+        InterfaceName::from_static_str_unchecked("org.bluez.GattCharacteristic1"),
+        changed,
+        Cow::Borrowed(&[]),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(offset: u16) -> Options<'static> {
+        HashMap::from([("offset", zvariant::Value::from(offset))])
+    }
+
+    #[test]
+    fn write_at_offset_zero_replaces() {
+        let mut data = vec![1; 10];
+        write_value(&mut data, &[7, 8, 9], &Options::new()).unwrap();
+        assert_eq!(data, [7, 8, 9]);
+        write_value(&mut data, &[5; 4], &at(0)).unwrap();
+        assert_eq!(data, [5; 4]);
+    }
+
+    #[test]
+    fn write_mid_buffer() {
+        let mut data = vec![0; 10];
+        write_value(&mut data, &[1, 2, 3], &at(2)).unwrap();
+        assert_eq!(data, [0, 0, 1, 2, 3, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn write_over_the_end_grows() {
+        let mut data = vec![0; 10];
+        write_value(&mut data, &[1, 2, 3], &at(8)).unwrap();
+        assert_eq!(data, [0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn write_at_end_appends() {
+        let mut data = vec![0; 4];
+        write_value(&mut data, &[1, 2], &at(4)).unwrap();
+        assert_eq!(data, [0, 0, 0, 0, 1, 2]);
+    }
+
+    #[test]
+    fn write_past_end_is_rejected() {
+        let mut data = vec![0; 4];
+        assert!(write_value(&mut data, &[1], &at(5)).is_err());
+        assert_eq!(data, [0; 4]);
+    }
+
+    #[test]
+    fn read_from_offset() {
+        let data = [1, 2, 3];
+        assert_eq!(read_value(&data, &at(1)).unwrap(), [2, 3]);
+        assert_eq!(read_value(&data, &at(3)).unwrap(), Vec::<u8>::new());
+        assert!(read_value(&data, &at(4)).is_err());
+    }
+}
@@ -4,9 +4,11 @@ use std::time::Duration;
 use log::debug;
 use uuid::Uuid;
 use zbus::interface;
+use zbus::message::Header;
 use zbus::zvariant::Type;
 
 use super::gatt::SupportedIncludes;
+use crate::trace;
 use crate::{experimental_property, unused_property};
 
 #[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Type)]
@@ -117,9 +119,25 @@ pub struct LEAdvertisement1 {
     pub tx_power: Option<i16>,
 }
 
+impl LEAdvertisement1 {
+    /// Clear the values bluez only takes with `--experimental`
+    pub(crate) fn clear_experimental(&mut self) {
+        #[cfg(feature = "experimental")]
+        {
+            self.data.clear();
+            self.discoverable = None;
+            self.discoverable_timeout = None;
+            self.min_interval = None;
+            self.max_interval = None;
+            self.tx_power = None;
+        }
+    }
+}
+
 #[interface(name = "org.bluez.LEAdvertisement1")]
 impl LEAdvertisement1 {
-    fn release(&self) -> zbus::fdo::Result<()> {
+    fn release(&self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
+        let _span = trace::enter(&header, "Release");
         debug!("LEAdvertisement1: release");
         Ok(())
     }
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use log::error;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::Connection;
+
+use super::gatt::exported;
+use super::MediaEndpoint1;
+use crate::proxy::media1::Media1Proxy;
+
+/// A set of media endpoints registered with `Media1.RegisterApplication`.
+///
+/// The endpoints are exported under `path` next to an object manager, which is
+/// the registration flow required for BAP (LE Audio) endpoints.
+pub struct MediaApplication {
+    connection: Connection,
+    adapter_path: OwnedObjectPath,
+    path: OwnedObjectPath,
+    endpoints: Vec<OwnedObjectPath>,
+}
+
+impl MediaApplication {
+    pub async fn register_new(
+        path: &str,
+        adapter_path: &str,
+        connection: &Connection,
+        endpoints: Vec<MediaEndpoint1>,
+    ) -> Result<Self, zbus::Error> {
+        let path = OwnedObjectPath::try_from(path)?;
+        let adapter_path = OwnedObjectPath::try_from(adapter_path)?;
+        let server = connection.object_server();
+        let added = server
+            .at(&path, zbus::fdo::ObjectManager)
+            .await
+            .map_err(|err| {
+                error!("{}: add_to_server {}", path.as_str(), err);
+                err
+            })?;
+        exported(&path, added)?;
+
+        let mut endpoint_paths = Vec::new();
+        let res = async {
+            for (count, endpoint) in endpoints.into_iter().enumerate() {
+                let endpoint_path =
+                    OwnedObjectPath::try_from(format!("{}/endpoint{count}", path.as_str()))?;
+                log::debug!("MediaEndpoint1: Added UUID: {}", endpoint.uuid);
+                let added = server.at(&endpoint_path, endpoint).await.map_err(|err| {
+                    error!("{}: add_to_server {}", endpoint_path.as_str(), err);
+                    err
+                })?;
+                exported(&endpoint_path, added)?;
+                endpoint_paths.push(endpoint_path);
+            }
+
+            let proxy = Media1Proxy::builder(connection)
+                .path(adapter_path.clone())?
+                .build()
+                .await?;
+            proxy
+                .register_application(&path, HashMap::default())
+                .await
+        }
+        .await;
+        if let Err(err) = res {
+            remove_objects(connection, &path, &endpoint_paths).await.ok();
+            return Err(err);
+        }
+
+        Ok(Self {
+            connection: connection.clone(),
+            adapter_path,
+            path,
+            endpoints: endpoint_paths,
+        })
+    }
+
+    pub fn endpoints(&self) -> &[OwnedObjectPath] {
+        &self.endpoints
+    }
+
+    /// Unregister the application from bluez and remove its objects from the
+    /// object server, even if bluez fails to unregister it. Consumes the
+    /// handle.
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        let res = async {
+            let proxy = Media1Proxy::builder(&self.connection)
+                .path(self.adapter_path.clone())?
+                .build()
+                .await?;
+            proxy.unregister_application(&self.path).await
+        }
+        .await;
+        remove_objects(&self.connection, &self.path, &self.endpoints).await?;
+        res
+    }
+}
+
+/// Remove the endpoints, then the object manager at `path`
+async fn remove_objects(
+    connection: &Connection,
+    path: &OwnedObjectPath,
+    endpoints: &[OwnedObjectPath],
+) -> Result<(), zbus::Error> {
+    let server = connection.object_server();
+    for endpoint in endpoints {
+        server.remove::<MediaEndpoint1, _>(endpoint).await?;
+    }
+    server.remove::<zbus::fdo::ObjectManager, _>(path).await?;
+    Ok(())
+}
@@ -0,0 +1,256 @@
+//! # MediaEndpoint1 implementation
+//!
+//! An endpoint advertises the codec and capabilities it supports. Bluez calls
+//! back in to the endpoint to pick a configuration when a stream is set up,
+//! which is forwarded to the `EndpointHandler` given at construction.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use log::debug;
+use uuid::Uuid;
+use zbus::interface;
+use zbus::message::Header;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+
+use crate::media::{CodecCapabilities, CodecHandler};
+use crate::trace;
+use crate::{experimental_property, unused_property};
+
+/// Application callbacks for a `MediaEndpoint1`
+pub trait EndpointHandler: Send + Sync {
+    /// Select a configuration from the remote `capabilities`
+    fn select_configuration(&self, capabilities: &[u8]) -> zbus::fdo::Result<Vec<u8>>;
+
+    /// Select the codec properties from the remote `capabilities` (BAP)
+    fn select_properties(
+        &self,
+        _capabilities: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<HashMap<String, OwnedValue>> {
+        Err(zbus::fdo::Error::NotSupported(
+            "SelectProperties not supported on MediaEndpoint1".to_string(),
+        ))
+    }
+
+    /// A transport was configured using this endpoint
+    fn set_configuration(
+        &self,
+        _transport: OwnedObjectPath,
+        _properties: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<()> {
+        Ok(())
+    }
+
+    /// The configuration of `transport` was cleared
+    fn clear_configuration(&self, _transport: OwnedObjectPath) {}
+
+    /// Bluez released the endpoint
+    fn release(&self) {}
+}
+
+pub struct MediaEndpoint1 {
+    /// UUID of the profile which the endpoint is for
+    pub uuid: Uuid,
+    /// Assigned number of the codec that the endpoint implements
+    pub codec: u8,
+    /// Vendor-specific Company ID and Codec ID the endpoint implements, used
+    /// when `codec` is `0xff`
+    pub vendor: Option<u32>,
+    /// Capabilities blob, as specified by the codec
+    pub capabilities: Vec<u8>,
+    /// Indicates if the endpoint supports delay reporting
+    pub delay_reporting: Option<bool>,
+    /// Audio locations bitmask of the endpoint (BAP)
+    #[cfg(feature = "experimental")]
+    pub locations: Option<u32>,
+    /// Supported audio contexts bitmask of the endpoint (BAP)
+    #[cfg(feature = "experimental")]
+    pub supported_context: Option<u16>,
+    /// Available audio contexts bitmask of the endpoint (BAP)
+    #[cfg(feature = "experimental")]
+    pub context: Option<u16>,
+    handler: Arc<dyn EndpointHandler>,
+}
+
+impl MediaEndpoint1 {
+    pub fn new(
+        uuid: Uuid,
+        codec: u8,
+        capabilities: Vec<u8>,
+        handler: Arc<dyn EndpointHandler>,
+    ) -> Self {
+        Self {
+            uuid,
+            codec,
+            vendor: None,
+            capabilities,
+            delay_reporting: None,
+            #[cfg(feature = "experimental")]
+            locations: None,
+            #[cfg(feature = "experimental")]
+            supported_context: None,
+            #[cfg(feature = "experimental")]
+            context: None,
+            handler,
+        }
+    }
+
+    /// Create an endpoint advertising `capabilities`, which also picks the
+    /// configuration when bluez calls `SelectConfiguration`
+    pub fn from_codec<C>(uuid: Uuid, capabilities: C) -> Self
+    where
+        C: CodecCapabilities + Send + Sync + 'static,
+    {
+        Self::new(
+            uuid,
+            C::CODEC,
+            capabilities.to_bytes(),
+            Arc::new(CodecHandler::new(capabilities)),
+        )
+    }
+}
+
+#[interface(name = "org.bluez.MediaEndpoint1")]
+impl MediaEndpoint1 {
+    /// ClearConfiguration method
+    fn clear_configuration(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        transport: OwnedObjectPath,
+    ) -> zbus::fdo::Result<()> {
+        let _span = trace::enter(&header, "ClearConfiguration");
+        debug!(
+            "MediaEndpoint1: clear_configuration: {}",
+            transport.as_str()
+        );
+        self.handler.clear_configuration(transport);
+        Ok(())
+    }
+
+    /// Release method
+    fn release(&self, #[zbus(header)] header: Header<'_>) -> zbus::fdo::Result<()> {
+        let _span = trace::enter(&header, "Release");
+        debug!("MediaEndpoint1: release");
+        self.handler.release();
+        Ok(())
+    }
+
+    /// SelectConfiguration method
+    fn select_configuration(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        capabilities: Vec<u8>,
+    ) -> zbus::fdo::Result<Vec<u8>> {
+        let _span = trace::enter(&header, "SelectConfiguration");
+        debug!("MediaEndpoint1: select_configuration: {capabilities:?}");
+        self.handler.select_configuration(&capabilities)
+    }
+
+    /// SelectProperties method
+    fn select_properties(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        capabilities: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<HashMap<String, OwnedValue>> {
+        let _span = trace::enter(&header, "SelectProperties");
+        debug!("MediaEndpoint1: select_properties");
+        self.handler.select_properties(capabilities)
+    }
+
+    /// SetConfiguration method
+    fn set_configuration(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        transport: OwnedObjectPath,
+        properties: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<()> {
+        let _span = trace::enter(&header, "SetConfiguration");
+        debug!("MediaEndpoint1: set_configuration: {}", transport.as_str());
+        self.handler.set_configuration(transport, properties)
+    }
+
+    /// Capabilities property
+    #[zbus(property)]
+    fn capabilities(&self) -> zbus::fdo::Result<Vec<u8>> {
+        Ok(self.capabilities.clone())
+    }
+
+    /// Codec property
+    #[zbus(property)]
+    fn codec(&self) -> zbus::fdo::Result<u8> {
+        Ok(self.codec)
+    }
+
+    /// Context property
+    #[zbus(property)]
+    fn context(&self) -> zbus::fdo::Result<u16> {
+        experimental_property!("context", "MediaEndpoint1");
+        #[cfg(feature = "experimental")]
+        {
+            self.context.map_or_else(
+                || {
+                    unused_property!("context", "MediaEndpoint1");
+                },
+                Ok,
+            )
+        }
+    }
+
+    /// DelayReporting property
+    #[zbus(property)]
+    fn delay_reporting(&self) -> zbus::fdo::Result<bool> {
+        self.delay_reporting.map_or_else(
+            || {
+                unused_property!("delay_reporting", "MediaEndpoint1");
+            },
+            Ok,
+        )
+    }
+
+    /// Locations property
+    #[zbus(property)]
+    fn locations(&self) -> zbus::fdo::Result<u32> {
+        experimental_property!("locations", "MediaEndpoint1");
+        #[cfg(feature = "experimental")]
+        {
+            self.locations.map_or_else(
+                || {
+                    unused_property!("locations", "MediaEndpoint1");
+                },
+                Ok,
+            )
+        }
+    }
+
+    /// SupportedContext property
+    #[zbus(property)]
+    fn supported_context(&self) -> zbus::fdo::Result<u16> {
+        experimental_property!("supported_context", "MediaEndpoint1");
+        #[cfg(feature = "experimental")]
+        {
+            self.supported_context.map_or_else(
+                || {
+                    unused_property!("supported_context", "MediaEndpoint1");
+                },
+                Ok,
+            )
+        }
+    }
+
+    /// UUID property
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> zbus::fdo::Result<String> {
+        Ok(self.uuid.to_string())
+    }
+
+    /// Vendor property
+    #[zbus(property)]
+    fn vendor(&self) -> zbus::fdo::Result<u32> {
+        self.vendor.map_or_else(
+            || {
+                unused_property!("vendor", "MediaEndpoint1");
+            },
+            Ok,
+        )
+    }
+}
@@ -1,4 +1,66 @@
 pub mod gatt;
 
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod advertisement_monitor;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use advertisement_monitor::*;
+
+mod advertisement_monitor1;
+pub use advertisement_monitor1::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod agent;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use agent::*;
+
+mod agent1;
+pub use agent1::*;
+
+mod auto_accept_agent;
+pub use auto_accept_agent::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod battery_provider;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use battery_provider::*;
+
+mod battery_provider1;
+pub use battery_provider1::*;
+
 mod le_advertisement1;
 pub use le_advertisement1::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod media_application;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use media_application::*;
+
+mod media_endpoint1;
+pub use media_endpoint1::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod obex_agent;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use obex_agent::*;
+
+mod obex_agent1;
+pub use obex_agent1::*;
+
+mod pairing;
+pub use pairing::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod policy_agent;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use policy_agent::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod profile;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use profile::*;
+
+mod profile1;
+pub use profile1::*;
+
+mod provisioner1;
+pub use provisioner1::*;
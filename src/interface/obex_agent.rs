@@ -0,0 +1,76 @@
+use std::marker::PhantomData;
+
+use log::error;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::Connection;
+
+use super::{ObexAgent1, ObexAgentHandler};
+use crate::proxy::obex::agent_manager1::AgentManager1Proxy;
+
+/// Handle to an agent registered with the obex `AgentManager1`
+pub struct ObexAgentHandle<H> {
+    connection: Connection,
+    path: OwnedObjectPath,
+    handler: PhantomData<fn() -> H>,
+}
+
+impl<H: ObexAgentHandler> ObexAgentHandle<H> {
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    /// Unregister the agent from obexd and remove it from the object server
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        AgentManager1Proxy::new(&self.connection)
+            .await?
+            .unregister_agent(&self.path)
+            .await?;
+        self.connection
+            .object_server()
+            .remove::<ObexAgent1<H>, _>(&self.path)
+            .await?;
+        Ok(())
+    }
+}
+
+impl<H: ObexAgentHandler> ObexAgent1<H> {
+    /// Export the agent at `path` and register it with obexd. `connection`
+    /// must be a session bus connection.
+    pub async fn register(
+        self,
+        path: &str,
+        connection: &Connection,
+    ) -> Result<ObexAgentHandle<H>, zbus::Error> {
+        let path = OwnedObjectPath::try_from(path)?;
+        connection
+            .object_server()
+            .at(&path, self)
+            .await
+            .map_err(|err| {
+                error!("{}: add_to_server {}", path.as_str(), err);
+                err
+            })?;
+
+        let res = async {
+            AgentManager1Proxy::new(connection)
+                .await?
+                .register_agent(&path)
+                .await
+        }
+        .await;
+        if let Err(err) = res {
+            connection
+                .object_server()
+                .remove::<ObexAgent1<H>, _>(&path)
+                .await
+                .ok();
+            return Err(err);
+        }
+
+        Ok(ObexAgentHandle {
+            connection: connection.clone(),
+            path,
+            handler: PhantomData,
+        })
+    }
+}
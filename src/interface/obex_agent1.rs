@@ -0,0 +1,153 @@
+//! # OBEX Agent1 implementation
+//!
+//! `obexd` asks the agent to accept incoming Object Push transfers and where
+//! to store them. The requests are forwarded to the `ObexAgentHandler` the
+//! agent was created with; the default rejects everything.
+
+use std::future::Future;
+
+use log::debug;
+use zbus::interface;
+use zbus::message::Header;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+
+use crate::proxy::obex::transfer1::Transfer1Proxy;
+use crate::trace;
+
+/// Errors an OBEX agent replies to obexd with
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.bluez.obex.Error")]
+pub enum ObexAgentError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    /// The push was rejected
+    Rejected(String),
+    /// The push was canceled
+    Canceled(String),
+}
+
+/// An incoming Object Push waiting for authorization
+#[derive(Debug, Clone)]
+pub struct PushRequest {
+    transfer: OwnedObjectPath,
+    session: OwnedObjectPath,
+    name: String,
+    type_: Option<String>,
+    size: Option<u64>,
+}
+
+impl PushRequest {
+    /// The `org.bluez.obex.Transfer1` object of the push
+    pub fn transfer(&self) -> &ObjectPath<'_> {
+        &self.transfer
+    }
+
+    /// The `org.bluez.obex.Session1` object the push belongs to
+    pub fn session(&self) -> &ObjectPath<'_> {
+        &self.session
+    }
+
+    /// The name of the object as sent by the remote device
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The MIME type of the object, if sent
+    pub fn type_(&self) -> Option<&str> {
+        self.type_.as_deref()
+    }
+
+    /// The size of the object in bytes, if sent
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    async fn fetch(
+        connection: &zbus::Connection,
+        transfer: OwnedObjectPath,
+    ) -> Result<Self, zbus::Error> {
+        let proxy = Transfer1Proxy::builder(connection)
+            .path(transfer.clone())?
+            .build()
+            .await?;
+        Ok(Self {
+            session: proxy.session().await?,
+            name: proxy.name().await.unwrap_or_default(),
+            type_: proxy.type_().await.ok(),
+            size: proxy.size().await.ok(),
+            transfer,
+        })
+    }
+}
+
+/// Application callbacks for an `ObexAgent1`
+pub trait ObexAgentHandler: Send + Sync + 'static {
+    /// Accept `request` by returning the full path to store the object at, or
+    /// a directory ending in '/' to store it there under its own name
+    fn authorize_push(
+        &self,
+        _request: PushRequest,
+    ) -> impl Future<Output = Result<String, ObexAgentError>> + Send {
+        async { Err(ObexAgentError::Rejected("Push not authorized".to_owned())) }
+    }
+
+    /// The last push request was canceled by obexd
+    fn cancel(&self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Obexd unregistered the agent
+    fn release(&self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+pub struct ObexAgent1<H> {
+    handler: H,
+}
+
+impl<H: ObexAgentHandler> ObexAgent1<H> {
+    pub fn new(handler: H) -> Self {
+        Self { handler }
+    }
+
+    pub fn handler(&self) -> &H {
+        &self.handler
+    }
+}
+
+#[interface(name = "org.bluez.obex.Agent1")]
+impl<H: ObexAgentHandler> ObexAgent1<H> {
+    /// AuthorizePush method
+    async fn authorize_push(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        transfer: OwnedObjectPath,
+        #[zbus(connection)] connection: &zbus::Connection,
+    ) -> Result<String, ObexAgentError> {
+        trace::handle(&header, "AuthorizePush", async move {
+            debug!("ObexAgent1: authorize_push: {}", transfer.as_str());
+            let request = PushRequest::fetch(connection, transfer).await?;
+            self.handler.authorize_push(request).await
+        })
+        .await
+    }
+
+    /// Cancel method
+    async fn cancel(&self, #[zbus(header)] header: Header<'_>) {
+        trace::handle(&header, "Cancel", async move {
+            debug!("ObexAgent1: cancel");
+            self.handler.cancel().await;
+        })
+        .await
+    }
+
+    /// Release method
+    async fn release(&self, #[zbus(header)] header: Header<'_>) {
+        trace::handle(&header, "Release", async move {
+            debug!("ObexAgent1: release");
+            self.handler.release().await;
+        })
+        .await
+    }
+}
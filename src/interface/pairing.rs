@@ -0,0 +1,181 @@
+//! # Pairing events
+//!
+//! `PairingAgent` turns each `Agent1` request into a `PairingEvent` so an
+//! application, e.g. a GUI showing pairing dialogs, can answer them from its
+//! own event loop. Requests that need an answer carry a `Reply` sender; if it
+//! is dropped without replying the request is canceled.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_channel::{mpsc, oneshot};
+use futures_lite::Stream;
+use zbus::zvariant::OwnedObjectPath;
+
+use super::{AgentError, AgentHandler};
+
+/// Sender for the answer to a `PairingEvent`
+pub type Reply<T> = oneshot::Sender<Result<T, AgentError>>;
+
+#[derive(Debug)]
+pub enum PairingEvent {
+    /// Bluez unregistered the agent
+    Release,
+    /// Reply with the PIN code (1-16 characters) to pair with `device`
+    RequestPinCode {
+        device: OwnedObjectPath,
+        reply: Reply<String>,
+    },
+    /// Show `pincode` so it can be entered on `device`
+    DisplayPinCode {
+        device: OwnedObjectPath,
+        pincode: String,
+        reply: Reply<()>,
+    },
+    /// Reply with the passkey (0-999999) to pair with `device`
+    RequestPasskey {
+        device: OwnedObjectPath,
+        reply: Reply<u32>,
+    },
+    /// Show `passkey`, `entered` is the number of digits typed on `device` so
+    /// far
+    DisplayPasskey {
+        device: OwnedObjectPath,
+        passkey: u32,
+        entered: u16,
+    },
+    /// Confirm that `passkey` is the one shown on `device`
+    RequestConfirmation {
+        device: OwnedObjectPath,
+        passkey: u32,
+        reply: Reply<()>,
+    },
+    /// Authorize pairing with `device` when no passkey is involved
+    RequestAuthorization {
+        device: OwnedObjectPath,
+        reply: Reply<()>,
+    },
+    /// Authorize `device` to connect to the service `uuid`
+    AuthorizeService {
+        device: OwnedObjectPath,
+        uuid: String,
+        reply: Reply<()>,
+    },
+    /// The last request was canceled by bluez, e.g. on timeout
+    Cancel,
+}
+
+/// Stream of the requests made to a `PairingAgent`
+pub struct PairingEvents {
+    events: mpsc::UnboundedReceiver<PairingEvent>,
+}
+
+impl Stream for PairingEvents {
+    type Item = PairingEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+/// An `AgentHandler` forwarding every request to a `PairingEvents` stream
+#[derive(Debug, Clone)]
+pub struct PairingAgent {
+    events: mpsc::UnboundedSender<PairingEvent>,
+}
+
+impl PairingAgent {
+    pub fn new() -> (Self, PairingEvents) {
+        let (tx, rx) = mpsc::unbounded();
+        (Self { events: tx }, PairingEvents { events: rx })
+    }
+
+    fn notify(&self, event: PairingEvent) {
+        self.events.unbounded_send(event).ok();
+    }
+
+    async fn request<T>(
+        &self,
+        event: impl FnOnce(Reply<T>) -> PairingEvent,
+    ) -> Result<T, AgentError> {
+        let (reply, answer) = oneshot::channel();
+        self.events
+            .unbounded_send(event(reply))
+            .map_err(|_| AgentError::Rejected("No pairing event listener".to_owned()))?;
+        answer
+            .await
+            .map_err(|_| AgentError::Canceled("Pairing request was not answered".to_owned()))?
+    }
+}
+
+impl AgentHandler for PairingAgent {
+    async fn release(&self) {
+        self.notify(PairingEvent::Release);
+    }
+
+    async fn request_pin_code(&self, device: OwnedObjectPath) -> Result<String, AgentError> {
+        self.request(|reply| PairingEvent::RequestPinCode { device, reply })
+            .await
+    }
+
+    async fn display_pin_code(
+        &self,
+        device: OwnedObjectPath,
+        pincode: String,
+    ) -> Result<(), AgentError> {
+        self.request(|reply| PairingEvent::DisplayPinCode {
+            device,
+            pincode,
+            reply,
+        })
+        .await
+    }
+
+    async fn request_passkey(&self, device: OwnedObjectPath) -> Result<u32, AgentError> {
+        self.request(|reply| PairingEvent::RequestPasskey { device, reply })
+            .await
+    }
+
+    async fn display_passkey(&self, device: OwnedObjectPath, passkey: u32, entered: u16) {
+        self.notify(PairingEvent::DisplayPasskey {
+            device,
+            passkey,
+            entered,
+        });
+    }
+
+    async fn request_confirmation(
+        &self,
+        device: OwnedObjectPath,
+        passkey: u32,
+    ) -> Result<(), AgentError> {
+        self.request(|reply| PairingEvent::RequestConfirmation {
+            device,
+            passkey,
+            reply,
+        })
+        .await
+    }
+
+    async fn request_authorization(&self, device: OwnedObjectPath) -> Result<(), AgentError> {
+        self.request(|reply| PairingEvent::RequestAuthorization { device, reply })
+            .await
+    }
+
+    async fn authorize_service(
+        &self,
+        device: OwnedObjectPath,
+        uuid: String,
+    ) -> Result<(), AgentError> {
+        self.request(|reply| PairingEvent::AuthorizeService {
+            device,
+            uuid,
+            reply,
+        })
+        .await
+    }
+
+    async fn cancel(&self) {
+        self.notify(PairingEvent::Cancel);
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+
+use log::info;
+use zbus::zvariant::OwnedObjectPath;
+
+use super::{AgentCapability, AgentError, AgentHandle, AgentHandler};
+use crate::address::BDAddr;
+use crate::bus::BluezBus;
+use crate::proxy::device1::Device1Proxy;
+
+/// Who may pair with a `PolicyAgent`, and how.
+///
+/// Without allowed addresses or name patterns every device may pair. With
+/// either set a device must have an allowed address or a name matching one
+/// of the patterns.
+#[derive(Debug, Clone, Default)]
+pub struct PairingPolicy {
+    addresses: HashSet<BDAddr>,
+    name_patterns: Vec<String>,
+    passkey: Option<u32>,
+}
+
+impl PairingPolicy {
+    /// Let every device pair with "Just Works"
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow the devices with one of the `addresses`
+    pub fn allow_addresses(mut self, addresses: impl IntoIterator<Item = BDAddr>) -> Self {
+        self.addresses.extend(addresses);
+        self
+    }
+
+    /// Allow the devices whose name matches one of the `patterns`. `*`
+    /// matches any run of characters and `?` any single one.
+    pub fn allow_name_patterns(
+        mut self,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.name_patterns
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only pair when the remote device shows `passkey`, e.g. one printed
+    /// on its label. "Just Works" pairing is rejected.
+    pub fn require_passkey_match(mut self, passkey: u32) -> Self {
+        self.passkey = Some(passkey);
+        self
+    }
+
+    /// The capability to register the agent with, `KeyboardOnly` to enter
+    /// the passkey if one is required and `NoInputNoOutput` otherwise
+    pub fn capability(&self) -> AgentCapability {
+        if self.passkey.is_some() {
+            AgentCapability::KeyboardOnly
+        } else {
+            AgentCapability::NoInputNoOutput
+        }
+    }
+
+    /// Whether a device with `address` and `name` passes the allow lists
+    pub fn allows(&self, address: Option<BDAddr>, name: Option<&str>) -> bool {
+        if self.addresses.is_empty() && self.name_patterns.is_empty() {
+            return true;
+        }
+        address.is_some_and(|address| self.addresses.contains(&address))
+            || name.is_some_and(|name| {
+                self.name_patterns
+                    .iter()
+                    .any(|pattern| glob_match(pattern, name))
+            })
+    }
+}
+
+/// Match `text` against a pattern of literal characters, `*` and `?`
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text it matched up to
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// An `AgentHandler` answering pairing and service authorization requests
+/// from a `PairingPolicy`
+#[derive(Debug, Clone)]
+pub struct PolicyAgent {
+    policy: PairingPolicy,
+    bus: BluezBus,
+    auto_trust: Option<bool>,
+}
+
+impl PolicyAgent {
+    /// Apply `policy`, reading device names from bluez on `bus`
+    pub fn new(policy: PairingPolicy, bus: impl Into<BluezBus>) -> Self {
+        Self {
+            policy,
+            bus: bus.into(),
+            auto_trust: None,
+        }
+    }
+
+    /// Mark the devices the policy lets pair `Trusted`, so they can
+    /// reconnect without authorization. With `wake_allowed` they may also
+    /// wake the host.
+    pub fn with_auto_trust(mut self, wake_allowed: bool) -> Self {
+        self.auto_trust = Some(wake_allowed);
+        self
+    }
+
+    pub fn policy(&self) -> &PairingPolicy {
+        &self.policy
+    }
+
+    /// Export the agent at `path` and register it as the default agent with
+    /// the capability of the policy
+    pub async fn register(self, path: &str) -> Result<AgentHandle<Self>, zbus::Error> {
+        let capability = self.policy.capability();
+        let bus = self.bus.clone();
+        let mut agent = super::Agent1::new(self);
+        if let Some(wake_allowed) = agent.handler().auto_trust {
+            agent = agent.with_auto_trust(wake_allowed);
+        }
+        agent.register(path, capability, true, bus).await
+    }
+
+    async fn name(&self, device: &OwnedObjectPath) -> Option<String> {
+        let proxy = Device1Proxy::builder(self.bus.connection())
+            .destination(self.bus.destination().clone())
+            .ok()?
+            .path(device.clone())
+            .ok()?
+            .build()
+            .await
+            .ok()?;
+        match proxy.name().await {
+            Ok(name) => Some(name),
+            Err(_) => proxy.alias().await.ok(),
+        }
+    }
+
+    async fn check(&self, device: &OwnedObjectPath) -> Result<(), AgentError> {
+        let address = BDAddr::from_device_path(device).ok();
+        let name = if self.policy.name_patterns.is_empty() {
+            None
+        } else {
+            self.name(device).await
+        };
+        if self.policy.allows(address, name.as_deref()) {
+            Ok(())
+        } else {
+            Err(AgentError::Rejected(format!(
+                "{} is not allowed to pair",
+                device.as_str()
+            )))
+        }
+    }
+
+    fn passkey(&self, device: &OwnedObjectPath) -> Result<u32, AgentError> {
+        self.policy.passkey.ok_or_else(|| {
+            AgentError::Rejected(format!("{}: no passkey to enter", device.as_str()))
+        })
+    }
+}
+
+impl AgentHandler for PolicyAgent {
+    async fn request_passkey(&self, device: OwnedObjectPath) -> Result<u32, AgentError> {
+        self.check(&device).await?;
+        let passkey = self.passkey(&device)?;
+        info!("PolicyAgent: entered passkey for {}", device.as_str());
+        Ok(passkey)
+    }
+
+    async fn request_confirmation(
+        &self,
+        device: OwnedObjectPath,
+        passkey: u32,
+    ) -> Result<(), AgentError> {
+        self.check(&device).await?;
+        if let Some(expected) = self.policy.passkey
+            && passkey != expected
+        {
+            return Err(AgentError::Rejected(format!(
+                "{}: passkey does not match",
+                device.as_str()
+            )));
+        }
+        info!("PolicyAgent: confirmed pairing with {}", device.as_str());
+        Ok(())
+    }
+
+    async fn request_authorization(&self, device: OwnedObjectPath) -> Result<(), AgentError> {
+        self.check(&device).await?;
+        if self.policy.passkey.is_some() {
+            return Err(AgentError::Rejected(format!(
+                "{}: pairing without a passkey",
+                device.as_str()
+            )));
+        }
+        info!("PolicyAgent: authorized pairing with {}", device.as_str());
+        Ok(())
+    }
+
+    async fn authorize_service(
+        &self,
+        device: OwnedObjectPath,
+        uuid: String,
+    ) -> Result<(), AgentError> {
+        self.check(&device).await?;
+        info!("PolicyAgent: authorized {uuid} for {}", device.as_str());
+        Ok(())
+    }
+}
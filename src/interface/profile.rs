@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use log::error;
+use zbus::names::OwnedBusName;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::Connection;
+
+use super::gatt::exported;
+use super::{Profile1, ProfileOptions};
+use crate::bus::BluezBus;
+use crate::proxy::profile_manager1::ProfileManager1Proxy;
+use crate::restart::{Registered, Registration};
+
+/// Handle to a profile registered with `ProfileManager1`
+pub struct ProfileHandle {
+    connection: Connection,
+    destination: OwnedBusName,
+    path: OwnedObjectPath,
+    registered: Arc<Registered>,
+}
+
+impl ProfileHandle {
+    pub fn path(&self) -> &ObjectPath<'_> {
+        &self.path
+    }
+
+    /// For `RestartWatcher::track()`
+    pub fn registration(&self) -> Registration {
+        Registration::new(&self.registered)
+    }
+
+    /// Unregister the profile from bluez and remove it from the object server
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        profile_manager(&self.connection, &self.destination)
+            .await?
+            .unregister_profile(&self.path)
+            .await?;
+        self.connection
+            .object_server()
+            .remove::<Profile1, _>(&self.path)
+            .await?;
+        Ok(())
+    }
+}
+
+async fn profile_manager(
+    connection: &Connection,
+    destination: &OwnedBusName,
+) -> Result<ProfileManager1Proxy<'static>, zbus::Error> {
+    ProfileManager1Proxy::builder(connection)
+        .destination(destination.clone())?
+        .path("/org/bluez")?
+        .build()
+        .await
+}
+
+impl Profile1 {
+    /// Export the profile at `path` and register it for the service `uuid`
+    pub async fn register(
+        self,
+        path: &str,
+        uuid: &str,
+        options: ProfileOptions,
+        bus: impl Into<BluezBus>,
+    ) -> Result<ProfileHandle, zbus::Error> {
+        let bus = bus.into();
+        let connection = bus.connection();
+        let path = OwnedObjectPath::try_from(path)?;
+        let added = connection
+            .object_server()
+            .at(&path, self)
+            .await
+            .map_err(|err| {
+                error!("{}: add_to_server {}", path.as_str(), err);
+                err
+            })?;
+        exported(&path, added)?;
+
+        if let Err(err) = profile_manager(connection, bus.destination())
+            .await?
+            .register_profile(&path, uuid, options.to_map())
+            .await
+        {
+            connection
+                .object_server()
+                .remove::<Profile1, _>(&path)
+                .await
+                .ok();
+            return Err(err);
+        }
+
+        Ok(ProfileHandle {
+            connection: connection.clone(),
+            destination: bus.destination().clone(),
+            registered: Arc::new(Registered::Profile {
+                path: path.clone(),
+                uuid: uuid.to_owned(),
+                options,
+            }),
+            path,
+        })
+    }
+}
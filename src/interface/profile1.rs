@@ -0,0 +1,219 @@
+//! # Profile1 implementation
+//!
+//! Bluez hands a connected socket to the profile for every new connection to
+//! or from a remote device. `Profile1` forwards these, and the disconnection
+//! and release requests, to a `ProfileEvents` stream.
+
+use std::collections::HashMap;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc;
+use futures_lite::Stream;
+use log::debug;
+use uuid::Uuid;
+use zbus::interface;
+use zbus::message::Header;
+use zbus::zvariant::{self, ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+use crate::enum_impl_to_from_str;
+use crate::trace;
+
+enum_impl_to_from_str! {
+    ProfileRole, {
+        Client : "client",
+        Server : "server",
+    }
+}
+
+/// Options for registering a profile with `ProfileManager1`. Unset options
+/// use the bluez defaults for the profile UUID.
+#[derive(Debug, Default, Clone)]
+pub struct ProfileOptions {
+    /// Human readable name for the profile
+    pub name: Option<String>,
+    /// The primary service class UUID, if different from the profile UUID
+    pub service: Option<Uuid>,
+    /// Restrict the profile to the client or server role
+    pub role: Option<ProfileRole>,
+    /// RFCOMM channel number
+    pub channel: Option<u16>,
+    /// L2CAP PSM number
+    pub psm: Option<u16>,
+    /// Require pairing before connecting
+    pub require_authentication: Option<bool>,
+    /// Require authorization by the agent before connecting
+    pub require_authorization: Option<bool>,
+    /// Connect to the profile automatically when the device connects
+    pub auto_connect: Option<bool>,
+    /// SDP record as XML, replacing the one bluez generates
+    pub service_record: Option<String>,
+    /// Profile version for the generated SDP record
+    pub version: Option<u16>,
+    /// Profile features for the generated SDP record
+    pub features: Option<u16>,
+}
+
+impl ProfileOptions {
+    pub fn to_map(&self) -> HashMap<&'static str, Value<'static>> {
+        let mut options = HashMap::new();
+        if let Some(name) = &self.name {
+            options.insert("Name", Value::from(name.clone()));
+        }
+        if let Some(service) = self.service {
+            options.insert("Service", Value::from(service.to_string()));
+        }
+        if let Some(role) = self.role {
+            options.insert("Role", Value::from(<&str>::from(role)));
+        }
+        if let Some(channel) = self.channel {
+            options.insert("Channel", Value::from(channel));
+        }
+        if let Some(psm) = self.psm {
+            options.insert("PSM", Value::from(psm));
+        }
+        if let Some(require) = self.require_authentication {
+            options.insert("RequireAuthentication", Value::from(require));
+        }
+        if let Some(require) = self.require_authorization {
+            options.insert("RequireAuthorization", Value::from(require));
+        }
+        if let Some(auto_connect) = self.auto_connect {
+            options.insert("AutoConnect", Value::from(auto_connect));
+        }
+        if let Some(record) = &self.service_record {
+            options.insert("ServiceRecord", Value::from(record.clone()));
+        }
+        if let Some(version) = self.version {
+            options.insert("Version", Value::from(version));
+        }
+        if let Some(features) = self.features {
+            options.insert("Features", Value::from(features));
+        }
+        options
+    }
+}
+
+/// A connection bluez handed to the profile
+#[derive(Debug)]
+pub struct ProfileConnection {
+    device: OwnedObjectPath,
+    socket: UnixStream,
+    properties: HashMap<String, OwnedValue>,
+}
+
+impl ProfileConnection {
+    /// The remote device
+    pub fn device(&self) -> &ObjectPath<'_> {
+        &self.device
+    }
+
+    /// The connected RFCOMM or L2CAP socket
+    pub fn socket(&self) -> &UnixStream {
+        &self.socket
+    }
+
+    /// Properties of the connection, e.g. "Version" and "Features"
+    pub fn properties(&self) -> &HashMap<String, OwnedValue> {
+        &self.properties
+    }
+
+    pub fn into_socket(self) -> UnixStream {
+        self.socket
+    }
+
+    /// Make the socket non-blocking for use with async readers and writers
+    #[cfg(feature = "async-io")]
+    pub fn into_async_socket(self) -> std::io::Result<async_io::Async<UnixStream>> {
+        async_io::Async::new(self.socket)
+    }
+
+    /// Make the socket non-blocking and register it with the current tokio
+    /// runtime
+    #[cfg(feature = "tokio")]
+    pub fn into_tokio_socket(self) -> std::io::Result<tokio::net::UnixStream> {
+        self.socket.set_nonblocking(true)?;
+        tokio::net::UnixStream::from_std(self.socket)
+    }
+}
+
+#[derive(Debug)]
+pub enum ProfileEvent {
+    /// A remote device connected
+    NewConnection(ProfileConnection),
+    /// Bluez asks for the connection to `device` to be closed, drop its
+    /// socket
+    RequestDisconnection(OwnedObjectPath),
+    /// Bluez unregistered the profile
+    Release,
+}
+
+/// Stream of the events received by a `Profile1`
+pub struct ProfileEvents {
+    events: mpsc::UnboundedReceiver<ProfileEvent>,
+}
+
+impl Stream for ProfileEvents {
+    type Item = ProfileEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+pub struct Profile1 {
+    events: mpsc::UnboundedSender<ProfileEvent>,
+}
+
+impl Profile1 {
+    pub fn new() -> (Self, ProfileEvents) {
+        let (tx, rx) = mpsc::unbounded();
+        (Self { events: tx }, ProfileEvents { events: rx })
+    }
+
+    fn send(&self, event: ProfileEvent) -> zbus::fdo::Result<()> {
+        self.events
+            .unbounded_send(event)
+            .map_err(|_| zbus::fdo::Error::Failed("No profile event listener".to_owned()))
+    }
+}
+
+#[interface(name = "org.bluez.Profile1")]
+impl Profile1 {
+    /// NewConnection method
+    fn new_connection(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+        fd: zvariant::OwnedFd,
+        fd_properties: HashMap<String, OwnedValue>,
+    ) -> zbus::fdo::Result<()> {
+        let _span = trace::enter(&header, "NewConnection");
+        debug!("Profile1: new connection from {}", device.as_str());
+        let socket = UnixStream::from(OwnedFd::from(fd));
+        self.send(ProfileEvent::NewConnection(ProfileConnection {
+            device,
+            socket,
+            properties: fd_properties,
+        }))
+    }
+
+    /// Release method
+    fn release(&self, #[zbus(header)] header: Header<'_>) {
+        let _span = trace::enter(&header, "Release");
+        self.send(ProfileEvent::Release).ok();
+    }
+
+    /// RequestDisconnection method
+    fn request_disconnection(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        device: OwnedObjectPath,
+    ) -> zbus::fdo::Result<()> {
+        let _span = trace::enter(&header, "RequestDisconnection");
+        debug!("Profile1: disconnection requested for {}", device.as_str());
+        self.send(ProfileEvent::RequestDisconnection(device))
+    }
+}
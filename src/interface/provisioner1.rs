@@ -0,0 +1,195 @@
+//! # Mesh Provisioner1 implementation
+//!
+//! Exported by a mesh application acting as provisioner. The mesh daemon
+//! reports unprovisioned beacons found by `UnprovisionedScan`, asks for the
+//! addresses of nodes being added, and reports the result of `AddNode`.
+//! Addresses are handed out sequentially from the range the provisioner was
+//! created with; everything else goes to a `ProvisionerEvents` stream.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc;
+use futures_lite::Stream;
+use log::{debug, warn};
+use uuid::Uuid;
+use zbus::interface;
+use zbus::message::Header;
+use zbus::zvariant::OwnedValue;
+
+use crate::trace;
+
+/// Last valid unicast address
+const MAX_UNICAST: u16 = 0x7fff;
+
+/// A device sending unprovisioned device beacons
+#[derive(Debug, Clone)]
+pub struct UnprovisionedDevice {
+    uuid: Uuid,
+    rssi: i16,
+    oob_info: u16,
+    uri_hash: Option<u32>,
+}
+
+impl UnprovisionedDevice {
+    /// Parse the `data` of a `ScanResult`, the beacon without its type
+    fn from_beacon(rssi: i16, data: &[u8]) -> Option<Self> {
+        let uuid = Uuid::from_slice(data.get(..16)?).ok()?;
+        let oob_info = u16::from_be_bytes(data.get(16..18)?.try_into().ok()?);
+        let uri_hash = data
+            .get(18..22)
+            .and_then(|hash| Some(u32::from_be_bytes(hash.try_into().ok()?)));
+        Some(Self {
+            uuid,
+            rssi,
+            oob_info,
+            uri_hash,
+        })
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn rssi(&self) -> i16 {
+        self.rssi
+    }
+
+    /// The OOB information bitfield of the beacon
+    pub fn oob_info(&self) -> u16 {
+        self.oob_info
+    }
+
+    /// Hash of the URI advertised alongside the beacon, if any
+    pub fn uri_hash(&self) -> Option<u32> {
+        self.uri_hash
+    }
+}
+
+#[derive(Debug)]
+pub enum ProvisionerEvent {
+    /// An unprovisioned device was found while scanning
+    ScanResult(UnprovisionedDevice),
+    /// The device `uuid` was provisioned with `count` elements starting at
+    /// `unicast`
+    AddNodeComplete { uuid: Uuid, unicast: u16, count: u8 },
+    /// Provisioning the device `uuid` failed
+    AddNodeFailed { uuid: Uuid, reason: String },
+}
+
+/// Stream of the events received by a `Provisioner1`
+pub struct ProvisionerEvents {
+    events: mpsc::UnboundedReceiver<ProvisionerEvent>,
+}
+
+impl Stream for ProvisionerEvents {
+    type Item = ProvisionerEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+pub struct Provisioner1 {
+    net_index: u16,
+    next_unicast: Mutex<u16>,
+    events: mpsc::UnboundedSender<ProvisionerEvent>,
+}
+
+impl Provisioner1 {
+    /// New nodes join the subnet `net_index` and get unicast addresses
+    /// starting from `first_unicast`
+    pub fn new(net_index: u16, first_unicast: u16) -> (Self, ProvisionerEvents) {
+        let (tx, rx) = mpsc::unbounded();
+        (
+            Self {
+                net_index,
+                next_unicast: Mutex::new(first_unicast),
+                events: tx,
+            },
+            ProvisionerEvents { events: rx },
+        )
+    }
+
+    /// The next unicast address that will be handed out
+    pub fn next_unicast(&self) -> u16 {
+        self.next_unicast
+            .lock()
+            .map(|next| *next)
+            .unwrap_or_default()
+    }
+
+    fn send(&self, event: ProvisionerEvent) {
+        if self.events.unbounded_send(event).is_err() {
+            warn!("Provisioner1: no event listener");
+        }
+    }
+}
+
+#[interface(name = "org.bluez.mesh.Provisioner1")]
+impl Provisioner1 {
+    /// AddNodeComplete method
+    fn add_node_complete(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        uuid: Vec<u8>,
+        unicast: u16,
+        count: u8,
+    ) {
+        let _span = trace::enter(&header, "AddNodeComplete");
+        debug!("Provisioner1: added node {unicast:#06x} ({count} elements)");
+        let uuid = Uuid::from_slice(&uuid).unwrap_or_default();
+        self.send(ProvisionerEvent::AddNodeComplete {
+            uuid,
+            unicast,
+            count,
+        });
+    }
+
+    /// AddNodeFailed method
+    fn add_node_failed(&self, #[zbus(header)] header: Header<'_>, uuid: Vec<u8>, reason: String) {
+        let _span = trace::enter(&header, "AddNodeFailed");
+        debug!("Provisioner1: add node failed: {reason}");
+        let uuid = Uuid::from_slice(&uuid).unwrap_or_default();
+        self.send(ProvisionerEvent::AddNodeFailed { uuid, reason });
+    }
+
+    /// RequestProvData method
+    fn request_prov_data(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        count: u8,
+    ) -> zbus::fdo::Result<(u16, u16)> {
+        let _span = trace::enter(&header, "RequestProvData");
+        let mut next = self
+            .next_unicast
+            .lock()
+            .map_err(|e| zbus::fdo::Error::Failed(format!("Could not lock unicast: {e}")))?;
+        let unicast = *next;
+        let end = unicast as u32 + count as u32;
+        if count == 0 || end > MAX_UNICAST as u32 + 1 {
+            return Err(zbus::fdo::Error::Failed(format!(
+                "No unicast range for {count} elements from {unicast:#06x}"
+            )));
+        }
+        *next = end as u16;
+        Ok((self.net_index, unicast))
+    }
+
+    /// ScanResult method
+    fn scan_result(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        rssi: i16,
+        data: Vec<u8>,
+        _options: HashMap<String, OwnedValue>,
+    ) {
+        let _span = trace::enter(&header, "ScanResult");
+        match UnprovisionedDevice::from_beacon(rssi, &data) {
+            Some(device) => self.send(ProvisionerEvent::ScanResult(device)),
+            None => warn!("Provisioner1: invalid beacon {data:02x?}"),
+        }
+    }
+}
@@ -1,7 +1,31 @@
 //! A crate to interface with the bluez daemon via DBUS
 
+pub mod adapter;
+pub mod address;
+pub mod advertising;
+pub mod bus;
+pub mod capabilities;
+pub mod client;
+pub mod codec;
+pub mod company_id;
+pub mod error;
 pub mod interface;
+pub mod media;
+pub mod mesh;
+pub mod obex;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub mod peripheral;
 pub mod proxy;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub mod restart;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod rt;
+#[cfg(all(feature = "testing", any(feature = "async-io", feature = "tokio")))]
+pub mod testing;
+mod trace;
+pub mod uuids;
+
+pub use uuid;
 
 #[macro_export]
 macro_rules! experimental_property {
@@ -0,0 +1,311 @@
+//! Byte layouts of the A2DP codec capability blobs used by `MediaEndpoint1`.
+//!
+//! The same structs are used for capabilities, where each bitmask field may
+//! hold several options, and for configurations, where each holds exactly one.
+
+use zbus::fdo::Error as ZbusError;
+
+use crate::interface::EndpointHandler;
+
+/// A codec capability blob with a fixed byte layout
+pub trait CodecCapabilities: Sized {
+    /// The A2DP codec assigned number
+    const CODEC: u8;
+
+    fn to_bytes(&self) -> Vec<u8>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ZbusError>;
+
+    /// Pick a single configuration supported by both `self` and `remote`
+    fn select_configuration(&self, remote: &Self) -> Option<Self>;
+}
+
+/// `EndpointHandler` answering `SelectConfiguration` from typed capabilities
+pub struct CodecHandler<C> {
+    capabilities: C,
+}
+
+impl<C: CodecCapabilities> CodecHandler<C> {
+    pub fn new(capabilities: C) -> Self {
+        Self { capabilities }
+    }
+}
+
+impl<C: CodecCapabilities + Send + Sync> EndpointHandler for CodecHandler<C> {
+    fn select_configuration(&self, capabilities: &[u8]) -> zbus::fdo::Result<Vec<u8>> {
+        let remote = C::from_bytes(capabilities)?;
+        self.capabilities
+            .select_configuration(&remote)
+            .map(|config| config.to_bytes())
+            .ok_or_else(|| ZbusError::InvalidArgs("No matching configuration".to_owned()))
+    }
+}
+
+fn check_len(codec: &str, bytes: &[u8], len: usize) -> Result<(), ZbusError> {
+    if bytes.len() < len {
+        return Err(ZbusError::InvalidArgs(format!(
+            "{codec} capabilities need {len} bytes, got {}",
+            bytes.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Pick the first bit of `preference` present in `mask`
+fn pick(mask: u8, preference: &[u8]) -> Option<u8> {
+    preference.iter().copied().find(|bit| mask & bit != 0)
+}
+
+/// SBC codec capabilities, see A2DP spec section 4.3.2
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SbcCapabilities {
+    /// Bitmask of `SbcCapabilities::FREQ_*`
+    pub frequencies: u8,
+    /// Bitmask of `SbcCapabilities::CHANNEL_MODE_*`
+    pub channel_modes: u8,
+    /// Bitmask of `SbcCapabilities::BLOCK_LENGTH_*`
+    pub block_lengths: u8,
+    /// Bitmask of `SbcCapabilities::SUBBANDS_*`
+    pub subbands: u8,
+    /// Bitmask of `SbcCapabilities::ALLOCATION_*`
+    pub allocation_methods: u8,
+    pub min_bitpool: u8,
+    pub max_bitpool: u8,
+}
+
+impl SbcCapabilities {
+    pub const ALLOCATION_LOUDNESS: u8 = 0x01;
+    pub const ALLOCATION_SNR: u8 = 0x02;
+    pub const BLOCK_LENGTH_12: u8 = 0x02;
+    pub const BLOCK_LENGTH_16: u8 = 0x01;
+    pub const BLOCK_LENGTH_4: u8 = 0x08;
+    pub const BLOCK_LENGTH_8: u8 = 0x04;
+    pub const CHANNEL_MODE_DUAL_CHANNEL: u8 = 0x04;
+    pub const CHANNEL_MODE_JOINT_STEREO: u8 = 0x01;
+    pub const CHANNEL_MODE_MONO: u8 = 0x08;
+    pub const CHANNEL_MODE_STEREO: u8 = 0x02;
+    pub const FREQ_16000: u8 = 0x08;
+    pub const FREQ_32000: u8 = 0x04;
+    pub const FREQ_44100: u8 = 0x02;
+    pub const FREQ_48000: u8 = 0x01;
+    pub const MAX_BITPOOL: u8 = 250;
+    pub const MIN_BITPOOL: u8 = 2;
+    pub const SUBBANDS_4: u8 = 0x02;
+    pub const SUBBANDS_8: u8 = 0x01;
+}
+
+impl Default for SbcCapabilities {
+    /// Everything the SBC spec allows
+    fn default() -> Self {
+        Self {
+            frequencies: 0x0f,
+            channel_modes: 0x0f,
+            block_lengths: 0x0f,
+            subbands: 0x03,
+            allocation_methods: 0x03,
+            min_bitpool: Self::MIN_BITPOOL,
+            max_bitpool: 53,
+        }
+    }
+}
+
+impl CodecCapabilities for SbcCapabilities {
+    const CODEC: u8 = 0x00;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        vec![
+            (self.frequencies & 0x0f) << 4 | (self.channel_modes & 0x0f),
+            (self.block_lengths & 0x0f) << 4
+                | (self.subbands & 0x03) << 2
+                | (self.allocation_methods & 0x03),
+            self.min_bitpool,
+            self.max_bitpool,
+        ]
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ZbusError> {
+        check_len("SBC", bytes, 4)?;
+        Ok(Self {
+            frequencies: bytes[0] >> 4,
+            channel_modes: bytes[0] & 0x0f,
+            block_lengths: bytes[1] >> 4,
+            subbands: (bytes[1] >> 2) & 0x03,
+            allocation_methods: bytes[1] & 0x03,
+            min_bitpool: bytes[2],
+            max_bitpool: bytes[3],
+        })
+    }
+
+    fn select_configuration(&self, remote: &Self) -> Option<Self> {
+        let min_bitpool = self.min_bitpool.max(remote.min_bitpool);
+        let max_bitpool = self.max_bitpool.min(remote.max_bitpool);
+        if min_bitpool > max_bitpool {
+            return None;
+        }
+        Some(Self {
+            frequencies: pick(
+                self.frequencies & remote.frequencies,
+                &[
+                    Self::FREQ_48000,
+                    Self::FREQ_44100,
+                    Self::FREQ_32000,
+                    Self::FREQ_16000,
+                ],
+            )?,
+            channel_modes: pick(
+                self.channel_modes & remote.channel_modes,
+                &[
+                    Self::CHANNEL_MODE_JOINT_STEREO,
+                    Self::CHANNEL_MODE_STEREO,
+                    Self::CHANNEL_MODE_DUAL_CHANNEL,
+                    Self::CHANNEL_MODE_MONO,
+                ],
+            )?,
+            block_lengths: pick(
+                self.block_lengths & remote.block_lengths,
+                &[
+                    Self::BLOCK_LENGTH_16,
+                    Self::BLOCK_LENGTH_12,
+                    Self::BLOCK_LENGTH_8,
+                    Self::BLOCK_LENGTH_4,
+                ],
+            )?,
+            subbands: pick(
+                self.subbands & remote.subbands,
+                &[
+                    Self::SUBBANDS_8,
+                    Self::SUBBANDS_4,
+                ],
+            )?,
+            allocation_methods: pick(
+                self.allocation_methods & remote.allocation_methods,
+                &[
+                    Self::ALLOCATION_LOUDNESS,
+                    Self::ALLOCATION_SNR,
+                ],
+            )?,
+            min_bitpool,
+            max_bitpool,
+        })
+    }
+}
+
+/// MPEG-2/4 AAC codec capabilities, see A2DP spec section 4.5.2
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct AacCapabilities {
+    /// Bitmask of `AacCapabilities::OBJECT_TYPE_*`
+    pub object_types: u8,
+    /// Bitmask of `AacCapabilities::FREQ_*`
+    pub frequencies: u16,
+    /// Bitmask of `AacCapabilities::CHANNELS_*`
+    pub channels: u8,
+    pub vbr: bool,
+    /// Maximum bitrate in bits per second, `0` if unknown
+    pub bitrate: u32,
+}
+
+impl AacCapabilities {
+    pub const CHANNELS_1: u8 = 0x02;
+    pub const CHANNELS_2: u8 = 0x01;
+    pub const FREQ_11025: u16 = 0x400;
+    pub const FREQ_12000: u16 = 0x200;
+    pub const FREQ_16000: u16 = 0x100;
+    pub const FREQ_22050: u16 = 0x080;
+    pub const FREQ_24000: u16 = 0x040;
+    pub const FREQ_32000: u16 = 0x020;
+    pub const FREQ_44100: u16 = 0x010;
+    pub const FREQ_48000: u16 = 0x008;
+    pub const FREQ_64000: u16 = 0x004;
+    pub const FREQ_8000: u16 = 0x800;
+    pub const FREQ_88200: u16 = 0x002;
+    pub const FREQ_96000: u16 = 0x001;
+    pub const OBJECT_TYPE_MPEG2_AAC_LC: u8 = 0x80;
+    pub const OBJECT_TYPE_MPEG4_AAC_LC: u8 = 0x40;
+    pub const OBJECT_TYPE_MPEG4_AAC_LTP: u8 = 0x20;
+    pub const OBJECT_TYPE_MPEG4_AAC_SCALABLE: u8 = 0x10;
+}
+
+impl Default for AacCapabilities {
+    /// AAC-LC at the common sample rates, stereo and mono, VBR
+    fn default() -> Self {
+        Self {
+            object_types: Self::OBJECT_TYPE_MPEG2_AAC_LC | Self::OBJECT_TYPE_MPEG4_AAC_LC,
+            frequencies: Self::FREQ_44100 | Self::FREQ_48000,
+            channels: Self::CHANNELS_1 | Self::CHANNELS_2,
+            vbr: true,
+            bitrate: 320_000,
+        }
+    }
+}
+
+impl CodecCapabilities for AacCapabilities {
+    const CODEC: u8 = 0x02;
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let bitrate = self.bitrate & 0x7f_ffff;
+        vec![
+            self.object_types,
+            (self.frequencies >> 4) as u8,
+            ((self.frequencies & 0x0f) as u8) << 4 | (self.channels & 0x03) << 2,
+            (self.vbr as u8) << 7 | (bitrate >> 16) as u8,
+            (bitrate >> 8) as u8,
+            bitrate as u8,
+        ]
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, ZbusError> {
+        check_len("AAC", bytes, 6)?;
+        Ok(Self {
+            object_types: bytes[0],
+            frequencies: (bytes[1] as u16) << 4 | (bytes[2] >> 4) as u16,
+            channels: (bytes[2] >> 2) & 0x03,
+            vbr: bytes[3] & 0x80 != 0,
+            bitrate: ((bytes[3] & 0x7f) as u32) << 16 | (bytes[4] as u32) << 8 | bytes[5] as u32,
+        })
+    }
+
+    fn select_configuration(&self, remote: &Self) -> Option<Self> {
+        let frequencies = self.frequencies & remote.frequencies;
+        let frequency = [
+            Self::FREQ_48000,
+            Self::FREQ_44100,
+            Self::FREQ_96000,
+            Self::FREQ_88200,
+            Self::FREQ_64000,
+            Self::FREQ_32000,
+            Self::FREQ_24000,
+            Self::FREQ_22050,
+            Self::FREQ_16000,
+            Self::FREQ_12000,
+            Self::FREQ_11025,
+            Self::FREQ_8000,
+        ]
+        .into_iter()
+        .find(|bit| frequencies & bit != 0)?;
+        let bitrate = match (self.bitrate, remote.bitrate) {
+            (0, rate) | (rate, 0) => rate,
+            (local, remote) => local.min(remote),
+        };
+        Some(Self {
+            object_types: pick(
+                self.object_types & remote.object_types,
+                &[
+                    Self::OBJECT_TYPE_MPEG4_AAC_LC,
+                    Self::OBJECT_TYPE_MPEG2_AAC_LC,
+                    Self::OBJECT_TYPE_MPEG4_AAC_LTP,
+                    Self::OBJECT_TYPE_MPEG4_AAC_SCALABLE,
+                ],
+            )?,
+            frequencies: frequency,
+            channels: pick(
+                self.channels & remote.channels,
+                &[
+                    Self::CHANNELS_2,
+                    Self::CHANNELS_1,
+                ],
+            )?,
+            vbr: self.vbr && remote.vbr,
+            bitrate,
+        })
+    }
+}
@@ -0,0 +1,60 @@
+//! # Media helpers
+//!
+//! Wrappers around the `org.bluez.Media*` interfaces for audio applications.
+
+use crate::enum_impl_to_from_str;
+
+mod codec;
+pub use codec::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod transport;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use transport::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod volume;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use volume::*;
+
+enum_impl_to_from_str! {
+    TransportState, {
+        Idle : "idle",
+        Pending : "pending",
+        Active : "active",
+        Broadcasting : "broadcasting",
+    }
+}
+
+/// AVRCP absolute volume, clamped to `0..=Volume::MAX`
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Volume(u8);
+
+impl Volume {
+    pub const MAX: u8 = 127;
+
+    /// Create a volume, values above `Volume::MAX` are clamped
+    pub fn new(value: u16) -> Self {
+        Self(value.min(Self::MAX as u16) as u8)
+    }
+
+    /// Create a volume from a 0-100 percentage
+    pub fn from_percent(percent: u8) -> Self {
+        Self::new((percent.min(100) as u16 * Self::MAX as u16 + 50) / 100)
+    }
+
+    pub fn value(self) -> u8 {
+        self.0
+    }
+
+    /// The volume as a 0-100 percentage
+    pub fn percent(self) -> u8 {
+        ((self.0 as u16 * 100 + Self::MAX as u16 / 2) / Self::MAX as u16) as u8
+    }
+}
+
+impl From<Volume> for u16 {
+    fn from(value: Volume) -> Self {
+        value.0 as u16
+    }
+}
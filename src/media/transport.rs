@@ -0,0 +1,142 @@
+use std::io;
+use std::os::fd::OwnedFd;
+use std::os::unix::net::UnixStream;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+
+use futures_lite::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, Stream, StreamExt};
+use zbus::zvariant::ObjectPath;
+use zbus::Connection;
+
+use super::TransportState;
+use crate::proxy::media_transport1::MediaTransport1Proxy;
+use crate::rt::AsyncStream;
+
+/// An acquired `MediaTransport1` stream.
+///
+/// The transport socket is packet based, reads return at most one frame of
+/// `read_mtu` bytes and writes send at most one frame of `write_mtu` bytes.
+pub struct AudioTransport {
+    proxy: MediaTransport1Proxy<'static>,
+    stream: AsyncStream,
+    read_mtu: u16,
+    write_mtu: u16,
+}
+
+impl AudioTransport {
+    async fn new(
+        connection: &Connection,
+        path: &ObjectPath<'_>,
+        try_acquire: bool,
+    ) -> Result<Self, zbus::Error> {
+        let proxy = MediaTransport1Proxy::builder(connection)
+            .path(path.to_owned())?
+            .build()
+            .await?;
+        let (fd, read_mtu, write_mtu) = if try_acquire {
+            proxy.try_acquire().await?
+        } else {
+            proxy.acquire().await?
+        };
+        let stream = AsyncStream::new(UnixStream::from(OwnedFd::from(fd)))?;
+        Ok(Self {
+            proxy,
+            stream,
+            read_mtu,
+            write_mtu,
+        })
+    }
+
+    /// Acquire the transport at `path`
+    pub async fn acquire(
+        connection: &Connection,
+        path: &ObjectPath<'_>,
+    ) -> Result<Self, zbus::Error> {
+        Self::new(connection, path, false).await
+    }
+
+    /// Acquire the transport at `path` only if it is in the pending state
+    pub async fn try_acquire(
+        connection: &Connection,
+        path: &ObjectPath<'_>,
+    ) -> Result<Self, zbus::Error> {
+        Self::new(connection, path, true).await
+    }
+
+    pub fn proxy(&self) -> &MediaTransport1Proxy<'static> {
+        &self.proxy
+    }
+
+    pub fn read_mtu(&self) -> u16 {
+        self.read_mtu
+    }
+
+    pub fn write_mtu(&self) -> u16 {
+        self.write_mtu
+    }
+
+    /// Receive one frame. An empty frame means the transport closed.
+    pub async fn recv_frame(&mut self) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; self.read_mtu as usize];
+        let len = self.stream.read(&mut buf).await?;
+        buf.truncate(len);
+        Ok(buf)
+    }
+
+    /// Send `frame`, split in to `write_mtu` sized packets if needed
+    pub async fn send_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        for packet in frame.chunks(self.write_mtu.max(1) as usize) {
+            self.stream.write_all(packet).await?;
+        }
+        Ok(())
+    }
+
+    /// The current transport state
+    pub async fn state(&self) -> Result<TransportState, zbus::Error> {
+        Ok(TransportState::from_str(&self.proxy.state().await?)?)
+    }
+
+    /// Stream of transport state changes
+    pub async fn state_changes(&self) -> impl Stream<Item = TransportState> + use<> {
+        self.proxy
+            .receive_state_changed()
+            .await
+            .then(|changed| async move { changed.get().await.ok() })
+            .filter_map(|state| TransportState::from_str(&state?).ok())
+    }
+
+    /// Release the transport back to bluez
+    pub async fn release(self) -> Result<(), zbus::Error> {
+        self.proxy.release().await
+    }
+}
+
+impl AsyncRead for AudioTransport {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.stream).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for AudioTransport {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let len = buf.len().min(self.write_mtu.max(1) as usize);
+        Pin::new(&mut self.stream).poll_write(cx, &buf[..len])
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.stream).poll_close(cx)
+    }
+}
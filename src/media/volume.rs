@@ -0,0 +1,43 @@
+use futures_lite::{Stream, StreamExt};
+use zbus::zvariant::ObjectPath;
+use zbus::Connection;
+
+use super::Volume;
+use crate::proxy::media_transport1::MediaTransport1Proxy;
+
+/// Absolute volume control of a `MediaTransport1`, for syncing volume with
+/// a headset
+pub struct VolumeControl {
+    proxy: MediaTransport1Proxy<'static>,
+}
+
+impl VolumeControl {
+    pub async fn new(connection: &Connection, path: &ObjectPath<'_>) -> Result<Self, zbus::Error> {
+        let proxy = MediaTransport1Proxy::builder(connection)
+            .path(path.to_owned())?
+            .build()
+            .await?;
+        Ok(Self { proxy })
+    }
+
+    pub fn proxy(&self) -> &MediaTransport1Proxy<'static> {
+        &self.proxy
+    }
+
+    pub async fn volume(&self) -> Result<Volume, zbus::Error> {
+        Ok(Volume::new(self.proxy.volume().await?))
+    }
+
+    pub async fn set_volume(&self, volume: Volume) -> Result<(), zbus::Error> {
+        self.proxy.set_volume(volume.into()).await
+    }
+
+    /// Stream of volume changes, e.g. from the headset volume buttons
+    pub async fn volume_changes(&self) -> impl Stream<Item = Volume> + use<> {
+        self.proxy
+            .receive_volume_changed()
+            .await
+            .then(|changed| async move { changed.get().await.ok() })
+            .filter_map(|volume| volume.map(Volume::new))
+    }
+}
@@ -0,0 +1,10 @@
+//! # Mesh helpers
+//!
+//! Provisioning through the bluez mesh daemon, `bluetooth-meshd`. The
+//! application must already be attached to its node, `Network1.Attach`
+//! returns the node path used here.
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod provisioner;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use provisioner::*;
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+
+use futures_lite::StreamExt;
+use log::debug;
+use uuid::Uuid;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::Connection;
+
+use crate::interface::{ProvisionerEvent, ProvisionerEvents, UnprovisionedDevice};
+use crate::proxy::mesh::management1::Management1Proxy;
+
+/// A node added to the network by `MeshProvisioner::add_node()`
+#[derive(Debug, Clone, Copy)]
+pub struct ProvisionedNode {
+    uuid: Uuid,
+    unicast: u16,
+    count: u8,
+}
+
+impl ProvisionedNode {
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    /// Address of the primary element
+    pub fn unicast(&self) -> u16 {
+        self.unicast
+    }
+
+    /// Number of elements, addressed sequentially from `unicast()`
+    pub fn count(&self) -> u8 {
+        self.count
+    }
+}
+
+/// Drives `Management1` on a provisioner node together with the events of
+/// its exported `Provisioner1`
+pub struct MeshProvisioner {
+    management: Management1Proxy<'static>,
+    events: ProvisionerEvents,
+}
+
+impl MeshProvisioner {
+    /// `node_path` is the node returned by `Network1.Attach`, `events` the
+    /// stream of the `Provisioner1` exported by the attached application
+    pub async fn new(
+        connection: &Connection,
+        node_path: &ObjectPath<'_>,
+        events: ProvisionerEvents,
+    ) -> Result<Self, zbus::Error> {
+        let management = Management1Proxy::builder(connection)
+            .path(node_path.to_owned())?
+            .build()
+            .await?;
+        Ok(Self { management, events })
+    }
+
+    pub fn management(&self) -> &Management1Proxy<'static> {
+        &self.management
+    }
+
+    /// Scan for unprovisioned devices for `seconds`, or until canceled if 0.
+    /// Results are returned by `next_unprovisioned()`.
+    pub async fn scan(&self, seconds: u16) -> Result<(), zbus::Error> {
+        self.management
+            .unprovisioned_scan(HashMap::from([("Seconds", Value::from(seconds))]))
+            .await
+    }
+
+    pub async fn cancel_scan(&self) -> Result<(), zbus::Error> {
+        self.management.unprovisioned_scan_cancel().await
+    }
+
+    /// Wait for the next unprovisioned device found by `scan()`
+    pub async fn next_unprovisioned(&mut self) -> Option<UnprovisionedDevice> {
+        while let Some(event) = self.events.next().await {
+            match event {
+                ProvisionerEvent::ScanResult(device) => return Some(device),
+                event => debug!("MeshProvisioner: ignoring {event:?}"),
+            }
+        }
+        None
+    }
+
+    /// Provision the device `uuid` and wait until it has joined the network
+    pub async fn add_node(&mut self, uuid: Uuid) -> Result<ProvisionedNode, zbus::Error> {
+        self.management
+            .add_node(uuid.as_bytes(), HashMap::default())
+            .await?;
+        while let Some(event) = self.events.next().await {
+            match event {
+                ProvisionerEvent::AddNodeComplete {
+                    uuid: added,
+                    unicast,
+                    count,
+                } if added == uuid => {
+                    return Ok(ProvisionedNode {
+                        uuid,
+                        unicast,
+                        count,
+                    });
+                }
+                ProvisionerEvent::AddNodeFailed {
+                    uuid: failed,
+                    reason,
+                } if failed == uuid => {
+                    return Err(zbus::Error::Failure(format!(
+                        "{uuid}: provisioning failed: {reason}"
+                    )));
+                }
+                event => debug!("MeshProvisioner: ignoring {event:?}"),
+            }
+        }
+        Err(zbus::Error::Failure(format!(
+            "{uuid}: provisioner events closed"
+        )))
+    }
+
+    /// Generate a new network key for the subnet `net_index`
+    pub async fn create_subnet(&self, net_index: u16) -> Result<(), zbus::Error> {
+        self.management.create_subnet(net_index).await
+    }
+
+    /// Generate a new application key `app_index` bound to the subnet
+    /// `net_index`
+    pub async fn create_app_key(&self, net_index: u16, app_index: u16) -> Result<(), zbus::Error> {
+        self.management.create_app_key(net_index, app_index).await
+    }
+
+    /// Add an existing application key to the local key database
+    pub async fn import_app_key(
+        &self,
+        net_index: u16,
+        app_index: u16,
+        app_key: [u8; 16],
+    ) -> Result<(), zbus::Error> {
+        self.management
+            .import_app_key(net_index, app_index, &app_key)
+            .await
+    }
+
+    /// Add a node provisioned elsewhere, so its device key can be used to
+    /// configure it
+    pub async fn import_remote_node(
+        &self,
+        primary: u16,
+        count: u8,
+        device_key: [u8; 16],
+    ) -> Result<(), zbus::Error> {
+        self.management
+            .import_remote_node(primary, count, &device_key)
+            .await
+    }
+
+    /// Remove a node and its device key from the local key database
+    pub async fn delete_remote_node(&self, primary: u16, count: u8) -> Result<(), zbus::Error> {
+        self.management.delete_remote_node(primary, count).await
+    }
+
+    /// Export the network keys, application keys and device keys known to the
+    /// provisioner, e.g. to hand over to another provisioner
+    pub async fn export_keys(&self) -> Result<HashMap<String, OwnedValue>, zbus::Error> {
+        self.management.export_keys().await
+    }
+}
@@ -0,0 +1,87 @@
+//! # OBEX helpers
+//!
+//! File transfers through the bluez OBEX daemon, `obexd`. It runs per user,
+//! so these helpers take a session bus connection.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::Value;
+
+use crate::enum_impl_to_from_str;
+
+mod phonebook;
+pub use phonebook::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod push;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use push::*;
+
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+mod receive;
+#[cfg(any(feature = "async-io", feature = "tokio"))]
+pub use receive::*;
+
+const TRANSFER_INTERFACE: &str = "org.bluez.obex.Transfer1";
+
+enum_impl_to_from_str! {
+    TransferStatus, {
+        Queued : "queued",
+        Active : "active",
+        Suspended : "suspended",
+        Complete : "complete",
+        Error : "error",
+    }
+}
+
+impl TransferStatus {
+    /// The transfer completed or failed, no further updates follow
+    pub fn is_finished(&self) -> bool {
+        matches!(self, TransferStatus::Complete | TransferStatus::Error)
+    }
+}
+
+/// Snapshot of the progress of an OBEX transfer
+#[derive(Debug, Clone, Copy)]
+pub struct TransferProgress {
+    status: TransferStatus,
+    transferred: u64,
+    size: Option<u64>,
+}
+
+impl TransferProgress {
+    pub fn status(&self) -> TransferStatus {
+        self.status
+    }
+
+    /// Bytes transferred so far
+    pub fn transferred(&self) -> u64 {
+        self.transferred
+    }
+
+    /// Total size in bytes, if known
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+
+    /// Apply the `Transfer1` properties in `changed`, returns false if none
+    /// of them were relevant
+    fn update(&mut self, changed: &HashMap<&str, Value<'_>>) -> bool {
+        let mut updated = false;
+        if let Some(Value::Str(status)) = changed.get("Status")
+            && let Ok(status) = status.as_str().parse()
+        {
+            self.status = status;
+            updated = true;
+        }
+        if let Some(Value::U64(transferred)) = changed.get("Transferred") {
+            self.transferred = *transferred;
+            updated = true;
+        }
+        if let Some(Value::U64(size)) = changed.get("Size") {
+            self.size = Some(*size);
+            updated = true;
+        }
+        updated
+    }
+}
@@ -0,0 +1,369 @@
+//! Parsing of the vCards (2.1 and 3.0) returned by PBAP `Pull` and `PullAll`
+//!
+//! 2.1 values may be `ENCODING=QUOTED-PRINTABLE`, they are decoded using
+//! their `CHARSET`. Only UTF-8 (the default) and ISO-8859-1 are known, other
+//! charsets are read as UTF-8.
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+/// The structured `N` property of a vCard
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PhonebookName {
+    pub family: String,
+    pub given: String,
+    pub additional: String,
+    pub prefix: String,
+    pub suffix: String,
+}
+
+/// A `TEL` property, `types` holds the parameters such as "CELL" or "HOME"
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PhonebookNumber {
+    pub number: String,
+    pub types: Vec<String>,
+}
+
+/// A contact or call history entry parsed from a vCard
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PhonebookEntry {
+    pub version: Option<String>,
+    pub formatted_name: Option<String>,
+    pub name: Option<PhonebookName>,
+    pub numbers: Vec<PhonebookNumber>,
+    pub emails: Vec<String>,
+    pub organization: Option<String>,
+    pub title: Option<String>,
+    pub birthday: Option<String>,
+    pub note: Option<String>,
+    pub uid: Option<String>,
+    /// `X-IRMC-CALL-DATETIME` of call history entries
+    pub call_datetime: Option<String>,
+}
+
+impl PhonebookEntry {
+    /// Parse every `BEGIN:VCARD` .. `END:VCARD` block in `data`, as written
+    /// to the target file of a PBAP pull
+    pub fn parse_all(data: &str) -> Vec<PhonebookEntry> {
+        let mut entries = Vec::new();
+        let mut entry: Option<PhonebookEntry> = None;
+        for line in unfold(data) {
+            let Some((name, params, value)) = split_property(&line) else {
+                continue;
+            };
+            match (name.as_str(), entry.as_mut()) {
+                ("BEGIN", _) if value.eq_ignore_ascii_case("VCARD") => {
+                    entry = Some(PhonebookEntry::default());
+                }
+                ("END", Some(_)) if value.eq_ignore_ascii_case("VCARD") => {
+                    entries.extend(entry.take());
+                }
+                (_, Some(entry)) => {
+                    let value = params.decode(value);
+                    entry.set_property(&name, params.types, &value)
+                }
+                _ => {}
+            }
+        }
+        entries
+    }
+
+    fn set_property(&mut self, name: &str, params: Vec<String>, value: &str) {
+        match name {
+            "VERSION" => self.version = Some(value.to_owned()),
+            "FN" => self.formatted_name = Some(unescape(value)),
+            "N" => {
+                let mut parts = split_unescaped(value, ';').into_iter();
+                self.name = Some(PhonebookName {
+                    family: parts.next().unwrap_or_default(),
+                    given: parts.next().unwrap_or_default(),
+                    additional: parts.next().unwrap_or_default(),
+                    prefix: parts.next().unwrap_or_default(),
+                    suffix: parts.next().unwrap_or_default(),
+                });
+            }
+            "TEL" => self.numbers.push(PhonebookNumber {
+                number: unescape(value),
+                types: params,
+            }),
+            "EMAIL" => self.emails.push(unescape(value)),
+            "ORG" => self.organization = Some(split_unescaped(value, ';').join(" ")),
+            "TITLE" => self.title = Some(unescape(value)),
+            "BDAY" => self.birthday = Some(value.to_owned()),
+            "NOTE" => self.note = Some(unescape(value)),
+            "UID" => self.uid = Some(unescape(value)),
+            "X-IRMC-CALL-DATETIME" => self.call_datetime = Some(value.to_owned()),
+            _ => {}
+        }
+    }
+}
+
+impl FromStr for PhonebookEntry {
+    type Err = zbus::fdo::Error;
+
+    /// Parse a single vCard
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        PhonebookEntry::parse_all(s)
+            .into_iter()
+            .next()
+            .ok_or_else(|| zbus::fdo::Error::InvalidArgs("No vCard found".to_owned()))
+    }
+}
+
+/// Join folded lines, continuations start with a space or tab. A
+/// quoted-printable line ending in a soft line break `=` continues on the
+/// next line too.
+fn unfold(data: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in data.lines() {
+        let Some(last) = lines.last_mut() else {
+            lines.push(line.to_owned());
+            continue;
+        };
+        if let Some(rest) = line.strip_prefix([' ', '\t']) {
+            last.push_str(rest);
+        } else if last.ends_with('=') && is_quoted_printable(last) {
+            last.pop();
+            last.push_str(line);
+        } else {
+            lines.push(line.to_owned());
+        }
+    }
+    lines
+}
+
+fn is_quoted_printable(line: &str) -> bool {
+    line.split_once(':').is_some_and(|(head, _)| {
+        head.split(';').any(|param| {
+            let value = param.split_once('=').map_or(param, |(_, value)| value);
+            value.eq_ignore_ascii_case("QUOTED-PRINTABLE")
+        })
+    })
+}
+
+/// The parameters of a property
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Params {
+    /// Upper-cased `TYPE` values, and bare 2.1 parameters such as "CELL"
+    types: Vec<String>,
+    quoted_printable: bool,
+    charset: Option<String>,
+}
+
+impl Params {
+    /// Undo the `ENCODING` of `value`
+    fn decode<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        if !self.quoted_printable {
+            return Cow::Borrowed(value);
+        }
+        let bytes = decode_quoted_printable(value);
+        match self.charset.as_deref() {
+            Some(charset)
+                if charset.eq_ignore_ascii_case("ISO-8859-1")
+                    || charset.eq_ignore_ascii_case("LATIN1") =>
+            {
+                Cow::Owned(bytes.iter().map(|&b| char::from(b)).collect())
+            }
+            _ => Cow::Owned(String::from_utf8_lossy(&bytes).into_owned()),
+        }
+    }
+}
+
+/// Split `NAME;PARAM;TYPE=A,B:value` into the upper-cased name, the
+/// parameters and the value
+fn split_property(line: &str) -> Option<(String, Params, &str)> {
+    let (head, value) = line.split_once(':')?;
+    let mut parts = head.split(';');
+    // Drop any group prefix, e.g. "item1.TEL"
+    let name = parts.next()?.rsplit('.').next()?.to_uppercase();
+    let mut params = Params::default();
+    for param in parts {
+        let (key, values) = match param.split_once('=') {
+            Some((key, values)) => (Some(key.to_uppercase()), values),
+            None => (None, param),
+        };
+        match key.as_deref() {
+            Some("ENCODING") | None if values.eq_ignore_ascii_case("QUOTED-PRINTABLE") => {
+                params.quoted_printable = true;
+            }
+            Some("CHARSET") => params.charset = Some(values.to_owned()),
+            Some("TYPE") | None => params
+                .types
+                .extend(values.split(',').map(|value| value.to_uppercase())),
+            Some(_) => {}
+        }
+    }
+    Some((name, params, value))
+}
+
+/// Decode `=XX` escapes, an invalid escape is kept as is
+fn decode_quoted_printable(value: &str) -> Vec<u8> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'=', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Split on unescaped `sep` and unescape each part
+fn split_unescaped(value: &str, sep: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            c if c == sep && !escaped => {
+                parts.push(unescape(&value[start..i]));
+                start = i + c.len_utf8();
+            }
+            _ => escaped = false,
+        }
+    }
+    parts.push(unescape(&value[start..]));
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_card() {
+        let data = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN:Alice\r\nEND:VCARD\r\n\
+                    BEGIN:VCARD\r\nVERSION:2.1\r\nFN:Bob\r\nEND:VCARD\r\n";
+        let entries = PhonebookEntry::parse_all(data);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].version.as_deref(), Some("3.0"));
+        assert_eq!(entries[0].formatted_name.as_deref(), Some("Alice"));
+        assert_eq!(entries[1].formatted_name.as_deref(), Some("Bob"));
+    }
+
+    #[test]
+    fn unfolds_continuation_lines() {
+        let entry: PhonebookEntry = "BEGIN:VCARD\nNOTE:a long\n  note\n\tspanning lines\nEND:VCARD"
+            .parse()
+            .unwrap();
+        assert_eq!(entry.note.as_deref(), Some("a long notespanning lines"));
+    }
+
+    #[test]
+    fn unescapes_values() {
+        let entry: PhonebookEntry =
+            "BEGIN:VCARD\nFN:Smith\\, John\nNOTE:one\\ntwo\\;three\\\\\nEND:VCARD"
+                .parse()
+                .unwrap();
+        assert_eq!(entry.formatted_name.as_deref(), Some("Smith, John"));
+        assert_eq!(entry.note.as_deref(), Some("one\ntwo;three\\"));
+    }
+
+    #[test]
+    fn splits_structured_name_on_unescaped_semicolons() {
+        let entry: PhonebookEntry = "BEGIN:VCARD\nN:Doe\\;Smith;Jane;;Dr.;\nEND:VCARD"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            entry.name,
+            Some(PhonebookName {
+                family: "Doe;Smith".to_owned(),
+                given: "Jane".to_owned(),
+                additional: String::new(),
+                prefix: "Dr.".to_owned(),
+                suffix: String::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn drops_group_prefix_and_collects_types() {
+        let entry: PhonebookEntry = "BEGIN:VCARD\n\
+                                     item1.TEL;TYPE=cell,voice:+1 555 0100\n\
+                                     TEL;HOME;WORK:+1 555 0101\n\
+                                     item2.EMAIL;type=INTERNET:jane@example.com\n\
+                                     END:VCARD"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            entry.numbers,
+            [
+                PhonebookNumber {
+                    number: "+1 555 0100".to_owned(),
+                    types: vec!["CELL".to_owned(), "VOICE".to_owned()],
+                },
+                PhonebookNumber {
+                    number: "+1 555 0101".to_owned(),
+                    types: vec!["HOME".to_owned(), "WORK".to_owned()],
+                },
+            ]
+        );
+        assert_eq!(entry.emails, ["jane@example.com"]);
+    }
+
+    #[test]
+    fn decodes_quoted_printable_utf8() {
+        let entry: PhonebookEntry = "BEGIN:VCARD\nVERSION:2.1\n\
+                                     N;CHARSET=UTF-8;ENCODING=QUOTED-PRINTABLE:Ren=C3=A9;Z=C3=B6e\n\
+                                     TEL;CELL;QUOTED-PRINTABLE:555=2D0100\n\
+                                     END:VCARD"
+            .parse()
+            .unwrap();
+        let name = entry.name.unwrap();
+        assert_eq!(name.family, "René");
+        assert_eq!(name.given, "Zöe");
+        assert_eq!(entry.numbers[0].number, "555-0100");
+        assert_eq!(entry.numbers[0].types, ["CELL"]);
+    }
+
+    #[test]
+    fn decodes_quoted_printable_latin1_with_soft_breaks() {
+        let entry: PhonebookEntry = "BEGIN:VCARD\nVERSION:2.1\n\
+                                     NOTE;ENCODING=QUOTED-PRINTABLE;CHARSET=ISO-8859-1:caf=E9 =\n\
+                                     cr=E8me\n\
+                                     END:VCARD"
+            .parse()
+            .unwrap();
+        assert_eq!(entry.note.as_deref(), Some("café crème"));
+    }
+
+    #[test]
+    fn keeps_invalid_quoted_printable_escapes() {
+        assert_eq!(decode_quoted_printable("a=ZZb=4"), b"a=ZZb=4");
+    }
+
+    #[test]
+    fn no_card_is_an_error() {
+        assert!("VERSION:3.0\nFN:Alice".parse::<PhonebookEntry>().is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use futures_lite::Stream;
+use log::warn;
+use zbus::fdo::PropertiesChanged;
+use zbus::message::Type as MessageType;
+use zbus::zvariant::{OwnedObjectPath, Value};
+use zbus::{Connection, MatchRule, MessageStream};
+
+use super::{TransferProgress, TransferStatus, TRANSFER_INTERFACE};
+use crate::address::BDAddr;
+use crate::proxy::obex::client1::Client1Proxy;
+use crate::proxy::obex::object_push1::ObjectPush1Proxy;
+use crate::proxy::obex::transfer1::Transfer1Proxy;
+
+/// Stream of the progress of a file sent with `push_file()`.
+///
+/// The stream ends after a `Complete` or `Error` status. Dropping it closes
+/// the OBEX session, canceling the transfer if it is still running.
+pub struct PushTransfer {
+    client: Client1Proxy<'static>,
+    session: OwnedObjectPath,
+    transfer: Transfer1Proxy<'static>,
+    /// `PropertiesChanged` of every object under the session
+    changed: MessageStream,
+    progress: TransferProgress,
+}
+
+impl PushTransfer {
+    pub fn transfer(&self) -> &Transfer1Proxy<'static> {
+        &self.transfer
+    }
+
+    /// The most recent progress
+    pub fn progress(&self) -> TransferProgress {
+        self.progress
+    }
+}
+
+impl Stream for PushTransfer {
+    type Item = TransferProgress;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.progress.status().is_finished() {
+            return Poll::Ready(None);
+        }
+        loop {
+            let Some(msg) = ready!(Pin::new(&mut self.changed).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            let msg = match msg {
+                Ok(msg) => msg,
+                Err(err) => {
+                    warn!("PushTransfer: {err}");
+                    continue;
+                }
+            };
+            let Some(signal) = PropertiesChanged::from_message(msg) else {
+                continue;
+            };
+            let header = signal.message().header();
+            if header.path() != Some(self.transfer.inner().path()) {
+                continue;
+            }
+            let Ok(args) = signal.args() else {
+                continue;
+            };
+            if args.interface_name != TRANSFER_INTERFACE {
+                continue;
+            }
+            if self.progress.update(&args.changed_properties) {
+                return Poll::Ready(Some(self.progress));
+            }
+        }
+    }
+}
+
+impl Drop for PushTransfer {
+    fn drop(&mut self) {
+        let client = self.client.clone();
+        let session = self.session.clone();
+        self.client
+            .inner()
+            .connection()
+            .executor()
+            .spawn(
+                async move {
+                    if let Err(err) = client.remove_session(&session).await {
+                        warn!("{}: RemoveSession {}", session.as_str(), err);
+                    }
+                },
+                "bluez-zbus remove_session",
+            )
+            .detach();
+    }
+}
+
+/// Send `file` to the device `destination` using Object Push.
+///
+/// `connection` must be a session bus connection.
+pub async fn push_file(
+    connection: &Connection,
+    destination: BDAddr,
+    file: &Path,
+) -> Result<PushTransfer, zbus::Error> {
+    let file = file.to_str().ok_or_else(|| {
+        zbus::Error::Failure(format!("{}: path is not valid UTF-8", file.display()))
+    })?;
+    let client = Client1Proxy::new(connection).await?;
+    let session = client
+        .create_session(
+            &destination.to_string(),
+            HashMap::from([("Target", Value::from("opp"))]),
+        )
+        .await?;
+
+    let push = async {
+        // Subscribe before SendFile, a small file can complete before it
+        // returns
+        let rule = MatchRule::builder()
+            .msg_type(MessageType::Signal)
+            .sender("org.bluez.obex")?
+            .interface("org.freedesktop.DBus.Properties")?
+            .member("PropertiesChanged")?
+            .path_namespace(session.clone())?
+            .build();
+        let changed = MessageStream::for_match_rule(rule, connection, None).await?;
+        let (path, properties) = ObjectPush1Proxy::builder(connection)
+            .path(session.clone())?
+            .build()
+            .await?
+            .send_file(file)
+            .await?;
+        let transfer = Transfer1Proxy::builder(connection)
+            .path(path)?
+            .build()
+            .await?;
+
+        let properties = properties
+            .iter()
+            .filter_map(|(key, value)| Some((key.as_str(), value.try_clone().ok()?.into())))
+            .collect();
+        let mut progress = TransferProgress {
+            status: TransferStatus::Queued,
+            transferred: 0,
+            size: None,
+        };
+        progress.update(&properties);
+        // Catch up with a transfer that finished while SendFile was running
+        if let Ok(status) = transfer.status().await
+            && let Ok(status) = status.parse()
+        {
+            progress.status = status;
+        }
+        Ok::<_, zbus::Error>((transfer, changed, progress))
+    };
+
+    match push.await {
+        Ok((transfer, changed, progress)) => Ok(PushTransfer {
+            client,
+            session,
+            transfer,
+            changed,
+            progress,
+        }),
+        Err(err) => {
+            client.remove_session(&session).await.ok();
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_channel::mpsc;
+use futures_lite::{Stream, StreamExt};
+use log::{debug, warn};
+use zbus::fdo::{PropertiesChangedStream, PropertiesProxy};
+use zbus::Connection;
+
+use super::{TransferProgress, TransferStatus, TRANSFER_INTERFACE};
+use crate::interface::{
+    ObexAgent1, ObexAgentError, ObexAgentHandle, ObexAgentHandler, PushRequest,
+};
+
+const OPP_AGENT_PATH: &str = "/org/bluez_zbus/obex/opp_receiver";
+
+/// A file received by an `OppReceiver`
+#[derive(Debug, Clone)]
+pub struct ReceivedFile {
+    path: PathBuf,
+    name: String,
+    size: u64,
+}
+
+impl ReceivedFile {
+    /// Where the file was stored
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The name of the object as sent by the remote device
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Bytes received
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+struct OppHandler {
+    connection: Connection,
+    directory: PathBuf,
+    received: mpsc::UnboundedSender<ReceivedFile>,
+}
+
+impl OppHandler {
+    /// Store the object under its own name, without any directory the remote
+    /// device may have sent
+    fn target(&self, request: &PushRequest) -> PathBuf {
+        let name = Path::new(request.name())
+            .file_name()
+            .unwrap_or("received".as_ref());
+        self.directory.join(name)
+    }
+
+    async fn watch(
+        mut changed: PropertiesChangedStream,
+        file: ReceivedFile,
+        received: mpsc::UnboundedSender<ReceivedFile>,
+    ) {
+        let mut progress = TransferProgress {
+            status: TransferStatus::Queued,
+            transferred: 0,
+            size: None,
+        };
+        while let Some(signal) = changed.next().await {
+            let Ok(args) = signal.args() else {
+                continue;
+            };
+            if args.interface_name != TRANSFER_INTERFACE
+                || !progress.update(&args.changed_properties)
+                || !progress.status().is_finished()
+            {
+                continue;
+            }
+            if matches!(progress.status(), TransferStatus::Complete) {
+                let size = progress.size().unwrap_or(progress.transferred());
+                received.unbounded_send(ReceivedFile { size, ..file }).ok();
+            } else {
+                warn!("OppReceiver: receiving {} failed", file.path.display());
+            }
+            return;
+        }
+    }
+}
+
+impl ObexAgentHandler for OppHandler {
+    async fn authorize_push(&self, request: PushRequest) -> Result<String, ObexAgentError> {
+        let path = self.target(&request);
+        let target = path.to_str().map(str::to_owned).ok_or_else(|| {
+            ObexAgentError::Rejected(format!("{}: path is not valid UTF-8", path.display()))
+        })?;
+        // Subscribe before accepting so the final status can't be missed
+        let changed = PropertiesProxy::builder(&self.connection)
+            .destination("org.bluez.obex")?
+            .path(request.transfer().to_owned())?
+            .build()
+            .await?
+            .receive_properties_changed()
+            .await?;
+        debug!("OppReceiver: accepting {} into {target}", request.name());
+
+        let file = ReceivedFile {
+            path,
+            name: request.name().to_owned(),
+            size: request.size().unwrap_or_default(),
+        };
+        self.connection
+            .executor()
+            .spawn(
+                Self::watch(changed, file, self.received.clone()),
+                "bluez-zbus opp transfer",
+            )
+            .detach();
+        Ok(target)
+    }
+}
+
+/// Accepts every incoming Object Push into a directory and streams the files
+/// once they are completely received
+pub struct OppReceiver {
+    agent: ObexAgentHandle<OppHandler>,
+    received: mpsc::UnboundedReceiver<ReceivedFile>,
+}
+
+impl OppReceiver {
+    /// Register an obex agent storing pushed files in `directory`.
+    /// `connection` must be a session bus connection.
+    pub async fn register(
+        connection: &Connection,
+        directory: impl Into<PathBuf>,
+    ) -> Result<Self, zbus::Error> {
+        let (tx, rx) = mpsc::unbounded();
+        let handler = OppHandler {
+            connection: connection.clone(),
+            directory: directory.into(),
+            received: tx,
+        };
+        let agent = ObexAgent1::new(handler)
+            .register(OPP_AGENT_PATH, connection)
+            .await?;
+        Ok(Self {
+            agent,
+            received: rx,
+        })
+    }
+
+    pub async fn unregister(self) -> Result<(), zbus::Error> {
+        self.agent.unregister().await
+    }
+}
+
+impl Stream for OppReceiver {
+    type Item = ReceivedFile;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.received).poll_next(cx)
+    }
+}
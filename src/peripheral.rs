@@ -0,0 +1,251 @@
+//! # Peripheral
+//!
+//! Sets up everything a peripheral needs in one go: the adapter is powered
+//! (and optionally made discoverable), an optional pairing agent registered,
+//! the GATT services exported, and the adverts registered. The returned
+//! `PeripheralHandle` tears it all down again, on `stop()` or when dropped.
+//!
+//! Pass `PeripheralHandle::registrations()` to a `RestartWatcher` to keep
+//! the peripheral up across bluetoothd restarts.
+
+use futures_lite::future::Boxed;
+use futures_lite::FutureExt;
+use log::warn;
+use zbus::Connection;
+
+use crate::advertising::{AdvertisementHandle, AdvertisingManager};
+use crate::client::Adapter;
+use crate::interface::gatt::{
+    GattApplication1, GattApplicationHandle, GattCharacteristic1, GattDescriptor1, GattService1,
+};
+use crate::interface::{Agent1, AgentCapability, AgentHandler, LEAdvertisement1};
+use crate::restart::Registration;
+
+/// Default object path of the GATT application
+pub const PERIPHERAL_APPLICATION_PATH: &str = "/org/bluez_zbus/peripheral";
+/// Default object path of the pairing agent
+pub const PERIPHERAL_AGENT_PATH: &str = "/org/bluez_zbus/peripheral/agent";
+
+type GattServiceDefinition = (
+    GattService1,
+    Vec<(GattCharacteristic1, Vec<GattDescriptor1>)>,
+);
+type Unregister = Box<dyn FnOnce() -> Boxed<Result<(), zbus::Error>> + Send>;
+type Register = Box<
+    dyn FnOnce(Connection, String) -> Boxed<Result<(Unregister, Registration), zbus::Error>> + Send,
+>;
+
+/// Builder for a peripheral, see the module docs
+pub struct Peripheral {
+    adapter: Adapter,
+    services: Vec<GattServiceDefinition>,
+    advertisements: Vec<LEAdvertisement1>,
+    agent: Option<Register>,
+    discoverable: Option<bool>,
+    application_path: String,
+    agent_path: String,
+}
+
+impl Peripheral {
+    pub fn new(adapter: Adapter) -> Self {
+        Self {
+            adapter,
+            services: Vec::new(),
+            advertisements: Vec::new(),
+            agent: None,
+            discoverable: None,
+            application_path: PERIPHERAL_APPLICATION_PATH.to_owned(),
+            agent_path: PERIPHERAL_AGENT_PATH.to_owned(),
+        }
+    }
+
+    /// Export a GATT service, e.g. from `BatteryService::build()`
+    pub fn with_service(mut self, service: GattServiceDefinition) -> Self {
+        self.services.push(service);
+        self
+    }
+
+    pub fn with_advertisement(mut self, advertisement: LEAdvertisement1) -> Self {
+        self.advertisements.push(advertisement);
+        self
+    }
+
+    /// Register `handler` as the default pairing agent while running
+    pub fn with_agent<H: AgentHandler>(mut self, handler: H, capability: AgentCapability) -> Self {
+        self.agent = Some(Box::new(move |connection: Connection, path: String| {
+            async move {
+                let handle = Agent1::new(handler)
+                    .register(&path, capability, true, &connection)
+                    .await?;
+                let registration = handle.registration();
+                let unregister: Unregister = Box::new(move || handle.unregister().boxed());
+                Ok((unregister, registration))
+            }
+            .boxed()
+        }));
+        self
+    }
+
+    /// Set the adapter's `Discoverable` property on start
+    pub fn with_discoverable(mut self, discoverable: bool) -> Self {
+        self.discoverable = Some(discoverable);
+        self
+    }
+
+    /// Export the GATT application at `path` instead of
+    /// `PERIPHERAL_APPLICATION_PATH`
+    pub fn with_application_path(mut self, path: &str) -> Self {
+        self.application_path = path.to_owned();
+        self
+    }
+
+    /// Export the agent at `path` instead of `PERIPHERAL_AGENT_PATH`
+    pub fn with_agent_path(mut self, path: &str) -> Self {
+        self.agent_path = path.to_owned();
+        self
+    }
+
+    /// Power the adapter and register the agent, services and adverts. On
+    /// failure whatever was already registered is torn down again.
+    pub async fn start(self) -> Result<PeripheralHandle, zbus::Error> {
+        let connection = self.adapter.connection().clone();
+        self.adapter.set_powered(true).await?;
+        if let Some(discoverable) = self.discoverable {
+            self.adapter.proxy().set_discoverable(discoverable).await?;
+        }
+
+        let mut handle = PeripheralHandle {
+            adapter: self.adapter,
+            application: None,
+            advertisements: Vec::new(),
+            agent: None,
+        };
+        if let Err(err) = handle
+            .register(
+                &connection,
+                self.agent.map(|agent| (agent, self.agent_path)),
+                self.services,
+                &self.application_path,
+                self.advertisements,
+            )
+            .await
+        {
+            if let Err(teardown) = handle.take_teardown().await {
+                warn!("Peripheral: teardown after failed start: {teardown}");
+            }
+            return Err(err);
+        }
+        Ok(handle)
+    }
+}
+
+/// A running peripheral, stopped when dropped
+pub struct PeripheralHandle {
+    adapter: Adapter,
+    application: Option<GattApplicationHandle>,
+    advertisements: Vec<AdvertisementHandle>,
+    agent: Option<(Unregister, Registration)>,
+}
+
+impl PeripheralHandle {
+    pub fn adapter(&self) -> &Adapter {
+        &self.adapter
+    }
+
+    pub fn application(&self) -> Option<&GattApplicationHandle> {
+        self.application.as_ref()
+    }
+
+    pub fn advertisements(&self) -> &[AdvertisementHandle] {
+        &self.advertisements
+    }
+
+    /// For `RestartWatcher::track()`
+    pub fn registrations(&self) -> Vec<Registration> {
+        self.agent
+            .iter()
+            .map(|(_, registration)| registration.clone())
+            .chain(self.application.iter().map(|app| app.registration()))
+            .chain(self.advertisements.iter().map(|adv| adv.registration()))
+            .collect()
+    }
+
+    async fn register(
+        &mut self,
+        connection: &Connection,
+        agent: Option<(Register, String)>,
+        services: Vec<GattServiceDefinition>,
+        application_path: &str,
+        advertisements: Vec<LEAdvertisement1>,
+    ) -> Result<(), zbus::Error> {
+        if let Some((register, path)) = agent {
+            self.agent = Some(register(connection.clone(), path).await?);
+        }
+        if !services.is_empty() {
+            self.application = Some(
+                GattApplication1::register_on(
+                    application_path,
+                    self.adapter.path().as_str(),
+                    connection.clone(),
+                    services,
+                )
+                .await?,
+            );
+        }
+        if !advertisements.is_empty() {
+            let manager = AdvertisingManager::new(connection, self.adapter.path().as_str()).await?;
+            for advertisement in advertisements {
+                self.advertisements
+                    .push(manager.register(advertisement).await?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Everything registered so far, unregistered in reverse order
+    fn take_teardown(&mut self) -> impl Future<Output = Result<(), zbus::Error>> + Send + 'static {
+        let application = self.application.take();
+        let advertisements = std::mem::take(&mut self.advertisements);
+        let agent = self.agent.take();
+        async move {
+            let mut res = Ok(());
+            for advertisement in advertisements {
+                res = res.and(advertisement.unregister().await);
+            }
+            if let Some(application) = application {
+                res = res.and(application.unregister().await);
+            }
+            if let Some((unregister, _)) = agent {
+                res = res.and(unregister().await);
+            }
+            res
+        }
+    }
+
+    /// Unregister the adverts, services and agent. Every step is attempted,
+    /// the first error is returned.
+    pub async fn stop(mut self) -> Result<(), zbus::Error> {
+        self.take_teardown().await
+    }
+}
+
+impl Drop for PeripheralHandle {
+    fn drop(&mut self) {
+        if self.application.is_none() && self.advertisements.is_empty() && self.agent.is_none() {
+            return;
+        }
+        let teardown = self.take_teardown();
+        self.adapter
+            .connection()
+            .executor()
+            .spawn(
+                async move {
+                    if let Err(err) = teardown.await {
+                        warn!("Peripheral: teardown on drop: {err}");
+                    }
+                },
+                "peripheral teardown",
+            )
+            .detach();
+    }
+}
@@ -1,5 +1,7 @@
 use zbus::proxy;
 
+use crate::adapter::{AdapterRoles, ExperimentalFeatures, PowerState};
+
 #[proxy(
     interface = "org.bluez.Adapter1",
     default_service = "org.bluez",
@@ -64,6 +66,10 @@ pub trait Adapter1 {
     #[zbus(property)]
     fn discovering(&self) -> zbus::Result<bool>;
 
+    /// ExperimentalFeatures property
+    #[zbus(property)]
+    fn experimental_features(&self) -> zbus::Result<ExperimentalFeatures>;
+
     /// Modalias property
     #[zbus(property)]
     fn modalias(&self) -> zbus::Result<String>;
@@ -90,6 +96,14 @@ pub trait Adapter1 {
     #[zbus(property, name = "Powered")]
     fn set_powered(&self, value: bool) -> zbus::Result<()>;
 
+    /// PowerState property (experimental)
+    #[zbus(property)]
+    fn power_state(&self) -> zbus::Result<PowerState>;
+
+    /// Roles property
+    #[zbus(property)]
+    fn roles(&self) -> zbus::Result<AdapterRoles>;
+
     /// UUIDs property
     #[zbus(property, name = "UUIDs")]
     fn uuids(&self) -> zbus::Result<Vec<String>>;
@@ -0,0 +1,22 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.AdvertisementMonitorManager1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait AdvertisementMonitorManager1 {
+    /// RegisterMonitor method
+    fn register_monitor(&self, application: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
+
+    /// UnregisterMonitor method
+    fn unregister_monitor(&self, application: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
+
+    /// SupportedFeatures property
+    #[zbus(property)]
+    fn supported_features(&self) -> zbus::Result<Vec<String>>;
+
+    /// SupportedMonitorTypes property
+    #[zbus(property)]
+    fn supported_monitor_types(&self) -> zbus::Result<Vec<String>>;
+}
@@ -0,0 +1,16 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.Battery1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait Battery1 {
+    /// Percentage property
+    #[zbus(property)]
+    fn percentage(&self) -> zbus::Result<u8>;
+
+    /// Source property
+    #[zbus(property)]
+    fn source(&self) -> zbus::Result<String>;
+}
@@ -0,0 +1,20 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.BatteryProviderManager1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait BatteryProviderManager1 {
+    /// RegisterBatteryProvider method
+    fn register_battery_provider(
+        &self,
+        provider: &zbus::zvariant::ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    /// UnregisterBatteryProvider method
+    fn unregister_battery_provider(
+        &self,
+        provider: &zbus::zvariant::ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+}
@@ -1,6 +1,10 @@
 use zbus::proxy;
 
-#[proxy(interface = "org.bluez.Device1", assume_defaults = true)]
+#[proxy(
+    interface = "org.bluez.Device1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
 pub trait Device1 {
     /// CancelPairing method
     fn cancel_pairing(&self) -> zbus::Result<()>;
@@ -40,7 +44,7 @@ pub trait Device1 {
 
     /// AdvertisingFlags property
     #[zbus(property)]
-    fn advertising_flags(&self) -> zbus::Result<Vec<bool>>;
+    fn advertising_flags(&self) -> zbus::Result<Vec<u8>>;
 
     /// Alias property
     #[zbus(property)]
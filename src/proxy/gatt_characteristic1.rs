@@ -0,0 +1,71 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.GattCharacteristic1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait GattCharacteristic1 {
+    /// AcquireNotify method
+    fn acquire_notify(
+        &self,
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<(zbus::zvariant::OwnedFd, u16)>;
+
+    /// AcquireWrite method
+    fn acquire_write(
+        &self,
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<(zbus::zvariant::OwnedFd, u16)>;
+
+    /// ReadValue method
+    fn read_value(
+        &self,
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<Vec<u8>>;
+
+    /// StartNotify method
+    fn start_notify(&self) -> zbus::Result<()>;
+
+    /// StopNotify method
+    fn stop_notify(&self) -> zbus::Result<()>;
+
+    /// WriteValue method
+    fn write_value(
+        &self,
+        value: &[u8],
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    /// Flags property
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<Vec<String>>;
+
+    /// MTU property
+    #[zbus(property, name = "MTU")]
+    fn mtu(&self) -> zbus::Result<u16>;
+
+    /// NotifyAcquired property
+    #[zbus(property)]
+    fn notify_acquired(&self) -> zbus::Result<bool>;
+
+    /// Notifying property
+    #[zbus(property)]
+    fn notifying(&self) -> zbus::Result<bool>;
+
+    /// Service property
+    #[zbus(property)]
+    fn service(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// UUID property
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> zbus::Result<String>;
+
+    /// Value property
+    #[zbus(property)]
+    fn value(&self) -> zbus::Result<Vec<u8>>;
+
+    /// WriteAcquired property
+    #[zbus(property)]
+    fn write_acquired(&self) -> zbus::Result<bool>;
+}
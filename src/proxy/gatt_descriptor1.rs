@@ -0,0 +1,37 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.GattDescriptor1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait GattDescriptor1 {
+    /// ReadValue method
+    fn read_value(
+        &self,
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<Vec<u8>>;
+
+    /// WriteValue method
+    fn write_value(
+        &self,
+        value: &[u8],
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    /// Characteristic property
+    #[zbus(property)]
+    fn characteristic(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// Flags property
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<Vec<String>>;
+
+    /// UUID property
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> zbus::Result<String>;
+
+    /// Value property
+    #[zbus(property)]
+    fn value(&self) -> zbus::Result<Vec<u8>>;
+}
@@ -0,0 +1,24 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.GattService1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait GattService1 {
+    /// Device property
+    #[zbus(property)]
+    fn device(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// Includes property
+    #[zbus(property)]
+    fn includes(&self) -> zbus::Result<Vec<zbus::zvariant::OwnedObjectPath>>;
+
+    /// Primary property
+    #[zbus(property)]
+    fn primary(&self) -> zbus::Result<bool>;
+
+    /// UUID property
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> zbus::Result<String>;
+}
@@ -0,0 +1,14 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.Input1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait Input1 {
+    /// ReconnectMode property
+    ///
+    /// One of "none", "host", "device" or "any"
+    #[zbus(property)]
+    fn reconnect_mode(&self) -> zbus::Result<String>;
+}
@@ -0,0 +1,45 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.Media1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait Media1 {
+    /// RegisterApplication method
+    fn register_application(
+        &self,
+        root: &zbus::zvariant::ObjectPath<'_>,
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    /// RegisterEndpoint method
+    fn register_endpoint(
+        &self,
+        endpoint: &zbus::zvariant::ObjectPath<'_>,
+        properties: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    /// RegisterPlayer method
+    fn register_player(
+        &self,
+        player: &zbus::zvariant::ObjectPath<'_>,
+        properties: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    /// UnregisterApplication method
+    fn unregister_application(
+        &self,
+        application: &zbus::zvariant::ObjectPath<'_>,
+    ) -> zbus::Result<()>;
+
+    /// UnregisterEndpoint method
+    fn unregister_endpoint(&self, endpoint: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
+
+    /// UnregisterPlayer method
+    fn unregister_player(&self, player: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
+
+    /// SupportedUUIDs property
+    #[zbus(property, name = "SupportedUUIDs")]
+    fn supported_uuids(&self) -> zbus::Result<Vec<String>>;
+}
@@ -0,0 +1,44 @@
+use zbus::proxy;
+
+/// Legacy AVRCP control interface, prefer `MediaPlayer1` where available
+#[proxy(
+    interface = "org.bluez.MediaControl1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait MediaControl1 {
+    /// FastForward method
+    fn fast_forward(&self) -> zbus::Result<()>;
+
+    /// Next method
+    fn next(&self) -> zbus::Result<()>;
+
+    /// Pause method
+    fn pause(&self) -> zbus::Result<()>;
+
+    /// Play method
+    fn play(&self) -> zbus::Result<()>;
+
+    /// Previous method
+    fn previous(&self) -> zbus::Result<()>;
+
+    /// Rewind method
+    fn rewind(&self) -> zbus::Result<()>;
+
+    /// Stop method
+    fn stop(&self) -> zbus::Result<()>;
+
+    /// VolumeDown method
+    fn volume_down(&self) -> zbus::Result<()>;
+
+    /// VolumeUp method
+    fn volume_up(&self) -> zbus::Result<()>;
+
+    /// Connected property
+    #[zbus(property)]
+    fn connected(&self) -> zbus::Result<bool>;
+
+    /// Player property
+    #[zbus(property)]
+    fn player(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+}
@@ -0,0 +1,41 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.MediaFolder1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait MediaFolder1 {
+    /// ChangeFolder method
+    fn change_folder(&self, folder: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
+
+    /// ListItems method
+    ///
+    /// Possible filter options: "Start", "End" and "Attributes"
+    fn list_items(
+        &self,
+        filter: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<
+        Vec<(
+            zbus::zvariant::OwnedObjectPath,
+            std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+        )>,
+    >;
+
+    /// Search method
+    ///
+    /// Returns the path of a new folder holding the search results
+    fn search(
+        &self,
+        value: &str,
+        filter: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// Name property
+    #[zbus(property)]
+    fn name(&self) -> zbus::Result<String>;
+
+    /// NumberOfItems property
+    #[zbus(property)]
+    fn number_of_items(&self) -> zbus::Result<u32>;
+}
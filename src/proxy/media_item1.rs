@@ -0,0 +1,41 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.MediaItem1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait MediaItem1 {
+    /// AddtoNowPlaying method
+    #[zbus(name = "AddtoNowPlaying")]
+    fn add_to_now_playing(&self) -> zbus::Result<()>;
+
+    /// Play method
+    fn play(&self) -> zbus::Result<()>;
+
+    /// FolderType property
+    #[zbus(property)]
+    fn folder_type(&self) -> zbus::Result<String>;
+
+    /// Metadata property
+    #[zbus(property)]
+    fn metadata(
+        &self,
+    ) -> zbus::Result<std::collections::HashMap<String, zbus::zvariant::OwnedValue>>;
+
+    /// Name property
+    #[zbus(property)]
+    fn name(&self) -> zbus::Result<String>;
+
+    /// Playable property
+    #[zbus(property)]
+    fn playable(&self) -> zbus::Result<bool>;
+
+    /// Player property
+    #[zbus(property)]
+    fn player(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// Type property
+    #[zbus(property)]
+    fn type_(&self) -> zbus::Result<String>;
+}
@@ -0,0 +1,57 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.MediaTransport1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait MediaTransport1 {
+    /// Acquire method
+    ///
+    /// Returns the transport fd with the read and write MTU
+    fn acquire(&self) -> zbus::Result<(zbus::zvariant::OwnedFd, u16, u16)>;
+
+    /// Release method
+    fn release(&self) -> zbus::Result<()>;
+
+    /// TryAcquire method
+    ///
+    /// Acquire the transport only if it is in "pending" state
+    fn try_acquire(&self) -> zbus::Result<(zbus::zvariant::OwnedFd, u16, u16)>;
+
+    /// Codec property
+    #[zbus(property)]
+    fn codec(&self) -> zbus::Result<u8>;
+
+    /// Configuration property
+    #[zbus(property)]
+    fn configuration(&self) -> zbus::Result<Vec<u8>>;
+
+    /// Delay property
+    #[zbus(property)]
+    fn delay(&self) -> zbus::Result<u16>;
+    #[zbus(property, name = "Delay")]
+    fn set_delay(&self, value: u16) -> zbus::Result<()>;
+
+    /// Device property
+    #[zbus(property)]
+    fn device(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// Endpoint property
+    #[zbus(property)]
+    fn endpoint(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// State property
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<String>;
+
+    /// UUID property
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> zbus::Result<String>;
+
+    /// Volume property
+    #[zbus(property)]
+    fn volume(&self) -> zbus::Result<u16>;
+    #[zbus(property, name = "Volume")]
+    fn set_volume(&self, value: u16) -> zbus::Result<()>;
+}
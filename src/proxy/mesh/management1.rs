@@ -0,0 +1,62 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.mesh.Management1",
+    default_service = "org.bluez.mesh",
+    assume_defaults = true
+)]
+pub trait Management1 {
+    /// AddNode method
+    fn add_node(
+        &self,
+        uuid: &[u8],
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    /// CreateAppKey method
+    fn create_app_key(&self, net_index: u16, app_index: u16) -> zbus::Result<()>;
+
+    /// CreateSubnet method
+    fn create_subnet(&self, net_index: u16) -> zbus::Result<()>;
+
+    /// DeleteAppKey method
+    fn delete_app_key(&self, app_index: u16) -> zbus::Result<()>;
+
+    /// DeleteRemoteNode method
+    fn delete_remote_node(&self, primary: u16, count: u8) -> zbus::Result<()>;
+
+    /// DeleteSubnet method
+    fn delete_subnet(&self, net_index: u16) -> zbus::Result<()>;
+
+    /// ExportKeys method
+    fn export_keys(
+        &self,
+    ) -> zbus::Result<std::collections::HashMap<String, zbus::zvariant::OwnedValue>>;
+
+    /// ImportAppKey method
+    fn import_app_key(&self, net_index: u16, app_index: u16, app_key: &[u8]) -> zbus::Result<()>;
+
+    /// ImportRemoteNode method
+    fn import_remote_node(&self, primary: u16, count: u8, device_key: &[u8]) -> zbus::Result<()>;
+
+    /// ImportSubnet method
+    fn import_subnet(&self, net_index: u16, net_key: &[u8]) -> zbus::Result<()>;
+
+    /// SetKeyPhase method
+    fn set_key_phase(&self, net_index: u16, phase: u8) -> zbus::Result<()>;
+
+    /// UnprovisionedScan method
+    fn unprovisioned_scan(
+        &self,
+        options: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<()>;
+
+    /// UnprovisionedScanCancel method
+    fn unprovisioned_scan_cancel(&self) -> zbus::Result<()>;
+
+    /// UpdateAppKey method
+    fn update_app_key(&self, app_index: u16) -> zbus::Result<()>;
+
+    /// UpdateSubnet method
+    fn update_subnet(&self, net_index: u16) -> zbus::Result<()>;
+}
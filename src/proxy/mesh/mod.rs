@@ -0,0 +1,4 @@
+//! Proxies for the bluez mesh daemon, `org.bluez.mesh`
+
+pub mod management1;
+pub mod network1;
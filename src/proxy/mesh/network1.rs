@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+use zbus::proxy;
+use zbus::zvariant::OwnedValue;
+
+/// Configuration of each element returned by `Attach`: the element index and
+/// its models with their options
+pub type NodeConfiguration = Vec<(u8, Vec<(u16, HashMap<String, OwnedValue>)>)>;
+
+#[proxy(
+    interface = "org.bluez.mesh.Network1",
+    default_service = "org.bluez.mesh",
+    default_path = "/org/bluez/mesh"
+)]
+pub trait Network1 {
+    /// Attach method
+    fn attach(
+        &self,
+        app_root: &zbus::zvariant::ObjectPath<'_>,
+        token: u64,
+    ) -> zbus::Result<(zbus::zvariant::OwnedObjectPath, NodeConfiguration)>;
+
+    /// Cancel method
+    fn cancel(&self) -> zbus::Result<()>;
+
+    /// CreateNetwork method
+    fn create_network(
+        &self,
+        app_root: &zbus::zvariant::ObjectPath<'_>,
+        uuid: &[u8],
+    ) -> zbus::Result<()>;
+
+    /// Import method
+    #[allow(clippy::too_many_arguments)]
+    fn import(
+        &self,
+        app_root: &zbus::zvariant::ObjectPath<'_>,
+        uuid: &[u8],
+        dev_key: &[u8],
+        net_key: &[u8],
+        net_index: u16,
+        flags: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+        iv_index: u32,
+        unicast: u16,
+    ) -> zbus::Result<()>;
+
+    /// Join method
+    fn join(&self, app_root: &zbus::zvariant::ObjectPath<'_>, uuid: &[u8]) -> zbus::Result<()>;
+
+    /// Leave method
+    fn leave(&self, token: u64) -> zbus::Result<()>;
+}
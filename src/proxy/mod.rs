@@ -1,7 +1,27 @@
 pub mod adapter1;
+pub mod advertisement_monitor_manager1;
 pub mod agent_manager1;
+pub mod battery1;
+pub mod battery_provider_manager1;
 pub mod device1;
+pub mod gatt_characteristic1;
+pub mod gatt_descriptor1;
 pub mod gatt_manager1;
+pub mod gatt_service1;
+pub mod input1;
 pub mod le_advertising_manager1;
+pub mod media1;
+pub mod media_control1;
+pub mod media_folder1;
+pub mod media_item1;
+pub mod media_player1;
+pub mod media_transport1;
+pub mod mesh;
+pub mod network1;
+pub mod obex;
 pub mod object_manager;
 pub mod profile_manager1;
+
+// Proxies generated by build.rs from xml/, for the interfaces without a
+// module above
+include!(concat!(env!("OUT_DIR"), "/proxies.rs"));
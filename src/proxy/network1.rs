@@ -0,0 +1,45 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.Network1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait Network1 {
+    /// Connect method
+    ///
+    /// `uuid` is one of "panu", "nap" or "gn", or the matching 128-bit UUID.
+    /// Returns the name of the created network interface.
+    fn connect(&self, uuid: &str) -> zbus::Result<String>;
+
+    /// Disconnect method
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    /// Connected property
+    #[zbus(property)]
+    fn connected(&self) -> zbus::Result<bool>;
+
+    /// Interface property
+    #[zbus(property)]
+    fn interface(&self) -> zbus::Result<String>;
+
+    /// UUID property
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> zbus::Result<String>;
+}
+
+#[proxy(
+    interface = "org.bluez.NetworkServer1",
+    default_service = "org.bluez",
+    assume_defaults = true
+)]
+pub trait NetworkServer1 {
+    /// Register method
+    ///
+    /// Register the server for `uuid` ("panu", "nap" or "gn") and attach
+    /// connections to the network `bridge`.
+    fn register(&self, uuid: &str, bridge: &str) -> zbus::Result<()>;
+
+    /// Unregister method
+    fn unregister(&self, uuid: &str) -> zbus::Result<()>;
+}
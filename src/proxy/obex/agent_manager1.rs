@@ -0,0 +1,14 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.obex.AgentManager1",
+    default_service = "org.bluez.obex",
+    default_path = "/org/bluez/obex"
+)]
+pub trait AgentManager1 {
+    /// RegisterAgent method
+    fn register_agent(&self, agent: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
+
+    /// UnregisterAgent method
+    fn unregister_agent(&self, agent: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
+}
@@ -0,0 +1,18 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.obex.Client1",
+    default_service = "org.bluez.obex",
+    default_path = "/org/bluez/obex"
+)]
+pub trait Client1 {
+    /// CreateSession method
+    fn create_session(
+        &self,
+        destination: &str,
+        args: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// RemoveSession method
+    fn remove_session(&self, session: &zbus::zvariant::ObjectPath<'_>) -> zbus::Result<()>;
+}
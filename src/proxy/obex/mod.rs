@@ -0,0 +1,8 @@
+//! Proxies for the bluez OBEX daemon, `org.bluez.obex` on the session bus
+
+pub mod agent_manager1;
+pub mod client1;
+pub mod object_push1;
+pub mod phonebook_access1;
+pub mod session1;
+pub mod transfer1;
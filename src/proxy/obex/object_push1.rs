@@ -0,0 +1,36 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.obex.ObjectPush1",
+    default_service = "org.bluez.obex",
+    assume_defaults = true
+)]
+pub trait ObjectPush1 {
+    /// ExchangeBusinessCards method
+    fn exchange_business_cards(
+        &self,
+        clientfile: &str,
+        targetfile: &str,
+    ) -> zbus::Result<(
+        zbus::zvariant::OwnedObjectPath,
+        std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+    )>;
+
+    /// PullBusinessCard method
+    fn pull_business_card(
+        &self,
+        targetfile: &str,
+    ) -> zbus::Result<(
+        zbus::zvariant::OwnedObjectPath,
+        std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+    )>;
+
+    /// SendFile method
+    fn send_file(
+        &self,
+        sourcefile: &str,
+    ) -> zbus::Result<(
+        zbus::zvariant::OwnedObjectPath,
+        std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+    )>;
+}
@@ -0,0 +1,75 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.obex.PhonebookAccess1",
+    default_service = "org.bluez.obex",
+    assume_defaults = true
+)]
+pub trait PhonebookAccess1 {
+    /// GetSize method
+    fn get_size(&self) -> zbus::Result<u16>;
+
+    /// List method
+    fn list(
+        &self,
+        filters: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<Vec<(String, String)>>;
+
+    /// ListFilterFields method
+    fn list_filter_fields(&self) -> zbus::Result<Vec<String>>;
+
+    /// Pull method
+    fn pull(
+        &self,
+        vcard: &str,
+        targetfile: &str,
+        filters: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<(
+        zbus::zvariant::OwnedObjectPath,
+        std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+    )>;
+
+    /// PullAll method
+    fn pull_all(
+        &self,
+        targetfile: &str,
+        filters: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<(
+        zbus::zvariant::OwnedObjectPath,
+        std::collections::HashMap<String, zbus::zvariant::OwnedValue>,
+    )>;
+
+    /// Search method
+    fn search(
+        &self,
+        field: &str,
+        value: &str,
+        filters: std::collections::HashMap<&str, zbus::zvariant::Value<'_>>,
+    ) -> zbus::Result<Vec<(String, String)>>;
+
+    /// Select method
+    fn select(&self, location: &str, phonebook: &str) -> zbus::Result<()>;
+
+    /// UpdateVersion method
+    fn update_version(&self) -> zbus::Result<()>;
+
+    /// DatabaseIdentifier property
+    #[zbus(property)]
+    fn database_identifier(&self) -> zbus::Result<String>;
+
+    /// FixedImageSize property
+    #[zbus(property)]
+    fn fixed_image_size(&self) -> zbus::Result<bool>;
+
+    /// Folder property
+    #[zbus(property)]
+    fn folder(&self) -> zbus::Result<String>;
+
+    /// PrimaryCounter property
+    #[zbus(property)]
+    fn primary_counter(&self) -> zbus::Result<String>;
+
+    /// SecondaryCounter property
+    #[zbus(property)]
+    fn secondary_counter(&self) -> zbus::Result<String>;
+}
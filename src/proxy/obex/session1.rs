@@ -0,0 +1,31 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.obex.Session1",
+    default_service = "org.bluez.obex",
+    assume_defaults = true
+)]
+pub trait Session1 {
+    /// GetCapabilities method
+    fn get_capabilities(&self) -> zbus::Result<String>;
+
+    /// Channel property
+    #[zbus(property)]
+    fn channel(&self) -> zbus::Result<u8>;
+
+    /// Destination property
+    #[zbus(property)]
+    fn destination(&self) -> zbus::Result<String>;
+
+    /// Root property
+    #[zbus(property)]
+    fn root(&self) -> zbus::Result<String>;
+
+    /// Source property
+    #[zbus(property)]
+    fn source(&self) -> zbus::Result<String>;
+
+    /// Target property
+    #[zbus(property)]
+    fn target(&self) -> zbus::Result<String>;
+}
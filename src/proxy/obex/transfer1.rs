@@ -0,0 +1,49 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.bluez.obex.Transfer1",
+    default_service = "org.bluez.obex",
+    assume_defaults = true
+)]
+pub trait Transfer1 {
+    /// Cancel method
+    fn cancel(&self) -> zbus::Result<()>;
+
+    /// Resume method
+    fn resume(&self) -> zbus::Result<()>;
+
+    /// Suspend method
+    fn suspend(&self) -> zbus::Result<()>;
+
+    /// Filename property
+    #[zbus(property)]
+    fn filename(&self) -> zbus::Result<String>;
+
+    /// Name property
+    #[zbus(property)]
+    fn name(&self) -> zbus::Result<String>;
+
+    /// Session property
+    #[zbus(property)]
+    fn session(&self) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    /// Size property
+    #[zbus(property)]
+    fn size(&self) -> zbus::Result<u64>;
+
+    /// Status property
+    #[zbus(property)]
+    fn status(&self) -> zbus::Result<String>;
+
+    /// Time property
+    #[zbus(property)]
+    fn time(&self) -> zbus::Result<u64>;
+
+    /// Transferred property
+    #[zbus(property)]
+    fn transferred(&self) -> zbus::Result<u64>;
+
+    /// Type property
+    #[zbus(property, name = "Type")]
+    fn type_(&self) -> zbus::Result<String>;
+}
@@ -1,8 +1,15 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 
 use serde::Deserialize;
+use uuid::Uuid;
+use zbus::fdo::InterfacesRemoved;
 use zbus::zvariant::{OwnedObjectPath, OwnedValue, Type};
 
+use crate::address::BDAddr;
+use crate::company_id::CompanyId;
+use crate::interface::gatt::{CharacteristicFlags, GattDescriptorFlags};
+
 #[derive(Debug, Type, Deserialize)]
 pub struct ManagedBluezObject {
     pub path: OwnedObjectPath,
@@ -18,24 +25,327 @@ impl ManagedBluezObject {
         }
         None
     }
+
+    pub fn adapter_data(&self) -> Option<BluezAdapter> {
+        self.data.get("org.bluez.Adapter1").map(BluezAdapter::from)
+    }
+
+    pub fn battery_data(&self) -> Option<BluezBattery> {
+        self.data.get("org.bluez.Battery1").map(BluezBattery::from)
+    }
+
+    pub fn gatt_service_data(&self) -> Option<BluezGattService> {
+        self.data
+            .get("org.bluez.GattService1")
+            .map(BluezGattService::from)
+    }
+
+    pub fn gatt_characteristic_data(&self) -> Option<BluezGattCharacteristic> {
+        self.data
+            .get("org.bluez.GattCharacteristic1")
+            .map(BluezGattCharacteristic::from)
+    }
+
+    pub fn gatt_descriptor_data(&self) -> Option<BluezGattDescriptor> {
+        self.data
+            .get("org.bluez.GattDescriptor1")
+            .map(BluezGattDescriptor::from)
+    }
+}
+
+/// Parse a list of UUID strings, skipping invalid entries
+pub(crate) fn uuid_list(value: Option<&OwnedValue>) -> Vec<Uuid> {
+    value
+        .and_then(|v| Vec::<String>::try_from(v.try_clone().ok()?).ok())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|uuid| Uuid::parse_str(uuid).ok())
+        .collect()
+}
+
+/// Parse the `UUID` property, nil if missing or invalid
+fn uuid(value: Option<&OwnedValue>) -> Uuid {
+    value
+        .and_then(|v| <&str>::try_from(v).ok())
+        .and_then(|v| Uuid::parse_str(v).ok())
+        .unwrap_or_default()
+}
+
+fn object_path(value: Option<&OwnedValue>) -> OwnedObjectPath {
+    value
+        .and_then(|v| OwnedObjectPath::try_from(v.try_clone().ok()?).ok())
+        .unwrap_or_default()
+}
+
+/// Parse a `Flags` property, skipping flags this crate doesn't know
+fn flag_list<T: FromStr>(value: Option<&OwnedValue>) -> Vec<T> {
+    value
+        .and_then(|v| Vec::<String>::try_from(v.try_clone().ok()?).ok())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|flag| T::from_str(flag).ok())
+        .collect()
+}
+
+fn bytes(value: Option<&OwnedValue>) -> Vec<u8> {
+    value
+        .and_then(|v| Vec::<u8>::try_from(v.try_clone().ok()?).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BluezAdapter {
+    address: String,
+    name: String,
+    alias: String,
+    powered: bool,
+    discoverable: bool,
+    discovering: bool,
+    uuids: Vec<Uuid>,
+    class: u32,
+}
+
+impl BluezAdapter {
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    pub fn powered(&self) -> bool {
+        self.powered
+    }
+
+    pub fn discoverable(&self) -> bool {
+        self.discoverable
+    }
+
+    pub fn discovering(&self) -> bool {
+        self.discovering
+    }
+
+    pub fn uuids(&self) -> &[Uuid] {
+        &self.uuids
+    }
+
+    pub fn class(&self) -> u32 {
+        self.class
+    }
 }
 
-#[derive(Debug, Default, Type, Deserialize)]
-#[serde(default)]
+impl From<&HashMap<String, OwnedValue>> for BluezAdapter {
+    fn from(value: &HashMap<String, OwnedValue>) -> Self {
+        Self {
+            address: value
+                .get("Address")
+                .map(|b| <&str>::try_from(b).unwrap_or_default())
+                .unwrap_or_default()
+                .to_string(),
+            name: value
+                .get("Name")
+                .map(|b| <&str>::try_from(b).unwrap_or_default())
+                .unwrap_or_default()
+                .to_string(),
+            alias: value
+                .get("Alias")
+                .map(|b| <&str>::try_from(b).unwrap_or_default())
+                .unwrap_or_default()
+                .to_string(),
+            powered: value
+                .get("Powered")
+                .map(|b| bool::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            discoverable: value
+                .get("Discoverable")
+                .map(|b| bool::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            discovering: value
+                .get("Discovering")
+                .map(|b| bool::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            uuids: uuid_list(value.get("UUIDs")),
+            class: value
+                .get("Class")
+                .map(|b| u32::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct BluezDevice {
     trusted: bool,
+    name: String,
     alias: String,
     address: String,
     address_type: String,
+    icon: String,
+    class: u32,
+    appearance: u16,
+    uuids: Vec<Uuid>,
     rssi: i16,
+    tx_power: i16,
+    manufacturer_data: HashMap<u16, Vec<u8>>,
+    service_data: HashMap<Uuid, Vec<u8>>,
+    modalias: String,
     legacy_pairing: bool,
     blocked: bool,
     connected: bool,
+    wake_allowed: bool,
     adapter: OwnedObjectPath,
     service_resolved: bool,
     bonded: bool,
     paired: bool,
-    // uuids:
+}
+
+impl BluezDevice {
+    pub fn trusted(&self) -> bool {
+        self.trusted
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn alias(&self) -> &str {
+        &self.alias
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// The parsed `address()`, `None` if bluez sent no valid address
+    pub fn bd_addr(&self) -> Option<BDAddr> {
+        self.address.parse().ok()
+    }
+
+    pub fn address_type(&self) -> &str {
+        &self.address_type
+    }
+
+    pub fn icon(&self) -> &str {
+        &self.icon
+    }
+
+    pub fn class(&self) -> u32 {
+        self.class
+    }
+
+    pub fn appearance(&self) -> u16 {
+        self.appearance
+    }
+
+    pub fn uuids(&self) -> &[Uuid] {
+        &self.uuids
+    }
+
+    pub fn rssi(&self) -> i16 {
+        self.rssi
+    }
+
+    pub fn tx_power(&self) -> i16 {
+        self.tx_power
+    }
+
+    /// Manufacturer specific advertising data keyed by company identifier
+    pub fn manufacturer_data(&self) -> &HashMap<u16, Vec<u8>> {
+        &self.manufacturer_data
+    }
+
+    /// Manufacturer specific advertising data from `company`
+    pub fn manufacturer_data_from(&self, company: CompanyId) -> Option<&[u8]> {
+        self.manufacturer_data
+            .get(&company.id())
+            .map(|data| data.as_slice())
+    }
+
+    /// Service advertising data keyed by service UUID
+    pub fn service_data(&self) -> &HashMap<Uuid, Vec<u8>> {
+        &self.service_data
+    }
+
+    pub fn modalias(&self) -> &str {
+        &self.modalias
+    }
+
+    pub fn legacy_pairing(&self) -> bool {
+        self.legacy_pairing
+    }
+
+    pub fn blocked(&self) -> bool {
+        self.blocked
+    }
+
+    pub fn connected(&self) -> bool {
+        self.connected
+    }
+
+    pub fn wake_allowed(&self) -> bool {
+        self.wake_allowed
+    }
+
+    pub fn adapter(&self) -> &OwnedObjectPath {
+        &self.adapter
+    }
+
+    pub fn services_resolved(&self) -> bool {
+        self.service_resolved
+    }
+
+    pub fn bonded(&self) -> bool {
+        self.bonded
+    }
+
+    pub fn paired(&self) -> bool {
+        self.paired
+    }
+}
+
+/// Parse a dictionary of byte arrays in variants, such as `ServiceData`,
+/// skipping entries that aren't byte arrays
+pub(crate) fn byte_map<K>(value: Option<&OwnedValue>) -> HashMap<K, Vec<u8>>
+where
+    K: std::hash::Hash + Eq,
+    HashMap<K, OwnedValue>: TryFrom<OwnedValue>,
+{
+    value
+        .and_then(|v| HashMap::<K, OwnedValue>::try_from(v.try_clone().ok()?).ok())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(key, value)| Some((key, Vec::<u8>::try_from(value).ok()?)))
+        .collect()
+}
+
+/// Convert the payloads of `Device1::manufacturer_data()` to byte arrays,
+/// failing on the first one that isn't
+pub fn manufacturer_data(
+    data: HashMap<u16, OwnedValue>,
+) -> Result<HashMap<u16, Vec<u8>>, zbus::Error> {
+    data.into_iter()
+        .map(|(company, value)| {
+            let value = Vec::<u8>::try_from(value).map_err(|e| {
+                zbus::Error::Failure(format!(
+                    "ManufacturerData for company {company:#06x} is not a byte array: {e}"
+                ))
+            })?;
+            Ok((company, value))
+        })
+        .collect()
+}
+
+/// Parse `ServiceData`, skipping entries whose key isn't a UUID
+pub(crate) fn service_data_map(value: Option<&OwnedValue>) -> HashMap<Uuid, Vec<u8>> {
+    byte_map::<String>(value)
+        .into_iter()
+        .filter_map(|(uuid, data)| Some((Uuid::parse_str(&uuid).ok()?, data)))
+        .collect()
 }
 
 impl From<&HashMap<String, OwnedValue>> for BluezDevice {
@@ -45,6 +355,11 @@ impl From<&HashMap<String, OwnedValue>> for BluezDevice {
                 .get("Trusted")
                 .map(|b| bool::try_from(b).unwrap_or_default())
                 .unwrap_or_default(),
+            name: value
+                .get("Name")
+                .map(|b| <&str>::try_from(b).unwrap_or_default())
+                .unwrap_or_default()
+                .to_string(),
             alias: value
                 .get("Alias")
                 .map(|b| <&str>::try_from(b).unwrap_or_default())
@@ -60,10 +375,35 @@ impl From<&HashMap<String, OwnedValue>> for BluezDevice {
                 .map(|b| <&str>::try_from(b).unwrap_or_default())
                 .unwrap_or_default()
                 .to_string(),
+            icon: value
+                .get("Icon")
+                .map(|b| <&str>::try_from(b).unwrap_or_default())
+                .unwrap_or_default()
+                .to_string(),
+            class: value
+                .get("Class")
+                .map(|b| u32::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            appearance: value
+                .get("Appearance")
+                .map(|b| u16::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            uuids: uuid_list(value.get("UUIDs")),
             rssi: value
                 .get("RSSI")
                 .map(|b| i16::try_from(b).unwrap_or_default())
                 .unwrap_or_default(),
+            tx_power: value
+                .get("TxPower")
+                .map(|b| i16::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            manufacturer_data: byte_map(value.get("ManufacturerData")),
+            service_data: service_data_map(value.get("ServiceData")),
+            modalias: value
+                .get("Modalias")
+                .map(|b| <&str>::try_from(b).unwrap_or_default())
+                .unwrap_or_default()
+                .to_string(),
             legacy_pairing: value
                 .get("LegacyPairing")
                 .map(|b| bool::try_from(b).unwrap_or_default())
@@ -76,6 +416,10 @@ impl From<&HashMap<String, OwnedValue>> for BluezDevice {
                 .get("Connected")
                 .map(|b| bool::try_from(b).unwrap_or_default())
                 .unwrap_or_default(),
+            wake_allowed: value
+                .get("WakeAllowed")
+                .map(|b| bool::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
             adapter: value
                 .get("Adapter")
                 .map(|b| OwnedObjectPath::try_from(b.clone()).unwrap_or_default())
@@ -95,3 +439,292 @@ impl From<&HashMap<String, OwnedValue>> for BluezDevice {
         }
     }
 }
+
+#[derive(Debug, Default, Clone)]
+pub struct BluezBattery {
+    percentage: u8,
+    source: String,
+}
+
+impl BluezBattery {
+    pub fn percentage(&self) -> u8 {
+        self.percentage
+    }
+
+    /// Where the level is reported from, e.g. "GATT Battery Service" or
+    /// "HFP Service"
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl From<&HashMap<String, OwnedValue>> for BluezBattery {
+    fn from(value: &HashMap<String, OwnedValue>) -> Self {
+        Self {
+            percentage: value
+                .get("Percentage")
+                .map(|b| u8::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            source: value
+                .get("Source")
+                .map(|b| <&str>::try_from(b).unwrap_or_default())
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BluezGattService {
+    uuid: Uuid,
+    primary: bool,
+    device: OwnedObjectPath,
+    includes: Vec<OwnedObjectPath>,
+    handle: u16,
+}
+
+impl BluezGattService {
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn primary(&self) -> bool {
+        self.primary
+    }
+
+    pub fn device(&self) -> &OwnedObjectPath {
+        &self.device
+    }
+
+    pub fn includes(&self) -> &[OwnedObjectPath] {
+        &self.includes
+    }
+
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+}
+
+impl From<&HashMap<String, OwnedValue>> for BluezGattService {
+    fn from(value: &HashMap<String, OwnedValue>) -> Self {
+        Self {
+            uuid: uuid(value.get("UUID")),
+            primary: value
+                .get("Primary")
+                .map(|b| bool::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            device: object_path(value.get("Device")),
+            includes: value
+                .get("Includes")
+                .and_then(|v| Vec::<OwnedObjectPath>::try_from(v.try_clone().ok()?).ok())
+                .unwrap_or_default(),
+            handle: value
+                .get("Handle")
+                .map(|b| u16::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BluezGattCharacteristic {
+    uuid: Uuid,
+    service: OwnedObjectPath,
+    value: Vec<u8>,
+    flags: Vec<CharacteristicFlags>,
+    notifying: bool,
+    write_acquired: bool,
+    notify_acquired: bool,
+    mtu: u16,
+    handle: u16,
+}
+
+impl BluezGattCharacteristic {
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn service(&self) -> &OwnedObjectPath {
+        &self.service
+    }
+
+    /// The cached value, only updated by bluez on reads and notifications
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    pub fn flags(&self) -> &[CharacteristicFlags] {
+        &self.flags
+    }
+
+    pub fn notifying(&self) -> bool {
+        self.notifying
+    }
+
+    pub fn write_acquired(&self) -> bool {
+        self.write_acquired
+    }
+
+    pub fn notify_acquired(&self) -> bool {
+        self.notify_acquired
+    }
+
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+}
+
+impl From<&HashMap<String, OwnedValue>> for BluezGattCharacteristic {
+    fn from(value: &HashMap<String, OwnedValue>) -> Self {
+        Self {
+            uuid: uuid(value.get("UUID")),
+            service: object_path(value.get("Service")),
+            value: bytes(value.get("Value")),
+            flags: flag_list(value.get("Flags")),
+            notifying: value
+                .get("Notifying")
+                .map(|b| bool::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            write_acquired: value
+                .get("WriteAcquired")
+                .map(|b| bool::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            notify_acquired: value
+                .get("NotifyAcquired")
+                .map(|b| bool::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            mtu: value
+                .get("MTU")
+                .map(|b| u16::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+            handle: value
+                .get("Handle")
+                .map(|b| u16::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BluezGattDescriptor {
+    uuid: Uuid,
+    characteristic: OwnedObjectPath,
+    value: Vec<u8>,
+    flags: Vec<GattDescriptorFlags>,
+    handle: u16,
+}
+
+impl BluezGattDescriptor {
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn characteristic(&self) -> &OwnedObjectPath {
+        &self.characteristic
+    }
+
+    /// The cached value, only updated by bluez on reads
+    pub fn value(&self) -> &[u8] {
+        &self.value
+    }
+
+    pub fn flags(&self) -> &[GattDescriptorFlags] {
+        &self.flags
+    }
+
+    pub fn handle(&self) -> u16 {
+        self.handle
+    }
+}
+
+impl From<&HashMap<String, OwnedValue>> for BluezGattDescriptor {
+    fn from(value: &HashMap<String, OwnedValue>) -> Self {
+        Self {
+            uuid: uuid(value.get("UUID")),
+            characteristic: object_path(value.get("Characteristic")),
+            value: bytes(value.get("Value")),
+            flags: flag_list(value.get("Flags")),
+            handle: value
+                .get("Handle")
+                .map(|b| u16::try_from(b).unwrap_or_default())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Interfaces bluez exports on its objects, as named in `InterfacesAdded` and
+/// `InterfacesRemoved`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KnownInterface {
+    Adapter1,
+    Device1,
+    Battery1,
+    GattService1,
+    GattCharacteristic1,
+    GattDescriptor1,
+    MediaTransport1,
+    MediaControl1,
+    MediaPlayer1,
+    Input1,
+    Network1,
+    /// Any other interface, such as the freedesktop standard interfaces
+    Other(String),
+}
+
+impl KnownInterface {
+    pub fn as_str(&self) -> &str {
+        match self {
+            KnownInterface::Adapter1 => "org.bluez.Adapter1",
+            KnownInterface::Device1 => "org.bluez.Device1",
+            KnownInterface::Battery1 => "org.bluez.Battery1",
+            KnownInterface::GattService1 => "org.bluez.GattService1",
+            KnownInterface::GattCharacteristic1 => "org.bluez.GattCharacteristic1",
+            KnownInterface::GattDescriptor1 => "org.bluez.GattDescriptor1",
+            KnownInterface::MediaTransport1 => "org.bluez.MediaTransport1",
+            KnownInterface::MediaControl1 => "org.bluez.MediaControl1",
+            KnownInterface::MediaPlayer1 => "org.bluez.MediaPlayer1",
+            KnownInterface::Input1 => "org.bluez.Input1",
+            KnownInterface::Network1 => "org.bluez.Network1",
+            KnownInterface::Other(name) => name,
+        }
+    }
+}
+
+impl From<&str> for KnownInterface {
+    fn from(name: &str) -> Self {
+        match name {
+            "org.bluez.Adapter1" => KnownInterface::Adapter1,
+            "org.bluez.Device1" => KnownInterface::Device1,
+            "org.bluez.Battery1" => KnownInterface::Battery1,
+            "org.bluez.GattService1" => KnownInterface::GattService1,
+            "org.bluez.GattCharacteristic1" => KnownInterface::GattCharacteristic1,
+            "org.bluez.GattDescriptor1" => KnownInterface::GattDescriptor1,
+            "org.bluez.MediaTransport1" => KnownInterface::MediaTransport1,
+            "org.bluez.MediaControl1" => KnownInterface::MediaControl1,
+            "org.bluez.MediaPlayer1" => KnownInterface::MediaPlayer1,
+            "org.bluez.Input1" => KnownInterface::Input1,
+            "org.bluez.Network1" => KnownInterface::Network1,
+            other => KnownInterface::Other(other.to_owned()),
+        }
+    }
+}
+
+/// The object path and removed interfaces of an `InterfacesRemoved` signal.
+///
+/// An object is gone entirely once its main interface, e.g.
+/// `KnownInterface::Device1`, is removed.
+pub fn interfaces_removed(
+    signal: &InterfacesRemoved,
+) -> Result<(OwnedObjectPath, Vec<KnownInterface>), zbus::Error> {
+    let args = signal.args()?;
+    let interfaces = args
+        .interfaces
+        .iter()
+        .map(|name| KnownInterface::from(name.as_str()))
+        .collect();
+    Ok((args.object_path.to_owned().into(), interfaces))
+}
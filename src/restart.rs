@@ -0,0 +1,276 @@
+//! # Surviving bluetoothd restarts
+//!
+//! When bluetoothd restarts it forgets every agent, profile, GATT
+//! application and advert registered with it, while the objects stay
+//! exported on our connection. `RestartWatcher` follows the `org.bluez` name
+//! owner and registers the tracked handles again once the daemon is back.
+//!
+//! ```ignore
+//! let mut watcher = RestartWatcher::new(&connection).await?;
+//! watcher.track(application.registration());
+//! watcher.track(advertisement.registration());
+//! while let Some(event) = watcher.next().await {
+//!     println!("{event:?}");
+//! }
+//! ```
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_channel::{mpsc, oneshot};
+use futures_lite::{future, Stream, StreamExt};
+use log::{debug, info, warn};
+use zbus::fdo::{DBusProxy, NameOwnerChangedStream};
+use zbus::names::OwnedBusName;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::Connection;
+
+use crate::bus::BluezBus;
+use crate::interface::{AgentCapability, ProfileOptions};
+use crate::proxy::agent_manager1::AgentManager1Proxy;
+use crate::proxy::gatt_manager1::GattManager1Proxy;
+use crate::proxy::le_advertising_manager1::LEAdvertisingManager1Proxy;
+use crate::proxy::profile_manager1::ProfileManager1Proxy;
+use crate::rt::sleep;
+
+/// Attempts at registering again, the adapters may not be exported yet right
+/// after the name is taken
+const REGISTER_ATTEMPTS: u32 = 5;
+const REGISTER_BACKOFF: Duration = Duration::from_millis(250);
+
+/// What a handle registered with bluez, enough to repeat the call
+#[derive(Debug)]
+pub(crate) enum Registered {
+    Agent {
+        path: OwnedObjectPath,
+        capability: AgentCapability,
+        default: bool,
+    },
+    Profile {
+        path: OwnedObjectPath,
+        uuid: String,
+        options: ProfileOptions,
+    },
+    Application {
+        adapter_path: OwnedObjectPath,
+        path: OwnedObjectPath,
+    },
+    Advertisement {
+        adapter_path: OwnedObjectPath,
+        path: OwnedObjectPath,
+    },
+}
+
+impl Registered {
+    fn path(&self) -> &OwnedObjectPath {
+        match self {
+            Self::Agent { path, .. }
+            | Self::Profile { path, .. }
+            | Self::Application { path, .. }
+            | Self::Advertisement { path, .. } => path,
+        }
+    }
+
+    /// Agents first so pairing works by the time anything is advertised
+    fn order(&self) -> u8 {
+        match self {
+            Self::Agent { .. } => 0,
+            Self::Profile { .. } => 1,
+            Self::Application { .. } => 2,
+            Self::Advertisement { .. } => 3,
+        }
+    }
+
+    async fn register(
+        &self,
+        connection: &Connection,
+        destination: &OwnedBusName,
+    ) -> Result<(), zbus::Error> {
+        match self {
+            Self::Agent {
+                path,
+                capability,
+                default,
+            } => {
+                let proxy = AgentManager1Proxy::builder(connection)
+                    .destination(destination.clone())?
+                    .path("/org/bluez")?
+                    .build()
+                    .await?;
+                proxy.register_agent(path, capability.into()).await?;
+                if *default {
+                    proxy.request_default_agent(path).await?;
+                }
+                Ok(())
+            }
+            Self::Profile {
+                path,
+                uuid,
+                options,
+            } => {
+                ProfileManager1Proxy::builder(connection)
+                    .destination(destination.clone())?
+                    .path("/org/bluez")?
+                    .build()
+                    .await?
+                    .register_profile(path, uuid, options.to_map())
+                    .await
+            }
+            Self::Application { adapter_path, path } => {
+                GattManager1Proxy::builder(connection)
+                    .destination(destination.clone())?
+                    .path(adapter_path.clone())?
+                    .build()
+                    .await?
+                    .register_application(path, Default::default())
+                    .await
+            }
+            Self::Advertisement { adapter_path, path } => {
+                LEAdvertisingManager1Proxy::builder(connection)
+                    .destination(destination.clone())?
+                    .path(adapter_path.clone())?
+                    .build()
+                    .await?
+                    .register_advertisement(path, Default::default())
+                    .await
+            }
+        }
+    }
+}
+
+/// A live registration to repeat after a restart, from the `registration()`
+/// of a handle. It lapses once the handle is unregistered or dropped.
+#[derive(Debug, Clone)]
+pub struct Registration(Weak<Registered>);
+
+impl Registration {
+    pub(crate) fn new(registered: &Arc<Registered>) -> Self {
+        Self(Arc::downgrade(registered))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RestartEvent {
+    /// bluetoothd left the bus
+    ServiceStopped,
+    /// bluetoothd is back and the tracked handles were registered again
+    ServiceRestarted,
+    /// Registering the object at `path` again failed
+    RegisterFailed {
+        path: OwnedObjectPath,
+        error: zbus::Error,
+    },
+}
+
+/// Stream of `RestartEvent`s. The watch stops when this is dropped.
+pub struct RestartWatcher {
+    registrations: Arc<Mutex<Vec<Registration>>>,
+    events: mpsc::UnboundedReceiver<RestartEvent>,
+    _stop: oneshot::Sender<()>,
+}
+
+impl RestartWatcher {
+    /// Watch the owner of the bus name of `bus`, `org.bluez` by default
+    pub async fn new(bus: impl Into<BluezBus>) -> Result<Self, zbus::Error> {
+        let bus = bus.into();
+        let connection = bus.connection();
+        let owner_changed = DBusProxy::new(connection)
+            .await?
+            .receive_name_owner_changed_with_args(&[(0, bus.destination().as_str())])
+            .await?;
+        let registrations = Arc::new(Mutex::new(Vec::new()));
+        let (sender, events) = mpsc::unbounded();
+        let (stop, stopped) = oneshot::channel::<()>();
+        connection
+            .executor()
+            .spawn(
+                future::or(
+                    watch(bus.clone(), owner_changed, registrations.clone(), sender),
+                    async {
+                        stopped.await.ok();
+                    },
+                ),
+                "bluez restart watcher",
+            )
+            .detach();
+        Ok(Self {
+            registrations,
+            events,
+            _stop: stop,
+        })
+    }
+
+    /// Register `registration` again whenever bluetoothd restarts
+    pub fn track(&self, registration: Registration) {
+        if let Ok(mut registrations) = self.registrations.lock() {
+            registrations.retain(|r| r.0.strong_count() > 0);
+            registrations.push(registration);
+        }
+    }
+}
+
+impl Stream for RestartWatcher {
+    type Item = RestartEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+async fn watch(
+    bus: BluezBus,
+    mut owner_changed: NameOwnerChangedStream,
+    registrations: Arc<Mutex<Vec<Registration>>>,
+    events: mpsc::UnboundedSender<RestartEvent>,
+) {
+    while let Some(signal) = owner_changed.next().await {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        if args.new_owner().is_none() {
+            info!("bluetoothd left the bus");
+            events.unbounded_send(RestartEvent::ServiceStopped).ok();
+            continue;
+        }
+        info!("bluetoothd restarted, registering again");
+
+        let mut live: Vec<Arc<Registered>> = registrations
+            .lock()
+            .map(|registrations| registrations.iter().filter_map(|r| r.0.upgrade()).collect())
+            .unwrap_or_default();
+        live.sort_by_key(|registered| registered.order());
+        for registered in live {
+            if let Err(error) = register(&bus, &registered).await {
+                warn!("{}: registering again failed: {error}", registered.path());
+                events
+                    .unbounded_send(RestartEvent::RegisterFailed {
+                        path: registered.path().clone(),
+                        error,
+                    })
+                    .ok();
+            }
+        }
+        events.unbounded_send(RestartEvent::ServiceRestarted).ok();
+    }
+}
+
+async fn register(bus: &BluezBus, registered: &Registered) -> Result<(), zbus::Error> {
+    let mut backoff = REGISTER_BACKOFF;
+    let mut attempt = 1;
+    loop {
+        match registered
+            .register(bus.connection(), bus.destination())
+            .await
+        {
+            Err(err) if attempt < REGISTER_ATTEMPTS => {
+                debug!("{}: register attempt {attempt}: {err}", registered.path());
+                sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
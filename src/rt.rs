@@ -0,0 +1,108 @@
+//! # Runtime glue
+//!
+//! The async API runs on async-io by default, or on tokio with the `tokio`
+//! feature. Like in zbus, tokio wins when both features are enabled, so the
+//! two reactors are never mixed. The few places that need timers or async
+//! sockets go through here so the rest of the crate doesn't care which one
+//! is in use.
+
+use std::io;
+use std::os::unix::net::UnixStream;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_lite::{AsyncRead, AsyncWrite};
+
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(not(feature = "tokio"))]
+    async_io::Timer::after(duration).await;
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep(duration).await;
+}
+
+/// A nonblocking unix socket registered with the runtime's reactor
+pub(crate) struct AsyncStream {
+    #[cfg(not(feature = "tokio"))]
+    inner: async_io::Async<UnixStream>,
+    #[cfg(feature = "tokio")]
+    inner: tokio::net::UnixStream,
+}
+
+impl AsyncStream {
+    pub(crate) fn new(stream: UnixStream) -> io::Result<Self> {
+        #[cfg(not(feature = "tokio"))]
+        let inner = async_io::Async::new(stream)?;
+        #[cfg(feature = "tokio")]
+        let inner = {
+            stream.set_nonblocking(true)?;
+            tokio::net::UnixStream::from_std(stream)?
+        };
+        Ok(Self { inner })
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+impl AsyncRead for AsyncStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+#[cfg(not(feature = "tokio"))]
+impl AsyncWrite for AsyncStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncRead for AsyncStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut buf = tokio::io::ReadBuf::new(buf);
+        match tokio::io::AsyncRead::poll_read(Pin::new(&mut self.inner), cx, &mut buf) {
+            Poll::Ready(Ok(())) => Poll::Ready(Ok(buf.filled().len())),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncWrite for AsyncStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(Pin::new(&mut self.inner), cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(Pin::new(&mut self.inner), cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_shutdown(Pin::new(&mut self.inner), cx)
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use zbus::message::Header;
+use zbus::object_server::{ObjectServer, SignalEmitter};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{fdo, interface};
+
+use super::MockDevice;
+
+/// `org.bluez.Adapter1` of a `MockBluez` adapter
+#[derive(Debug)]
+pub struct MockAdapter {
+    pub(super) address: String,
+    pub(super) name: String,
+    pub(super) alias: String,
+    pub(super) powered: bool,
+    pub(super) discoverable: bool,
+    pub(super) pairable: bool,
+    pub(super) discovering: bool,
+    /// The last filter passed to `SetDiscoveryFilter`
+    pub(super) discovery_filter: HashMap<String, OwnedValue>,
+}
+
+impl MockAdapter {
+    pub(super) fn new(name: &str, address: &str) -> Self {
+        Self {
+            address: address.to_owned(),
+            name: name.to_owned(),
+            alias: name.to_owned(),
+            powered: true,
+            discoverable: false,
+            pairable: true,
+            discovering: false,
+            discovery_filter: HashMap::new(),
+        }
+    }
+}
+
+#[interface(interface = "org.bluez.Adapter1")]
+impl MockAdapter {
+    async fn start_discovery(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if !self.powered {
+            return Err(fdo::Error::Failed("Not Ready".to_owned()));
+        }
+        self.discovering = true;
+        self.discovering_changed(&emitter).await?;
+        Ok(())
+    }
+
+    async fn stop_discovery(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        self.discovering = false;
+        self.discovering_changed(&emitter).await?;
+        Ok(())
+    }
+
+    fn set_discovery_filter(&mut self, properties: HashMap<&str, Value<'_>>) -> fdo::Result<()> {
+        self.discovery_filter = properties
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_owned(), value.try_to_owned().ok()?)))
+            .collect();
+        Ok(())
+    }
+
+    fn get_discovery_filters(&self) -> Vec<String> {
+        [
+            "UUIDs", "RSSI", "Pathloss", "Transport", "DuplicateData", "Discoverable",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    async fn remove_device(
+        &self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(object_server)] server: &ObjectServer,
+        device: ObjectPath<'_>,
+    ) -> fdo::Result<()> {
+        let adapter = header.path().map(|path| path.as_str()).unwrap_or_default();
+        if !device.as_str().starts_with(&format!("{adapter}/")) {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "{device} is not a device of {adapter}"
+            )));
+        }
+        if !server.remove::<MockDevice, _>(&device).await? {
+            return Err(fdo::Error::Failed(format!("{device} does not exist")));
+        }
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    #[zbus(property)]
+    fn address_type(&self) -> String {
+        "public".to_owned()
+    }
+
+    #[zbus(property)]
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    #[zbus(property)]
+    fn alias(&self) -> String {
+        self.alias.clone()
+    }
+
+    #[zbus(property)]
+    fn set_alias(&mut self, alias: String) {
+        self.alias = alias;
+    }
+
+    #[zbus(property)]
+    fn powered(&self) -> bool {
+        self.powered
+    }
+
+    #[zbus(property)]
+    fn set_powered(&mut self, powered: bool) {
+        self.powered = powered;
+    }
+
+    #[zbus(property)]
+    fn discoverable(&self) -> bool {
+        self.discoverable
+    }
+
+    #[zbus(property)]
+    fn set_discoverable(&mut self, discoverable: bool) {
+        self.discoverable = discoverable;
+    }
+
+    #[zbus(property)]
+    fn pairable(&self) -> bool {
+        self.pairable
+    }
+
+    #[zbus(property)]
+    fn set_pairable(&mut self, pairable: bool) {
+        self.pairable = pairable;
+    }
+
+    #[zbus(property)]
+    fn discovering(&self) -> bool {
+        self.discovering
+    }
+
+    #[zbus(property, name = "UUIDs")]
+    fn uuids(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn class(&self) -> u32 {
+        0
+    }
+
+    /// The modalias of bluez 5.72
+    #[zbus(property)]
+    fn modalias(&self) -> String {
+        "usb:v1D6Bp0246d0548".to_owned()
+    }
+
+    #[zbus(property)]
+    fn roles(&self) -> Vec<String> {
+        [
+            "central", "peripheral",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+}
+
+/// Object path of the device with `address` under `adapter`
+pub(super) fn device_path(
+    adapter: &ObjectPath<'_>,
+    address: &str,
+) -> Result<OwnedObjectPath, zbus::Error> {
+    Ok(OwnedObjectPath::try_from(format!(
+        "{adapter}/dev_{}",
+        address.replace(':', "_")
+    ))?)
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use zbus::fdo::{self, PropertiesProxy};
+use zbus::message::Header;
+use zbus::names::{InterfaceName, OwnedUniqueName};
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{interface, Connection};
+
+/// An advert registered with a `MockAdvertisingManager`
+#[derive(Debug, Clone)]
+pub struct RegisteredAdvertisement {
+    /// Unique name of the registering connection, `None` on a peer-to-peer
+    /// connection
+    pub sender: Option<OwnedUniqueName>,
+    pub path: OwnedObjectPath,
+    /// The `LEAdvertisement1` properties at registration
+    pub properties: HashMap<String, OwnedValue>,
+}
+
+/// `org.bluez.LEAdvertisingManager1` of a `MockBluez` adapter. Like bluez it
+/// reads the properties of an advert when it registers.
+#[derive(Debug)]
+pub struct MockAdvertisingManager {
+    pub(super) advertisements: Vec<RegisteredAdvertisement>,
+    pub(super) supported_instances: u8,
+}
+
+impl Default for MockAdvertisingManager {
+    fn default() -> Self {
+        Self {
+            advertisements: Vec::new(),
+            supported_instances: 5,
+        }
+    }
+}
+
+#[interface(interface = "org.bluez.LEAdvertisingManager1")]
+impl MockAdvertisingManager {
+    async fn register_advertisement(
+        &mut self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        advertisement: ObjectPath<'_>,
+        _options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<()> {
+        let sender: Option<OwnedUniqueName> = header.sender().map(|s| s.to_owned().into());
+        if self
+            .advertisements
+            .iter()
+            .any(|ad| ad.sender == sender && ad.path.as_str() == advertisement.as_str())
+        {
+            return Err(fdo::Error::Failed("Already Exists".to_owned()));
+        }
+        if self.advertisements.len() >= self.supported_instances as usize {
+            return Err(fdo::Error::Failed(
+                "Maximum advertisements reached".to_owned(),
+            ));
+        }
+
+        let mut builder = PropertiesProxy::builder(connection).path(advertisement.clone())?;
+        if let Some(sender) = &sender {
+            builder = builder.destination(sender.clone())?;
+        }
+        let properties = builder
+            .build()
+            .await?
+            .get_all(InterfaceName::from_static_str_unchecked(
+                "org.bluez.LEAdvertisement1",
+            ))
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("{advertisement}: {e}")))?;
+
+        self.advertisements.push(RegisteredAdvertisement {
+            sender,
+            path: advertisement.into(),
+            properties,
+        });
+        self.active_instances_changed(&emitter).await?;
+        Ok(())
+    }
+
+    async fn unregister_advertisement(
+        &mut self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        advertisement: ObjectPath<'_>,
+    ) -> fdo::Result<()> {
+        let sender: Option<OwnedUniqueName> = header.sender().map(|s| s.to_owned().into());
+        let count = self.advertisements.len();
+        self.advertisements
+            .retain(|ad| !(ad.sender == sender && ad.path.as_str() == advertisement.as_str()));
+        if self.advertisements.len() == count {
+            return Err(fdo::Error::Failed("Does Not Exist".to_owned()));
+        }
+        self.active_instances_changed(&emitter).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn active_instances(&self) -> u8 {
+        self.advertisements.len() as u8
+    }
+
+    #[zbus(property)]
+    fn supported_instances(&self) -> u8 {
+        self.supported_instances
+    }
+
+    #[zbus(property)]
+    fn supported_includes(&self) -> Vec<String> {
+        [
+            "tx-power", "appearance", "local-name",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+}
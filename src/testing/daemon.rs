@@ -0,0 +1,69 @@
+use std::env;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+
+use zbus::Connection;
+
+/// A private `dbus-daemon` for the lifetime of the value, killed on drop.
+/// The daemon binary is `dbus-daemon` from `PATH` unless `DBUS_DAEMON` is set.
+#[derive(Debug)]
+pub struct TestBus {
+    daemon: Child,
+    address: String,
+}
+
+impl TestBus {
+    /// Launch the daemon and wait until it is listening
+    pub fn new() -> Result<Self, zbus::Error> {
+        let program = env::var("DBUS_DAEMON").unwrap_or_else(|_| "dbus-daemon".to_owned());
+        let mut daemon = Command::new(&program)
+            .arg("--session")
+            .arg("--nofork")
+            .arg("--nopidfile")
+            .arg("--print-address=1")
+            .arg(format!(
+                "--address=unix:tmpdir={}",
+                env::temp_dir().display()
+            ))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| zbus::Error::Failure(format!("{program}: {e}")))?;
+
+        let mut address = String::new();
+        let read = daemon
+            .stdout
+            .take()
+            .map(|stdout| BufReader::new(stdout).read_line(&mut address));
+        let address = address.trim().to_owned();
+        if address.is_empty() {
+            daemon.kill().ok();
+            daemon.wait().ok();
+            return Err(match read {
+                Some(Err(e)) => e.into(),
+                _ => zbus::Error::Failure(format!("{program} did not print an address")),
+            });
+        }
+        Ok(Self { daemon, address })
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// A new connection to the bus. Each call is a separate peer with its own
+    /// unique name, so a test can serve `MockBluez` on one and run the code
+    /// under test on another.
+    pub async fn connection(&self) -> Result<Connection, zbus::Error> {
+        zbus::connection::Builder::address(self.address.as_str())?
+            .build()
+            .await
+    }
+}
+
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        self.daemon.kill().ok();
+        self.daemon.wait().ok();
+    }
+}
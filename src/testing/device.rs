@@ -0,0 +1,214 @@
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedObjectPath;
+use zbus::{fdo, interface};
+
+/// `org.bluez.Device1` of a `MockBluez` device. Connecting also resolves
+/// services, pairing always succeeds.
+#[derive(Debug)]
+pub struct MockDevice {
+    pub(super) adapter: OwnedObjectPath,
+    pub(super) address: String,
+    pub(super) name: Option<String>,
+    pub(super) alias: String,
+    pub(super) rssi: Option<i16>,
+    pub(super) uuids: Vec<String>,
+    pub(super) connected: bool,
+    pub(super) services_resolved: bool,
+    pub(super) paired: bool,
+    pub(super) trusted: bool,
+    pub(super) wake_allowed: bool,
+    pub(super) blocked: bool,
+}
+
+impl MockDevice {
+    pub(super) fn new(adapter: OwnedObjectPath, address: &str, name: Option<&str>) -> Self {
+        Self {
+            adapter,
+            address: address.to_owned(),
+            name: name.map(str::to_owned),
+            alias: name.map_or_else(|| address.replace(':', "-"), str::to_owned),
+            rssi: None,
+            uuids: Vec::new(),
+            connected: false,
+            services_resolved: false,
+            paired: false,
+            trusted: false,
+            wake_allowed: false,
+            blocked: false,
+        }
+    }
+
+    /// Set `Connected` and `ServicesResolved` and emit the change
+    pub(super) async fn set_connected(
+        &mut self,
+        emitter: &SignalEmitter<'_>,
+        connected: bool,
+    ) -> zbus::Result<()> {
+        self.connected = connected;
+        self.connected_changed(emitter).await?;
+        self.services_resolved = connected;
+        self.services_resolved_changed(emitter).await
+    }
+
+    /// Set `Name` and `Alias` and emit the change
+    pub(super) async fn set_name(
+        &mut self,
+        emitter: &SignalEmitter<'_>,
+        name: &str,
+    ) -> zbus::Result<()> {
+        self.name = Some(name.to_owned());
+        self.alias = name.to_owned();
+        self.name_changed(emitter).await?;
+        self.alias_changed(emitter).await
+    }
+
+    /// Set `UUIDs` and emit the change
+    pub(super) async fn set_uuids(
+        &mut self,
+        emitter: &SignalEmitter<'_>,
+        uuids: Vec<String>,
+    ) -> zbus::Result<()> {
+        self.uuids = uuids;
+        self.u_u_i_ds_changed(emitter).await
+    }
+}
+
+#[interface(interface = "org.bluez.Device1")]
+impl MockDevice {
+    async fn connect(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if self.blocked {
+            return Err(fdo::Error::Failed("Blocked".to_owned()));
+        }
+        if !self.connected {
+            self.set_connected(&emitter, true).await?;
+        }
+        Ok(())
+    }
+
+    async fn disconnect(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if self.connected {
+            self.set_connected(&emitter, false).await?;
+        }
+        Ok(())
+    }
+
+    async fn pair(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        if self.paired {
+            return Err(fdo::Error::Failed("Already Exists".to_owned()));
+        }
+        self.paired = true;
+        self.paired_changed(&emitter).await?;
+        Ok(())
+    }
+
+    fn cancel_pairing(&self) -> fdo::Result<()> {
+        Err(fdo::Error::Failed("Does Not Exist".to_owned()))
+    }
+
+    #[zbus(property)]
+    fn adapter(&self) -> OwnedObjectPath {
+        self.adapter.clone()
+    }
+
+    #[zbus(property)]
+    fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    #[zbus(property)]
+    fn address_type(&self) -> String {
+        "public".to_owned()
+    }
+
+    #[zbus(property)]
+    fn name(&self) -> fdo::Result<String> {
+        self.name
+            .clone()
+            .ok_or_else(|| fdo::Error::UnknownProperty("Name".to_owned()))
+    }
+
+    #[zbus(property)]
+    fn alias(&self) -> String {
+        self.alias.clone()
+    }
+
+    #[zbus(property)]
+    fn set_alias(&mut self, alias: String) {
+        self.alias = alias;
+    }
+
+    #[zbus(property, name = "RSSI")]
+    fn rssi(&self) -> fdo::Result<i16> {
+        self.rssi
+            .ok_or_else(|| fdo::Error::UnknownProperty("RSSI".to_owned()))
+    }
+
+    #[zbus(property, name = "UUIDs")]
+    fn uuids(&self) -> Vec<String> {
+        self.uuids.clone()
+    }
+
+    #[zbus(property)]
+    fn connected(&self) -> bool {
+        self.connected
+    }
+
+    #[zbus(property)]
+    fn services_resolved(&self) -> bool {
+        self.services_resolved
+    }
+
+    #[zbus(property)]
+    fn paired(&self) -> bool {
+        self.paired
+    }
+
+    #[zbus(property)]
+    fn bonded(&self) -> bool {
+        self.paired
+    }
+
+    #[zbus(property)]
+    fn trusted(&self) -> bool {
+        self.trusted
+    }
+
+    #[zbus(property)]
+    fn set_trusted(&mut self, trusted: bool) {
+        self.trusted = trusted;
+    }
+
+    #[zbus(property)]
+    fn wake_allowed(&self) -> bool {
+        self.wake_allowed
+    }
+
+    #[zbus(property)]
+    fn set_wake_allowed(&mut self, wake_allowed: bool) {
+        self.wake_allowed = wake_allowed;
+    }
+
+    #[zbus(property)]
+    fn blocked(&self) -> bool {
+        self.blocked
+    }
+
+    #[zbus(property)]
+    fn set_blocked(&mut self, blocked: bool) {
+        self.blocked = blocked;
+    }
+
+    #[zbus(property)]
+    fn legacy_pairing(&self) -> bool {
+        false
+    }
+}
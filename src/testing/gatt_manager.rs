@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use zbus::fdo::{self, ManagedObjects, ObjectManagerProxy};
+use zbus::message::Header;
+use zbus::names::OwnedUniqueName;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, Value};
+use zbus::{interface, Connection};
+
+/// A GATT application registered with a `MockGattManager`
+#[derive(Debug, Clone)]
+pub struct RegisteredApplication {
+    /// Unique name of the registering connection, `None` on a peer-to-peer
+    /// connection
+    pub sender: Option<OwnedUniqueName>,
+    pub path: OwnedObjectPath,
+    /// The application's `GetManagedObjects` reply at registration
+    pub objects: ManagedObjects,
+}
+
+/// `org.bluez.GattManager1` of a `MockBluez` adapter. Like bluez it reads the
+/// attributes of an application with `GetManagedObjects` when it registers.
+#[derive(Debug, Default)]
+pub struct MockGattManager {
+    pub(super) applications: Vec<RegisteredApplication>,
+}
+
+#[interface(interface = "org.bluez.GattManager1")]
+impl MockGattManager {
+    async fn register_application(
+        &mut self,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(connection)] connection: &Connection,
+        application: ObjectPath<'_>,
+        _options: HashMap<&str, Value<'_>>,
+    ) -> fdo::Result<()> {
+        let sender: Option<OwnedUniqueName> = header.sender().map(|s| s.to_owned().into());
+        if self
+            .applications
+            .iter()
+            .any(|app| app.sender == sender && app.path.as_str() == application.as_str())
+        {
+            return Err(fdo::Error::Failed("Already Exists".to_owned()));
+        }
+
+        let mut builder = ObjectManagerProxy::builder(connection).path(application.clone())?;
+        if let Some(sender) = &sender {
+            builder = builder.destination(sender.clone())?;
+        }
+        let objects = builder
+            .build()
+            .await?
+            .get_managed_objects()
+            .await
+            .map_err(|e| fdo::Error::Failed(format!("{application}: {e}")))?;
+        if !objects.values().any(|interfaces| {
+            interfaces
+                .keys()
+                .any(|name| name.as_str() == "org.bluez.GattService1")
+        }) {
+            return Err(fdo::Error::Failed(format!("{application}: no services")));
+        }
+
+        self.applications.push(RegisteredApplication {
+            sender,
+            path: application.into(),
+            objects,
+        });
+        Ok(())
+    }
+
+    fn unregister_application(
+        &mut self,
+        #[zbus(header)] header: Header<'_>,
+        application: ObjectPath<'_>,
+    ) -> fdo::Result<()> {
+        let sender: Option<OwnedUniqueName> = header.sender().map(|s| s.to_owned().into());
+        let count = self.applications.len();
+        self.applications
+            .retain(|app| !(app.sender == sender && app.path.as_str() == application.as_str()));
+        if self.applications.len() == count {
+            return Err(fdo::Error::Failed("Does Not Exist".to_owned()));
+        }
+        Ok(())
+    }
+}
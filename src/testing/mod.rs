@@ -0,0 +1,218 @@
+//! # In-process bluez for tests
+//!
+//! `MockBluez` serves `Adapter1`, `Device1`, `GattManager1`,
+//! `LEAdvertisingManager1` and the GATT services of devices from a
+//! connection of the test itself, with an `ObjectManager` at `/` like
+//! bluetoothd. Code under test talks to it over a private bus or a
+//! peer-to-peer connection instead of a real daemon, and the test checks what
+//! got registered. `TestBus` launches the private bus.
+//!
+//! ```ignore
+//! let bus = TestBus::new()?;
+//! let server = bus.connection().await?;
+//! let client = bus.connection().await?;
+//! let bluez = MockBluez::new(&server).await?;
+//! let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+//! GattApplication1::register_on("/com/example", adapter.as_str(), client, services).await?;
+//! assert_eq!(bluez.applications(&adapter).await?.len(), 1);
+//! ```
+
+mod adapter;
+pub use adapter::*;
+
+mod advertising_manager;
+pub use advertising_manager::*;
+
+mod daemon;
+pub use daemon::*;
+
+mod device;
+pub use device::*;
+
+mod gatt_manager;
+pub use gatt_manager::*;
+
+mod remote_gatt;
+pub use remote_gatt::*;
+
+use std::sync::atomic::{AtomicU16, Ordering};
+
+use uuid::Uuid;
+use zbus::fdo::ObjectManager;
+use zbus::object_server::InterfaceRef;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath};
+use zbus::Connection;
+
+use crate::bus::BLUEZ_DESTINATION;
+
+/// Mock bluetoothd served on a connection
+pub struct MockBluez {
+    connection: Connection,
+    /// Next attribute handle, numbering the GATT object paths like bluez
+    handles: AtomicU16,
+}
+
+impl MockBluez {
+    /// Serve the mock on `connection`. On a bus connection `org.bluez` is
+    /// requested too, so it must be a private bus.
+    pub async fn new(connection: &Connection) -> Result<Self, zbus::Error> {
+        connection.object_server().at("/", ObjectManager).await?;
+        if connection.unique_name().is_some() {
+            connection.request_name(BLUEZ_DESTINATION).await?;
+        }
+        Ok(Self {
+            connection: connection.clone(),
+            handles: AtomicU16::new(1),
+        })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// Add a powered adapter at `/org/bluez/{name}`, with a `GattManager1`
+    /// and an `LEAdvertisingManager1`
+    pub async fn add_adapter(
+        &self,
+        name: &str,
+        address: &str,
+    ) -> Result<OwnedObjectPath, zbus::Error> {
+        let path = OwnedObjectPath::try_from(format!("/org/bluez/{name}"))?;
+        let server = self.connection.object_server();
+        server.at(&path, MockAdapter::new(name, address)).await?;
+        server.at(&path, MockGattManager::default()).await?;
+        server.at(&path, MockAdvertisingManager::default()).await?;
+        Ok(path)
+    }
+
+    /// Add a disconnected device with `address` under `adapter`, as if it was
+    /// just discovered
+    pub async fn add_device(
+        &self,
+        adapter: &ObjectPath<'_>,
+        address: &str,
+        name: Option<&str>,
+    ) -> Result<OwnedObjectPath, zbus::Error> {
+        let path = adapter::device_path(adapter, address)?;
+        let device = MockDevice::new(adapter.to_owned().into(), address, name);
+        if !self.connection.object_server().at(&path, device).await? {
+            return Err(zbus::Error::Failure(format!("{path} already exists")));
+        }
+        Ok(path)
+    }
+
+    pub async fn adapter(
+        &self,
+        adapter: &ObjectPath<'_>,
+    ) -> Result<InterfaceRef<MockAdapter>, zbus::Error> {
+        self.connection.object_server().interface(adapter).await
+    }
+
+    pub async fn device(
+        &self,
+        device: &ObjectPath<'_>,
+    ) -> Result<InterfaceRef<MockDevice>, zbus::Error> {
+        self.connection.object_server().interface(device).await
+    }
+
+    /// Connect or disconnect `device` from the remote side
+    pub async fn set_connected(
+        &self,
+        device: &ObjectPath<'_>,
+        connected: bool,
+    ) -> Result<(), zbus::Error> {
+        let device = self.device(device).await?;
+        let emitter = device.signal_emitter().clone();
+        device
+            .get_mut()
+            .await
+            .set_connected(&emitter, connected)
+            .await
+    }
+
+    /// Give `device` a name, as if a scan response just arrived
+    pub async fn set_name(&self, device: &ObjectPath<'_>, name: &str) -> Result<(), zbus::Error> {
+        let device = self.device(device).await?;
+        let emitter = device.signal_emitter().clone();
+        device.get_mut().await.set_name(&emitter, name).await
+    }
+
+    /// Set the service UUIDs `device` advertises
+    pub async fn set_uuids(
+        &self,
+        device: &ObjectPath<'_>,
+        uuids: &[Uuid],
+    ) -> Result<(), zbus::Error> {
+        let device = self.device(device).await?;
+        let emitter = device.signal_emitter().clone();
+        let uuids = uuids.iter().map(Uuid::to_string).collect();
+        device.get_mut().await.set_uuids(&emitter, uuids).await
+    }
+
+    /// Add a primary service with `uuid` to the GATT database of `device`
+    pub async fn add_service(
+        &self,
+        device: &ObjectPath<'_>,
+        uuid: Uuid,
+    ) -> Result<OwnedObjectPath, zbus::Error> {
+        let handle = self.handles.fetch_add(1, Ordering::SeqCst);
+        let path = OwnedObjectPath::try_from(format!("{device}/service{handle:04x}"))?;
+        let service = MockService {
+            device: device.to_owned().into(),
+            uuid: uuid.to_string(),
+        };
+        self.connection.object_server().at(&path, service).await?;
+        Ok(path)
+    }
+
+    /// Add a readable characteristic holding `value` to `service`
+    pub async fn add_characteristic(
+        &self,
+        service: &ObjectPath<'_>,
+        uuid: Uuid,
+        value: Vec<u8>,
+    ) -> Result<OwnedObjectPath, zbus::Error> {
+        let handle = self.handles.fetch_add(1, Ordering::SeqCst);
+        let path = OwnedObjectPath::try_from(format!("{service}/char{handle:04x}"))?;
+        let characteristic =
+            MockCharacteristic::new(service.to_owned().into(), uuid.to_string(), value);
+        self.connection
+            .object_server()
+            .at(&path, characteristic)
+            .await?;
+        Ok(path)
+    }
+
+    pub async fn characteristic(
+        &self,
+        characteristic: &ObjectPath<'_>,
+    ) -> Result<InterfaceRef<MockCharacteristic>, zbus::Error> {
+        self.connection.object_server().interface(characteristic).await
+    }
+
+    /// The GATT applications registered on `adapter`
+    pub async fn applications(
+        &self,
+        adapter: &ObjectPath<'_>,
+    ) -> Result<Vec<RegisteredApplication>, zbus::Error> {
+        let manager = self
+            .connection
+            .object_server()
+            .interface::<_, MockGattManager>(adapter)
+            .await?;
+        Ok(manager.get().await.applications.clone())
+    }
+
+    /// The adverts registered on `adapter`
+    pub async fn advertisements(
+        &self,
+        adapter: &ObjectPath<'_>,
+    ) -> Result<Vec<RegisteredAdvertisement>, zbus::Error> {
+        let manager = self
+            .connection
+            .object_server()
+            .interface::<_, MockAdvertisingManager>(adapter)
+            .await?;
+        Ok(manager.get().await.advertisements.clone())
+    }
+}
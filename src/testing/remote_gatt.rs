@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use zbus::zvariant::{OwnedObjectPath, Value};
+use zbus::{fdo, interface};
+
+use crate::rt::sleep;
+
+/// How long a `MockCharacteristic` takes to answer, so overlapping requests
+/// actually overlap
+const RESPONSE_DELAY: Duration = Duration::from_millis(50);
+
+/// `org.bluez.GattService1` of a `MockBluez` device
+#[derive(Debug)]
+pub struct MockService {
+    pub(super) device: OwnedObjectPath,
+    pub(super) uuid: String,
+}
+
+#[interface(interface = "org.bluez.GattService1")]
+impl MockService {
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> String {
+        self.uuid.clone()
+    }
+
+    #[zbus(property)]
+    fn primary(&self) -> bool {
+        true
+    }
+
+    #[zbus(property)]
+    fn device(&self) -> OwnedObjectPath {
+        self.device.clone()
+    }
+}
+
+/// `org.bluez.GattCharacteristic1` of a `MockBluez` device. Reads return the
+/// value after a short delay and count how many were answered at once.
+#[derive(Debug)]
+pub struct MockCharacteristic {
+    pub(super) service: OwnedObjectPath,
+    pub(super) uuid: String,
+    pub(super) value: Vec<u8>,
+    pending: AtomicUsize,
+    most_pending: AtomicUsize,
+}
+
+impl MockCharacteristic {
+    pub(super) fn new(service: OwnedObjectPath, uuid: String, value: Vec<u8>) -> Self {
+        Self {
+            service,
+            uuid,
+            value,
+            pending: AtomicUsize::new(0),
+            most_pending: AtomicUsize::new(0),
+        }
+    }
+
+    /// The most `ReadValue` calls that were in progress at the same time
+    pub fn most_concurrent_reads(&self) -> usize {
+        self.most_pending.load(Ordering::SeqCst)
+    }
+}
+
+#[interface(interface = "org.bluez.GattCharacteristic1")]
+impl MockCharacteristic {
+    async fn read_value(&self, _options: HashMap<&str, Value<'_>>) -> fdo::Result<Vec<u8>> {
+        let pending = self.pending.fetch_add(1, Ordering::SeqCst) + 1;
+        self.most_pending.fetch_max(pending, Ordering::SeqCst);
+        sleep(RESPONSE_DELAY).await;
+        self.pending.fetch_sub(1, Ordering::SeqCst);
+        Ok(self.value.clone())
+    }
+
+    #[zbus(property, name = "UUID")]
+    fn uuid(&self) -> String {
+        self.uuid.clone()
+    }
+
+    #[zbus(property)]
+    fn service(&self) -> OwnedObjectPath {
+        self.service.clone()
+    }
+
+    #[zbus(property)]
+    fn flags(&self) -> Vec<String> {
+        vec!["read".to_owned()]
+    }
+}
@@ -0,0 +1,62 @@
+//! # Tracing spans
+//!
+//! With the `tracing` feature, calls into exported interfaces and the proxy
+//! calls made by the client helpers run inside a `bluez` span with the object
+//! path, interface and method as fields. Without it these do nothing.
+
+use std::future::Future;
+
+use zbus::message::Header;
+use zbus::Proxy;
+
+#[cfg(feature = "tracing")]
+fn span(path: &str, interface: &str, method: &str) -> tracing::Span {
+    tracing::debug_span!("bluez", path, interface, method)
+}
+
+#[cfg(feature = "tracing")]
+fn handler_span(header: &Header<'_>, method: &str) -> tracing::Span {
+    span(
+        header.path().map(|p| p.as_str()).unwrap_or_default(),
+        header.interface().map(|i| i.as_str()).unwrap_or_default(),
+        method,
+    )
+}
+
+/// Run `fut`, a call of `method` through `proxy`, inside a span
+#[allow(unused_variables)]
+pub(crate) async fn call<F: Future>(proxy: &Proxy<'_>, method: &str, fut: F) -> F::Output {
+    #[cfg(feature = "tracing")]
+    let fut = tracing::Instrument::instrument(
+        fut,
+        span(proxy.path().as_str(), proxy.interface().as_str(), method),
+    );
+    fut.await
+}
+
+/// Run `fut`, the handler of an incoming call, inside a span
+#[allow(unused_variables)]
+pub(crate) async fn handle<F: Future>(header: &Header<'_>, method: &str, fut: F) -> F::Output {
+    #[cfg(feature = "tracing")]
+    let fut = tracing::Instrument::instrument(fut, handler_span(header, method));
+    fut.await
+}
+
+/// Guard keeping the span of a blocking handler entered
+#[cfg(feature = "tracing")]
+pub(crate) type Entered = tracing::span::EnteredSpan;
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Entered;
+
+/// Enter the span of a blocking handler of an incoming call
+#[allow(unused_variables)]
+pub(crate) fn enter(header: &Header<'_>, method: &str) -> Entered {
+    #[cfg(feature = "tracing")]
+    {
+        handler_span(header, method).entered()
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        Entered
+    }
+}
@@ -0,0 +1,175 @@
+//! # Bluetooth SIG assigned UUIDs
+//!
+//! Standard services, characteristics and descriptors, expanded to 128-bit
+//! UUIDs with the Bluetooth base UUID.
+
+use uuid::Uuid;
+
+/// `0000xxxx-0000-1000-8000-00805f9b34fb`, the UUID 16-bit and 32-bit
+/// assigned numbers are expanded into
+pub const BLUETOOTH_BASE_UUID: Uuid = Uuid::from_u128(0x00000000_0000_1000_8000_00805f9b34fb);
+
+/// Expand a 16-bit assigned number to a full UUID
+pub const fn uuid_from_u16(short: u16) -> Uuid {
+    uuid_from_u32(short as u32)
+}
+
+/// Expand a 32-bit assigned number to a full UUID
+pub const fn uuid_from_u32(short: u32) -> Uuid {
+    Uuid::from_u128(BLUETOOTH_BASE_UUID.as_u128() | ((short as u128) << 96))
+}
+
+/// The 16-bit assigned number of `uuid`, if it is one
+pub fn as_u16(uuid: &Uuid) -> Option<u16> {
+    as_u32(uuid).and_then(|short| u16::try_from(short).ok())
+}
+
+/// The 32-bit assigned number of `uuid`, if it is one
+pub fn as_u32(uuid: &Uuid) -> Option<u32> {
+    let value = uuid.as_u128();
+    if value & ((1 << 96) - 1) == BLUETOOTH_BASE_UUID.as_u128() {
+        Some((value >> 96) as u32)
+    } else {
+        None
+    }
+}
+
+/// Services
+pub mod service {
+    use uuid::Uuid;
+
+    use super::uuid_from_u16;
+
+    /// Generic Access
+    pub const GENERIC_ACCESS: Uuid = uuid_from_u16(0x1800);
+    /// Generic Attribute
+    pub const GENERIC_ATTRIBUTE: Uuid = uuid_from_u16(0x1801);
+    /// Immediate Alert
+    pub const IMMEDIATE_ALERT: Uuid = uuid_from_u16(0x1802);
+    /// Link Loss
+    pub const LINK_LOSS: Uuid = uuid_from_u16(0x1803);
+    /// Tx Power
+    pub const TX_POWER: Uuid = uuid_from_u16(0x1804);
+    /// Current Time
+    pub const CURRENT_TIME: Uuid = uuid_from_u16(0x1805);
+    /// Health Thermometer
+    pub const HEALTH_THERMOMETER: Uuid = uuid_from_u16(0x1809);
+    /// Device Information
+    pub const DEVICE_INFORMATION: Uuid = uuid_from_u16(0x180a);
+    /// Heart Rate
+    pub const HEART_RATE: Uuid = uuid_from_u16(0x180d);
+    /// Battery
+    pub const BATTERY: Uuid = uuid_from_u16(0x180f);
+    /// Blood Pressure
+    pub const BLOOD_PRESSURE: Uuid = uuid_from_u16(0x1810);
+    /// Human Interface Device
+    pub const HUMAN_INTERFACE_DEVICE: Uuid = uuid_from_u16(0x1812);
+    /// Scan Parameters
+    pub const SCAN_PARAMETERS: Uuid = uuid_from_u16(0x1813);
+    /// Running Speed and Cadence
+    pub const RUNNING_SPEED_AND_CADENCE: Uuid = uuid_from_u16(0x1814);
+    /// Cycling Speed and Cadence
+    pub const CYCLING_SPEED_AND_CADENCE: Uuid = uuid_from_u16(0x1816);
+    /// Environmental Sensing
+    pub const ENVIRONMENTAL_SENSING: Uuid = uuid_from_u16(0x181a);
+    /// User Data
+    pub const USER_DATA: Uuid = uuid_from_u16(0x181c);
+    /// Weight Scale
+    pub const WEIGHT_SCALE: Uuid = uuid_from_u16(0x181d);
+    /// Fitness Machine
+    pub const FITNESS_MACHINE: Uuid = uuid_from_u16(0x1826);
+    /// Serial Port Profile
+    pub const SERIAL_PORT: Uuid = uuid_from_u16(0x1101);
+    /// A2DP Audio Source
+    pub const AUDIO_SOURCE: Uuid = uuid_from_u16(0x110a);
+    /// A2DP Audio Sink
+    pub const AUDIO_SINK: Uuid = uuid_from_u16(0x110b);
+    /// Hands-Free
+    pub const HANDSFREE: Uuid = uuid_from_u16(0x111e);
+    /// Hands-Free Audio Gateway
+    pub const HANDSFREE_AUDIO_GATEWAY: Uuid = uuid_from_u16(0x111f);
+    /// OBEX Object Push
+    pub const OBEX_OBJECT_PUSH: Uuid = uuid_from_u16(0x1105);
+    /// Phonebook Access Server
+    pub const PHONEBOOK_ACCESS_SERVER: Uuid = uuid_from_u16(0x112f);
+}
+
+/// Characteristics
+pub mod characteristic {
+    use uuid::Uuid;
+
+    use super::uuid_from_u16;
+
+    /// Device Name
+    pub const DEVICE_NAME: Uuid = uuid_from_u16(0x2a00);
+    /// Appearance
+    pub const APPEARANCE: Uuid = uuid_from_u16(0x2a01);
+    /// Service Changed
+    pub const SERVICE_CHANGED: Uuid = uuid_from_u16(0x2a05);
+    /// Alert Level
+    pub const ALERT_LEVEL: Uuid = uuid_from_u16(0x2a06);
+    /// Tx Power Level
+    pub const TX_POWER_LEVEL: Uuid = uuid_from_u16(0x2a07);
+    /// Battery Level
+    pub const BATTERY_LEVEL: Uuid = uuid_from_u16(0x2a19);
+    /// Temperature Measurement
+    pub const TEMPERATURE_MEASUREMENT: Uuid = uuid_from_u16(0x2a1c);
+    /// System ID
+    pub const SYSTEM_ID: Uuid = uuid_from_u16(0x2a23);
+    /// Model Number String
+    pub const MODEL_NUMBER_STRING: Uuid = uuid_from_u16(0x2a24);
+    /// Serial Number String
+    pub const SERIAL_NUMBER_STRING: Uuid = uuid_from_u16(0x2a25);
+    /// Firmware Revision String
+    pub const FIRMWARE_REVISION_STRING: Uuid = uuid_from_u16(0x2a26);
+    /// Hardware Revision String
+    pub const HARDWARE_REVISION_STRING: Uuid = uuid_from_u16(0x2a27);
+    /// Software Revision String
+    pub const SOFTWARE_REVISION_STRING: Uuid = uuid_from_u16(0x2a28);
+    /// Manufacturer Name String
+    pub const MANUFACTURER_NAME_STRING: Uuid = uuid_from_u16(0x2a29);
+    /// Current Time
+    pub const CURRENT_TIME: Uuid = uuid_from_u16(0x2a2b);
+    /// Blood Pressure Measurement
+    pub const BLOOD_PRESSURE_MEASUREMENT: Uuid = uuid_from_u16(0x2a35);
+    /// Heart Rate Measurement
+    pub const HEART_RATE_MEASUREMENT: Uuid = uuid_from_u16(0x2a37);
+    /// Body Sensor Location
+    pub const BODY_SENSOR_LOCATION: Uuid = uuid_from_u16(0x2a38);
+    /// Heart Rate Control Point
+    pub const HEART_RATE_CONTROL_POINT: Uuid = uuid_from_u16(0x2a39);
+    /// PnP ID
+    pub const PNP_ID: Uuid = uuid_from_u16(0x2a50);
+    /// CSC Measurement
+    pub const CSC_MEASUREMENT: Uuid = uuid_from_u16(0x2a5b);
+    /// Temperature
+    pub const TEMPERATURE: Uuid = uuid_from_u16(0x2a6e);
+    /// Humidity
+    pub const HUMIDITY: Uuid = uuid_from_u16(0x2a6f);
+}
+
+/// Descriptors
+pub mod descriptor {
+    use uuid::Uuid;
+
+    use super::uuid_from_u16;
+
+    /// Characteristic Extended Properties
+    pub const CHARACTERISTIC_EXTENDED_PROPERTIES: Uuid = uuid_from_u16(0x2900);
+    /// Characteristic User Description
+    pub const CHARACTERISTIC_USER_DESCRIPTION: Uuid = uuid_from_u16(0x2901);
+    /// Client Characteristic Configuration (CCCD)
+    pub const CLIENT_CHARACTERISTIC_CONFIGURATION: Uuid = uuid_from_u16(0x2902);
+    /// Server Characteristic Configuration
+    pub const SERVER_CHARACTERISTIC_CONFIGURATION: Uuid = uuid_from_u16(0x2903);
+    /// Characteristic Presentation Format
+    pub const CHARACTERISTIC_PRESENTATION_FORMAT: Uuid = uuid_from_u16(0x2904);
+    /// Characteristic Aggregate Format
+    pub const CHARACTERISTIC_AGGREGATE_FORMAT: Uuid = uuid_from_u16(0x2905);
+    /// Valid Range
+    pub const VALID_RANGE: Uuid = uuid_from_u16(0x2906);
+    /// Report Reference
+    pub const REPORT_REFERENCE: Uuid = uuid_from_u16(0x2908);
+    /// Environmental Sensing Measurement
+    pub const ENVIRONMENTAL_SENSING_MEASUREMENT: Uuid = uuid_from_u16(0x290c);
+}
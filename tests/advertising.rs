@@ -0,0 +1,119 @@
+use bluez_zbus::advertising::AdvertisingManager;
+use bluez_zbus::interface::LEAdvertisement1;
+use bluez_zbus::testing::{MockBluez, TestBus};
+
+#[test]
+fn advertisement_registers_and_unregisters() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let manager = AdvertisingManager::new(&client, adapter.as_str()).await?;
+        let advert = manager
+            .register(LEAdvertisement1 {
+                local_name: Some("bluez-zbus".to_owned()),
+                ..Default::default()
+            })
+            .await?;
+
+        let advertisements = bluez.advertisements(&adapter).await?;
+        assert_eq!(advertisements.len(), 1);
+        assert_eq!(advertisements[0].path.as_str(), advert.path().as_str());
+        let local_name = String::try_from(advertisements[0].properties["LocalName"].try_clone()?)?;
+        assert_eq!(local_name, "bluez-zbus");
+
+        advert.unregister().await?;
+        assert!(bluez.advertisements(&adapter).await?.is_empty());
+        Ok(())
+    })
+}
+
+#[test]
+fn advertisement_fails_when_instances_run_out() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let manager = AdvertisingManager::new(&client, adapter.as_str()).await?;
+        let supported = manager.proxy().supported_instances().await?;
+        let mut adverts = Vec::new();
+        for _ in 0..supported {
+            adverts.push(manager.register(LEAdvertisement1::default()).await?);
+        }
+        assert!(manager.register(LEAdvertisement1::default()).await.is_err());
+        assert_eq!(
+            bluez.advertisements(&adapter).await?.len(),
+            supported as usize
+        );
+
+        for advert in adverts {
+            advert.unregister().await?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(feature = "experimental")]
+#[test]
+fn experimental_values_follow_the_bus() -> Result<(), zbus::Error> {
+    use bluez_zbus::bus::BluezBus;
+
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+        let advert = || LEAdvertisement1 {
+            tx_power: Some(4),
+            ..Default::default()
+        };
+
+        let experimental = AdvertisingManager::new(&client, adapter.as_str()).await?;
+        let stable = AdvertisingManager::new(
+            BluezBus::new(client.clone()).with_experimental(false),
+            adapter.as_str(),
+        )
+        .await?
+        .with_base_path("/org/bluez/stable")?;
+        let first = experimental.register(advert()).await?;
+        let second = stable.register(advert()).await?;
+
+        let advertisements = bluez.advertisements(&adapter).await?;
+        assert!(advertisements[0].properties.contains_key("TxPower"));
+        assert!(!advertisements[1].properties.contains_key("TxPower"));
+
+        first.unregister().await?;
+        second.unregister().await?;
+        Ok(())
+    })
+}
+
+#[test]
+fn advertisement_is_removed_when_bluez_fails_to_unregister() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let manager = AdvertisingManager::new(&client, adapter.as_str()).await?;
+        let advert = manager.register(LEAdvertisement1::default()).await?;
+        let path = advert.path().to_owned();
+        // Bluez already dropped the advert, e.g. after the adapter reset
+        manager.proxy().unregister_advertisement(&path).await?;
+
+        assert!(advert.unregister().await.is_err());
+        assert!(
+            client
+                .object_server()
+                .interface::<_, LEAdvertisement1>(&path)
+                .await
+                .is_err()
+        );
+        Ok(())
+    })
+}
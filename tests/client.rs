@@ -0,0 +1,180 @@
+use bluez_zbus::bus::BluezBus;
+use std::time::Duration;
+
+use async_io::Timer;
+use bluez_zbus::client::{
+    default_adapter, Adapter, BluezSession, Central, Device, DeviceMatcher, ReadOptions,
+};
+use bluez_zbus::testing::{MockBluez, TestBus};
+use futures_lite::future;
+use uuid::Uuid;
+use zbus::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
+
+#[test]
+fn client_uses_the_destination_of_the_bus() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let mock = bus.connection().await?;
+        let bluez = MockBluez::new(&mock).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let device = bluez
+            .add_device(&adapter, "66:77:88:99:AA:BB", Some("Sensor"))
+            .await?;
+        // Only reachable through the unique name from now on
+        mock.release_name("org.bluez").await?;
+        let client = BluezBus::new(bus.connection().await?)
+            .with_destination(mock.unique_name().unwrap().as_str())?;
+
+        assert!(default_adapter(bus.connection().await?).await.is_err());
+        let found = default_adapter(&client).await?;
+        assert_eq!(found.path().as_str(), adapter.as_str());
+        let devices = found.devices().await?;
+        assert_eq!(devices.len(), 1);
+        assert_eq!(devices[0].path().as_str(), device.as_str());
+
+        let session = BluezSession::new(&client).await?;
+        assert_eq!(session.adapters().len(), 1);
+        let (path, data) = session
+            .device_by_address("66:77:88:99:AA:BB".parse().unwrap())
+            .unwrap();
+        assert_eq!(path.as_str(), device.as_str());
+        assert_eq!(data.name(), "Sensor");
+        Ok(())
+    })
+}
+
+#[test]
+fn central_disconnects_only_its_devices() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let device = bluez.add_device(&adapter, "66:77:88:99:AA:BB", None).await?;
+        let client = bus.connection().await?;
+        let central = Central::new(Adapter::new(&client, &adapter).await?);
+
+        assert!(central.disconnect(&device).await.is_err());
+        assert!(central.devices().is_empty());
+
+        let properties = PropertiesProxy::builder(&client)
+            .destination("org.bluez")?
+            .path(device.clone())?
+            .build()
+            .await?;
+        let is_connected = || async {
+            let connected = properties
+                .get(InterfaceName::from_static_str_unchecked("org.bluez.Device1"), "Connected")
+                .await?;
+            Ok::<_, zbus::Error>(bool::try_from(connected)?)
+        };
+        central.connect(&device).await?;
+        assert!(is_connected().await?);
+        assert_eq!(central.devices().len(), 1);
+        central.disconnect(&device).await?;
+        assert!(!is_connected().await?);
+        assert!(central.devices().is_empty());
+        Ok(())
+    })
+}
+
+/// Wait until `adapter` is discovering, plus a little for the client to
+/// check the devices bluez already knew about
+async fn discovery_started(adapter: &Adapter) -> Result<(), zbus::Error> {
+    while !adapter.proxy().discovering().await? {
+        Timer::after(Duration::from_millis(10)).await;
+    }
+    Timer::after(Duration::from_millis(100)).await;
+    Ok(())
+}
+
+#[test]
+fn wait_for_device_sees_a_late_name() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter_path = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        // Known before discovery, without a name yet
+        let device = bluez
+            .add_device(&adapter_path, "66:77:88:99:AA:BB", None)
+            .await?;
+        let client = bus.connection().await?;
+        let adapter = Adapter::new(&client, &adapter_path).await?;
+
+        let (found, named) = future::zip(
+            adapter.wait_for_device(
+                DeviceMatcher::NamePrefix("Sensor".to_owned()),
+                Duration::from_secs(5),
+            ),
+            async {
+                discovery_started(&adapter).await?;
+                bluez.set_name(&device, "Sensor 1").await
+            },
+        )
+        .await;
+        named?;
+        assert_eq!(found?.path().as_str(), device.as_str());
+        Ok(())
+    })
+}
+
+#[test]
+fn connect_first_advertising_sees_late_uuids() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter_path = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        // Known before discovery, its services not advertised yet
+        let device = bluez
+            .add_device(&adapter_path, "66:77:88:99:AA:BB", Some("Sensor"))
+            .await?;
+        let client = bus.connection().await?;
+        let central = Central::new(Adapter::new(&client, &adapter_path).await?);
+        let uuid = Uuid::new_v4();
+
+        let (connected, advertised) = future::zip(
+            central.connect_first_advertising(uuid, Duration::from_secs(5)),
+            async {
+                discovery_started(central.adapter()).await?;
+                bluez.set_uuids(&device, &[uuid]).await
+            },
+        )
+        .await;
+        advertised?;
+        let connected = connected?;
+        assert_eq!(connected.path().as_str(), device.as_str());
+        assert_eq!(central.devices().len(), 1);
+        Ok(())
+    })
+}
+
+#[test]
+fn device_sends_gatt_requests_one_at_a_time() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let path = bluez
+            .add_device(&adapter, "66:77:88:99:AA:BB", Some("Sensor"))
+            .await?;
+        let service_uuid = Uuid::new_v4();
+        let char_uuid = Uuid::new_v4();
+        let service = bluez.add_service(&path, service_uuid).await?;
+        let char = bluez
+            .add_characteristic(&service, char_uuid, vec![1, 2])
+            .await?;
+
+        let device = Device::new(bus.connection().await?, &path).await?;
+        device.connect().await?;
+        let (first, second) = future::zip(
+            device.read_characteristic(service_uuid, char_uuid, ReadOptions::default()),
+            device.read_characteristic(service_uuid, char_uuid, ReadOptions::default()),
+        )
+        .await;
+        assert_eq!(first?, [1, 2]);
+        assert_eq!(second?, [1, 2]);
+        let char = bluez.characteristic(&char).await?;
+        assert_eq!(char.get().await.most_concurrent_reads(), 1);
+        Ok(())
+    })
+}
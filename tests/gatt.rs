@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_io::Timer;
+
+use bluez_zbus::interface::gatt::{
+    AccessPolicy, BatteryService, BatteryServiceHandle, CharacteristicFlags, GattApplication1,
+    GattCharacteristic1, GattDescriptor1, GattDescriptorFlags, GattOperation, GattService1,
+};
+use bluez_zbus::uuids::characteristic;
+use bluez_zbus::proxy::gatt_characteristic1::GattCharacteristic1Proxy;
+use bluez_zbus::testing::{MockBluez, TestBus};
+use futures_lite::StreamExt;
+use uuid::Uuid;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::ObjectPath;
+
+#[test]
+fn application_registers_and_serves_values() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let service_uuid = Uuid::new_v4();
+        let char_uuid = Uuid::new_v4();
+        let mut app = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            vec![(
+                GattService1::new(service_uuid, true),
+                vec![(
+                    GattCharacteristic1::new(
+                        char_uuid,
+                        Some(vec![1, 2, 3]),
+                        vec![
+                            CharacteristicFlags::Read,
+                            CharacteristicFlags::Write,
+                        ],
+                    ),
+                    vec![
+                        GattDescriptor1::new(
+                            Uuid::new_v4(),
+                            Some(vec![4]),
+                            vec![GattDescriptorFlags::Read],
+                        ),
+                    ],
+                )],
+            )],
+        )
+        .await?;
+
+        let applications = bluez.applications(&adapter).await?;
+        assert_eq!(applications.len(), 1);
+        assert_eq!(applications[0].path.as_str(), "/com/example/app");
+        assert_eq!(applications[0].sender.as_ref(), client.unique_name());
+        let service = &app.services()[0];
+        let char = &service.characteristics()[&char_uuid];
+        assert_eq!(applications[0].objects.len(), 3);
+        assert!(applications[0].objects.contains_key(service.path()));
+        assert!(applications[0].objects.contains_key(char.path()));
+
+        let proxy = GattCharacteristic1Proxy::builder(bluez.connection())
+            .destination(client.unique_name().unwrap().to_owned())?
+            .path(char.path().to_owned())?
+            .build()
+            .await?;
+        assert_eq!(proxy.uuid().await?, char_uuid.to_string());
+        assert_eq!(proxy.read_value(HashMap::new()).await?, [1, 2, 3]);
+        proxy.write_value(&[7, 8], HashMap::new()).await?;
+        assert_eq!(char.value().await, [7, 8]);
+
+        let extra = Uuid::new_v4();
+        app.add_service(GattService1::new(extra, false), Vec::new())
+            .await?;
+        assert_eq!(app.services().len(), 2);
+        app.remove_service(extra).await?;
+        assert_eq!(app.services().len(), 1);
+
+        app.unregister().await?;
+        assert!(bluez.applications(&adapter).await?.is_empty());
+        assert!(proxy.read_value(HashMap::new()).await.is_err());
+        Ok(())
+    })
+}
+
+#[test]
+fn application_without_services_is_rejected() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let result = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            Vec::new(),
+        )
+        .await;
+        assert!(result.is_err());
+        assert!(bluez.applications(&adapter).await?.is_empty());
+        Ok(())
+    })
+}
+
+#[test]
+fn rejected_application_can_register_again() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+        let services = || {
+            vec![(
+                GattService1::new(Uuid::new_v4(), true),
+                vec![(
+                    GattCharacteristic1::new(
+                        Uuid::new_v4(),
+                        Some(vec![1]),
+                        vec![CharacteristicFlags::Read],
+                    ),
+                    vec![GattDescriptor1::new(
+                        Uuid::new_v4(),
+                        Some(vec![2]),
+                        vec![GattDescriptorFlags::Read],
+                    )],
+                )],
+            )]
+        };
+
+        // No GattManager1 there, the services are exported before it fails
+        let result = GattApplication1::register_on(
+            "/com/example/app",
+            "/org/bluez/hci1",
+            &client,
+            services(),
+        )
+        .await;
+        assert!(result.is_err());
+        let result = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            Vec::new(),
+        )
+        .await;
+        assert!(result.is_err());
+
+        let app = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            services(),
+        )
+        .await?;
+        let applications = bluez.applications(&adapter).await?;
+        assert_eq!(applications.len(), 1);
+        assert_eq!(applications[0].objects.len(), 3);
+        app.unregister().await?;
+        Ok(())
+    })
+}
+
+#[test]
+fn battery_level_is_found_in_the_battery_service() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        // A vendor service with its own Battery Level, registered first
+        let vendor = (
+            GattService1::new(Uuid::new_v4(), true),
+            vec![(
+                GattCharacteristic1::new(
+                    characteristic::BATTERY_LEVEL,
+                    Some(vec![7]),
+                    vec![CharacteristicFlags::Read],
+                ),
+                Vec::new(),
+            )],
+        );
+        let app = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            vec![vendor, BatteryService::new(150).build()],
+        )
+        .await?;
+
+        let battery = BatteryServiceHandle::find(&app).unwrap();
+        assert_eq!(battery.level().await, 100);
+        battery.set_level(42).await?;
+        assert_eq!(battery.level().await, 42);
+        battery.set_level(250).await?;
+        assert_eq!(battery.level().await, 100);
+        let vendor_level = &app.services()[0].characteristics()[&characteristic::BATTERY_LEVEL];
+        assert_eq!(vendor_level.value().await, [7]);
+        app.unregister().await?;
+        Ok(())
+    })
+}
+
+struct DenyAll;
+
+impl AccessPolicy for DenyAll {
+    fn allow(&self, _: Option<&ObjectPath<'_>>, _: GattOperation, _: Uuid) -> bool {
+        false
+    }
+}
+
+#[test]
+fn access_policy_denies_with_not_authorized() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let char_uuid = Uuid::new_v4();
+        let app = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            vec![(
+                GattService1::new(Uuid::new_v4(), true),
+                vec![(
+                    GattCharacteristic1::new(
+                        char_uuid,
+                        Some(vec![1]),
+                        vec![CharacteristicFlags::Read],
+                    )
+                    .with_access_policy(Arc::new(DenyAll)),
+                    Vec::new(),
+                )],
+            )],
+        )
+        .await?;
+
+        let char = &app.services()[0].characteristics()[&char_uuid];
+        let proxy = GattCharacteristic1Proxy::builder(bluez.connection())
+            .destination(client.unique_name().unwrap().to_owned())?
+            .path(char.path().to_owned())?
+            .build()
+            .await?;
+        match proxy.read_value(HashMap::new()).await {
+            Err(zbus::Error::MethodError(name, _, _)) => {
+                assert_eq!(name.as_str(), "org.bluez.Error.NotAuthorized")
+            }
+            other => panic!("expected NotAuthorized, got {other:?}"),
+        }
+        Ok(())
+    })
+}
+
+#[test]
+fn remove_service_tolerates_objects_already_gone() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let service_uuid = Uuid::new_v4();
+        let char_uuid = Uuid::new_v4();
+        let mut app = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            vec![(
+                GattService1::new(service_uuid, true),
+                vec![(
+                    GattCharacteristic1::new(
+                        char_uuid,
+                        Some(vec![1]),
+                        vec![CharacteristicFlags::Read],
+                    ),
+                    Vec::new(),
+                )],
+            )],
+        )
+        .await?;
+
+        let service_path = app.services()[0].path().to_owned();
+        let char_path = app.services()[0].characteristics()[&char_uuid]
+            .path()
+            .to_owned();
+        client
+            .object_server()
+            .remove::<GattCharacteristic1, _>(&char_path)
+            .await?;
+
+        app.remove_service(service_uuid).await?;
+        assert!(app.services().is_empty());
+        assert!(
+            client
+                .object_server()
+                .interface::<_, GattService1>(&service_path)
+                .await
+                .is_err()
+        );
+        app.unregister().await?;
+        Ok(())
+    })
+}
+
+#[test]
+fn notify_acquired_follows_the_socket() -> Result<(), zbus::Error> {
+    zbus::block_on(async {
+        let bus = TestBus::new()?;
+        let bluez = MockBluez::new(&bus.connection().await?).await?;
+        let adapter = bluez.add_adapter("hci0", "00:11:22:33:44:55").await?;
+        let client = bus.connection().await?;
+
+        let char_uuid = Uuid::new_v4();
+        let (char, mut writers) =
+            GattCharacteristic1::new(char_uuid, None, vec![CharacteristicFlags::Notify])
+                .with_notify_fd();
+        let app = GattApplication1::register_on(
+            "/com/example/app",
+            adapter.as_str(),
+            &client,
+            vec![(GattService1::new(Uuid::new_v4(), true), vec![(char, Vec::new())])],
+        )
+        .await?;
+
+        let proxy = GattCharacteristic1Proxy::builder(bluez.connection())
+            .destination(client.unique_name().unwrap().to_owned())?
+            .path(app.services()[0].characteristics()[&char_uuid].path().to_owned())?
+            .cache_properties(CacheProperties::No)
+            .build()
+            .await?;
+        assert!(!proxy.notify_acquired().await?);
+        let (fd, _) = proxy.acquire_notify(HashMap::new()).await?;
+        assert!(proxy.notify_acquired().await?);
+
+        let mut writer = writers.next().await.unwrap();
+        drop(fd);
+        writer.closed().await;
+        let mut released = false;
+        for _ in 0..100 {
+            if !proxy.notify_acquired().await? {
+                released = true;
+                break;
+            }
+            Timer::after(Duration::from_millis(10)).await;
+        }
+        assert!(released);
+
+        app.unregister().await?;
+        Ok(())
+    })
+}